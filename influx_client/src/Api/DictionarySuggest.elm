@@ -0,0 +1,35 @@
+module Api.DictionarySuggest exposing (SuggestionMode(..), dictionarySuggest)
+
+import Http
+import Json.Decode as Decode
+import Url.Builder
+
+
+type SuggestionMode
+    = Prefix
+    | Fuzzy
+
+
+suggestionModeToString : SuggestionMode -> String
+suggestionModeToString mode =
+    case mode of
+        Prefix ->
+            "prefix"
+
+        Fuzzy ->
+            "fuzzy"
+
+
+dictionarySuggest : { dictPath : String, query : String, mode : SuggestionMode } -> (Result Http.Error (List String) -> msg) -> Cmd msg
+dictionarySuggest { dictPath, query, mode } toMsg =
+    Http.get
+        { url =
+            Url.Builder.crossOrigin
+                "http://127.0.0.1:3000"
+                [ "dictionary", "suggest" ]
+                [ Url.Builder.string "dict_path" dictPath
+                , Url.Builder.string "query" query
+                , Url.Builder.string "mode" (suggestionModeToString mode)
+                ]
+        , expect = Http.expectJson toMsg (Decode.list Decode.string)
+        }
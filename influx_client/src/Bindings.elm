@@ -44,6 +44,7 @@ influxResourceIdEncoder enum =
 type alias Language =
     { id : Maybe (InfluxResourceId)
     , name : String
+    , languageCode : String
     , dicts : List (String)
     , ttsRate : Maybe (Float)
     , ttsPitch : Maybe (Float)
@@ -59,6 +60,7 @@ languageEncoder struct =
     Json.Encode.object
         [ ( "id", (Maybe.withDefault Json.Encode.null << Maybe.map (influxResourceIdEncoder)) struct.id )
         , ( "name", (Json.Encode.string) struct.name )
+        , ( "language_code", (Json.Encode.string) struct.languageCode )
         , ( "dicts", (Json.Encode.list (Json.Encode.string)) struct.dicts )
         , ( "tts_rate", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.float)) struct.ttsRate )
         , ( "tts_pitch", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.float)) struct.ttsPitch )
@@ -85,6 +87,7 @@ parserConfigEncoder struct =
 
 type alias LanguageCreateRequest =
     { name : String
+    , languageCode : String
     , dicts : List (String)
     , ttsRate : Maybe (Float)
     , ttsPitch : Maybe (Float)
@@ -99,6 +102,7 @@ languageCreateRequestEncoder : LanguageCreateRequest -> Json.Encode.Value
 languageCreateRequestEncoder struct =
     Json.Encode.object
         [ ( "name", (Json.Encode.string) struct.name )
+        , ( "language_code", (Json.Encode.string) struct.languageCode )
         , ( "dicts", (Json.Encode.list (Json.Encode.string)) struct.dicts )
         , ( "tts_rate", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.float)) struct.ttsRate )
         , ( "tts_pitch", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.float)) struct.ttsPitch )
@@ -234,6 +238,8 @@ type alias Phrase =
     { id : Maybe (InfluxResourceId)
     , langId : InfluxResourceId
     , orthographySeq : List (String)
+    , maxGap : Maybe (Int)
+    , matchByLemma : Bool
     , definition : String
     , notes : String
     , originalContext : String
@@ -247,6 +253,8 @@ phraseEncoder struct =
         [ ( "id", (Maybe.withDefault Json.Encode.null << Maybe.map (influxResourceIdEncoder)) struct.id )
         , ( "lang_id", (influxResourceIdEncoder) struct.langId )
         , ( "orthography_seq", (Json.Encode.list (Json.Encode.string)) struct.orthographySeq )
+        , ( "max_gap", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.int)) struct.maxGap )
+        , ( "match_by_lemma", (Json.Encode.bool) struct.matchByLemma )
         , ( "definition", (Json.Encode.string) struct.definition )
         , ( "notes", (Json.Encode.string) struct.notes )
         , ( "original_context", (Json.Encode.string) struct.originalContext )
@@ -410,6 +418,7 @@ type alias GetDocResponse =
     { docPackage : DocPackage
     , annotatedDoc : AnnotatedDocV2
     , termDict : TermDictionary
+    , staleAnnotation : Bool
     }
 
 
@@ -419,6 +428,47 @@ getDocResponseEncoder struct =
         [ ( "doc_package", (docPackageEncoder) struct.docPackage )
         , ( "annotated_doc", (annotatedDocV2Encoder) struct.annotatedDoc )
         , ( "term_dict", (termDictionaryEncoder) struct.termDict )
+        , ( "stale_annotation", (Json.Encode.bool) struct.staleAnnotation )
+        ]
+
+
+type alias GetDocRangeRequest =
+    { documentId : InfluxResourceId
+    , sentenceStart : Int
+    , sentenceCount : Int
+    }
+
+
+getDocRangeRequestEncoder : GetDocRangeRequest -> Json.Encode.Value
+getDocRangeRequestEncoder struct =
+    Json.Encode.object
+        [ ( "document_id", (influxResourceIdEncoder) struct.documentId )
+        , ( "sentence_start", (Json.Encode.int) struct.sentenceStart )
+        , ( "sentence_count", (Json.Encode.int) struct.sentenceCount )
+        ]
+
+
+type alias GetDocRangeResponse =
+    { docPackage : DocPackage
+    , annotatedDoc : AnnotatedDocV2
+    , termDict : TermDictionary
+    , staleAnnotation : Bool
+    , sentenceStart : Int
+    , sentenceEnd : Int
+    , totalSentences : Int
+    }
+
+
+getDocRangeResponseEncoder : GetDocRangeResponse -> Json.Encode.Value
+getDocRangeResponseEncoder struct =
+    Json.Encode.object
+        [ ( "doc_package", (docPackageEncoder) struct.docPackage )
+        , ( "annotated_doc", (annotatedDocV2Encoder) struct.annotatedDoc )
+        , ( "term_dict", (termDictionaryEncoder) struct.termDict )
+        , ( "stale_annotation", (Json.Encode.bool) struct.staleAnnotation )
+        , ( "sentence_start", (Json.Encode.int) struct.sentenceStart )
+        , ( "sentence_end", (Json.Encode.int) struct.sentenceEnd )
+        , ( "total_sentences", (Json.Encode.int) struct.totalSentences )
         ]
 
 
@@ -426,6 +476,7 @@ type alias TermEditRequest =
     { requestedAction : TermEditAction
     , term : Term
     , documentId : Maybe (InfluxResourceId)
+    , fillDefinition : Bool
     }
 
 
@@ -435,13 +486,14 @@ termEditRequestEncoder struct =
         [ ( "requested_action", (termEditActionEncoder) struct.requestedAction )
         , ( "term", (termEncoder) struct.term )
         , ( "document_id", (Maybe.withDefault Json.Encode.null << Maybe.map (influxResourceIdEncoder)) struct.documentId )
+        , ( "fill_definition", (Json.Encode.bool) struct.fillDefinition )
         ]
 
 
 type alias TermEditResponse =
     { performedAction : TermEditAction
     , term : Term
-    , updatedAnnotatedDoc : Maybe (AnnotatedDocV2)
+    , annotatedDocDelta : Maybe (AnnotatedDocDelta)
     }
 
 
@@ -450,7 +502,87 @@ termEditResponseEncoder struct =
     Json.Encode.object
         [ ( "performed_action", (termEditActionEncoder) struct.performedAction )
         , ( "term", (termEncoder) struct.term )
-        , ( "updated_annotated_doc", (Maybe.withDefault Json.Encode.null << Maybe.map (annotatedDocV2Encoder)) struct.updatedAnnotatedDoc )
+        , ( "annotated_doc_delta", (Maybe.withDefault Json.Encode.null << Maybe.map (annotatedDocDeltaEncoder)) struct.annotatedDocDelta )
+        ]
+
+
+type alias AnnotatedDocDelta =
+    { changedSegments : List (DocSegV2)
+    , phraseMatches : List (PhraseMatch)
+    , termDict : TermDictionary
+    }
+
+
+annotatedDocDeltaEncoder : AnnotatedDocDelta -> Json.Encode.Value
+annotatedDocDeltaEncoder struct =
+    Json.Encode.object
+        [ ( "changed_segments", (Json.Encode.list (docSegV2Encoder)) struct.changedSegments )
+        , ( "phrase_matches", (Json.Encode.list (phraseMatchEncoder)) struct.phraseMatches )
+        , ( "term_dict", (termDictionaryEncoder) struct.termDict )
+        ]
+
+
+type alias PhraseSuggestionsRequest =
+    { langId : InfluxResourceId
+    , minCount : Maybe (Int)
+    , maxSuggestions : Maybe (Int)
+    }
+
+
+phraseSuggestionsRequestEncoder : PhraseSuggestionsRequest -> Json.Encode.Value
+phraseSuggestionsRequestEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "min_count", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.int)) struct.minCount )
+        , ( "max_suggestions", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.int)) struct.maxSuggestions )
+        ]
+
+
+type alias PhraseSuggestionsResponse =
+    { suggestions : List (PhraseSuggestion)
+    }
+
+
+phraseSuggestionsResponseEncoder : PhraseSuggestionsResponse -> Json.Encode.Value
+phraseSuggestionsResponseEncoder struct =
+    Json.Encode.object
+        [ ( "suggestions", (Json.Encode.list (phraseSuggestionEncoder)) struct.suggestions )
+        ]
+
+
+type alias AcceptPhraseSuggestionRequest =
+    { langId : InfluxResourceId
+    , suggestion : PhraseSuggestion
+    }
+
+
+acceptPhraseSuggestionRequestEncoder : AcceptPhraseSuggestionRequest -> Json.Encode.Value
+acceptPhraseSuggestionRequestEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "suggestion", (phraseSuggestionEncoder) struct.suggestion )
+        ]
+
+
+type alias PhraseSuggestion =
+    { orthographySeq : List (String)
+    , matchByLemma : Bool
+    , score : Float
+    , count : Int
+    , dependencyLinkedRatio : Float
+    , exampleSentences : List (String)
+    }
+
+
+phraseSuggestionEncoder : PhraseSuggestion -> Json.Encode.Value
+phraseSuggestionEncoder struct =
+    Json.Encode.object
+        [ ( "orthography_seq", (Json.Encode.list (Json.Encode.string)) struct.orthographySeq )
+        , ( "match_by_lemma", (Json.Encode.bool) struct.matchByLemma )
+        , ( "score", (Json.Encode.float) struct.score )
+        , ( "count", (Json.Encode.int) struct.count )
+        , ( "dependency_linked_ratio", (Json.Encode.float) struct.dependencyLinkedRatio )
+        , ( "example_sentences", (Json.Encode.list (Json.Encode.string)) struct.exampleSentences )
         ]
 
 
@@ -610,291 +742,1436 @@ setCardStateResponseEncoder struct =
         ]
 
 
-type StardictType
-    = Html
-    | Other (String)
+type TimelineGranularity
+    = Day
+    | Week
 
 
-stardictTypeEncoder : StardictType -> Json.Encode.Value
-stardictTypeEncoder enum =
+timelineGranularityEncoder : TimelineGranularity -> Json.Encode.Value
+timelineGranularityEncoder enum =
     case enum of
-        Html ->
-            Json.Encode.string "Html"
-        Other inner ->
-            Json.Encode.object [ ( "Other", Json.Encode.string inner ) ]
-
-type alias WordDefinition =
-    { word : String
-    , segments : List (WordDefinitionSegment)
-    , dictionaryInfo : DictionaryInfo
+        Day ->
+            Json.Encode.string "Day"
+        Week ->
+            Json.Encode.string "Week"
+
+type alias VocabTimelineBucket =
+    { bucketStart : String
+    , statusCounts : Dict String (Int)
+    , newTerms : Int
+    , wordsLearned : Int
     }
 
 
-wordDefinitionEncoder : WordDefinition -> Json.Encode.Value
-wordDefinitionEncoder struct =
+vocabTimelineBucketEncoder : VocabTimelineBucket -> Json.Encode.Value
+vocabTimelineBucketEncoder struct =
     Json.Encode.object
-        [ ( "word", (Json.Encode.string) struct.word )
-        , ( "segments", (Json.Encode.list (wordDefinitionSegmentEncoder)) struct.segments )
-        , ( "dictionary_info", (dictionaryInfoEncoder) struct.dictionaryInfo )
+        [ ( "bucket_start", (Json.Encode.string) struct.bucketStart )
+        , ( "status_counts", (Json.Encode.dict identity (Json.Encode.int)) struct.statusCounts )
+        , ( "new_terms", (Json.Encode.int) struct.newTerms )
+        , ( "words_learned", (Json.Encode.int) struct.wordsLearned )
         ]
 
 
-type alias WordDefinitionSegment =
-    { types : StardictType
-    , text : String
+type alias VocabTimelineRequest =
+    { langId : InfluxResourceId
+    , granularity : TimelineGranularity
+    , backfill : Bool
     }
 
 
-wordDefinitionSegmentEncoder : WordDefinitionSegment -> Json.Encode.Value
-wordDefinitionSegmentEncoder struct =
+vocabTimelineRequestEncoder : VocabTimelineRequest -> Json.Encode.Value
+vocabTimelineRequestEncoder struct =
     Json.Encode.object
-        [ ( "types", (stardictTypeEncoder) struct.types )
-        , ( "text", (Json.Encode.string) struct.text )
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "granularity", (timelineGranularityEncoder) struct.granularity )
+        , ( "backfill", (Json.Encode.bool) struct.backfill )
         ]
 
 
-type alias DictionaryInfo =
-    { name : String
-    , directoryName : String
-    , baseUrl : String
+type alias VocabTimelineResponse =
+    { buckets : List (VocabTimelineBucket)
     }
 
 
-dictionaryInfoEncoder : DictionaryInfo -> Json.Encode.Value
-dictionaryInfoEncoder struct =
+vocabTimelineResponseEncoder : VocabTimelineResponse -> Json.Encode.Value
+vocabTimelineResponseEncoder struct =
     Json.Encode.object
-        [ ( "name", (Json.Encode.string) struct.name )
-        , ( "directory_name", (Json.Encode.string) struct.directoryName )
-        , ( "base_url", (Json.Encode.string) struct.baseUrl )
+        [ ( "buckets", (Json.Encode.list (vocabTimelineBucketEncoder)) struct.buckets )
         ]
 
 
-type alias TermDictionary =
-    { tokenDict : Dict String (Token)
-    , phraseDict : Dict String (Phrase)
+type alias SetFrequencyListRequest =
+    { langId : InfluxResourceId
+    , lemmas : List (String)
     }
 
 
-termDictionaryEncoder : TermDictionary -> Json.Encode.Value
-termDictionaryEncoder struct =
+setFrequencyListRequestEncoder : SetFrequencyListRequest -> Json.Encode.Value
+setFrequencyListRequestEncoder struct =
     Json.Encode.object
-        [ ( "token_dict", (Json.Encode.dict identity (tokenEncoder)) struct.tokenDict )
-        , ( "phrase_dict", (Json.Encode.dict identity (phraseEncoder)) struct.phraseDict )
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "lemmas", (Json.Encode.list (Json.Encode.string)) struct.lemmas )
         ]
 
 
-type alias AnnotatedDocV2 =
-    { text : String
-    , segments : List (DocSegV2)
-    , orthographySet : List (String)
-    , lemmaSet : List (String)
-    , parserConfig : ParserConfig
+type alias SetFrequencyListResponse =
+    { lemmaCount : Int
+    , documentsRescored : Int
     }
 
 
-annotatedDocV2Encoder : AnnotatedDocV2 -> Json.Encode.Value
-annotatedDocV2Encoder struct =
+setFrequencyListResponseEncoder : SetFrequencyListResponse -> Json.Encode.Value
+setFrequencyListResponseEncoder struct =
     Json.Encode.object
-        [ ( "text", (Json.Encode.string) struct.text )
-        , ( "segments", (Json.Encode.list (docSegV2Encoder)) struct.segments )
-        , ( "orthography_set", (Json.Encode.list (Json.Encode.string)) struct.orthographySet )
-        , ( "lemma_set", (Json.Encode.list (Json.Encode.string)) struct.lemmaSet )
-        , ( "parser_config", (parserConfigEncoder) struct.parserConfig )
+        [ ( "lemma_count", (Json.Encode.int) struct.lemmaCount )
+        , ( "documents_rescored", (Json.Encode.int) struct.documentsRescored )
         ]
 
 
-type alias DocSegV2 =
-    { text : String
-    , startChar : Int
-    , endChar : Int
-    , inner : DocSegVariants
+type alias FrequentUnknownLemmasRequest =
+    { langId : InfluxResourceId
+    , limit : Maybe (Int)
     }
 
 
-docSegV2Encoder : DocSegV2 -> Json.Encode.Value
-docSegV2Encoder struct =
+frequentUnknownLemmasRequestEncoder : FrequentUnknownLemmasRequest -> Json.Encode.Value
+frequentUnknownLemmasRequestEncoder struct =
     Json.Encode.object
-        [ ( "text", (Json.Encode.string) struct.text )
-        , ( "start_char", (Json.Encode.int) struct.startChar )
-        , ( "end_char", (Json.Encode.int) struct.endChar )
-        , ( "inner", (docSegVariantsEncoder) struct.inner )
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "limit", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.int)) struct.limit )
         ]
 
 
-type DocSegVariants
-    = Sentence { segments : List (SentSegV2) }
-    | DocumentWhitespace
+type alias FrequentUnknownLemmasResponse =
+    { lemmas : List (RankedLemma)
+    }
 
 
-docSegVariantsEncoder : DocSegVariants -> Json.Encode.Value
-docSegVariantsEncoder enum =
-    case enum of
-        Sentence { segments } ->
-            Json.Encode.object [ ( "Sentence", Json.Encode.object [ ( "segments", (Json.Encode.list (sentSegV2Encoder)) segments ) ] ) ]
-        DocumentWhitespace ->
-            Json.Encode.string "DocumentWhitespace"
+frequentUnknownLemmasResponseEncoder : FrequentUnknownLemmasResponse -> Json.Encode.Value
+frequentUnknownLemmasResponseEncoder struct =
+    Json.Encode.object
+        [ ( "lemmas", (Json.Encode.list (rankedLemmaEncoder)) struct.lemmas )
+        ]
 
-type alias SentSegV2 =
-    { sentenceIdx : Int
-    , text : String
-    , startChar : Int
-    , endChar : Int
-    , inner : SentSegVariants
-    , attributes : SegAttribute
+
+type alias RankedLemma =
+    { lemma : String
+    , rank : Int
     }
 
 
-sentSegV2Encoder : SentSegV2 -> Json.Encode.Value
-sentSegV2Encoder struct =
+rankedLemmaEncoder : RankedLemma -> Json.Encode.Value
+rankedLemmaEncoder struct =
     Json.Encode.object
-        [ ( "sentence_idx", (Json.Encode.int) struct.sentenceIdx )
-        , ( "text", (Json.Encode.string) struct.text )
-        , ( "start_char", (Json.Encode.int) struct.startChar )
-        , ( "end_char", (Json.Encode.int) struct.endChar )
-        , ( "inner", (sentSegVariantsEncoder) struct.inner )
-        , ( "attributes", (segAttributeEncoder) struct.attributes )
+        [ ( "lemma", (Json.Encode.string) struct.lemma )
+        , ( "rank", (Json.Encode.int) struct.rank )
         ]
 
 
-type SentSegVariants
-    = TokenSeg { idx : Int, orthography : String }
-    | PhraseSeg { normalisedOrthography : String, components : List (SentSegV2) }
-    | WhitespaceSeg
-    | PunctuationSeg
+type FrequencyListFormat
+    = Counts
+    | Ranks
+    | Yomitan
 
 
-sentSegVariantsEncoder : SentSegVariants -> Json.Encode.Value
-sentSegVariantsEncoder enum =
+frequencyListFormatEncoder : FrequencyListFormat -> Json.Encode.Value
+frequencyListFormatEncoder enum =
     case enum of
-        TokenSeg { idx, orthography } ->
-            Json.Encode.object [ ( "TokenSeg", Json.Encode.object [ ( "idx", (Json.Encode.int) idx ), ( "orthography", (Json.Encode.string) orthography ) ] ) ]
-        PhraseSeg { normalisedOrthography, components } ->
-            Json.Encode.object [ ( "PhraseSeg", Json.Encode.object [ ( "normalised_orthography", (Json.Encode.string) normalisedOrthography ), ( "components", (Json.Encode.list (sentSegV2Encoder)) components ) ] ) ]
-        WhitespaceSeg ->
-            Json.Encode.string "WhitespaceSeg"
-        PunctuationSeg ->
-            Json.Encode.string "PunctuationSeg"
-
-type alias SegAttribute =
-    { lemma : Maybe (String)
-    , upos : Maybe (String)
-    , xpos : Maybe (String)
-    , dependency : Maybe (( Int, String ))
-    , misc : Dict String (String)
-    , conjugationChain : Maybe (List (ConjugationStep))
+        Counts ->
+            Json.Encode.string "Counts"
+        Ranks ->
+            Json.Encode.string "Ranks"
+        Yomitan ->
+            Json.Encode.string "Yomitan"
+
+type alias TermListRequest =
+    { langId : InfluxResourceId
+    , status : Maybe (TokenStatus)
     }
 
 
-segAttributeEncoder : SegAttribute -> Json.Encode.Value
-segAttributeEncoder struct =
+termListRequestEncoder : TermListRequest -> Json.Encode.Value
+termListRequestEncoder struct =
     Json.Encode.object
-        [ ( "lemma", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.string)) struct.lemma )
-        , ( "upos", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.string)) struct.upos )
-        , ( "xpos", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.string)) struct.xpos )
-        , ( "dependency", (Maybe.withDefault Json.Encode.null << Maybe.map (\( a, b) -> Json.Encode.list identity [ Json.Encode.int a, Json.Encode.string b ])) struct.dependency )
-        , ( "misc", (Json.Encode.dict identity (Json.Encode.string)) struct.misc )
-        , ( "conjugation_chain", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.list (conjugationStepEncoder))) struct.conjugationChain )
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "status", (Maybe.withDefault Json.Encode.null << Maybe.map (tokenStatusEncoder)) struct.status )
         ]
 
 
-type alias ConjugationStep =
-    { step : Int
-    , form : String
-    , result : String
+type alias RankedToken =
+    { token : Token
+    , frequencyRank : Maybe (Int)
     }
 
 
-conjugationStepEncoder : ConjugationStep -> Json.Encode.Value
-conjugationStepEncoder struct =
+rankedTokenEncoder : RankedToken -> Json.Encode.Value
+rankedTokenEncoder struct =
     Json.Encode.object
-        [ ( "step", (Json.Encode.int) struct.step )
-        , ( "form", (Json.Encode.string) struct.form )
-        , ( "result", (Json.Encode.string) struct.result )
+        [ ( "token", (tokenEncoder) struct.token )
+        , ( "frequency_rank", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.int)) struct.frequencyRank )
         ]
 
 
-influxResourceIdDecoder : Json.Decode.Decoder InfluxResourceId
-influxResourceIdDecoder = 
-    Json.Decode.oneOf
-        [ Json.Decode.map SerialId (Json.Decode.field "SerialId" (Json.Decode.int))
-        , Json.Decode.map StringId (Json.Decode.field "StringId" (Json.Decode.string))
+type alias TermListResponse =
+    { tokens : List (RankedToken)
+    }
+
+
+termListResponseEncoder : TermListResponse -> Json.Encode.Value
+termListResponseEncoder struct =
+    Json.Encode.object
+        [ ( "tokens", (Json.Encode.list (rankedTokenEncoder)) struct.tokens )
         ]
 
-languageDecoder : Json.Decode.Decoder Language
-languageDecoder =
-    Json.Decode.succeed Language
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "name" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dicts" (Json.Decode.list (Json.Decode.string))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tts_rate" (Json.Decode.nullable (Json.Decode.float))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tts_pitch" (Json.Decode.nullable (Json.Decode.float))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tts_voice" (Json.Decode.nullable (Json.Decode.string))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "deepl_source_lang" (Json.Decode.nullable (Json.Decode.string))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "deepl_target_lang" (Json.Decode.nullable (Json.Decode.string))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "parser_config" (parserConfigDecoder)))
 
+type alias RecommendDocumentsRequest =
+    { langId : InfluxResourceId
+    , maxUnknownTokenRatio : Maybe (Float)
+    , limit : Maybe (Int)
+    , refresh : Bool
+    }
 
-parserConfigDecoder : Json.Decode.Decoder ParserConfig
-parserConfigDecoder =
-    Json.Decode.succeed ParserConfig
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "which_parser" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "parser_args" (Json.Decode.dict (Json.Decode.string))))
 
+recommendDocumentsRequestEncoder : RecommendDocumentsRequest -> Json.Encode.Value
+recommendDocumentsRequestEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "max_unknown_token_ratio", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.float)) struct.maxUnknownTokenRatio )
+        , ( "limit", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.int)) struct.limit )
+        , ( "refresh", (Json.Encode.bool) struct.refresh )
+        ]
 
-documentDecoder : Json.Decode.Decoder Document
-documentDecoder =
-    Json.Decode.succeed Document
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "title" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "content" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "doc_type" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tags" (Json.Decode.list (Json.Decode.string))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "created_ts" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_ts" (Json.Decode.string)))
 
+type alias RecommendDocumentsResponse =
+    { recommended : List (DocumentDifficulty)
+    }
 
-documentCreateRequestDecoder : Json.Decode.Decoder DocumentCreateRequest
-documentCreateRequestDecoder =
-    Json.Decode.succeed DocumentCreateRequest
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "title" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "content" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "doc_type" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tags" (Json.Decode.list (Json.Decode.string))))
+
+recommendDocumentsResponseEncoder : RecommendDocumentsResponse -> Json.Encode.Value
+recommendDocumentsResponseEncoder struct =
+    Json.Encode.object
+        [ ( "recommended", (Json.Encode.list (documentDifficultyEncoder)) struct.recommended )
+        ]
+
+
+type TokenisationJobStatus
+    = Queued
+    | Running
+    | Done
+    | Failed
+
+
+tokenisationJobStatusEncoder : TokenisationJobStatus -> Json.Encode.Value
+tokenisationJobStatusEncoder enum =
+    case enum of
+        Queued ->
+            Json.Encode.string "QUEUED"
+        Running ->
+            Json.Encode.string "RUNNING"
+        Done ->
+            Json.Encode.string "DONE"
+        Failed ->
+            Json.Encode.string "FAILED"
+
+type alias TokenisationJob =
+    { id : InfluxResourceId
+    , documentId : InfluxResourceId
+    , status : TokenisationJobStatus
+    , attempts : Int
+    , lastError : Maybe (String)
+    , createdTs : String
+    , updatedTs : String
+    }
+
+
+tokenisationJobEncoder : TokenisationJob -> Json.Encode.Value
+tokenisationJobEncoder struct =
+    Json.Encode.object
+        [ ( "id", (influxResourceIdEncoder) struct.id )
+        , ( "document_id", (influxResourceIdEncoder) struct.documentId )
+        , ( "status", (tokenisationJobStatusEncoder) struct.status )
+        , ( "attempts", (Json.Encode.int) struct.attempts )
+        , ( "last_error", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.string)) struct.lastError )
+        , ( "created_ts", (Json.Encode.string) struct.createdTs )
+        , ( "updated_ts", (Json.Encode.string) struct.updatedTs )
+        ]
+
+
+type alias TokenisationJobsRequest =
+    { documentId : Maybe (InfluxResourceId)
+    , status : Maybe (TokenisationJobStatus)
+    }
+
+
+tokenisationJobsRequestEncoder : TokenisationJobsRequest -> Json.Encode.Value
+tokenisationJobsRequestEncoder struct =
+    Json.Encode.object
+        [ ( "document_id", (Maybe.withDefault Json.Encode.null << Maybe.map (influxResourceIdEncoder)) struct.documentId )
+        , ( "status", (Maybe.withDefault Json.Encode.null << Maybe.map (tokenisationJobStatusEncoder)) struct.status )
+        ]
+
+
+type alias RetryTokenisationJobsRequest =
+    { jobId : Maybe (InfluxResourceId)
+    }
+
+
+retryTokenisationJobsRequestEncoder : RetryTokenisationJobsRequest -> Json.Encode.Value
+retryTokenisationJobsRequestEncoder struct =
+    Json.Encode.object
+        [ ( "job_id", (Maybe.withDefault Json.Encode.null << Maybe.map (influxResourceIdEncoder)) struct.jobId )
+        ]
+
+
+type AnnotationCacheScope
+    = Document (InfluxResourceId)
+    | Language (InfluxResourceId)
+    | ParserConfig (ParserConfig)
+    | Everything
+
+
+annotationCacheScopeEncoder : AnnotationCacheScope -> Json.Encode.Value
+annotationCacheScopeEncoder enum =
+    case enum of
+        Document inner ->
+            Json.Encode.object [ ( "Document", influxResourceIdEncoder inner ) ]
+        Language inner ->
+            Json.Encode.object [ ( "Language", influxResourceIdEncoder inner ) ]
+        ParserConfig inner ->
+            Json.Encode.object [ ( "ParserConfig", parserConfigEncoder inner ) ]
+        Everything ->
+            Json.Encode.string "Everything"
+
+type alias DocumentCacheStats =
+    { documentId : InfluxResourceId
+    , title : String
+    , entries : Int
+    , bytes : Int
+    , lastUpdatedTs : String
+    }
+
+
+documentCacheStatsEncoder : DocumentCacheStats -> Json.Encode.Value
+documentCacheStatsEncoder struct =
+    Json.Encode.object
+        [ ( "document_id", (influxResourceIdEncoder) struct.documentId )
+        , ( "title", (Json.Encode.string) struct.title )
+        , ( "entries", (Json.Encode.int) struct.entries )
+        , ( "bytes", (Json.Encode.int) struct.bytes )
+        , ( "last_updated_ts", (Json.Encode.string) struct.lastUpdatedTs )
+        ]
+
+
+type alias DifficultyFeatures =
+    { meanSentenceLength : Float
+    , rareLemmaRatio : Maybe (Float)
+    , meanDependencyDepth : Maybe (Float)
+    , unknownTokenRatio : Float
+    }
+
+
+difficultyFeaturesEncoder : DifficultyFeatures -> Json.Encode.Value
+difficultyFeaturesEncoder struct =
+    Json.Encode.object
+        [ ( "mean_sentence_length", (Json.Encode.float) struct.meanSentenceLength )
+        , ( "rare_lemma_ratio", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.float)) struct.rareLemmaRatio )
+        , ( "mean_dependency_depth", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.float)) struct.meanDependencyDepth )
+        , ( "unknown_token_ratio", (Json.Encode.float) struct.unknownTokenRatio )
+        ]
+
+
+type alias DocumentDifficulty =
+    { documentId : InfluxResourceId
+    , title : String
+    , score : Float
+    , features : DifficultyFeatures
+    , updatedTs : String
+    }
+
+
+documentDifficultyEncoder : DocumentDifficulty -> Json.Encode.Value
+documentDifficultyEncoder struct =
+    Json.Encode.object
+        [ ( "document_id", (influxResourceIdEncoder) struct.documentId )
+        , ( "title", (Json.Encode.string) struct.title )
+        , ( "score", (Json.Encode.float) struct.score )
+        , ( "features", (difficultyFeaturesEncoder) struct.features )
+        , ( "updated_ts", (Json.Encode.string) struct.updatedTs )
+        ]
+
+
+type alias LanguageCacheStats =
+    { langId : InfluxResourceId
+    , langName : String
+    , entries : Int
+    , bytes : Int
+    , documents : List (DocumentCacheStats)
+    }
+
+
+languageCacheStatsEncoder : LanguageCacheStats -> Json.Encode.Value
+languageCacheStatsEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "lang_name", (Json.Encode.string) struct.langName )
+        , ( "entries", (Json.Encode.int) struct.entries )
+        , ( "bytes", (Json.Encode.int) struct.bytes )
+        , ( "documents", (Json.Encode.list (documentCacheStatsEncoder)) struct.documents )
+        ]
+
+
+type alias AnnotationCacheStatsResponse =
+    { totalEntries : Int
+    , totalBytes : Int
+    , languages : List (LanguageCacheStats)
+    }
+
+
+annotationCacheStatsResponseEncoder : AnnotationCacheStatsResponse -> Json.Encode.Value
+annotationCacheStatsResponseEncoder struct =
+    Json.Encode.object
+        [ ( "total_entries", (Json.Encode.int) struct.totalEntries )
+        , ( "total_bytes", (Json.Encode.int) struct.totalBytes )
+        , ( "languages", (Json.Encode.list (languageCacheStatsEncoder)) struct.languages )
+        ]
+
+
+type alias InvalidateAnnotationCacheResponse =
+    { removed : Int
+    }
+
+
+invalidateAnnotationCacheResponseEncoder : InvalidateAnnotationCacheResponse -> Json.Encode.Value
+invalidateAnnotationCacheResponseEncoder struct =
+    Json.Encode.object
+        [ ( "removed", (Json.Encode.int) struct.removed )
+        ]
+
+
+type alias AnnotationCacheSettings =
+    { bypassCache : Bool
+    }
+
+
+annotationCacheSettingsEncoder : AnnotationCacheSettings -> Json.Encode.Value
+annotationCacheSettingsEncoder struct =
+    Json.Encode.object
+        [ ( "bypass_cache", (Json.Encode.bool) struct.bypassCache )
+        ]
+
+
+type StardictType
+    = Html
+    | Other (String)
+
+
+stardictTypeEncoder : StardictType -> Json.Encode.Value
+stardictTypeEncoder enum =
+    case enum of
+        Html ->
+            Json.Encode.string "Html"
+        Other inner ->
+            Json.Encode.object [ ( "Other", Json.Encode.string inner ) ]
+
+type alias WordDefinition =
+    { word : String
+    , segments : List (WordDefinitionSegment)
+    , dictionaryInfo : DictionaryInfo
+    , matchedCandidate : Maybe (LookupCandidate)
+    }
+
+
+wordDefinitionEncoder : WordDefinition -> Json.Encode.Value
+wordDefinitionEncoder struct =
+    Json.Encode.object
+        [ ( "word", (Json.Encode.string) struct.word )
+        , ( "segments", (Json.Encode.list (wordDefinitionSegmentEncoder)) struct.segments )
+        , ( "dictionary_info", (dictionaryInfoEncoder) struct.dictionaryInfo )
+        , ( "matched_candidate", (Maybe.withDefault Json.Encode.null << Maybe.map (lookupCandidateEncoder)) struct.matchedCandidate )
+        ]
+
+
+type alias WordDefinitionSegment =
+    { types : StardictType
+    , text : String
+    , plainText : String
+    }
+
+
+wordDefinitionSegmentEncoder : WordDefinitionSegment -> Json.Encode.Value
+wordDefinitionSegmentEncoder struct =
+    Json.Encode.object
+        [ ( "types", (stardictTypeEncoder) struct.types )
+        , ( "text", (Json.Encode.string) struct.text )
+        , ( "plain_text", (Json.Encode.string) struct.plainText )
+        ]
+
+
+type alias DictionaryInfo =
+    { name : String
+    , directoryName : String
+    , baseUrl : String
+    }
+
+
+dictionaryInfoEncoder : DictionaryInfo -> Json.Encode.Value
+dictionaryInfoEncoder struct =
+    Json.Encode.object
+        [ ( "name", (Json.Encode.string) struct.name )
+        , ( "directory_name", (Json.Encode.string) struct.directoryName )
+        , ( "base_url", (Json.Encode.string) struct.baseUrl )
+        ]
+
+
+type alias LookupCandidate =
+    { text : String
+    , source : LookupCandidateSource
+    }
+
+
+lookupCandidateEncoder : LookupCandidate -> Json.Encode.Value
+lookupCandidateEncoder struct =
+    Json.Encode.object
+        [ ( "text", (Json.Encode.string) struct.text )
+        , ( "source", (lookupCandidateSourceEncoder) struct.source )
+        ]
+
+
+type LookupCandidateSource
+    = Surface
+    | Lemma
+    | Conjugation { step : Int, form : String }
+    | CaseFolded
+    | DiacriticsStripped
+
+
+lookupCandidateSourceEncoder : LookupCandidateSource -> Json.Encode.Value
+lookupCandidateSourceEncoder enum =
+    case enum of
+        Surface ->
+            Json.Encode.string "Surface"
+        Lemma ->
+            Json.Encode.string "Lemma"
+        Conjugation { step, form } ->
+            Json.Encode.object [ ( "Conjugation", Json.Encode.object [ ( "step", (Json.Encode.int) step ), ( "form", (Json.Encode.string) form ) ] ) ]
+        CaseFolded ->
+            Json.Encode.string "CaseFolded"
+        DiacriticsStripped ->
+            Json.Encode.string "DiacriticsStripped"
+
+type DictionaryFormat
+    = StarDict
+    | Dsl
+    | MDict
+    | Yomitan
+
+
+dictionaryFormatEncoder : DictionaryFormat -> Json.Encode.Value
+dictionaryFormatEncoder enum =
+    case enum of
+        StarDict ->
+            Json.Encode.string "StarDict"
+        Dsl ->
+            Json.Encode.string "Dsl"
+        MDict ->
+            Json.Encode.string "MDict"
+        Yomitan ->
+            Json.Encode.string "Yomitan"
+
+type alias InstalledDictionary =
+    { dictPath : String
+    , directoryName : String
+    , format : DictionaryFormat
+    , bookname : String
+    , wordcount : Int
+    , author : String
+    , sametypesequence : Maybe (String)
+    }
+
+
+installedDictionaryEncoder : InstalledDictionary -> Json.Encode.Value
+installedDictionaryEncoder struct =
+    Json.Encode.object
+        [ ( "dict_path", (Json.Encode.string) struct.dictPath )
+        , ( "directory_name", (Json.Encode.string) struct.directoryName )
+        , ( "format", (dictionaryFormatEncoder) struct.format )
+        , ( "bookname", (Json.Encode.string) struct.bookname )
+        , ( "wordcount", (Json.Encode.int) struct.wordcount )
+        , ( "author", (Json.Encode.string) struct.author )
+        , ( "sametypesequence", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.string)) struct.sametypesequence )
+        ]
+
+
+type alias DictionaryLoadStats =
+    { ifoPath : String
+    , loads : Int
+    , lastLoadMillis : Int
+    , totalLoadMillis : Int
+    , loaded : Bool
+    }
+
+
+dictionaryLoadStatsEncoder : DictionaryLoadStats -> Json.Encode.Value
+dictionaryLoadStatsEncoder struct =
+    Json.Encode.object
+        [ ( "ifo_path", (Json.Encode.string) struct.ifoPath )
+        , ( "loads", (Json.Encode.int) struct.loads )
+        , ( "last_load_millis", (Json.Encode.int) struct.lastLoadMillis )
+        , ( "total_load_millis", (Json.Encode.int) struct.totalLoadMillis )
+        , ( "loaded", (Json.Encode.bool) struct.loaded )
+        ]
+
+
+type alias LanguageDictionary =
+    { langId : InfluxResourceId
+    , dictPath : String
+    , priority : Int
+    , enabled : Bool
+    }
+
+
+languageDictionaryEncoder : LanguageDictionary -> Json.Encode.Value
+languageDictionaryEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "dict_path", (Json.Encode.string) struct.dictPath )
+        , ( "priority", (Json.Encode.int) struct.priority )
+        , ( "enabled", (Json.Encode.bool) struct.enabled )
+        ]
+
+
+type alias LanguageDictionariesRequest =
+    { langId : InfluxResourceId
+    }
+
+
+languageDictionariesRequestEncoder : LanguageDictionariesRequest -> Json.Encode.Value
+languageDictionariesRequestEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        ]
+
+
+type alias LanguageDictionaryEntry =
+    { dictionary : LanguageDictionary
+    , installed : Maybe (InstalledDictionary)
+    }
+
+
+languageDictionaryEntryEncoder : LanguageDictionaryEntry -> Json.Encode.Value
+languageDictionaryEntryEncoder struct =
+    Json.Encode.object
+        [ ( "dictionary", (languageDictionaryEncoder) struct.dictionary )
+        , ( "installed", (Maybe.withDefault Json.Encode.null << Maybe.map (installedDictionaryEncoder)) struct.installed )
+        ]
+
+
+type alias LanguageDictionariesResponse =
+    { dictionaries : List (LanguageDictionaryEntry)
+    }
+
+
+languageDictionariesResponseEncoder : LanguageDictionariesResponse -> Json.Encode.Value
+languageDictionariesResponseEncoder struct =
+    Json.Encode.object
+        [ ( "dictionaries", (Json.Encode.list (languageDictionaryEntryEncoder)) struct.dictionaries )
+        ]
+
+
+type alias LanguageDictionarySetting =
+    { dictPath : String
+    , enabled : Bool
+    }
+
+
+languageDictionarySettingEncoder : LanguageDictionarySetting -> Json.Encode.Value
+languageDictionarySettingEncoder struct =
+    Json.Encode.object
+        [ ( "dict_path", (Json.Encode.string) struct.dictPath )
+        , ( "enabled", (Json.Encode.bool) struct.enabled )
+        ]
+
+
+type alias SetLanguageDictionariesRequest =
+    { langId : InfluxResourceId
+    , dictionaries : List (LanguageDictionarySetting)
+    }
+
+
+setLanguageDictionariesRequestEncoder : SetLanguageDictionariesRequest -> Json.Encode.Value
+setLanguageDictionariesRequestEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "dictionaries", (Json.Encode.list (languageDictionarySettingEncoder)) struct.dictionaries )
+        ]
+
+
+type alias LanguageDictionaryLookupRequest =
+    { langId : InfluxResourceId
+    , query : String
+    , attributes : Maybe (SegAttribute)
+    }
+
+
+languageDictionaryLookupRequestEncoder : LanguageDictionaryLookupRequest -> Json.Encode.Value
+languageDictionaryLookupRequestEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        , ( "query", (Json.Encode.string) struct.query )
+        , ( "attributes", (Maybe.withDefault Json.Encode.null << Maybe.map (segAttributeEncoder)) struct.attributes )
+        ]
+
+
+type alias FillDefinitionsRequest =
+    { langId : InfluxResourceId
+    }
+
+
+fillDefinitionsRequestEncoder : FillDefinitionsRequest -> Json.Encode.Value
+fillDefinitionsRequestEncoder struct =
+    Json.Encode.object
+        [ ( "lang_id", (influxResourceIdEncoder) struct.langId )
+        ]
+
+
+type alias FillDefinitionsResponse =
+    { filled : List (Token)
+    , notFound : List (String)
+    }
+
+
+fillDefinitionsResponseEncoder : FillDefinitionsResponse -> Json.Encode.Value
+fillDefinitionsResponseEncoder struct =
+    Json.Encode.object
+        [ ( "filled", (Json.Encode.list (tokenEncoder)) struct.filled )
+        , ( "not_found", (Json.Encode.list (Json.Encode.string)) struct.notFound )
+        ]
+
+
+type alias RemoveDictionaryRequest =
+    { directoryName : String
+    }
+
+
+removeDictionaryRequestEncoder : RemoveDictionaryRequest -> Json.Encode.Value
+removeDictionaryRequestEncoder struct =
+    Json.Encode.object
+        [ ( "directory_name", (Json.Encode.string) struct.directoryName )
+        ]
+
+
+type alias RenameDictionaryRequest =
+    { directoryName : String
+    , newDirectoryName : String
+    }
+
+
+renameDictionaryRequestEncoder : RenameDictionaryRequest -> Json.Encode.Value
+renameDictionaryRequestEncoder struct =
+    Json.Encode.object
+        [ ( "directory_name", (Json.Encode.string) struct.directoryName )
+        , ( "new_directory_name", (Json.Encode.string) struct.newDirectoryName )
+        ]
+
+
+type DictionaryCacheAction
+    = Clear
+    | Rebuild
+
+
+dictionaryCacheActionEncoder : DictionaryCacheAction -> Json.Encode.Value
+dictionaryCacheActionEncoder enum =
+    case enum of
+        Clear ->
+            Json.Encode.string "Clear"
+        Rebuild ->
+            Json.Encode.string "Rebuild"
+
+type alias DictionaryCacheRequest =
+    { dictPath : String
+    , action : DictionaryCacheAction
+    }
+
+
+dictionaryCacheRequestEncoder : DictionaryCacheRequest -> Json.Encode.Value
+dictionaryCacheRequestEncoder struct =
+    Json.Encode.object
+        [ ( "dict_path", (Json.Encode.string) struct.dictPath )
+        , ( "action", (dictionaryCacheActionEncoder) struct.action )
+        ]
+
+
+type alias TermDictionary =
+    { tokenDict : Dict String (Token)
+    , phraseDict : Dict String (Phrase)
+    , frequencyRanks : Dict String (Int)
+    }
+
+
+termDictionaryEncoder : TermDictionary -> Json.Encode.Value
+termDictionaryEncoder struct =
+    Json.Encode.object
+        [ ( "token_dict", (Json.Encode.dict identity (tokenEncoder)) struct.tokenDict )
+        , ( "phrase_dict", (Json.Encode.dict identity (phraseEncoder)) struct.phraseDict )
+        , ( "frequency_ranks", (Json.Encode.dict identity (Json.Encode.int)) struct.frequencyRanks )
+        ]
+
+
+type alias AnnotatedDocV2 =
+    { text : String
+    , segments : List (DocSegV2)
+    , orthographySet : List (String)
+    , lemmaSet : List (String)
+    , parserConfig : ParserConfig
+    , phraseMatches : List (PhraseMatch)
+    }
+
+
+annotatedDocV2Encoder : AnnotatedDocV2 -> Json.Encode.Value
+annotatedDocV2Encoder struct =
+    Json.Encode.object
+        [ ( "text", (Json.Encode.string) struct.text )
+        , ( "segments", (Json.Encode.list (docSegV2Encoder)) struct.segments )
+        , ( "orthography_set", (Json.Encode.list (Json.Encode.string)) struct.orthographySet )
+        , ( "lemma_set", (Json.Encode.list (Json.Encode.string)) struct.lemmaSet )
+        , ( "parser_config", (parserConfigEncoder) struct.parserConfig )
+        , ( "phrase_matches", (Json.Encode.list (phraseMatchEncoder)) struct.phraseMatches )
+        ]
+
+
+type alias PhraseMatch =
+    { sentenceIdx : Int
+    , normalisedOrthography : String
+    , startChar : Int
+    , endChar : Int
+    , componentSpans : List (( Int, Int ))
+    , inBestFit : Bool
+    }
+
+
+phraseMatchEncoder : PhraseMatch -> Json.Encode.Value
+phraseMatchEncoder struct =
+    Json.Encode.object
+        [ ( "sentence_idx", (Json.Encode.int) struct.sentenceIdx )
+        , ( "normalised_orthography", (Json.Encode.string) struct.normalisedOrthography )
+        , ( "start_char", (Json.Encode.int) struct.startChar )
+        , ( "end_char", (Json.Encode.int) struct.endChar )
+        , ( "component_spans", (Json.Encode.list (\( a, b) -> Json.Encode.list identity [ Json.Encode.int a, Json.Encode.int b ])) struct.componentSpans )
+        , ( "in_best_fit", (Json.Encode.bool) struct.inBestFit )
+        ]
+
+
+type alias DocSegV2 =
+    { text : String
+    , startChar : Int
+    , endChar : Int
+    , inner : DocSegVariants
+    }
+
+
+docSegV2Encoder : DocSegV2 -> Json.Encode.Value
+docSegV2Encoder struct =
+    Json.Encode.object
+        [ ( "text", (Json.Encode.string) struct.text )
+        , ( "start_char", (Json.Encode.int) struct.startChar )
+        , ( "end_char", (Json.Encode.int) struct.endChar )
+        , ( "inner", (docSegVariantsEncoder) struct.inner )
+        ]
+
+
+type DocSegVariants
+    = Sentence { segments : List (SentSegV2) }
+    | DocumentWhitespace
+
+
+docSegVariantsEncoder : DocSegVariants -> Json.Encode.Value
+docSegVariantsEncoder enum =
+    case enum of
+        Sentence { segments } ->
+            Json.Encode.object [ ( "Sentence", Json.Encode.object [ ( "segments", (Json.Encode.list (sentSegV2Encoder)) segments ) ] ) ]
+        DocumentWhitespace ->
+            Json.Encode.string "DocumentWhitespace"
+
+type alias SentSegV2 =
+    { sentenceIdx : Int
+    , text : String
+    , startChar : Int
+    , endChar : Int
+    , inner : SentSegVariants
+    , attributes : SegAttribute
+    }
+
+
+sentSegV2Encoder : SentSegV2 -> Json.Encode.Value
+sentSegV2Encoder struct =
+    Json.Encode.object
+        [ ( "sentence_idx", (Json.Encode.int) struct.sentenceIdx )
+        , ( "text", (Json.Encode.string) struct.text )
+        , ( "start_char", (Json.Encode.int) struct.startChar )
+        , ( "end_char", (Json.Encode.int) struct.endChar )
+        , ( "inner", (sentSegVariantsEncoder) struct.inner )
+        , ( "attributes", (segAttributeEncoder) struct.attributes )
+        ]
+
+
+type SentSegVariants
+    = TokenSeg { idx : Int, orthography : String }
+    | PhraseSeg { normalisedOrthography : String, components : List (SentSegV2), gaps : List (SentSegV2) }
+    | WhitespaceSeg
+    | PunctuationSeg
+
+
+sentSegVariantsEncoder : SentSegVariants -> Json.Encode.Value
+sentSegVariantsEncoder enum =
+    case enum of
+        TokenSeg { idx, orthography } ->
+            Json.Encode.object [ ( "TokenSeg", Json.Encode.object [ ( "idx", (Json.Encode.int) idx ), ( "orthography", (Json.Encode.string) orthography ) ] ) ]
+        PhraseSeg { normalisedOrthography, components, gaps } ->
+            Json.Encode.object [ ( "PhraseSeg", Json.Encode.object [ ( "normalised_orthography", (Json.Encode.string) normalisedOrthography ), ( "components", (Json.Encode.list (sentSegV2Encoder)) components ), ( "gaps", (Json.Encode.list (sentSegV2Encoder)) gaps ) ] ) ]
+        WhitespaceSeg ->
+            Json.Encode.string "WhitespaceSeg"
+        PunctuationSeg ->
+            Json.Encode.string "PunctuationSeg"
+
+type alias SegAttribute =
+    { lemma : Maybe (String)
+    , upos : Maybe (String)
+    , xpos : Maybe (String)
+    , dependency : Maybe (( Int, String ))
+    , misc : Dict String (String)
+    , conjugationChain : Maybe (List (ConjugationStep))
+    }
+
+
+segAttributeEncoder : SegAttribute -> Json.Encode.Value
+segAttributeEncoder struct =
+    Json.Encode.object
+        [ ( "lemma", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.string)) struct.lemma )
+        , ( "upos", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.string)) struct.upos )
+        , ( "xpos", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.string)) struct.xpos )
+        , ( "dependency", (Maybe.withDefault Json.Encode.null << Maybe.map (\( a, b) -> Json.Encode.list identity [ Json.Encode.int a, Json.Encode.string b ])) struct.dependency )
+        , ( "misc", (Json.Encode.dict identity (Json.Encode.string)) struct.misc )
+        , ( "conjugation_chain", (Maybe.withDefault Json.Encode.null << Maybe.map (Json.Encode.list (conjugationStepEncoder))) struct.conjugationChain )
+        ]
+
+
+type alias ConjugationStep =
+    { step : Int
+    , form : String
+    , result : String
+    }
+
+
+conjugationStepEncoder : ConjugationStep -> Json.Encode.Value
+conjugationStepEncoder struct =
+    Json.Encode.object
+        [ ( "step", (Json.Encode.int) struct.step )
+        , ( "form", (Json.Encode.string) struct.form )
+        , ( "result", (Json.Encode.string) struct.result )
+        ]
+
+
+influxResourceIdDecoder : Json.Decode.Decoder InfluxResourceId
+influxResourceIdDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.map SerialId (Json.Decode.field "SerialId" (Json.Decode.int))
+        , Json.Decode.map StringId (Json.Decode.field "StringId" (Json.Decode.string))
+        ]
+
+languageDecoder : Json.Decode.Decoder Language
+languageDecoder =
+    Json.Decode.succeed Language
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "name" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "language_code" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dicts" (Json.Decode.list (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tts_rate" (Json.Decode.nullable (Json.Decode.float))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tts_pitch" (Json.Decode.nullable (Json.Decode.float))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tts_voice" (Json.Decode.nullable (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "deepl_source_lang" (Json.Decode.nullable (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "deepl_target_lang" (Json.Decode.nullable (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "parser_config" (parserConfigDecoder)))
+
+
+parserConfigDecoder : Json.Decode.Decoder ParserConfig
+parserConfigDecoder =
+    Json.Decode.succeed ParserConfig
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "which_parser" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "parser_args" (Json.Decode.dict (Json.Decode.string))))
+
+
+documentDecoder : Json.Decode.Decoder Document
+documentDecoder =
+    Json.Decode.succeed Document
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "title" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "content" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "doc_type" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tags" (Json.Decode.list (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "created_ts" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_ts" (Json.Decode.string)))
+
+
+documentCreateRequestDecoder : Json.Decode.Decoder DocumentCreateRequest
+documentCreateRequestDecoder =
+    Json.Decode.succeed DocumentCreateRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "title" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "content" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "doc_type" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tags" (Json.Decode.list (Json.Decode.string))))
 
 
 docPackageDecoder : Json.Decode.Decoder DocPackage
 docPackageDecoder =
     Json.Decode.succeed DocPackage
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "language_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document" (documentDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "language" (languageDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "language_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document" (documentDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "language" (languageDecoder)))
+
+
+tokenDecoder : Json.Decode.Decoder Token
+tokenDecoder =
+    Json.Decode.succeed Token
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "orthography" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phonetic" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "definition" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "notes" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "original_context" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status" (tokenStatusDecoder)))
+
+
+tokenStatusDecoder : Json.Decode.Decoder TokenStatus
+tokenStatusDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "UNMARKED" ->
+                            Json.Decode.succeed Unmarked
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "L1" ->
+                            Json.Decode.succeed L1
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "L2" ->
+                            Json.Decode.succeed L2
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "L3" ->
+                            Json.Decode.succeed L3
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "L4" ->
+                            Json.Decode.succeed L4
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "L5" ->
+                            Json.Decode.succeed L5
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "KNOWN" ->
+                            Json.Decode.succeed Known
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "IGNORED" ->
+                            Json.Decode.succeed Ignored
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        ]
+
+phraseDecoder : Json.Decode.Decoder Phrase
+phraseDecoder =
+    Json.Decode.succeed Phrase
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "orthography_seq" (Json.Decode.list (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "max_gap" (Json.Decode.nullable (Json.Decode.int))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "match_by_lemma" (Json.Decode.bool)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "definition" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "notes" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "original_context" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status" (tokenStatusDecoder)))
+
+
+cardTypeDecoder : Json.Decode.Decoder CardType
+cardTypeDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "RECOGNITION" ->
+                            Json.Decode.succeed Recognition
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "PRODUCTION" ->
+                            Json.Decode.succeed Production
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "CLOZE" ->
+                            Json.Decode.succeed Cloze
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        ]
+
+cardStateDecoder : Json.Decode.Decoder CardState
+cardStateDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "ACTIVE" ->
+                            Json.Decode.succeed Active
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "SUSPENDED" ->
+                            Json.Decode.succeed Suspended
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "ARCHIVED" ->
+                            Json.Decode.succeed Archived
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "DISABLED" ->
+                            Json.Decode.succeed Disabled
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        ]
+
+fsrsLanguageConfigDecoder : Json.Decode.Decoder FsrsLanguageConfig
+fsrsLanguageConfigDecoder =
+    Json.Decode.succeed FsrsLanguageConfig
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fsrs_weights" (Json.Decode.list (Json.Decode.float))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "desired_retention" (Json.Decode.float)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "maximum_interval" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "request_retention" (Json.Decode.nullable (Json.Decode.float))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "enabled_card_types" (Json.Decode.list (cardTypeDecoder))))
+
+
+cardDecoder : Json.Decode.Decoder Card
+cardDecoder =
+    Json.Decode.succeed Card
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "token_id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phrase_id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_type" (cardTypeDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_state" (cardStateDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fsrs_memory" (Json.Decode.nullable (serializableMemoryStateDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "due_date" (Json.Decode.nullable (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "last_review" (Json.Decode.nullable (Json.Decode.string))))
+
+
+reviewLogDecoder : Json.Decode.Decoder ReviewLog
+reviewLogDecoder =
+    Json.Decode.succeed ReviewLog
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rating" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "review_time_ms" (Json.Decode.nullable (Json.Decode.int))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fsrs_memory_before" (Json.Decode.nullable (serializableMemoryStateDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fsrs_memory_after" (Json.Decode.nullable (serializableMemoryStateDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "review_date" (Json.Decode.string)))
+
+
+serializableMemoryStateDecoder : Json.Decode.Decoder SerializableMemoryState
+serializableMemoryStateDecoder =
+    Json.Decode.succeed SerializableMemoryState
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "stability" (Json.Decode.float)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "difficulty" (Json.Decode.float)))
+
+
+termDecoder : Json.Decode.Decoder Term
+termDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.map TokenTerm (Json.Decode.field "TokenTerm" (tokenDecoder))
+        , Json.Decode.map PhraseTerm (Json.Decode.field "PhraseTerm" (phraseDecoder))
+        ]
+
+termEditActionDecoder : Json.Decode.Decoder TermEditAction
+termEditActionDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "CreateTerm" ->
+                            Json.Decode.succeed CreateTerm
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "UpdateTerm" ->
+                            Json.Decode.succeed UpdateTerm
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "DeleteTerm" ->
+                            Json.Decode.succeed DeleteTerm
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        ]
+
+getDocResponseDecoder : Json.Decode.Decoder GetDocResponse
+getDocResponseDecoder =
+    Json.Decode.succeed GetDocResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "doc_package" (docPackageDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "annotated_doc" (annotatedDocV2Decoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term_dict" (termDictionaryDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "stale_annotation" (Json.Decode.bool)))
+
+
+getDocRangeRequestDecoder : Json.Decode.Decoder GetDocRangeRequest
+getDocRangeRequestDecoder =
+    Json.Decode.succeed GetDocRangeRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "sentence_start" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "sentence_count" (Json.Decode.int)))
 
 
-tokenDecoder : Json.Decode.Decoder Token
-tokenDecoder =
-    Json.Decode.succeed Token
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+getDocRangeResponseDecoder : Json.Decode.Decoder GetDocRangeResponse
+getDocRangeResponseDecoder =
+    Json.Decode.succeed GetDocRangeResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "doc_package" (docPackageDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "annotated_doc" (annotatedDocV2Decoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term_dict" (termDictionaryDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "stale_annotation" (Json.Decode.bool)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "sentence_start" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "sentence_end" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "total_sentences" (Json.Decode.int)))
+
+
+termEditRequestDecoder : Json.Decode.Decoder TermEditRequest
+termEditRequestDecoder =
+    Json.Decode.succeed TermEditRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "requested_action" (termEditActionDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term" (termDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document_id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fill_definition" (Json.Decode.bool)))
+
+
+termEditResponseDecoder : Json.Decode.Decoder TermEditResponse
+termEditResponseDecoder =
+    Json.Decode.succeed TermEditResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "performed_action" (termEditActionDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term" (termDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "annotated_doc_delta" (Json.Decode.nullable (annotatedDocDeltaDecoder))))
+
+
+annotatedDocDeltaDecoder : Json.Decode.Decoder AnnotatedDocDelta
+annotatedDocDeltaDecoder =
+    Json.Decode.succeed AnnotatedDocDelta
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "changed_segments" (Json.Decode.list (docSegV2Decoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phrase_matches" (Json.Decode.list (phraseMatchDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term_dict" (termDictionaryDecoder)))
+
+
+phraseSuggestionsRequestDecoder : Json.Decode.Decoder PhraseSuggestionsRequest
+phraseSuggestionsRequestDecoder =
+    Json.Decode.succeed PhraseSuggestionsRequest
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "orthography" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phonetic" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "definition" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "notes" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "original_context" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status" (tokenStatusDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "min_count" (Json.Decode.nullable (Json.Decode.int))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "max_suggestions" (Json.Decode.nullable (Json.Decode.int))))
 
 
-tokenStatusDecoder : Json.Decode.Decoder TokenStatus
-tokenStatusDecoder = 
+phraseSuggestionsResponseDecoder : Json.Decode.Decoder PhraseSuggestionsResponse
+phraseSuggestionsResponseDecoder =
+    Json.Decode.succeed PhraseSuggestionsResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "suggestions" (Json.Decode.list (phraseSuggestionDecoder))))
+
+
+acceptPhraseSuggestionRequestDecoder : Json.Decode.Decoder AcceptPhraseSuggestionRequest
+acceptPhraseSuggestionRequestDecoder =
+    Json.Decode.succeed AcceptPhraseSuggestionRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "suggestion" (phraseSuggestionDecoder)))
+
+
+phraseSuggestionDecoder : Json.Decode.Decoder PhraseSuggestion
+phraseSuggestionDecoder =
+    Json.Decode.succeed PhraseSuggestion
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "orthography_seq" (Json.Decode.list (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "match_by_lemma" (Json.Decode.bool)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "score" (Json.Decode.float)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "count" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dependency_linked_ratio" (Json.Decode.float)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "example_sentences" (Json.Decode.list (Json.Decode.string))))
+
+
+getDocsRequestDecoder : Json.Decode.Decoder GetDocsRequest
+getDocsRequestDecoder =
+    Json.Decode.succeed GetDocsRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "language_id" (Json.Decode.nullable (influxResourceIdDecoder))))
+
+
+reviewableCardIdDecoder : Json.Decode.Decoder ReviewableCardId
+reviewableCardIdDecoder = 
+        let
+            elmRsConstructNewTokenCard tokenId cardType =
+                        NewTokenCard { tokenId = tokenId, cardType = cardType }
+            elmRsConstructNewPhraseCard phraseId cardType =
+                        NewPhraseCard { phraseId = phraseId, cardType = cardType }
+        in
+    Json.Decode.oneOf
+        [ Json.Decode.map ExistingCard (Json.Decode.field "ExistingCard" (influxResourceIdDecoder))
+        , Json.Decode.field "NewTokenCard" (Json.Decode.succeed elmRsConstructNewTokenCard |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "token_id" (influxResourceIdDecoder))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_type" (cardTypeDecoder))))
+        , Json.Decode.field "NewPhraseCard" (Json.Decode.succeed elmRsConstructNewPhraseCard |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phrase_id" (influxResourceIdDecoder))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_type" (cardTypeDecoder))))
+        ]
+
+cardWithTermDecoder : Json.Decode.Decoder CardWithTerm
+cardWithTermDecoder =
+    Json.Decode.succeed CardWithTerm
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card" (cardDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term" (termDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "is_new_card" (Json.Decode.bool)))
+
+
+getNextDueCardRequestDecoder : Json.Decode.Decoder GetNextDueCardRequest
+getNextDueCardRequestDecoder =
+    Json.Decode.succeed GetNextDueCardRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_types" (Json.Decode.nullable (Json.Decode.list (cardTypeDecoder)))))
+
+
+getNextDueCardResponseDecoder : Json.Decode.Decoder GetNextDueCardResponse
+getNextDueCardResponseDecoder =
+    Json.Decode.succeed GetNextDueCardResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card" (Json.Decode.nullable (cardWithTermDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "remaining_due_count" (Json.Decode.int)))
+
+
+submitReviewRequestDecoder : Json.Decode.Decoder SubmitReviewRequest
+submitReviewRequestDecoder =
+    Json.Decode.succeed SubmitReviewRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_identifier" (reviewableCardIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rating" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "review_time_ms" (Json.Decode.nullable (Json.Decode.int))))
+
+
+submitReviewResponseDecoder : Json.Decode.Decoder SubmitReviewResponse
+submitReviewResponseDecoder =
+    Json.Decode.succeed SubmitReviewResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_card" (cardDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "review_log" (reviewLogDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "next_due_date" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "was_new_card" (Json.Decode.bool)))
+
+
+updateFsrsConfigRequestDecoder : Json.Decode.Decoder UpdateFsrsConfigRequest
+updateFsrsConfigRequestDecoder =
+    Json.Decode.succeed UpdateFsrsConfigRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "new_config" (fsrsLanguageConfigDecoder)))
+
+
+updateFsrsConfigResponseDecoder : Json.Decode.Decoder UpdateFsrsConfigResponse
+updateFsrsConfigResponseDecoder =
+    Json.Decode.succeed UpdateFsrsConfigResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_config" (fsrsLanguageConfigDecoder)))
+
+
+setCardStateRequestDecoder : Json.Decode.Decoder SetCardStateRequest
+setCardStateRequestDecoder =
+    Json.Decode.succeed SetCardStateRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "new_state" (cardStateDecoder)))
+
+
+setCardStateResponseDecoder : Json.Decode.Decoder SetCardStateResponse
+setCardStateResponseDecoder =
+    Json.Decode.succeed SetCardStateResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_card" (cardDecoder)))
+
+
+timelineGranularityDecoder : Json.Decode.Decoder TimelineGranularity
+timelineGranularityDecoder = 
     Json.Decode.oneOf
         [ Json.Decode.string
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "UNMARKED" ->
-                            Json.Decode.succeed Unmarked
+                        "Day" ->
+                            Json.Decode.succeed Day
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -902,8 +2179,79 @@ tokenStatusDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "L1" ->
-                            Json.Decode.succeed L1
+                        "Week" ->
+                            Json.Decode.succeed Week
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        ]
+
+vocabTimelineBucketDecoder : Json.Decode.Decoder VocabTimelineBucket
+vocabTimelineBucketDecoder =
+    Json.Decode.succeed VocabTimelineBucket
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "bucket_start" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status_counts" (Json.Decode.dict (Json.Decode.int))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "new_terms" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "words_learned" (Json.Decode.int)))
+
+
+vocabTimelineRequestDecoder : Json.Decode.Decoder VocabTimelineRequest
+vocabTimelineRequestDecoder =
+    Json.Decode.succeed VocabTimelineRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "granularity" (timelineGranularityDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "backfill" (Json.Decode.bool)))
+
+
+vocabTimelineResponseDecoder : Json.Decode.Decoder VocabTimelineResponse
+vocabTimelineResponseDecoder =
+    Json.Decode.succeed VocabTimelineResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "buckets" (Json.Decode.list (vocabTimelineBucketDecoder))))
+
+
+setFrequencyListRequestDecoder : Json.Decode.Decoder SetFrequencyListRequest
+setFrequencyListRequestDecoder =
+    Json.Decode.succeed SetFrequencyListRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lemmas" (Json.Decode.list (Json.Decode.string))))
+
+
+setFrequencyListResponseDecoder : Json.Decode.Decoder SetFrequencyListResponse
+setFrequencyListResponseDecoder =
+    Json.Decode.succeed SetFrequencyListResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lemma_count" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "documents_rescored" (Json.Decode.int)))
+
+
+frequentUnknownLemmasRequestDecoder : Json.Decode.Decoder FrequentUnknownLemmasRequest
+frequentUnknownLemmasRequestDecoder =
+    Json.Decode.succeed FrequentUnknownLemmasRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "limit" (Json.Decode.nullable (Json.Decode.int))))
+
+
+frequentUnknownLemmasResponseDecoder : Json.Decode.Decoder FrequentUnknownLemmasResponse
+frequentUnknownLemmasResponseDecoder =
+    Json.Decode.succeed FrequentUnknownLemmasResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lemmas" (Json.Decode.list (rankedLemmaDecoder))))
+
+
+rankedLemmaDecoder : Json.Decode.Decoder RankedLemma
+rankedLemmaDecoder =
+    Json.Decode.succeed RankedLemma
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lemma" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rank" (Json.Decode.int)))
+
+
+frequencyListFormatDecoder : Json.Decode.Decoder FrequencyListFormat
+frequencyListFormatDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "Counts" ->
+                            Json.Decode.succeed Counts
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -911,8 +2259,8 @@ tokenStatusDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "L2" ->
-                            Json.Decode.succeed L2
+                        "Ranks" ->
+                            Json.Decode.succeed Ranks
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -920,8 +2268,57 @@ tokenStatusDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "L3" ->
-                            Json.Decode.succeed L3
+                        "Yomitan" ->
+                            Json.Decode.succeed Yomitan
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        ]
+
+termListRequestDecoder : Json.Decode.Decoder TermListRequest
+termListRequestDecoder =
+    Json.Decode.succeed TermListRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status" (Json.Decode.nullable (tokenStatusDecoder))))
+
+
+rankedTokenDecoder : Json.Decode.Decoder RankedToken
+rankedTokenDecoder =
+    Json.Decode.succeed RankedToken
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "token" (tokenDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "frequency_rank" (Json.Decode.nullable (Json.Decode.int))))
+
+
+termListResponseDecoder : Json.Decode.Decoder TermListResponse
+termListResponseDecoder =
+    Json.Decode.succeed TermListResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "tokens" (Json.Decode.list (rankedTokenDecoder))))
+
+
+recommendDocumentsRequestDecoder : Json.Decode.Decoder RecommendDocumentsRequest
+recommendDocumentsRequestDecoder =
+    Json.Decode.succeed RecommendDocumentsRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "max_unknown_token_ratio" (Json.Decode.nullable (Json.Decode.float))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "limit" (Json.Decode.nullable (Json.Decode.int))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "refresh" (Json.Decode.bool)))
+
+
+recommendDocumentsResponseDecoder : Json.Decode.Decoder RecommendDocumentsResponse
+recommendDocumentsResponseDecoder =
+    Json.Decode.succeed RecommendDocumentsResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "recommended" (Json.Decode.list (documentDifficultyDecoder))))
+
+
+tokenisationJobStatusDecoder : Json.Decode.Decoder TokenisationJobStatus
+tokenisationJobStatusDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "QUEUED" ->
+                            Json.Decode.succeed Queued
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -929,8 +2326,8 @@ tokenStatusDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "L4" ->
-                            Json.Decode.succeed L4
+                        "RUNNING" ->
+                            Json.Decode.succeed Running
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -938,8 +2335,8 @@ tokenStatusDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "L5" ->
-                            Json.Decode.succeed L5
+                        "DONE" ->
+                            Json.Decode.succeed Done
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -947,75 +2344,174 @@ tokenStatusDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "KNOWN" ->
-                            Json.Decode.succeed Known
+                        "FAILED" ->
+                            Json.Decode.succeed Failed
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
+        ]
+
+tokenisationJobDecoder : Json.Decode.Decoder TokenisationJob
+tokenisationJobDecoder =
+    Json.Decode.succeed TokenisationJob
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status" (tokenisationJobStatusDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "attempts" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "last_error" (Json.Decode.nullable (Json.Decode.string))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "created_ts" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_ts" (Json.Decode.string)))
+
+
+tokenisationJobsRequestDecoder : Json.Decode.Decoder TokenisationJobsRequest
+tokenisationJobsRequestDecoder =
+    Json.Decode.succeed TokenisationJobsRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document_id" (Json.Decode.nullable (influxResourceIdDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status" (Json.Decode.nullable (tokenisationJobStatusDecoder))))
+
+
+retryTokenisationJobsRequestDecoder : Json.Decode.Decoder RetryTokenisationJobsRequest
+retryTokenisationJobsRequestDecoder =
+    Json.Decode.succeed RetryTokenisationJobsRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "job_id" (Json.Decode.nullable (influxResourceIdDecoder))))
+
+
+annotationCacheScopeDecoder : Json.Decode.Decoder AnnotationCacheScope
+annotationCacheScopeDecoder = 
+    Json.Decode.oneOf
+        [ Json.Decode.map Document (Json.Decode.field "Document" (influxResourceIdDecoder))
+        , Json.Decode.map Language (Json.Decode.field "Language" (influxResourceIdDecoder))
+        , Json.Decode.map ParserConfig (Json.Decode.field "ParserConfig" (parserConfigDecoder))
         , Json.Decode.string
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "IGNORED" ->
-                            Json.Decode.succeed Ignored
+                        "Everything" ->
+                            Json.Decode.succeed Everything
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
         ]
 
-phraseDecoder : Json.Decode.Decoder Phrase
-phraseDecoder =
-    Json.Decode.succeed Phrase
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
+documentCacheStatsDecoder : Json.Decode.Decoder DocumentCacheStats
+documentCacheStatsDecoder =
+    Json.Decode.succeed DocumentCacheStats
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "title" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "entries" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "bytes" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "last_updated_ts" (Json.Decode.string)))
+
+
+difficultyFeaturesDecoder : Json.Decode.Decoder DifficultyFeatures
+difficultyFeaturesDecoder =
+    Json.Decode.succeed DifficultyFeatures
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "mean_sentence_length" (Json.Decode.float)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rare_lemma_ratio" (Json.Decode.nullable (Json.Decode.float))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "mean_dependency_depth" (Json.Decode.nullable (Json.Decode.float))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "unknown_token_ratio" (Json.Decode.float)))
+
+
+documentDifficultyDecoder : Json.Decode.Decoder DocumentDifficulty
+documentDifficultyDecoder =
+    Json.Decode.succeed DocumentDifficulty
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "title" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "score" (Json.Decode.float)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "features" (difficultyFeaturesDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_ts" (Json.Decode.string)))
+
+
+languageCacheStatsDecoder : Json.Decode.Decoder LanguageCacheStats
+languageCacheStatsDecoder =
+    Json.Decode.succeed LanguageCacheStats
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "orthography_seq" (Json.Decode.list (Json.Decode.string))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "definition" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "notes" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "original_context" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status" (tokenStatusDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_name" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "entries" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "bytes" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "documents" (Json.Decode.list (documentCacheStatsDecoder))))
 
 
-cardTypeDecoder : Json.Decode.Decoder CardType
-cardTypeDecoder = 
+annotationCacheStatsResponseDecoder : Json.Decode.Decoder AnnotationCacheStatsResponse
+annotationCacheStatsResponseDecoder =
+    Json.Decode.succeed AnnotationCacheStatsResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "total_entries" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "total_bytes" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "languages" (Json.Decode.list (languageCacheStatsDecoder))))
+
+
+invalidateAnnotationCacheResponseDecoder : Json.Decode.Decoder InvalidateAnnotationCacheResponse
+invalidateAnnotationCacheResponseDecoder =
+    Json.Decode.succeed InvalidateAnnotationCacheResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "removed" (Json.Decode.int)))
+
+
+annotationCacheSettingsDecoder : Json.Decode.Decoder AnnotationCacheSettings
+annotationCacheSettingsDecoder =
+    Json.Decode.succeed AnnotationCacheSettings
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "bypass_cache" (Json.Decode.bool)))
+
+
+stardictTypeDecoder : Json.Decode.Decoder StardictType
+stardictTypeDecoder = 
     Json.Decode.oneOf
         [ Json.Decode.string
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "RECOGNITION" ->
-                            Json.Decode.succeed Recognition
-                        unexpected ->
-                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
-                )
-        , Json.Decode.string
-            |> Json.Decode.andThen
-                (\x ->
-                    case x of
-                        "PRODUCTION" ->
-                            Json.Decode.succeed Production
-                        unexpected ->
-                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
-                )
-        , Json.Decode.string
-            |> Json.Decode.andThen
-                (\x ->
-                    case x of
-                        "CLOZE" ->
-                            Json.Decode.succeed Cloze
+                        "Html" ->
+                            Json.Decode.succeed Html
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
+        , Json.Decode.map Other (Json.Decode.field "Other" (Json.Decode.string))
         ]
 
-cardStateDecoder : Json.Decode.Decoder CardState
-cardStateDecoder = 
+wordDefinitionDecoder : Json.Decode.Decoder WordDefinition
+wordDefinitionDecoder =
+    Json.Decode.succeed WordDefinition
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "word" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "segments" (Json.Decode.list (wordDefinitionSegmentDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dictionary_info" (dictionaryInfoDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "matched_candidate" (Json.Decode.nullable (lookupCandidateDecoder))))
+
+
+wordDefinitionSegmentDecoder : Json.Decode.Decoder WordDefinitionSegment
+wordDefinitionSegmentDecoder =
+    Json.Decode.succeed WordDefinitionSegment
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "types" (stardictTypeDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "text" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "plain_text" (Json.Decode.string)))
+
+
+dictionaryInfoDecoder : Json.Decode.Decoder DictionaryInfo
+dictionaryInfoDecoder =
+    Json.Decode.succeed DictionaryInfo
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "name" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "directory_name" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "base_url" (Json.Decode.string)))
+
+
+lookupCandidateDecoder : Json.Decode.Decoder LookupCandidate
+lookupCandidateDecoder =
+    Json.Decode.succeed LookupCandidate
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "text" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "source" (lookupCandidateSourceDecoder)))
+
+
+lookupCandidateSourceDecoder : Json.Decode.Decoder LookupCandidateSource
+lookupCandidateSourceDecoder = 
+        let
+            elmRsConstructConjugation step form =
+                        Conjugation { step = step, form = form }
+        in
     Json.Decode.oneOf
         [ Json.Decode.string
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "ACTIVE" ->
-                            Json.Decode.succeed Active
+                        "Surface" ->
+                            Json.Decode.succeed Surface
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -1023,17 +2519,18 @@ cardStateDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "SUSPENDED" ->
-                            Json.Decode.succeed Suspended
+                        "Lemma" ->
+                            Json.Decode.succeed Lemma
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
+        , Json.Decode.field "Conjugation" (Json.Decode.succeed elmRsConstructConjugation |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "step" (Json.Decode.int))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "form" (Json.Decode.string))))
         , Json.Decode.string
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "ARCHIVED" ->
-                            Json.Decode.succeed Archived
+                        "CaseFolded" ->
+                            Json.Decode.succeed CaseFolded
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -1041,73 +2538,22 @@ cardStateDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "DISABLED" ->
-                            Json.Decode.succeed Disabled
+                        "DiacriticsStripped" ->
+                            Json.Decode.succeed DiacriticsStripped
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
         ]
 
-fsrsLanguageConfigDecoder : Json.Decode.Decoder FsrsLanguageConfig
-fsrsLanguageConfigDecoder =
-    Json.Decode.succeed FsrsLanguageConfig
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fsrs_weights" (Json.Decode.list (Json.Decode.float))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "desired_retention" (Json.Decode.float)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "maximum_interval" (Json.Decode.int)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "request_retention" (Json.Decode.nullable (Json.Decode.float))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "enabled_card_types" (Json.Decode.list (cardTypeDecoder))))
-
-
-cardDecoder : Json.Decode.Decoder Card
-cardDecoder =
-    Json.Decode.succeed Card
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "token_id" (Json.Decode.nullable (influxResourceIdDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phrase_id" (Json.Decode.nullable (influxResourceIdDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_type" (cardTypeDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_state" (cardStateDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fsrs_memory" (Json.Decode.nullable (serializableMemoryStateDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "due_date" (Json.Decode.nullable (Json.Decode.string))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "last_review" (Json.Decode.nullable (Json.Decode.string))))
-
-
-reviewLogDecoder : Json.Decode.Decoder ReviewLog
-reviewLogDecoder =
-    Json.Decode.succeed ReviewLog
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.nullable (influxResourceIdDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rating" (Json.Decode.int)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "review_time_ms" (Json.Decode.nullable (Json.Decode.int))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fsrs_memory_before" (Json.Decode.nullable (serializableMemoryStateDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fsrs_memory_after" (Json.Decode.nullable (serializableMemoryStateDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "review_date" (Json.Decode.string)))
-
-
-serializableMemoryStateDecoder : Json.Decode.Decoder SerializableMemoryState
-serializableMemoryStateDecoder =
-    Json.Decode.succeed SerializableMemoryState
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "stability" (Json.Decode.float)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "difficulty" (Json.Decode.float)))
-
-
-termDecoder : Json.Decode.Decoder Term
-termDecoder = 
-    Json.Decode.oneOf
-        [ Json.Decode.map TokenTerm (Json.Decode.field "TokenTerm" (tokenDecoder))
-        , Json.Decode.map PhraseTerm (Json.Decode.field "PhraseTerm" (phraseDecoder))
-        ]
-
-termEditActionDecoder : Json.Decode.Decoder TermEditAction
-termEditActionDecoder = 
+dictionaryFormatDecoder : Json.Decode.Decoder DictionaryFormat
+dictionaryFormatDecoder = 
     Json.Decode.oneOf
         [ Json.Decode.string
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "CreateTerm" ->
-                            Json.Decode.succeed CreateTerm
+                        "StarDict" ->
+                            Json.Decode.succeed StarDict
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -1115,8 +2561,8 @@ termEditActionDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "UpdateTerm" ->
-                            Json.Decode.succeed UpdateTerm
+                        "Dsl" ->
+                            Json.Decode.succeed Dsl
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
@@ -1124,157 +2570,148 @@ termEditActionDecoder =
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "DeleteTerm" ->
-                            Json.Decode.succeed DeleteTerm
+                        "MDict" ->
+                            Json.Decode.succeed MDict
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "Yomitan" ->
+                            Json.Decode.succeed Yomitan
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
         ]
 
-getDocResponseDecoder : Json.Decode.Decoder GetDocResponse
-getDocResponseDecoder =
-    Json.Decode.succeed GetDocResponse
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "doc_package" (docPackageDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "annotated_doc" (annotatedDocV2Decoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term_dict" (termDictionaryDecoder)))
-
+installedDictionaryDecoder : Json.Decode.Decoder InstalledDictionary
+installedDictionaryDecoder =
+    Json.Decode.succeed InstalledDictionary
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dict_path" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "directory_name" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "format" (dictionaryFormatDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "bookname" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "wordcount" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "author" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "sametypesequence" (Json.Decode.nullable (Json.Decode.string))))
+
+
+dictionaryLoadStatsDecoder : Json.Decode.Decoder DictionaryLoadStats
+dictionaryLoadStatsDecoder =
+    Json.Decode.succeed DictionaryLoadStats
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "ifo_path" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "loads" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "last_load_millis" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "total_load_millis" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "loaded" (Json.Decode.bool)))
+
+
+languageDictionaryDecoder : Json.Decode.Decoder LanguageDictionary
+languageDictionaryDecoder =
+    Json.Decode.succeed LanguageDictionary
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dict_path" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "priority" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "enabled" (Json.Decode.bool)))
 
-termEditRequestDecoder : Json.Decode.Decoder TermEditRequest
-termEditRequestDecoder =
-    Json.Decode.succeed TermEditRequest
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "requested_action" (termEditActionDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term" (termDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "document_id" (Json.Decode.nullable (influxResourceIdDecoder))))
 
+languageDictionariesRequestDecoder : Json.Decode.Decoder LanguageDictionariesRequest
+languageDictionariesRequestDecoder =
+    Json.Decode.succeed LanguageDictionariesRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
 
-termEditResponseDecoder : Json.Decode.Decoder TermEditResponse
-termEditResponseDecoder =
-    Json.Decode.succeed TermEditResponse
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "performed_action" (termEditActionDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term" (termDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_annotated_doc" (Json.Decode.nullable (annotatedDocV2Decoder))))
 
+languageDictionaryEntryDecoder : Json.Decode.Decoder LanguageDictionaryEntry
+languageDictionaryEntryDecoder =
+    Json.Decode.succeed LanguageDictionaryEntry
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dictionary" (languageDictionaryDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "installed" (Json.Decode.nullable (installedDictionaryDecoder))))
 
-getDocsRequestDecoder : Json.Decode.Decoder GetDocsRequest
-getDocsRequestDecoder =
-    Json.Decode.succeed GetDocsRequest
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "language_id" (Json.Decode.nullable (influxResourceIdDecoder))))
 
+languageDictionariesResponseDecoder : Json.Decode.Decoder LanguageDictionariesResponse
+languageDictionariesResponseDecoder =
+    Json.Decode.succeed LanguageDictionariesResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dictionaries" (Json.Decode.list (languageDictionaryEntryDecoder))))
 
-reviewableCardIdDecoder : Json.Decode.Decoder ReviewableCardId
-reviewableCardIdDecoder = 
-        let
-            elmRsConstructNewTokenCard tokenId cardType =
-                        NewTokenCard { tokenId = tokenId, cardType = cardType }
-            elmRsConstructNewPhraseCard phraseId cardType =
-                        NewPhraseCard { phraseId = phraseId, cardType = cardType }
-        in
-    Json.Decode.oneOf
-        [ Json.Decode.map ExistingCard (Json.Decode.field "ExistingCard" (influxResourceIdDecoder))
-        , Json.Decode.field "NewTokenCard" (Json.Decode.succeed elmRsConstructNewTokenCard |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "token_id" (influxResourceIdDecoder))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_type" (cardTypeDecoder))))
-        , Json.Decode.field "NewPhraseCard" (Json.Decode.succeed elmRsConstructNewPhraseCard |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phrase_id" (influxResourceIdDecoder))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_type" (cardTypeDecoder))))
-        ]
 
-cardWithTermDecoder : Json.Decode.Decoder CardWithTerm
-cardWithTermDecoder =
-    Json.Decode.succeed CardWithTerm
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card" (cardDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "term" (termDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "is_new_card" (Json.Decode.bool)))
+languageDictionarySettingDecoder : Json.Decode.Decoder LanguageDictionarySetting
+languageDictionarySettingDecoder =
+    Json.Decode.succeed LanguageDictionarySetting
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dict_path" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "enabled" (Json.Decode.bool)))
 
 
-getNextDueCardRequestDecoder : Json.Decode.Decoder GetNextDueCardRequest
-getNextDueCardRequestDecoder =
-    Json.Decode.succeed GetNextDueCardRequest
+setLanguageDictionariesRequestDecoder : Json.Decode.Decoder SetLanguageDictionariesRequest
+setLanguageDictionariesRequestDecoder =
+    Json.Decode.succeed SetLanguageDictionariesRequest
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_types" (Json.Decode.nullable (Json.Decode.list (cardTypeDecoder)))))
-
-
-getNextDueCardResponseDecoder : Json.Decode.Decoder GetNextDueCardResponse
-getNextDueCardResponseDecoder =
-    Json.Decode.succeed GetNextDueCardResponse
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card" (Json.Decode.nullable (cardWithTermDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "remaining_due_count" (Json.Decode.int)))
-
-
-submitReviewRequestDecoder : Json.Decode.Decoder SubmitReviewRequest
-submitReviewRequestDecoder =
-    Json.Decode.succeed SubmitReviewRequest
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_identifier" (reviewableCardIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rating" (Json.Decode.int)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "review_time_ms" (Json.Decode.nullable (Json.Decode.int))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dictionaries" (Json.Decode.list (languageDictionarySettingDecoder))))
 
 
-submitReviewResponseDecoder : Json.Decode.Decoder SubmitReviewResponse
-submitReviewResponseDecoder =
-    Json.Decode.succeed SubmitReviewResponse
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_card" (cardDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "review_log" (reviewLogDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "next_due_date" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "was_new_card" (Json.Decode.bool)))
+languageDictionaryLookupRequestDecoder : Json.Decode.Decoder LanguageDictionaryLookupRequest
+languageDictionaryLookupRequestDecoder =
+    Json.Decode.succeed LanguageDictionaryLookupRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "query" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "attributes" (Json.Decode.nullable (segAttributeDecoder))))
 
 
-updateFsrsConfigRequestDecoder : Json.Decode.Decoder UpdateFsrsConfigRequest
-updateFsrsConfigRequestDecoder =
-    Json.Decode.succeed UpdateFsrsConfigRequest
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "new_config" (fsrsLanguageConfigDecoder)))
+fillDefinitionsRequestDecoder : Json.Decode.Decoder FillDefinitionsRequest
+fillDefinitionsRequestDecoder =
+    Json.Decode.succeed FillDefinitionsRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lang_id" (influxResourceIdDecoder)))
 
 
-updateFsrsConfigResponseDecoder : Json.Decode.Decoder UpdateFsrsConfigResponse
-updateFsrsConfigResponseDecoder =
-    Json.Decode.succeed UpdateFsrsConfigResponse
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_config" (fsrsLanguageConfigDecoder)))
+fillDefinitionsResponseDecoder : Json.Decode.Decoder FillDefinitionsResponse
+fillDefinitionsResponseDecoder =
+    Json.Decode.succeed FillDefinitionsResponse
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "filled" (Json.Decode.list (tokenDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "not_found" (Json.Decode.list (Json.Decode.string))))
 
 
-setCardStateRequestDecoder : Json.Decode.Decoder SetCardStateRequest
-setCardStateRequestDecoder =
-    Json.Decode.succeed SetCardStateRequest
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "card_id" (influxResourceIdDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "new_state" (cardStateDecoder)))
+removeDictionaryRequestDecoder : Json.Decode.Decoder RemoveDictionaryRequest
+removeDictionaryRequestDecoder =
+    Json.Decode.succeed RemoveDictionaryRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "directory_name" (Json.Decode.string)))
 
 
-setCardStateResponseDecoder : Json.Decode.Decoder SetCardStateResponse
-setCardStateResponseDecoder =
-    Json.Decode.succeed SetCardStateResponse
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "updated_card" (cardDecoder)))
+renameDictionaryRequestDecoder : Json.Decode.Decoder RenameDictionaryRequest
+renameDictionaryRequestDecoder =
+    Json.Decode.succeed RenameDictionaryRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "directory_name" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "new_directory_name" (Json.Decode.string)))
 
 
-stardictTypeDecoder : Json.Decode.Decoder StardictType
-stardictTypeDecoder = 
+dictionaryCacheActionDecoder : Json.Decode.Decoder DictionaryCacheAction
+dictionaryCacheActionDecoder = 
     Json.Decode.oneOf
         [ Json.Decode.string
             |> Json.Decode.andThen
                 (\x ->
                     case x of
-                        "Html" ->
-                            Json.Decode.succeed Html
+                        "Clear" ->
+                            Json.Decode.succeed Clear
+                        unexpected ->
+                            Json.Decode.fail <| "Unexpected variant " ++ unexpected
+                )
+        , Json.Decode.string
+            |> Json.Decode.andThen
+                (\x ->
+                    case x of
+                        "Rebuild" ->
+                            Json.Decode.succeed Rebuild
                         unexpected ->
                             Json.Decode.fail <| "Unexpected variant " ++ unexpected
                 )
-        , Json.Decode.map Other (Json.Decode.field "Other" (Json.Decode.string))
         ]
 
-wordDefinitionDecoder : Json.Decode.Decoder WordDefinition
-wordDefinitionDecoder =
-    Json.Decode.succeed WordDefinition
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "word" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "segments" (Json.Decode.list (wordDefinitionSegmentDecoder))))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dictionary_info" (dictionaryInfoDecoder)))
-
-
-wordDefinitionSegmentDecoder : Json.Decode.Decoder WordDefinitionSegment
-wordDefinitionSegmentDecoder =
-    Json.Decode.succeed WordDefinitionSegment
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "types" (stardictTypeDecoder)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "text" (Json.Decode.string)))
-
-
-dictionaryInfoDecoder : Json.Decode.Decoder DictionaryInfo
-dictionaryInfoDecoder =
-    Json.Decode.succeed DictionaryInfo
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "name" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "directory_name" (Json.Decode.string)))
-        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "base_url" (Json.Decode.string)))
+dictionaryCacheRequestDecoder : Json.Decode.Decoder DictionaryCacheRequest
+dictionaryCacheRequestDecoder =
+    Json.Decode.succeed DictionaryCacheRequest
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "dict_path" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "action" (dictionaryCacheActionDecoder)))
 
 
 termDictionaryDecoder : Json.Decode.Decoder TermDictionary
@@ -1282,6 +2719,7 @@ termDictionaryDecoder =
     Json.Decode.succeed TermDictionary
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "token_dict" (Json.Decode.dict (tokenDecoder))))
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phrase_dict" (Json.Decode.dict (phraseDecoder))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "frequency_ranks" (Json.Decode.dict (Json.Decode.int))))
 
 
 annotatedDocV2Decoder : Json.Decode.Decoder AnnotatedDocV2
@@ -1292,6 +2730,18 @@ annotatedDocV2Decoder =
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "orthography_set" (Json.Decode.list (Json.Decode.string))))
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "lemma_set" (Json.Decode.list (Json.Decode.string))))
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "parser_config" (parserConfigDecoder)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "phrase_matches" (Json.Decode.list (phraseMatchDecoder))))
+
+
+phraseMatchDecoder : Json.Decode.Decoder PhraseMatch
+phraseMatchDecoder =
+    Json.Decode.succeed PhraseMatch
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "sentence_idx" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "normalised_orthography" (Json.Decode.string)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "start_char" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "end_char" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "component_spans" (Json.Decode.list (Json.Decode.map2 (\a b -> ( a, b )) (Json.Decode.index 0 (Json.Decode.int)) (Json.Decode.index 1 (Json.Decode.int))))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "in_best_fit" (Json.Decode.bool)))
 
 
 docSegV2Decoder : Json.Decode.Decoder DocSegV2
@@ -1338,12 +2788,12 @@ sentSegVariantsDecoder =
         let
             elmRsConstructTokenSeg idx orthography =
                         TokenSeg { idx = idx, orthography = orthography }
-            elmRsConstructPhraseSeg normalisedOrthography components =
-                        PhraseSeg { normalisedOrthography = normalisedOrthography, components = components }
+            elmRsConstructPhraseSeg normalisedOrthography components gaps =
+                        PhraseSeg { normalisedOrthography = normalisedOrthography, components = components, gaps = gaps }
         in
     Json.Decode.oneOf
         [ Json.Decode.field "TokenSeg" (Json.Decode.succeed elmRsConstructTokenSeg |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "idx" (Json.Decode.int))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "orthography" (Json.Decode.string))))
-        , Json.Decode.field "PhraseSeg" (Json.Decode.succeed elmRsConstructPhraseSeg |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "normalised_orthography" (Json.Decode.string))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "components" (Json.Decode.list (sentSegV2Decoder)))))
+        , Json.Decode.field "PhraseSeg" (Json.Decode.succeed elmRsConstructPhraseSeg |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "normalised_orthography" (Json.Decode.string))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "components" (Json.Decode.list (sentSegV2Decoder)))) |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "gaps" (Json.Decode.list (sentSegV2Decoder)))))
         , Json.Decode.string
             |> Json.Decode.andThen
                 (\x ->
@@ -177,6 +177,48 @@ viewPhraseSubsegment args seg =
             unreachableHtml "phrase within phrase???"
 
 
+{-| a segment within a gapped phrase's span that is not part of the phrase, e.g. "the meeting" in "put the meeting off".
+gap tokens keep their own status and handle their own clicks, so they are not taken for the phrase
+-}
+viewPhraseGap :
+    Args msg
+    -> SentSegV2
+    -> Html msg
+viewPhraseGap args seg =
+    let
+        ownEvent eventName msg =
+            Events.stopPropagationOn eventName (Decode.succeed ( msg, True ))
+    in
+    case seg.inner of
+        TokenSeg { orthography } ->
+            span
+                [ class "phrase-gap"
+                , class "single-token-span"
+                , case tokenDictLookup args.dict orthography of
+                    Just tkn ->
+                        tokenStatusToClass tkn.status
+
+                    Nothing ->
+                        class "tkn-nostatus"
+                , ownEvent "mousedown" (args.mouse_handler (FocusContext.SelectMouseDown seg))
+                , ownEvent "mouseup" (args.mouse_handler (FocusContext.SelectMouseUp ()))
+                , ownEvent "dblclick" (args.on_token_double_click seg)
+                , onMouseEnter (args.mouse_handler (FocusContext.SelectMouseEnter seg))
+                , class "clickable-tkn-span"
+                , classIf (args.focus_predicate seg) "tkn-focus"
+                ]
+                [ text seg.text ]
+
+        WhitespaceSeg ->
+            span [ class "phrase-gap", class "sentence-whitespace-span" ] [ text seg.text ]
+
+        PunctuationSeg ->
+            span [ class "phrase-gap", class "sentence-punctuation-span" ] [ text seg.text ]
+
+        PhraseSeg _ ->
+            unreachableHtml "phrase within phrase???"
+
+
 tokenStatusToClass : TokenStatus -> Attribute msg
 tokenStatusToClass status =
     case status of
@@ -260,8 +302,9 @@ viewRegisteredPhrase :
     -> Phrase
     -> SentSegV2
     -> List SentSegV2
+    -> List SentSegV2
     -> Html msg
-viewRegisteredPhrase args attrs phrase seg components =
+viewRegisteredPhrase args attrs phrase seg components gaps =
     let
         topText =
             getAnnotationText args.annotation_config.topAnnotation Nothing (Just phrase) seg
@@ -276,10 +319,17 @@ viewRegisteredPhrase args attrs phrase seg components =
                         Utils.htmlOfString furiganaHtml
 
                     Nothing ->
-                        List.map (viewPhraseSubsegment args) components
+                        phraseParts
 
             else
-                List.map (viewPhraseSubsegment args) components
+                phraseParts
+
+        -- gapped phrases only list their own segments as components, the rest of the span is in gaps
+        phraseParts =
+            List.map (\component -> ( component.startChar, viewPhraseSubsegment args component )) components
+                ++ List.map (\gap -> ( gap.startChar, viewPhraseGap args gap )) gaps
+                |> List.sortBy Tuple.first
+                |> List.map Tuple.second
 
         popoverContent =
             Popover.viewPhrasePopover phrase seg
@@ -325,13 +375,13 @@ viewSentenceSegment args seg =
                         Just tkn ->
                             viewRegisteredTkn args [ class "single-token-span" ] seg.text tkn seg
 
-                PhraseSeg { normalisedOrthography, components } ->
+                PhraseSeg { normalisedOrthography, components, gaps } ->
                     case phraseDictLookup args.dict normalisedOrthography of
                         Nothing ->
                             [ unreachableHtml "Phrase not found in dict" ]
 
                         Just phrase ->
-                            [ viewRegisteredPhrase args [ class "phrase-span" ] phrase seg components ]
+                            [ viewRegisteredPhrase args [ class "phrase-span" ] phrase seg components gaps ]
 
                 WhitespaceSeg ->
                     [ span [ class "sentence-whitespace-span", classIf (args.focus_predicate seg) "tkn-focus" ] [ text seg.text ] ]
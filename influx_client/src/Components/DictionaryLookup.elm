@@ -2,20 +2,23 @@ module Components.DictionaryLookup exposing (Model, Msg(..), init, update, view)
 
 import Api.DictionaryList
 import Api.DictionaryLookup
+import Api.DictionarySuggest exposing (SuggestionMode(..))
 import Bindings exposing (StardictType(..), WordDefinition, WordDefinitionSegment)
 import Components.FormElements3 exposing (SelectCOption, buttonC, inputC, selectC)
 import Effect exposing (Effect)
 import Html exposing (..)
 import Html.Attributes exposing (attribute, class, placeholder, value)
-import Html.Events exposing (onClick, onInput)
+import Html.Events exposing (on, onClick, onInput)
 import Html.Styled
 import Http
+import Json.Decode as Decode
 
 
 type alias Model =
     { dictPath : String
     , query : String
     , lookupResult : LookupResult
+    , suggestions : List String
     , availableDictionaries : List String
     , dictionariesLoadStatus : DictionariesLoadStatus
     }
@@ -41,6 +44,7 @@ init availableDictionaries =
     { dictPath = ""
     , query = ""
     , lookupResult = NotStarted
+    , suggestions = []
     , availableDictionaries = availableDictionaries
     , dictionariesLoadStatus =
         if List.isEmpty availableDictionaries then
@@ -56,6 +60,8 @@ type Msg
     | QueryChanged String
     | LookupClicked
     | LookupResponded (Result Http.Error (List WordDefinition))
+    | SuggestionClicked String
+    | SuggestionsResponded String (Result Http.Error (List String))
     | DictionariesLoaded (Result Http.Error (List String))
 
 
@@ -66,7 +72,31 @@ update msg model =
             ( { model | dictPath = newPath }, Effect.none )
 
         QueryChanged newQuery ->
-            ( { model | query = newQuery }, Effect.none )
+            ( { model
+                | query = newQuery
+                , suggestions =
+                    if String.isEmpty (String.trim newQuery) then
+                        []
+
+                    else
+                        model.suggestions
+              }
+            , suggest Prefix { model | query = newQuery }
+            )
+
+        SuggestionClicked suggestion ->
+            update LookupClicked { model | query = suggestion, suggestions = [] }
+
+        SuggestionsResponded forQuery (Ok suggestions) ->
+            -- a response for an older query arrives too late to be useful
+            if forQuery == model.query then
+                ( { model | suggestions = suggestions }, Effect.none )
+
+            else
+                ( model, Effect.none )
+
+        SuggestionsResponded _ (Err _) ->
+            ( { model | suggestions = [] }, Effect.none )
 
         LookupClicked ->
             if String.isEmpty model.dictPath || String.isEmpty model.query then
@@ -91,7 +121,13 @@ update msg model =
                 )
 
         LookupResponded (Ok definitions) ->
-            ( { model | lookupResult = Success definitions }, Effect.none )
+            ( { model | lookupResult = Success definitions, suggestions = [] }
+            , if List.isEmpty definitions then
+                suggest Fuzzy model
+
+              else
+                Effect.none
+            )
 
         LookupResponded (Err err) ->
             ( { model | lookupResult = Error (httpErrorToString err) }, Effect.none )
@@ -112,6 +148,19 @@ update msg model =
             )
 
 
+suggest : SuggestionMode -> Model -> Effect Msg
+suggest mode model =
+    if String.isEmpty model.dictPath || String.isEmpty (String.trim model.query) then
+        Effect.none
+
+    else
+        Effect.sendCmd
+            (Api.DictionarySuggest.dictionarySuggest
+                { dictPath = model.dictPath, query = model.query, mode = mode }
+                (SuggestionsResponded model.query)
+            )
+
+
 httpErrorToString : Http.Error -> String
 httpErrorToString error =
     case error of
@@ -151,6 +200,7 @@ view model =
                 , onPress = Just LookupClicked
                 , compact = False
                 }
+        , viewSuggestions model
         , viewLookupResult model.lookupResult
         ]
 
@@ -167,7 +217,7 @@ viewDictionarySelector model =
         DictionariesLoadedSuccess ->
             if List.isEmpty model.availableDictionaries then
                 Html.div [ class "error" ]
-                    [ Html.text "No dictionaries found. Please add .ifo files to the dictionaries directory." ]
+                    [ Html.text "No dictionaries found. Please add StarDict (.ifo), DSL, MDict (.mdx) or Yomitan (.zip) dictionaries to the dictionaries directory." ]
 
             else
                 Html.div []
@@ -191,6 +241,34 @@ viewDictionarySelector model =
             Html.div [] []
 
 
+viewSuggestions : Model -> Html Msg
+viewSuggestions model =
+    if List.isEmpty model.suggestions then
+        Html.div [] []
+
+    else
+        Html.div [ class "suggestions" ]
+            [ Html.span []
+                [ Html.text
+                    (case model.lookupResult of
+                        Success [] ->
+                            "Did you mean: "
+
+                        _ ->
+                            "Suggestions: "
+                    )
+                ]
+            , Html.span []
+                (List.map
+                    (\suggestion ->
+                        Html.a [ class "suggestion", onClick (SuggestionClicked suggestion) ] [ Html.text suggestion ]
+                    )
+                    model.suggestions
+                    |> List.intersperse (Html.text ", ")
+                )
+            ]
+
+
 viewLookupResult : LookupResult -> Html Msg
 viewLookupResult result =
     case result of
@@ -242,6 +320,7 @@ viewSegment definition segIndex segment =
                         [ attribute "inner-html" segment.text
                         , attribute "base-url" definition.dictionaryInfo.baseUrl
                         , class "html-content-shadow"
+                        , on "dictionary-lookup" (Decode.map SuggestionClicked (Decode.at [ "detail", "word" ] Decode.string))
                         ]
                         []
                     ]
@@ -64,10 +64,11 @@ type FormMsg
 type Msg
     = InputChanged FormMsg
       -- upward propagation
-    | RequestEditTerm TermEditAction Term (Maybe InfluxResourceId)
+      -- the Bool asks the server to fill an empty token definition from the language's top dictionary
+    | RequestEditTerm TermEditAction Term (Maybe InfluxResourceId) Bool
     | OverwriteTerm Term
     | AddToast String
-    | GotUpdatedAnnotatedDoc AnnotatedDocV2
+    | GotAnnotatedDocDelta AnnotatedDocDelta
       -- downward propagation
     | EditingSegUpdated (Maybe (List SentSegV2)) (Maybe SentSegV2)
     | GotTermEditResponse (Result Http.Error TermEditResponse)
@@ -231,9 +232,9 @@ handleGotTermEditAck model label res =
                 ([ Effect.sendMsg <| OverwriteTerm updated_term
                  , Effect.sendMsg <| AddToast (label ++ ": Success")
                  ]
-                    ++ (case response.updatedAnnotatedDoc of
-                            Just doc ->
-                                [ Effect.sendMsg <| GotUpdatedAnnotatedDoc doc ]
+                    ++ (case response.annotatedDocDelta of
+                            Just delta ->
+                                [ Effect.sendMsg <| GotAnnotatedDocDelta delta ]
 
                             Nothing ->
                                 []
@@ -372,6 +373,16 @@ tokenStatusToString status =
             "UNMARKED"
 
 
+canFillDefinition : Term -> Bool
+canFillDefinition term =
+    case term of
+        TokenTerm token ->
+            String.isEmpty (String.trim token.definition)
+
+        PhraseTerm _ ->
+            False
+
+
 viewTermForm :
     TermFormModel
     -> (Msg -> msg)
@@ -421,17 +432,22 @@ viewTermForm form lift args =
               , buttons =
                     List.filterMap identity
                         [ if form.write_action == Create then
-                            Just (buttonC { label = "Create", onPress = Just (lift (RequestEditTerm CreateTerm form.working_term args.document_id)), compact = True })
+                            Just (buttonC { label = "Create", onPress = Just (lift (RequestEditTerm CreateTerm form.working_term args.document_id False)), compact = True })
+
+                          else
+                            Nothing
+                        , if form.write_action == Create && canFillDefinition form.working_term then
+                            Just (buttonC { label = "Create with dictionary definition", onPress = Just (lift (RequestEditTerm CreateTerm form.working_term args.document_id True)), compact = True })
 
                           else
                             Nothing
                         , if form.write_action == Update then
-                            Just (buttonC { label = "Update", onPress = Just (lift (RequestEditTerm UpdateTerm form.working_term args.document_id)), compact = True })
+                            Just (buttonC { label = "Update", onPress = Just (lift (RequestEditTerm UpdateTerm form.working_term args.document_id False)), compact = True })
 
                           else
                             Nothing
                         , if form.write_action == Update then
-                            Just (buttonC { label = "Delete", onPress = Just (lift (RequestEditTerm DeleteTerm form.working_term args.document_id)), compact = True })
+                            Just (buttonC { label = "Delete", onPress = Just (lift (RequestEditTerm DeleteTerm form.working_term args.document_id False)), compact = True })
 
                           else
                             Nothing
@@ -12,6 +12,7 @@ import Dict exposing (Dict)
 type alias T =
     { tokenDict : Dict String Token
     , phraseDict : Dict String Phrase
+    , frequencyRanks : Dict String Int
     , lang_id : InfluxResourceId
     }
 
@@ -24,6 +25,7 @@ empty : T
 empty =
     { tokenDict = Dict.empty
     , phraseDict = Dict.empty
+    , frequencyRanks = Dict.empty
     , lang_id = SerialId -1 -- placeholder
     }
 
@@ -32,6 +34,7 @@ fromTermDictionary : InfluxResourceId -> TermDictionary -> T
 fromTermDictionary lang_id term_dict =
     { tokenDict = term_dict.tokenDict
     , phraseDict = term_dict.phraseDict
+    , frequencyRanks = term_dict.frequencyRanks
     , lang_id = lang_id
     }
 
@@ -46,6 +49,24 @@ lookupPhrase dict_ctx term =
     Dict.get term dict_ctx.phraseDict
 
 
+{-| rank of the orthography in the language's frequency list, 1 being the most frequent
+-}
+lookupFrequencyRank : T -> String -> Maybe Int
+lookupFrequencyRank dict_ctx orthography =
+    Dict.get orthography dict_ctx.frequencyRanks
+
+
+{-| entries from the TermDictionary take precedence over those already known
+-}
+mergeTermDictionary : T -> TermDictionary -> T
+mergeTermDictionary dict_ctx term_dict =
+    { dict_ctx
+        | tokenDict = Dict.union term_dict.tokenDict dict_ctx.tokenDict
+        , phraseDict = Dict.union term_dict.phraseDict dict_ctx.phraseDict
+        , frequencyRanks = Dict.union term_dict.frequencyRanks dict_ctx.frequencyRanks
+    }
+
+
 overwriteTerm : T -> Term -> T
 overwriteTerm dict_ctx term =
     case term of
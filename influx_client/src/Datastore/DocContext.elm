@@ -1,6 +1,7 @@
 module Datastore.DocContext exposing (..)
 
-import Bindings exposing (AnnotatedDocV2, DocSegV2, InfluxResourceId(..))
+import Bindings exposing (AnnotatedDocDelta, AnnotatedDocV2, DocSegV2, InfluxResourceId(..))
+import Dict
 
 
 type alias T =
@@ -24,3 +25,19 @@ fromAnnotatedDocument lang_id annotated_doc =
     , lang_id = lang_id
     , segments = annotated_doc.segments
     }
+
+
+{-| replace the sentences a term edit annotated again, matching them by start char
+-}
+applyDelta : AnnotatedDocDelta -> T -> T
+applyDelta delta doc =
+    let
+        changed =
+            Dict.fromList (List.map (\seg -> ( seg.startChar, seg )) delta.changedSegments)
+    in
+    { doc
+        | segments =
+            List.map
+                (\seg -> Dict.get seg.startChar changed |> Maybe.withDefault seg)
+                doc.segments
+    }
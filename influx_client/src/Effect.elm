@@ -179,13 +179,14 @@ ttsGetVoices =
         }
 
 
-ttsSpeak : { text : String, voice : Maybe String, rate : Maybe Float, pitch : Maybe Float } -> Effect msg
+ttsSpeak : { text : String, lang : Maybe String, voice : Maybe String, rate : Maybe Float, pitch : Maybe Float } -> Effect msg
 ttsSpeak options =
     SendMessageToJavaScript
         { tag = "SPEAK"
         , data =
             Json.Encode.object
                 [ ( "text", Json.Encode.string options.text )
+                , ( "lang", Maybe.withDefault Json.Encode.null (Maybe.map Json.Encode.string options.lang) )
                 , ( "voice", Maybe.withDefault Json.Encode.null (Maybe.map Json.Encode.string options.voice) )
                 , ( "rate", Maybe.withDefault Json.Encode.null (Maybe.map Json.Encode.float options.rate) )
                 , ( "pitch", Maybe.withDefault Json.Encode.null (Maybe.map Json.Encode.float options.pitch) )
@@ -211,13 +212,14 @@ ttsCancel =
         }
 
 
-ttsCancelAndSpeak : { text : String, voice : Maybe String, rate : Maybe Float, pitch : Maybe Float } -> Effect msg
+ttsCancelAndSpeak : { text : String, lang : Maybe String, voice : Maybe String, rate : Maybe Float, pitch : Maybe Float } -> Effect msg
 ttsCancelAndSpeak options =
     SendMessageToJavaScript
         { tag = "CANCEL_AND_SPEAK"
         , data =
             Json.Encode.object
                 [ ( "text", Json.Encode.string options.text )
+                , ( "lang", Maybe.withDefault Json.Encode.null (Maybe.map Json.Encode.string options.lang) )
                 , ( "voice", Maybe.withDefault Json.Encode.null (Maybe.map Json.Encode.string options.voice) )
                 , ( "rate", Maybe.withDefault Json.Encode.null (Maybe.map Json.Encode.float options.rate) )
                 , ( "pitch", Maybe.withDefault Json.Encode.null (Maybe.map Json.Encode.float options.pitch) )
@@ -287,13 +287,16 @@ update msg model =
 
         TermEditorEvent formMsg ->
             case formMsg of
-                TermEditForm.RequestEditTerm action term document_id ->
+                TermEditForm.RequestEditTerm action term document_id fill_definition ->
                     ( model
-                    , Effect.sendCmd (Api.TermEdit.edit { requestedAction = action, term = term, documentId = document_id } (TermEditorEvent << TermEditForm.GotTermEditResponse))
+                    , Effect.sendCmd (Api.TermEdit.edit { requestedAction = action, term = term, documentId = document_id, fillDefinition = fill_definition } (TermEditorEvent << TermEditForm.GotTermEditResponse))
                     )
 
-                TermEditForm.GotUpdatedAnnotatedDoc updated_doc ->
-                    ( { model | working_doc = DocContext.fromAnnotatedDocument model.working_doc.lang_id updated_doc }
+                TermEditForm.GotAnnotatedDocDelta delta ->
+                    ( { model
+                        | working_doc = DocContext.applyDelta delta model.working_doc
+                        , working_dict = DictContext.mergeTermDictionary model.working_dict delta.termDict
+                      }
                     , Effect.adjustAnnotationWidths
                     )
 
@@ -336,6 +339,7 @@ update msg model =
                         ( model
                         , Effect.ttsCancelAndSpeak
                             { text = selectedText
+                            , lang = Just language.languageCode
                             , voice = language.ttsVoice
                             , rate = language.ttsRate
                             , pitch = language.ttsPitch
@@ -382,8 +386,23 @@ update msg model =
 
                         language =
                             response.docPackage.language
+
+                        -- DeepL source languages are plain language subtags, e.g. ZH for zh-Hant
+                        sourceLang =
+                            case ( language.deeplSourceLang, String.split "-" language.languageCode ) of
+                                ( Just configured, _ ) ->
+                                    Just configured
+
+                                ( Nothing, "und" :: _ ) ->
+                                    Nothing
+
+                                ( Nothing, subtag :: _ ) ->
+                                    Just (String.toUpper subtag)
+
+                                ( Nothing, [] ) ->
+                                    Nothing
                     in
-                    case ( language.deeplSourceLang, language.deeplTargetLang ) of
+                    case ( sourceLang, language.deeplTargetLang ) of
                         ( Just sourceLang, Just targetLang ) ->
                             if String.isEmpty (String.trim selectedText) then
                                 ( model, Effect.none )
@@ -102,6 +102,7 @@ emptyLanguage : Language
 emptyLanguage =
     { id = Nothing
     , name = ""
+    , languageCode = ""
     , dicts = []
     , ttsRate = Nothing
     , ttsPitch = Nothing
@@ -193,6 +194,7 @@ init route () =
 type Msg
     = LanguageDataResponded (Result Http.Error (Maybe Language))
     | UpdateNameInput String
+    | UpdateLanguageCodeInput String
     | UpdateDictsList (List String)
     | UpdateDictInput String
     | UpdateTtsRateInput Float
@@ -256,6 +258,9 @@ update msg model =
         UpdateNameInput value ->
             updateWorkingLanguage (\lang -> { lang | name = value }) model
 
+        UpdateLanguageCodeInput value ->
+            updateWorkingLanguage (\lang -> { lang | languageCode = String.trim value }) model
+
         UpdateDictsList newDicts ->
             updateWorkingLanguage (\lang -> { lang | dicts = newDicts }) model
 
@@ -400,6 +405,7 @@ update msg model =
                                 createRequest : LanguageCreateRequest
                                 createRequest =
                                     { name = workingLanguage.name
+                                    , languageCode = workingLanguage.languageCode
                                     , dicts = workingLanguage.dicts
                                     , ttsRate = workingLanguage.ttsRate
                                     , ttsPitch = workingLanguage.ttsPitch
@@ -736,6 +742,14 @@ viewLanguageForm mode { originalLanguage, workingLanguage, currentDictInput, tts
                         , placeholder = "Enter language name..."
                         , compact = False
                         }
+                    , inputWithTooltipC
+                        { label = "Language Code"
+                        , tooltip = "BCP-47 language tag used for parsing, translation and TTS (e.g., en, fr, zh-Hant)"
+                        , toMsg = UpdateLanguageCodeInput
+                        , value_ = workingLanguage.languageCode
+                        , placeholder = "en, fr, zh-Hant..."
+                        , compact = False
+                        }
                     , stringListC
                         { label = "Dictionaries"
                         , items = workingLanguage.dicts
@@ -834,7 +848,7 @@ viewDictionarySelectorRow selectedDictPath availableDictionaries dictionariesLoa
         DictionariesLoadedSuccess ->
             if List.isEmpty availableDictionaries then
                 div [ class "error" ]
-                    [ text "No dictionaries found. Please add .ifo files to the dictionaries directory." ]
+                    [ text "No dictionaries found. Please add StarDict (.ifo), DSL, MDict (.mdx) or Yomitan (.zip) dictionaries to the dictionaries directory." ]
 
             else
                 div []
@@ -889,6 +903,7 @@ viewParserDropdown selectedParser =
         , options =
             [ { value = "base_spacy", label = "Plain spaCy" }
             , { value = "enhanced_japanese", label = "Enhanced Japanese" }
+            , { value = "unicode", label = "Built-in (no NLP service, space-delimited languages)" }
             ]
         , value_ =
             if String.isEmpty selectedParser then
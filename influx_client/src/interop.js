@@ -16,6 +16,16 @@ class ShadowElement extends HTMLElement {
     constructor() {
         super();
         this.attachShadow({ mode: 'open' });
+        // cross-references in definitions are rewritten by the server to influx://lookup/<word>
+        this.shadowRoot.addEventListener('click', (event) => {
+            const link = event.target.closest && event.target.closest('a[href^="influx://lookup/"]');
+            if (!link) {
+                return;
+            }
+            event.preventDefault();
+            const word = decodeURIComponent(link.getAttribute('href').slice('influx://lookup/'.length));
+            this.dispatchEvent(new CustomEvent('dictionary-lookup', { detail: { word }, bubbles: true }));
+        });
     }
 
     attributeChangedCallback(name, oldValue, newValue) {
@@ -93,6 +103,10 @@ export const onReady = ({ app, env }) => {
                     return;
                 case "SPEAK":
                     let utterance = new SpeechSynthesisUtterance(data.text);
+                    // BCP-47 code of the language, so the browser picks a matching voice when none is set
+                    if (data.lang) {
+                        utterance.lang = data.lang;
+                    }
                     if (data.voice) {
                         let voice = speechSynthesis.getVoices().find(v => v.name === data.voice);
                         if (voice) {
@@ -116,6 +130,9 @@ export const onReady = ({ app, env }) => {
                     // Small delay to ensure cancel completes before starting new speech
                     setTimeout(() => {
                         let utterance = new SpeechSynthesisUtterance(data.text);
+                        if (data.lang) {
+                            utterance.lang = data.lang;
+                        }
                         if (data.voice) {
                             let voice = speechSynthesis.getVoices().find(v => v.name === data.voice);
                             if (voice) {
@@ -248,9 +265,12 @@ function processAllResourcePaths(htmlContent, baseUrl) {
 
 function isRelativePath(path) {
     // Check if path is relative (not starting with http://, https://, //, or /)
+    // nor with any other scheme, e.g. influx://lookup/ links, nor an in-page #anchor
     return path &&
         !path.startsWith('http://') &&
         !path.startsWith('https://') &&
         !path.startsWith('//') &&
-        !path.startsWith('/');
+        !path.startsWith('/') &&
+        !path.startsWith('#') &&
+        !/^[a-zA-Z][a-zA-Z0-9+.-]*:/.test(path);
 }
@@ -0,0 +1,58 @@
+-- Token status transitions, used for vocabulary growth charts
+CREATE TABLE IF NOT EXISTS token_status_history (
+    id BIGSERIAL PRIMARY KEY,
+    lang_id BIGINT NOT NULL REFERENCES language (id) ON DELETE CASCADE,
+
+    -- no foreign key on token_id so that history survives token deletion
+    token_id BIGINT NOT NULL,
+    orthography TEXT NOT NULL,
+    from_status token_status, -- NULL when the token was created
+    to_status token_status NOT NULL, -- UNMARKED when the token was deleted
+    is_backfilled BOOLEAN NOT NULL DEFAULT FALSE,
+
+    changed_ts TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX idx_token_status_history_lang_ts ON token_status_history (lang_id, changed_ts);
+CREATE INDEX idx_token_status_history_token ON token_status_history (token_id);
+
+-- tokens that predate the history are created at created_ts. one updated since is taken to have reached its
+-- current status at updated_ts, starting UNMARKED, so it is not counted as learned the day it was created
+INSERT INTO token_status_history (lang_id, token_id, orthography, from_status, to_status, is_backfilled, changed_ts)
+SELECT t.lang_id, t.id, t.orthography, entry.from_status, entry.to_status, TRUE, entry.changed_ts
+FROM token t
+CROSS JOIN LATERAL (
+    VALUES
+        (NULL::token_status, CASE WHEN t.updated_ts > t.created_ts THEN 'UNMARKED'::token_status ELSE t.status END, t.created_ts),
+        ('UNMARKED'::token_status, t.status, t.updated_ts)
+) AS entry (from_status, to_status, changed_ts)
+WHERE entry.from_status IS NULL OR (t.updated_ts > t.created_ts AND t.status <> 'UNMARKED');
+
+CREATE OR REPLACE FUNCTION record_token_status_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO token_status_history (lang_id, token_id, orthography, from_status, to_status)
+        VALUES (NEW.lang_id, NEW.id, NEW.orthography, NULL, NEW.status);
+        RETURN NEW;
+    ELSIF TG_OP = 'UPDATE' THEN
+        IF NEW.status IS DISTINCT FROM OLD.status THEN
+            INSERT INTO token_status_history (lang_id, token_id, orthography, from_status, to_status)
+            VALUES (NEW.lang_id, NEW.id, NEW.orthography, OLD.status, NEW.status);
+        END IF;
+        RETURN NEW;
+    ELSE
+        -- skip when the whole language is being deleted
+        IF EXISTS (SELECT 1 FROM language WHERE id = OLD.lang_id) THEN
+            INSERT INTO token_status_history (lang_id, token_id, orthography, from_status, to_status)
+            VALUES (OLD.lang_id, OLD.id, OLD.orthography, OLD.status, 'UNMARKED');
+        END IF;
+        RETURN OLD;
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_token_status_change_token
+AFTER INSERT OR UPDATE OR DELETE ON token
+FOR EACH ROW
+EXECUTE FUNCTION record_token_status_change();
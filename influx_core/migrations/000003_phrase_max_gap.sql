@@ -0,0 +1,2 @@
+-- NULL means contiguous; '*' in orthography_seq is a wildcard slot
+ALTER TABLE phrase ADD COLUMN max_gap INTEGER CHECK (max_gap IS NULL OR max_gap >= 0);
@@ -0,0 +1,2 @@
+-- match phrases against lemma sequences as well as orthography sequences
+ALTER TABLE phrase ADD COLUMN match_by_lemma BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,28 @@
+-- Background tokenisation, one job per document, re-queued whenever the document or its parser config changes
+CREATE TYPE tokenisation_job_status AS ENUM (
+    'QUEUED',
+    'RUNNING',
+    'DONE',
+    'FAILED'
+);
+
+CREATE TABLE IF NOT EXISTS tokenisation_job (
+    id BIGSERIAL PRIMARY KEY,
+    document_id BIGINT NOT NULL REFERENCES document (id) ON DELETE CASCADE,
+
+    status tokenisation_job_status NOT NULL DEFAULT 'QUEUED',
+    attempts INTEGER NOT NULL DEFAULT 0, -- runs since the job was last queued
+    last_error TEXT,
+
+    created_ts TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    updated_ts TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+
+    UNIQUE(document_id)
+);
+
+CREATE INDEX idx_tokenisation_job_status ON tokenisation_job (status, updated_ts);
+
+CREATE TRIGGER set_updated_ts_tokenisation_job
+BEFORE UPDATE ON tokenisation_job
+FOR EACH ROW
+EXECUTE FUNCTION set_updated_ts();
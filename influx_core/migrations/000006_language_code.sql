@@ -0,0 +1,30 @@
+-- BCP-47 tag, 'und' when it could not be inferred from the name
+ALTER TABLE language ADD COLUMN language_code TEXT NOT NULL DEFAULT 'und';
+
+-- languages that predate language_code get it inferred from their name
+UPDATE language
+SET language_code = CASE lower(trim(name))
+    WHEN 'english' THEN 'en'
+    WHEN 'french' THEN 'fr'
+    WHEN 'français' THEN 'fr'
+    WHEN 'german' THEN 'de'
+    WHEN 'deutsch' THEN 'de'
+    WHEN 'spanish' THEN 'es'
+    WHEN 'español' THEN 'es'
+    WHEN 'italian' THEN 'it'
+    WHEN 'italiano' THEN 'it'
+    WHEN 'portuguese' THEN 'pt'
+    WHEN 'português' THEN 'pt'
+    WHEN 'dutch' THEN 'nl'
+    WHEN 'russian' THEN 'ru'
+    WHEN 'japanese' THEN 'ja'
+    WHEN '日本語' THEN 'ja'
+    WHEN 'korean' THEN 'ko'
+    WHEN '한국어' THEN 'ko'
+    WHEN 'mandarin' THEN 'zh-Hant'
+    WHEN 'chinese' THEN 'zh'
+    WHEN '中文' THEN 'zh'
+    WHEN 'cantonese' THEN 'yue'
+    ELSE 'und'
+END
+WHERE language_code = 'und';
@@ -0,0 +1,3 @@
+-- version reported by the tokeniser, entries from other versions are not used.
+-- empty for entries cached while the version was unknown, which are replaced once it is known
+ALTER TABLE annotated_document_cache ADD COLUMN parser_version TEXT NOT NULL DEFAULT '';
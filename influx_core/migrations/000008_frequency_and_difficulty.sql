@@ -0,0 +1,27 @@
+-- Lemma frequency list per language, rank 1 is the most frequent lemma
+CREATE TABLE IF NOT EXISTS lemma_frequency (
+    lang_id BIGINT NOT NULL REFERENCES language (id) ON DELETE CASCADE,
+    lemma TEXT NOT NULL,
+    rank INTEGER NOT NULL,
+
+    PRIMARY KEY (lang_id, lemma)
+);
+
+-- Difficulty estimate of a document for the learner, see nlp::difficulty
+CREATE TABLE IF NOT EXISTS document_difficulty (
+    document_id BIGINT PRIMARY KEY REFERENCES document (id) ON DELETE CASCADE,
+
+    score DOUBLE PRECISION NOT NULL, -- 0 (easiest) to 100
+    mean_sentence_length DOUBLE PRECISION NOT NULL,
+    rare_lemma_ratio DOUBLE PRECISION, -- NULL when the language has no frequency list
+    mean_dependency_depth DOUBLE PRECISION, -- NULL when the parser gives no dependencies
+    unknown_token_ratio DOUBLE PRECISION NOT NULL,
+
+    created_ts TIMESTAMPTZ NOT NULL DEFAULT current_timestamp,
+    updated_ts TIMESTAMPTZ NOT NULL DEFAULT current_timestamp
+);
+
+CREATE TRIGGER set_updated_ts_document_difficulty
+BEFORE UPDATE ON document_difficulty
+FOR EACH ROW
+EXECUTE FUNCTION set_updated_ts();
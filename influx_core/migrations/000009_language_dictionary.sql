@@ -0,0 +1,9 @@
+-- Dictionaries enabled for each language, looked up in ascending priority
+CREATE TABLE IF NOT EXISTS language_dictionary (
+    lang_id BIGINT NOT NULL REFERENCES language (id) ON DELETE CASCADE,
+    dict_path TEXT NOT NULL, -- .ifo path relative to the dictionaries directory
+    priority INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+
+    PRIMARY KEY (lang_id, dict_path)
+);
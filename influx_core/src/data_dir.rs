@@ -13,6 +13,16 @@ pub fn get_dictionaries_dir() -> Result<PathBuf> {
     Ok(get_data_dir()?.join("dictionaries").join("stardicts"))
 }
 
+/// saved phrase tries, one file per language. safe to delete, they are rebuilt from the database
+pub fn get_phrase_tries_dir() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join("phrase_tries"))
+}
+
+/// sled caches of StarDict dictionaries, one directory per dictionary. safe to delete, they are rebuilt on load
+pub fn get_stardict_cache_dir() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join("stardict_cache"))
+}
+
 pub fn init_data_directories() -> Result<()> {
     let data_dir = get_data_dir()?;
     let dictionaries_dir = get_dictionaries_dir()?;
@@ -28,7 +38,8 @@ pub fn init_data_directories() -> Result<()> {
     Ok(())
 }
 
+/// absolute path of a dict_path from a client, which has to stay inside the dictionaries directory
 pub fn resolve_dict_path(relative_path: &str) -> Result<PathBuf> {
     let dictionaries_dir = get_dictionaries_dir()?;
-    Ok(dictionaries_dir.join(relative_path))
+    crate::integration::dictionary_registry::dictionary_file_path(&dictionaries_dir, relative_path)
 }
@@ -0,0 +1,245 @@
+///! which installed dictionaries each language looks words up in, and in what order
+use super::*;
+use crate::db::InfluxResourceId;
+use crate::prelude::*;
+use DB::*;
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct LanguageDictionary {
+    pub lang_id: InfluxResourceId,
+    /// .ifo path relative to the dictionaries directory, see integration::dictionary_registry
+    pub dict_path: String,
+    /// lower is looked up first
+    pub priority: i32,
+    pub enabled: bool,
+}
+
+impl DB {
+    /// the language's dictionaries, highest priority first, including disabled ones
+    pub async fn get_language_dictionaries(
+        &self,
+        lang_id: InfluxResourceId,
+    ) -> Result<Vec<LanguageDictionary>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query_as!(
+                    LanguageDictionary,
+                    r#"
+                        SELECT lang_id as "lang_id: InfluxResourceId", dict_path, priority, enabled
+                        FROM language_dictionary
+                        WHERE lang_id = $1
+                        ORDER BY priority ASC, dict_path ASC
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(records)
+            }
+        }
+    }
+
+    /// replace the language's dictionaries with (dict_path, enabled) pairs, prioritised in the order given.
+    /// a repeated dict_path keeps its first position
+    pub async fn set_language_dictionaries(
+        &self,
+        lang_id: InfluxResourceId,
+        dictionaries: &[(String, bool)],
+    ) -> Result<Vec<LanguageDictionary>> {
+        let mut dict_paths: Vec<String> = vec![];
+        let mut enabled: Vec<bool> = vec![];
+        for (dict_path, is_enabled) in dictionaries {
+            if !dict_paths.contains(dict_path) {
+                dict_paths.push(dict_path.clone());
+                enabled.push(*is_enabled);
+            }
+        }
+
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let mut tx = pool.begin().await?;
+                sqlx::query!(
+                    r#"
+                        DELETE FROM language_dictionary
+                        WHERE lang_id = $1
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query!(
+                    r#"
+                        INSERT INTO language_dictionary (lang_id, dict_path, priority, enabled)
+                        SELECT $1, dict_path, (priority - 1)::int, enabled
+                        FROM UNNEST($2::text[], $3::bool[]) WITH ORDINALITY AS entry(dict_path, enabled, priority)
+                    "#,
+                    lang_id.as_i64()?,
+                    &dict_paths,
+                    &enabled
+                )
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+            }
+        }
+        self.get_language_dictionaries(lang_id).await
+    }
+
+    /// enable a dictionary for the language after its others, e.g. once it is installed.
+    /// a dictionary the language already has keeps its place
+    pub async fn append_language_dictionary(
+        &self,
+        lang_id: InfluxResourceId,
+        dict_path: &str,
+    ) -> Result<Vec<LanguageDictionary>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO language_dictionary (lang_id, dict_path, priority, enabled)
+                        SELECT $1, $2, COALESCE(MAX(priority) + 1, 0), TRUE
+                        FROM language_dictionary
+                        WHERE lang_id = $1
+                        ON CONFLICT (lang_id, dict_path) DO NOTHING
+                    "#,
+                    lang_id.as_i64()?,
+                    dict_path
+                )
+                .execute(pool.as_ref())
+                .await?;
+            }
+        }
+        self.get_language_dictionaries(lang_id).await
+    }
+
+    /// drop every language's references to the dictionaries in a removed directory
+    pub async fn remove_dictionary_directory(&self, directory_name: &str) -> Result<u64> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let result = sqlx::query!(
+                    r#"
+                        DELETE FROM language_dictionary
+                        WHERE starts_with(dict_path, $1)
+                    "#,
+                    format!("{}/", directory_name)
+                )
+                .execute(pool.as_ref())
+                .await?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    }
+
+    /// point every language's references to the dictionaries in a renamed directory at its new name
+    pub async fn rename_dictionary_directory(
+        &self,
+        directory_name: &str,
+        new_directory_name: &str,
+    ) -> Result<u64> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let result = sqlx::query!(
+                    r#"
+                        UPDATE language_dictionary
+                        SET dict_path = $2 || substr(dict_path, length($1) + 1)
+                        WHERE starts_with(dict_path, $1)
+                    "#,
+                    format!("{}/", directory_name),
+                    format!("{}/", new_directory_name)
+                )
+                .execute(pool.as_ref())
+                .await?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{test_language, TestDb};
+    use expect_test::expect;
+
+    fn render(dictionaries: &[LanguageDictionary]) -> String {
+        dictionaries
+            .iter()
+            .map(|dictionary| {
+                format!(
+                    "{} {} enabled={}",
+                    dictionary.priority, dictionary.dict_path, dictionary.enabled
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[tokio::test]
+    async fn test_language_dictionaries() {
+        let test_db = TestDb::new().await.unwrap();
+        let db = &test_db.db;
+        let lang_id = db
+            .create_language(test_language("French", "fr", "unicode"))
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+        assert_eq!(
+            db.get_language_dictionaries(lang_id.clone()).await.unwrap(),
+            vec![]
+        );
+
+        let set = db
+            .set_language_dictionaries(
+                lang_id.clone(),
+                &[
+                    ("fr-en/fr-en.ifo".to_string(), true),
+                    ("wiktionary/fr.ifo".to_string(), false),
+                    ("fr-en/fr-en.ifo".to_string(), false),
+                    ("larousse/larousse.ifo".to_string(), true),
+                ],
+            )
+            .await
+            .unwrap();
+        expect![[r#"
+            0 fr-en/fr-en.ifo enabled=true
+            1 wiktionary/fr.ifo enabled=false
+            2 larousse/larousse.ifo enabled=true"#]]
+        .assert_eq(&render(&set));
+
+        let set = db
+            .set_language_dictionaries(
+                lang_id.clone(),
+                &[("larousse/larousse.ifo".to_string(), true)],
+            )
+            .await
+            .unwrap();
+        expect!["0 larousse/larousse.ifo enabled=true"].assert_eq(&render(&set));
+
+        db.append_language_dictionary(lang_id.clone(), "fr-en/fr-en.ifo")
+            .await
+            .unwrap();
+        let set = db
+            .append_language_dictionary(lang_id.clone(), "larousse/larousse.ifo")
+            .await
+            .unwrap();
+        expect![[r#"
+            0 larousse/larousse.ifo enabled=true
+            1 fr-en/fr-en.ifo enabled=true"#]]
+        .assert_eq(&render(&set));
+
+        assert_eq!(
+            db.rename_dictionary_directory("fr-en", "French")
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(db.remove_dictionary_directory("larousse").await.unwrap(), 1);
+        expect!["1 French/fr-en.ifo enabled=true"].assert_eq(&render(
+            &db.get_language_dictionaries(lang_id).await.unwrap(),
+        ));
+    }
+}
@@ -62,6 +62,57 @@ pub struct DocPackage {
     pub language: crate::db::models::lang::Language,
 }
 
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub enum AnnotationCacheScope {
+    Document(InfluxResourceId),
+    Language(InfluxResourceId),
+    /// entries made with this parser config, in any language
+    ParserConfig(crate::db::models::lang::ParserConfig),
+    Everything,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct DocumentCacheStats {
+    pub document_id: InfluxResourceId,
+    pub title: String,
+    pub entries: i64,
+    /// stored size of the cached annotations
+    pub bytes: i64,
+    pub last_updated_ts: DateTime<Utc>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct LanguageCacheStats {
+    pub lang_id: InfluxResourceId,
+    pub lang_name: String,
+    pub entries: i64,
+    pub bytes: i64,
+    pub documents: Vec<DocumentCacheStats>,
+}
+
+/// what a document's difficulty score is computed from, see nlp::difficulty
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct DifficultyFeatures {
+    /// lexical tokens per sentence
+    pub mean_sentence_length: f64,
+    /// share of tokens whose lemma is rare or missing from the frequency list. None without a frequency list
+    pub rare_lemma_ratio: Option<f64>,
+    /// mean over sentences of the deepest token's distance from the root. None without dependencies
+    pub mean_dependency_depth: Option<f64>,
+    /// share of tokens the learner has not marked, at the time of scoring
+    pub unknown_token_ratio: f64,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct DocumentDifficulty {
+    pub document_id: InfluxResourceId,
+    pub title: String,
+    /// 0 for the easiest text to 100 for the hardest
+    pub score: f64,
+    pub features: DifficultyFeatures,
+    pub updated_ts: DateTime<Utc>,
+}
+
 use DB::*;
 
 impl DB {
@@ -104,7 +155,7 @@ impl DB {
                     r#"
                         SELECT 
                             d.id, d.lang_id, d.title, d.content, d.doc_type, d.tags, d.created_ts, d.updated_ts,
-                            l.name as lang_name, l.dicts as lang_dicts,
+                            l.name as lang_name, l.language_code as lang_language_code, l.dicts as lang_dicts,
                             l.tts_rate as lang_tts_rate, l.tts_pitch as lang_tts_pitch, l.tts_voice as lang_tts_voice
                         FROM document d
                         JOIN language l ON d.lang_id = l.id
@@ -134,6 +185,7 @@ impl DB {
                         language: crate::db::models::lang::Language {
                             id: Some(InfluxResourceId::SerialId(record.lang_id)),
                             name: record.lang_name,
+                            language_code: record.lang_language_code,
                             dicts: record.lang_dicts,
                             tts_rate: record.lang_tts_rate,
                             tts_pitch: record.lang_tts_pitch,
@@ -224,10 +276,14 @@ impl DB {
         }
     }
 
+    /// the cached annotation of this text. with no parser_version, entries of any version match.
+    /// entries cached while the version was unknown are stored with an empty version, so once it is known
+    /// they miss, the text is tokenised again and set_annotated_document_cache overwrites them
     pub async fn get_annotated_document_cache(
         &self,
         document_id: InfluxResourceId,
         text_checksum: &str,
+        parser_version: Option<&str>,
     ) -> Result<Option<serde_json::Value>> {
         match self {
             Postgres { pool } | EmbeddedPostgres { pool, .. } => {
@@ -236,9 +292,11 @@ impl DB {
                         SELECT cached_data
                         FROM annotated_document_cache
                         WHERE document_id = $1 AND text_checksum = $2
+                          AND ($3::text IS NULL OR parser_version = $3)
                     "#,
                     document_id.as_i64()?,
-                    text_checksum
+                    text_checksum,
+                    parser_version
                 )
                 .fetch_optional(pool.as_ref())
                 .await?;
@@ -248,25 +306,247 @@ impl DB {
         }
     }
 
+    /// most recently cached annotated document regardless of checksum, used when the text cannot be re-tokenised
+    pub async fn get_latest_annotated_document_cache(
+        &self,
+        document_id: InfluxResourceId,
+    ) -> Result<Option<serde_json::Value>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let record = sqlx::query!(
+                    r#"
+                        SELECT cached_data
+                        FROM annotated_document_cache
+                        WHERE document_id = $1
+                        ORDER BY updated_ts DESC
+                        LIMIT 1
+                    "#,
+                    document_id.as_i64()?
+                )
+                .fetch_optional(pool.as_ref())
+                .await?;
+
+                Ok(record.map(|r| r.cached_data))
+            }
+        }
+    }
+
+    /// most recently cached annotated document for every document in the language, with its document id
+    pub async fn get_annotated_document_caches_by_lang(
+        &self,
+        lang_id: InfluxResourceId,
+    ) -> Result<Vec<(InfluxResourceId, serde_json::Value)>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query!(
+                    r#"
+                        SELECT DISTINCT ON (c.document_id) c.document_id, c.cached_data
+                        FROM annotated_document_cache c
+                        JOIN document d ON c.document_id = d.id
+                        WHERE d.lang_id = $1
+                        ORDER BY c.document_id, c.updated_ts DESC
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(records
+                    .into_iter()
+                    .map(|r| (InfluxResourceId::SerialId(r.document_id), r.cached_data))
+                    .collect())
+            }
+        }
+    }
+
+    /// cache the annotation of the document's current text, removing entries for texts it superseded
     pub async fn set_annotated_document_cache(
         &self,
         document_id: InfluxResourceId,
         text_checksum: &str,
+        parser_version: &str,
         cached_data: &serde_json::Value,
     ) -> Result<()> {
         match self {
             Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let mut tx = pool.begin().await?;
                 sqlx::query!(
                     r#"
-                        INSERT INTO annotated_document_cache (document_id, text_checksum, cached_data)
-                        VALUES ($1, $2, $3)
+                        INSERT INTO annotated_document_cache (document_id, text_checksum, parser_version, cached_data)
+                        VALUES ($1, $2, $3, $4)
                         ON CONFLICT (document_id, text_checksum)
-                        DO UPDATE SET cached_data = EXCLUDED.cached_data, updated_ts = CURRENT_TIMESTAMP
+                        DO UPDATE SET cached_data = EXCLUDED.cached_data, parser_version = EXCLUDED.parser_version, updated_ts = CURRENT_TIMESTAMP
                     "#,
                     document_id.as_i64()?,
                     text_checksum,
+                    parser_version,
                     cached_data
                 )
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query!(
+                    r#"
+                        DELETE FROM annotated_document_cache
+                        WHERE document_id = $1 AND text_checksum <> $2
+                    "#,
+                    document_id.as_i64()?,
+                    text_checksum
+                )
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// cache entries and their size, per document, grouped by language
+    pub async fn get_annotated_document_cache_stats(&self) -> Result<Vec<LanguageCacheStats>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query!(
+                    r#"
+                        SELECT
+                            d.lang_id, l.name as lang_name, c.document_id, d.title,
+                            COUNT(*) as "entries!",
+                            SUM(pg_column_size(c.cached_data))::bigint as "bytes!",
+                            MAX(c.updated_ts) as "last_updated_ts!"
+                        FROM annotated_document_cache c
+                        JOIN document d ON c.document_id = d.id
+                        JOIN language l ON d.lang_id = l.id
+                        GROUP BY d.lang_id, l.name, c.document_id, d.title
+                        ORDER BY d.lang_id, c.document_id
+                    "#
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                let mut languages: Vec<LanguageCacheStats> = vec![];
+                for record in records {
+                    let lang_id = InfluxResourceId::SerialId(record.lang_id);
+                    if languages
+                        .last()
+                        .is_none_or(|language| language.lang_id != lang_id)
+                    {
+                        languages.push(LanguageCacheStats {
+                            lang_id,
+                            lang_name: record.lang_name,
+                            entries: 0,
+                            bytes: 0,
+                            documents: vec![],
+                        });
+                    }
+                    let language = languages.last_mut().unwrap();
+                    language.entries += record.entries;
+                    language.bytes += record.bytes;
+                    language.documents.push(DocumentCacheStats {
+                        document_id: InfluxResourceId::SerialId(record.document_id),
+                        title: record.title,
+                        entries: record.entries,
+                        bytes: record.bytes,
+                        last_updated_ts: record.last_updated_ts,
+                    });
+                }
+                Ok(languages)
+            }
+        }
+    }
+
+    /// remove cached annotations, returns the number of entries removed
+    pub async fn invalidate_annotated_document_cache(
+        &self,
+        scope: AnnotationCacheScope,
+    ) -> Result<u64> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let result = match scope {
+                    AnnotationCacheScope::Document(document_id) => {
+                        sqlx::query!(
+                            r#"
+                                DELETE FROM annotated_document_cache WHERE document_id = $1
+                            "#,
+                            document_id.as_i64()?
+                        )
+                        .execute(pool.as_ref())
+                        .await?
+                    }
+                    AnnotationCacheScope::Language(lang_id) => {
+                        sqlx::query!(
+                            r#"
+                                DELETE FROM annotated_document_cache c
+                                USING document d
+                                WHERE c.document_id = d.id AND d.lang_id = $1
+                            "#,
+                            lang_id.as_i64()?
+                        )
+                        .execute(pool.as_ref())
+                        .await?
+                    }
+                    AnnotationCacheScope::ParserConfig(parser_config) => {
+                        sqlx::query!(
+                            r#"
+                                DELETE FROM annotated_document_cache WHERE cached_data->'parser_config' = $1
+                            "#,
+                            serde_json::to_value(&parser_config)?
+                        )
+                        .execute(pool.as_ref())
+                        .await?
+                    }
+                    AnnotationCacheScope::Everything => {
+                        sqlx::query!(
+                            r#"
+                                DELETE FROM annotated_document_cache
+                            "#
+                        )
+                        .execute(pool.as_ref())
+                        .await?
+                    }
+                };
+
+                Ok(result.rows_affected())
+            }
+        }
+    }
+
+    /// store the difficulties of several documents in one statement, replacing their previous scores
+    pub async fn set_document_difficulties(
+        &self,
+        difficulties: &[(InfluxResourceId, f64, DifficultyFeatures)],
+    ) -> Result<()> {
+        let mut document_ids = vec![];
+        let mut scores = vec![];
+        let mut mean_sentence_lengths = vec![];
+        let mut rare_lemma_ratios = vec![];
+        let mut mean_dependency_depths = vec![];
+        let mut unknown_token_ratios = vec![];
+        for (document_id, score, features) in difficulties {
+            document_ids.push(document_id.as_i64()?);
+            scores.push(*score);
+            mean_sentence_lengths.push(features.mean_sentence_length);
+            rare_lemma_ratios.push(features.rare_lemma_ratio);
+            mean_dependency_depths.push(features.mean_dependency_depth);
+            unknown_token_ratios.push(features.unknown_token_ratio);
+        }
+
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO document_difficulty (document_id, score, mean_sentence_length, rare_lemma_ratio, mean_dependency_depth, unknown_token_ratio)
+                        SELECT * FROM UNNEST($1::bigint[], $2::float8[], $3::float8[], $4::float8[], $5::float8[], $6::float8[])
+                        ON CONFLICT (document_id)
+                        DO UPDATE SET score = EXCLUDED.score, mean_sentence_length = EXCLUDED.mean_sentence_length,
+                            rare_lemma_ratio = EXCLUDED.rare_lemma_ratio, mean_dependency_depth = EXCLUDED.mean_dependency_depth,
+                            unknown_token_ratio = EXCLUDED.unknown_token_ratio
+                    "#,
+                    &document_ids,
+                    &scores,
+                    &mean_sentence_lengths,
+                    &rare_lemma_ratios as &[Option<f64>],
+                    &mean_dependency_depths as &[Option<f64>],
+                    &unknown_token_ratios
+                )
                 .execute(pool.as_ref())
                 .await?;
 
@@ -274,4 +554,264 @@ impl DB {
             }
         }
     }
+
+    /// stored difficulties of the language's documents, easiest first. unscored documents are left out
+    pub async fn get_document_difficulties(
+        &self,
+        lang_id: InfluxResourceId,
+    ) -> Result<Vec<DocumentDifficulty>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query!(
+                    r#"
+                        SELECT dd.document_id, d.title, dd.score, dd.mean_sentence_length, dd.rare_lemma_ratio,
+                            dd.mean_dependency_depth, dd.unknown_token_ratio, dd.updated_ts
+                        FROM document_difficulty dd
+                        JOIN document d ON dd.document_id = d.id
+                        WHERE d.lang_id = $1
+                        ORDER BY dd.score ASC, dd.document_id ASC
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(records
+                    .into_iter()
+                    .map(|record| DocumentDifficulty {
+                        document_id: InfluxResourceId::SerialId(record.document_id),
+                        title: record.title,
+                        score: record.score,
+                        features: DifficultyFeatures {
+                            mean_sentence_length: record.mean_sentence_length,
+                            rare_lemma_ratio: record.rare_lemma_ratio,
+                            mean_dependency_depth: record.mean_dependency_depth,
+                            unknown_token_ratio: record.unknown_token_ratio,
+                        },
+                        updated_ts: record.updated_ts,
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::lang::ParserConfig;
+    use crate::test_utils::{test_language, TestDb};
+    use expect_test::expect;
+    use serde_json::json;
+
+    fn render(languages: &[LanguageCacheStats]) -> String {
+        languages
+            .iter()
+            .map(|language| {
+                let documents = language
+                    .documents
+                    .iter()
+                    .map(|document| format!("  {} entries={}", document.title, document.entries))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!(
+                    "{} entries={}\n{}",
+                    language.lang_name, language.entries, documents
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[tokio::test]
+    async fn test_annotated_document_cache_management() {
+        let test_db = TestDb::new().await.unwrap();
+        let db = &test_db.db;
+        let mut document_ids = vec![];
+        for (lang_name, language_code, which_parser) in
+            [("English", "en", "base_spacy"), ("French", "fr", "unicode")]
+        {
+            let lang = db
+                .create_language(test_language(lang_name, language_code, which_parser))
+                .await
+                .unwrap();
+            for title in ["a", "b"] {
+                let document = db
+                    .create_document(DocumentCreateRequest {
+                        lang_id: lang.id.clone().unwrap(),
+                        title: format!("{} {}", lang_name, title),
+                        content: String::new(),
+                        doc_type: "Text".to_string(),
+                        tags: vec![],
+                    })
+                    .await
+                    .unwrap();
+                let cached_data = json!({
+                    "parser_config": lang.parser_config,
+                });
+                db.set_annotated_document_cache(
+                    document.id.clone().unwrap(),
+                    "checksum1",
+                    "v1",
+                    &cached_data,
+                )
+                .await
+                .unwrap();
+                document_ids.push(document.id.unwrap());
+            }
+        }
+
+        // editing the text supersedes the old entry
+        db.set_annotated_document_cache(document_ids[0].clone(), "checksum2", "v1", &json!({}))
+            .await
+            .unwrap();
+        assert!(db
+            .get_annotated_document_cache(document_ids[0].clone(), "checksum1", None)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .get_annotated_document_cache(document_ids[0].clone(), "checksum2", Some("v2"))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .get_annotated_document_cache(document_ids[0].clone(), "checksum2", Some("v1"))
+            .await
+            .unwrap()
+            .is_some());
+
+        // an entry cached while the version was unknown is used until the version is known, then replaced
+        db.set_annotated_document_cache(document_ids[1].clone(), "checksum1", "", &json!({}))
+            .await
+            .unwrap();
+        assert!(db
+            .get_annotated_document_cache(document_ids[1].clone(), "checksum1", None)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(db
+            .get_annotated_document_cache(document_ids[1].clone(), "checksum1", Some("v1"))
+            .await
+            .unwrap()
+            .is_none());
+        db.set_annotated_document_cache(document_ids[1].clone(), "checksum1", "v1", &json!({}))
+            .await
+            .unwrap();
+        assert!(db
+            .get_annotated_document_cache(document_ids[1].clone(), "checksum1", Some("v1"))
+            .await
+            .unwrap()
+            .is_some());
+        expect![[r#"
+            English entries=2
+              English a entries=1
+              English b entries=1
+            French entries=2
+              French a entries=1
+              French b entries=1"#]]
+        .assert_eq(&render(
+            &db.get_annotated_document_cache_stats().await.unwrap(),
+        ));
+
+        let removed = db
+            .invalidate_annotated_document_cache(AnnotationCacheScope::Document(
+                document_ids[0].clone(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        let removed = db
+            .invalidate_annotated_document_cache(AnnotationCacheScope::ParserConfig(ParserConfig {
+                which_parser: "unicode".to_string(),
+                parser_args: Default::default(),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        expect![[r#"
+            English entries=1
+              English b entries=1"#]]
+        .assert_eq(&render(
+            &db.get_annotated_document_cache_stats().await.unwrap(),
+        ));
+
+        let lang_id = db
+            .get_document_by_id(document_ids[1].clone())
+            .await
+            .unwrap()
+            .unwrap()
+            .lang_id;
+        let removed = db
+            .invalidate_annotated_document_cache(AnnotationCacheScope::Language(lang_id))
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert_eq!(
+            db.invalidate_annotated_document_cache(AnnotationCacheScope::Everything)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_document_difficulties() {
+        let test_db = TestDb::new().await.unwrap();
+        let db = &test_db.db;
+        let lang_id = db
+            .create_language(test_language("English", "en", "unicode"))
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+        let mut document_ids = vec![];
+        for title in ["easy", "hard"] {
+            let document = db
+                .create_document(DocumentCreateRequest {
+                    lang_id: lang_id.clone(),
+                    title: title.to_string(),
+                    content: String::new(),
+                    doc_type: "Text".to_string(),
+                    tags: vec![],
+                })
+                .await
+                .unwrap();
+            document_ids.push(document.id.unwrap());
+        }
+        let features = |mean_sentence_length| DifficultyFeatures {
+            mean_sentence_length,
+            rare_lemma_ratio: None,
+            mean_dependency_depth: Some(2.0),
+            unknown_token_ratio: 0.5,
+        };
+        let scores = |difficulties: Vec<DocumentDifficulty>| {
+            difficulties
+                .into_iter()
+                .map(|difficulty| (difficulty.title, difficulty.score))
+                .collect::<Vec<_>>()
+        };
+
+        db.set_document_difficulties(&[
+            (document_ids[0].clone(), 10.0, features(5.0)),
+            (document_ids[1].clone(), 90.0, features(30.0)),
+        ])
+        .await
+        .unwrap();
+        assert_eq!(
+            scores(db.get_document_difficulties(lang_id.clone()).await.unwrap()),
+            vec![("easy".to_string(), 10.0), ("hard".to_string(), 90.0)]
+        );
+
+        // scoring again replaces the previous score
+        db.set_document_difficulties(&[(document_ids[0].clone(), 95.0, features(40.0))])
+            .await
+            .unwrap();
+        let difficulties = db.get_document_difficulties(lang_id).await.unwrap();
+        assert_eq!(difficulties[1].features, features(40.0));
+        assert_eq!(
+            scores(difficulties),
+            vec![("hard".to_string(), 90.0), ("easy".to_string(), 95.0)]
+        );
+    }
 }
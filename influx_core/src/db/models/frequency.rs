@@ -0,0 +1,261 @@
+///! lemma frequency lists, one per language, ranking lemmas from the most frequent
+use super::*;
+use crate::db::InfluxResourceId;
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use DB::*;
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct RankedLemma {
+    pub lemma: String,
+    pub rank: i32,
+}
+
+impl DB {
+    /// replace the language's frequency list. lemmas are lowercased and ranked by position, from 1.
+    /// empty and repeated lemmas are dropped before ranking, so ranks have no gaps. returns the number of lemmas stored
+    pub async fn replace_frequency_list(
+        &self,
+        lang_id: InfluxResourceId,
+        lemmas: &[String],
+    ) -> Result<u64> {
+        let mut seen = BTreeSet::new();
+        let ranked = lemmas
+            .iter()
+            .map(|lemma| lemma.trim().to_lowercase())
+            .filter(|lemma| !lemma.is_empty() && seen.insert(lemma.clone()))
+            .zip(1..)
+            .map(|(lemma, rank)| RankedLemma { lemma, rank })
+            .collect::<Vec<_>>();
+        self.replace_ranked_frequency_list(lang_id, &ranked).await
+    }
+
+    /// replace the language's frequency list, keeping the ranks given, e.g. by a rank file. lemmas are lowercased,
+    /// empty ones dropped, and a lemma given several times keeps its best rank. returns the number of lemmas stored
+    pub async fn replace_ranked_frequency_list(
+        &self,
+        lang_id: InfluxResourceId,
+        ranked: &[RankedLemma],
+    ) -> Result<u64> {
+        let mut best: BTreeMap<String, i32> = BTreeMap::new();
+        for RankedLemma { lemma, rank } in ranked {
+            let lemma = lemma.trim().to_lowercase();
+            if lemma.is_empty() {
+                continue;
+            }
+            best.entry(lemma)
+                .and_modify(|best_rank| *best_rank = (*best_rank).min(*rank))
+                .or_insert(*rank);
+        }
+        let (lemmas, ranks): (Vec<String>, Vec<i32>) = best.into_iter().unzip();
+
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let mut tx = pool.begin().await?;
+                sqlx::query!(
+                    r#"
+                        DELETE FROM lemma_frequency
+                        WHERE lang_id = $1
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .execute(&mut *tx)
+                .await?;
+                let result = sqlx::query!(
+                    r#"
+                        INSERT INTO lemma_frequency (lang_id, lemma, rank)
+                        SELECT $1, lemma, rank
+                        FROM UNNEST($2::text[], $3::int[]) AS entry(lemma, rank)
+                    "#,
+                    lang_id.as_i64()?,
+                    &lemmas,
+                    &ranks
+                )
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    }
+
+    /// number of lemmas in the language's frequency list, 0 when it has none
+    pub async fn frequency_list_size(&self, lang_id: InfluxResourceId) -> Result<i64> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let record = sqlx::query!(
+                    r#"
+                        SELECT COUNT(*) as "count!"
+                        FROM lemma_frequency
+                        WHERE lang_id = $1
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .fetch_one(pool.as_ref())
+                .await?;
+
+                Ok(record.count)
+            }
+        }
+    }
+
+    /// the most frequent lemmas the learner has no token for, or only an UNMARKED one, most frequent first
+    pub async fn query_frequent_unknown_lemmas(
+        &self,
+        lang_id: InfluxResourceId,
+        limit: i64,
+    ) -> Result<Vec<RankedLemma>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query_as!(
+                    RankedLemma,
+                    r#"
+                        SELECT f.lemma, f.rank
+                        FROM lemma_frequency f
+                        LEFT JOIN token t ON t.lang_id = f.lang_id AND t.orthography = f.lemma
+                        WHERE f.lang_id = $1 AND (t.id IS NULL OR t.status = 'UNMARKED')
+                        ORDER BY f.rank
+                        LIMIT $2
+                    "#,
+                    lang_id.as_i64()?,
+                    limit
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(records)
+            }
+        }
+    }
+
+    /// ranks of the lemmas in the language's frequency list. lemmas not in the list are left out
+    pub async fn get_lemma_ranks(
+        &self,
+        lang_id: InfluxResourceId,
+        lemmas: &BTreeSet<String>,
+    ) -> Result<BTreeMap<String, i32>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query!(
+                    r#"
+                        SELECT lemma, rank
+                        FROM lemma_frequency
+                        WHERE lang_id = $1 AND lemma = ANY($2)
+                    "#,
+                    lang_id.as_i64()?,
+                    &lemmas.iter().cloned().collect::<Vec<String>>()
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(records
+                    .into_iter()
+                    .map(|record| (record.lemma, record.rank))
+                    .collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::vocab::{Token, TokenStatus};
+    use crate::test_utils::{test_language, TestDb};
+    use maplit::btreemap;
+
+    #[tokio::test]
+    async fn test_frequency_list() {
+        let test_db = TestDb::new().await.unwrap();
+        let db = &test_db.db;
+        let lang_id = db
+            .create_language(test_language("English", "en", "unicode"))
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+        assert_eq!(db.frequency_list_size(lang_id.clone()).await.unwrap(), 0);
+
+        let lemmas = ["the", "Be", "", "to", "the", "of"]
+            .into_iter()
+            .map(|lemma| lemma.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            db.replace_frequency_list(lang_id.clone(), &lemmas)
+                .await
+                .unwrap(),
+            4
+        );
+        let wanted = ["be", "of", "zebra"]
+            .into_iter()
+            .map(|lemma| lemma.to_string())
+            .collect();
+        assert_eq!(
+            db.get_lemma_ranks(lang_id.clone(), &wanted).await.unwrap(),
+            btreemap! {"be".to_string() => 2, "of".to_string() => 4}
+        );
+
+        // be is known, the others have no token
+        db.create_token(Token::fancier_token(
+            lang_id.clone(),
+            "be",
+            "",
+            "",
+            TokenStatus::KNOWN,
+        ))
+        .await
+        .unwrap();
+        let unknown = |limit| db.query_frequent_unknown_lemmas(lang_id.clone(), limit);
+        assert_eq!(
+            unknown(2).await.unwrap(),
+            vec![
+                RankedLemma {
+                    lemma: "the".to_string(),
+                    rank: 1
+                },
+                RankedLemma {
+                    lemma: "to".to_string(),
+                    rank: 3
+                },
+            ]
+        );
+        assert_eq!(
+            unknown(10)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|ranked| ranked.lemma)
+                .collect::<Vec<_>>(),
+            vec!["the", "to", "of"]
+        );
+
+        // explicit ranks are kept, gaps included, and a repeated lemma keeps its best rank
+        let ranked = [("The", 1), ("of", 4), ("be", 7), ("the", 9)]
+            .into_iter()
+            .map(|(lemma, rank)| RankedLemma {
+                lemma: lemma.to_string(),
+                rank,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            db.replace_ranked_frequency_list(lang_id.clone(), &ranked)
+                .await
+                .unwrap(),
+            3
+        );
+        assert_eq!(
+            db.get_lemma_ranks(lang_id.clone(), &wanted).await.unwrap(),
+            btreemap! {"be".to_string() => 7, "of".to_string() => 4}
+        );
+
+        db.replace_frequency_list(lang_id.clone(), &["zebra".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(db.frequency_list_size(lang_id.clone()).await.unwrap(), 1);
+        assert_eq!(
+            db.get_lemma_ranks(lang_id, &wanted).await.unwrap(),
+            btreemap! {"zebra".to_string() => 1}
+        );
+    }
+}
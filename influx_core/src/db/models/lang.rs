@@ -27,6 +27,8 @@ pub struct ParserConfig {
 pub struct Language {
     pub id: Option<InfluxResourceId>,
     pub name: String,
+    /// BCP-47 tag, e.g. "fr" or "zh-Hant", used by the tokeniser, translators and TTS
+    pub language_code: String,
     pub dicts: Vec<String>,
     pub tts_rate: Option<f64>,
     pub tts_pitch: Option<f64>,
@@ -40,6 +42,7 @@ pub struct Language {
 pub struct LanguageInDB {
     pub id: InfluxResourceId,
     pub name: String,
+    pub language_code: String,
     pub dicts: Vec<String>,
     pub tts_rate: Option<f64>,
     pub tts_pitch: Option<f64>,
@@ -54,6 +57,7 @@ impl From<LanguageInDB> for Language {
         Language {
             id: Some(db_entry.id),
             name: db_entry.name,
+            language_code: db_entry.language_code,
             dicts: db_entry.dicts,
             tts_rate: db_entry.tts_rate,
             tts_pitch: db_entry.tts_pitch,
@@ -65,6 +69,88 @@ impl From<LanguageInDB> for Language {
     }
 }
 
+impl Language {
+    /// the language subtag alone, e.g. "zh" for "zh-Hant-TW"
+    pub fn primary_language_subtag(&self) -> &str {
+        self.language_code
+            .split('-')
+            .next()
+            .unwrap_or(&self.language_code)
+    }
+}
+
+/// check that `code` is a well-formed BCP-47 language tag and give its subtags their conventional case,
+/// e.g. "zh_hant_tw" becomes "zh-Hant-TW". grandfathered tags like "i-klingon" are not accepted, nor are
+/// 5-8 letter language subtags, which are well-formed but unregistered and usually a language name by mistake
+pub fn normalise_language_code(code: &str) -> Result<String> {
+    let invalid = || anyhow::anyhow!("Invalid BCP-47 language code: {:?}", code);
+    let is_alpha = |subtag: &str| subtag.chars().all(|c| c.is_ascii_alphabetic());
+    let is_digit = |subtag: &str| subtag.chars().all(|c| c.is_ascii_digit());
+    let is_alphanumeric = |subtag: &str| {
+        (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+    };
+
+    let mut subtags = code.trim().split(['-', '_']).peekable();
+    let language = subtags.next().unwrap_or_default();
+    if !(matches!(language.len(), 2..=3) && is_alpha(language)) {
+        return Err(invalid());
+    }
+    let mut normalised = vec![language.to_ascii_lowercase()];
+
+    // how far into language-extlang-script-region-variant we are, since each may only follow the ones before it
+    let (extlang, script, region, variant) = (1, 2, 3, 4);
+    let mut reached = 0;
+    let mut extlang_count = 0;
+    while let Some(subtag) = subtags.next() {
+        if !is_alphanumeric(subtag) {
+            return Err(invalid());
+        }
+        let len = subtag.len();
+        if len == 1 {
+            // an extension, or private use with "x", takes the subtags up to the next singleton
+            let singleton = subtag.to_ascii_lowercase();
+            let min_len = if singleton == "x" { 1 } else { 2 };
+            let mut extension = vec![singleton.clone()];
+            while let Some(next) = subtags.next_if(|next| singleton == "x" || next.len() != 1) {
+                if !is_alphanumeric(next) || next.len() < min_len {
+                    return Err(invalid());
+                }
+                extension.push(next.to_ascii_lowercase());
+            }
+            if extension.len() == 1 {
+                return Err(invalid());
+            }
+            normalised.extend(extension);
+            reached = variant + 1;
+        } else if reached <= extlang
+            && len == 3
+            && is_alpha(subtag)
+            && language.len() <= 3
+            && extlang_count < 3
+        {
+            extlang_count += 1;
+            normalised.push(subtag.to_ascii_lowercase());
+            reached = extlang;
+        } else if reached < script && len == 4 && is_alpha(subtag) {
+            normalised.push(subtag[..1].to_ascii_uppercase() + &subtag[1..].to_ascii_lowercase());
+            reached = script;
+        } else if reached < region
+            && ((len == 2 && is_alpha(subtag)) || (len == 3 && is_digit(subtag)))
+        {
+            normalised.push(subtag.to_ascii_uppercase());
+            reached = region;
+        } else if reached <= variant
+            && (len >= 5 || (len == 4 && subtag.starts_with(|c: char| c.is_ascii_digit())))
+        {
+            normalised.push(subtag.to_ascii_lowercase());
+            reached = variant;
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(normalised.join("-"))
+}
+
 use DB::*;
 
 impl DB {
@@ -85,9 +171,9 @@ impl DB {
                 let record = sqlx::query_as!(
                     LanguageInDB,
                     r#"
-                        INSERT INTO language (name, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config )
-                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-                        RETURNING id, name, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config as "parser_config: sqlx::types::Json<ParserConfig>"
+                        INSERT INTO language (name, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config, language_code)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                        RETURNING id, name, language_code, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config as "parser_config: sqlx::types::Json<ParserConfig>"
                     "#,
                     language.name,
                     &language.dicts,
@@ -96,7 +182,8 @@ impl DB {
                     language.tts_voice,
                     language.deepl_source_lang,
                     language.deepl_target_lang,
-                    serde_json::to_value(&language.parser_config)?
+                    serde_json::to_value(&language.parser_config)?,
+                    normalise_language_code(&language.language_code)?
                 )
                 .fetch_one(pool.as_ref())
                 .await?;
@@ -121,7 +208,7 @@ impl DB {
                 let records: Vec<Language> = sqlx::query_as!(
                     LanguageInDB,
                     r#"
-                        SELECT id, name, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config as "parser_config: sqlx::types::Json<ParserConfig>"
+                        SELECT id, name, language_code, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config as "parser_config: sqlx::types::Json<ParserConfig>"
                         FROM language
                     "#
                 )
@@ -148,7 +235,7 @@ impl DB {
                 let record = sqlx::query_as!(
                     LanguageInDB,
                     r#"
-                        SELECT id, name, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config as "parser_config: sqlx::types::Json<ParserConfig>"
+                        SELECT id, name, language_code, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config as "parser_config: sqlx::types::Json<ParserConfig>"
                         FROM language
                         WHERE id = $1;
                     "#,
@@ -184,9 +271,9 @@ impl DB {
                     LanguageInDB,
                     r#"
                         UPDATE language 
-                        SET name = $2, dicts = $3, tts_rate = $4, tts_pitch = $5, tts_voice = $6, deepl_source_lang = $7, deepl_target_lang = $8, parser_config = $9
+                        SET name = $2, dicts = $3, tts_rate = $4, tts_pitch = $5, tts_voice = $6, deepl_source_lang = $7, deepl_target_lang = $8, parser_config = $9, language_code = $10
                         WHERE id = $1
-                        RETURNING id, name, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config as "parser_config: sqlx::types::Json<ParserConfig>"
+                        RETURNING id, name, language_code, dicts, tts_rate, tts_pitch, tts_voice, deepl_source_lang, deepl_target_lang, parser_config as "parser_config: sqlx::types::Json<ParserConfig>"
                     "#,
                     id.as_i64()?,
                     language.name,
@@ -196,7 +283,8 @@ impl DB {
                     language.tts_voice,
                     language.deepl_source_lang,
                     language.deepl_target_lang,
-                    serde_json::to_value(&language.parser_config)?
+                    serde_json::to_value(&language.parser_config)?,
+                    normalise_language_code(&language.language_code)?
                 )
                 .fetch_one(pool.as_ref())
                 .await?;
@@ -267,6 +355,7 @@ mod tests {
         Language {
             id: None,
             name: name.to_string(),
+            language_code: "en".to_string(),
             dicts: vec!["dict1".to_string(), "dict2".to_string()],
             tts_rate: Some(1.0),
             tts_pitch: Some(0.5),
@@ -280,6 +369,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_normalise_language_code() {
+        let results = [
+            "fr",
+            "EN_gb",
+            "zh-hant",
+            "zh-hant-tw",
+            "es-419",
+            "sl-rozaj-biske",
+            "de-CH-1996",
+            "zh-yue-HK",
+            "en-US-u-ca-gregory",
+            "en-x-twain",
+            "und",
+            "",
+            "e",
+            "english",
+            "en-",
+            "en-Latn-Latn",
+            "en-US-GB",
+            "en-u",
+            "fr-123456789",
+        ]
+        .iter()
+        .map(|code| match normalise_language_code(code) {
+            Ok(normalised) => format!("{:?} -> {}", code, normalised),
+            Err(_) => format!("{:?} -> invalid", code),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+        expect![[r#"
+            "fr" -> fr
+            "EN_gb" -> en-GB
+            "zh-hant" -> zh-Hant
+            "zh-hant-tw" -> zh-Hant-TW
+            "es-419" -> es-419
+            "sl-rozaj-biske" -> sl-rozaj-biske
+            "de-CH-1996" -> de-CH-1996
+            "zh-yue-HK" -> zh-yue-HK
+            "en-US-u-ca-gregory" -> en-US-u-ca-gregory
+            "en-x-twain" -> en-x-twain
+            "und" -> und
+            "" -> invalid
+            "e" -> invalid
+            "english" -> invalid
+            "en-" -> invalid
+            "en-Latn-Latn" -> invalid
+            "en-US-GB" -> invalid
+            "en-u" -> invalid
+            "fr-123456789" -> invalid"#]]
+        .assert_eq(&results);
+    }
+
     #[tokio::test]
     #[tracing_test::traced_test]
     async fn test_language_crud_operations() {
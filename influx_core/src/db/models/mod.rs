@@ -1,10 +1,14 @@
 #![allow(unused_imports)]
 
+pub mod dictionary;
 pub mod document;
+pub mod frequency;
 pub mod fsrs;
 pub mod lang;
 pub mod phrase;
 pub mod seed;
+pub mod token_history;
+pub mod tokenisation_job;
 pub mod vocab;
 
 pub(crate) use crate::DB;
@@ -12,7 +12,10 @@ use DB::*;
 //     Thing::from((TABLE.to_string(), id))
 // }
 
-#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, Hash, ElmDerives!)]
+/// orthography_seq element that matches any single lexical segment
+pub const PHRASE_WILDCARD: &str = "*";
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash, ElmDerives!)]
 pub struct Phrase {
     // #[serde(deserialize_with = "deserialize_surreal_thing_opt")]
     pub id: Option<InfluxResourceId>,
@@ -20,6 +23,12 @@ pub struct Phrase {
     pub lang_id: InfluxResourceId,
 
     pub orthography_seq: Vec<String>,
+    /// maximum number of lexical segments allowed between consecutive components. None means contiguous
+    #[serde(default)]
+    pub max_gap: Option<i32>,
+    /// match against lemma sequences as well as orthography sequences, so that inflected forms are found
+    #[serde(default)]
+    pub match_by_lemma: bool,
     pub definition: String,
     pub notes: String,
     pub original_context: String,
@@ -27,12 +36,21 @@ pub struct Phrase {
     pub status: TokenStatus,
 }
 
+/// summary of a language's phrase table that changes whenever a phrase is created, updated or deleted
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq)]
+pub struct PhraseFingerprint {
+    pub count: i64,
+    pub last_updated: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 impl Phrase {
     pub fn essential_phrase(lang_id: InfluxResourceId, orthography_seq: Vec<String>) -> Self {
         Phrase {
             id: None,
             lang_id: lang_id,
             orthography_seq,
+            max_gap: None,
+            match_by_lemma: false,
             definition: "placeholder".to_string(),
             notes: "some essential phrase".to_string(),
             original_context: "".to_string(),
@@ -45,12 +63,48 @@ impl Phrase {
             id: None,
             lang_id: lang_id,
             orthography_seq: orthography_seq,
+            max_gap: None,
+            match_by_lemma: false,
             definition: "".to_string(),
             notes: "".to_string(),
             original_context: "".to_string(),
             status: TokenStatus::UNMARKED,
         }
     }
+
+    /// whether this phrase has to be matched with gaps or wildcards rather than as a contiguous run
+    pub fn is_pattern(&self) -> bool {
+        self.max_gap.is_some_and(|max_gap| max_gap > 0)
+            || self.orthography_seq.iter().any(|x| x == PHRASE_WILDCARD)
+    }
+
+    /// - wildcards may not be the first or last element, since phrases are looked up by onset
+    pub fn validate_pattern(&self) -> Result<()> {
+        let first_or_last_is_wildcard = self.orthography_seq.first().map(String::as_str)
+            == Some(PHRASE_WILDCARD)
+            || self.orthography_seq.last().map(String::as_str) == Some(PHRASE_WILDCARD);
+        if first_or_last_is_wildcard {
+            return Err(anyhow::anyhow!(
+                "phrase cannot start or end with a wildcard: {:?}",
+                self.orthography_seq
+            ));
+        }
+        if self.max_gap.is_some_and(|max_gap| max_gap < 0) {
+            return Err(anyhow::anyhow!("phrase max_gap cannot be negative"));
+        }
+        Ok(())
+    }
+
+    /// pattern elements for gapped matching, with None standing for a wildcard slot
+    pub fn pattern_elements(&self) -> Vec<Option<String>> {
+        self.orthography_seq
+            .iter()
+            .map(|x| match x.as_str() {
+                PHRASE_WILDCARD => None,
+                _ => Some(x.clone()),
+            })
+            .collect()
+    }
 }
 
 impl DB {
@@ -79,7 +133,7 @@ impl DB {
                 let record = sqlx::query_as!(
                     Phrase,
                     r#"
-                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, definition, notes, original_context, status as "status: TokenStatus"
+                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status as "status: TokenStatus"
                         FROM phrase
                         WHERE orthography_seq = $1 AND lang_id = $2
                     "#,
@@ -102,6 +156,7 @@ impl DB {
             .iter()
             .all(|s| s.to_lowercase() == *s));
         assert!(phrase.id.is_none());
+        phrase.validate_pattern()?;
         assert!(
             !self
                 .phrase_exists(phrase.lang_id.clone(), phrase.orthography_seq.clone())
@@ -125,12 +180,14 @@ impl DB {
                 let record = sqlx::query_as!(
                     Phrase,
                     r#"
-                        INSERT INTO phrase (lang_id, orthography_seq, definition, notes, original_context, status)
-                        VALUES ($1, $2, $3, $4, $5, $6)
-                        RETURNING id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, definition, notes, original_context, status as "status: TokenStatus"
+                        INSERT INTO phrase (lang_id, orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                        RETURNING id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status as "status: TokenStatus"
                     "#,
                     phrase.lang_id.as_i64()?,
                     &phrase.orthography_seq,
+                    phrase.max_gap,
+                    phrase.match_by_lemma,
                     phrase.definition,
                     phrase.notes,
                     phrase.original_context,
@@ -158,7 +215,7 @@ impl DB {
                 let record = sqlx::query_as!(
                     Phrase,
                     r#"
-                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, definition, notes, original_context, status as "status: TokenStatus"
+                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status as "status: TokenStatus"
                         FROM phrase
                         WHERE id = $1
                     "#,
@@ -172,6 +229,8 @@ impl DB {
     }
 
     /// - requires that all orthography in orthography_seq is lowercase
+    /// - annotation no longer looks phrases up by onset. nlp::phrase_index keeps every phrase of the language,
+    ///   lemma phrases included, so this only finds phrases by their first orthography
     pub async fn query_phrase_by_onset_orthographies(
         &self,
         lang_id: InfluxResourceId,
@@ -200,7 +259,7 @@ impl DB {
                 let record = sqlx::query_as!(
                     Phrase,
                     r#"
-                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, definition, notes, original_context, status as "status: TokenStatus"
+                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status as "status: TokenStatus"
                         FROM phrase
                         WHERE orthography_seq[1] = ANY($1) AND lang_id = $2
                     "#,
@@ -239,7 +298,7 @@ impl DB {
                 let record = sqlx::query_as!(
                     Phrase,
                     r#"
-                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, definition, notes, original_context, status as "status: TokenStatus"
+                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status as "status: TokenStatus"
                         FROM phrase
                         WHERE orthography_seq = $1 AND lang_id = $2
                     "#,
@@ -254,6 +313,46 @@ impl DB {
         }
     }
 
+    pub async fn query_phrases_by_lang_id(&self, lang_id: InfluxResourceId) -> Result<Vec<Phrase>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let record = sqlx::query_as!(
+                    Phrase,
+                    r#"
+                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status as "status: TokenStatus"
+                        FROM phrase
+                        WHERE lang_id = $1
+                    "#,
+                    lang_id.as_i64()?,
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(record)
+            }
+        }
+    }
+
+    pub async fn phrase_fingerprint(&self, lang_id: InfluxResourceId) -> Result<PhraseFingerprint> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let record = sqlx::query_as!(
+                    PhraseFingerprint,
+                    r#"
+                        SELECT COUNT(*) as "count!", MAX(updated_ts) as last_updated
+                        FROM phrase
+                        WHERE lang_id = $1
+                    "#,
+                    lang_id.as_i64()?,
+                )
+                .fetch_one(pool.as_ref())
+                .await?;
+
+                Ok(record)
+            }
+        }
+    }
+
     /// - does not require that orthographies are lowercase. they will be converted to lowercase
     pub async fn get_phrases_from_text_set(
         &self,
@@ -292,6 +391,7 @@ impl DB {
             .iter()
             .all(|s| s.to_lowercase() == *s));
         assert!(phrase.id.is_some());
+        phrase.validate_pattern()?;
         let id = phrase
             .id
             .clone()
@@ -322,12 +422,14 @@ impl DB {
                     Phrase,
                     r#"
                         UPDATE phrase
-                        SET lang_id = $1, orthography_seq = $2, definition = $3, notes = $4, original_context = $5, status = $6
-                        WHERE id = $7
-                        RETURNING id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, definition, notes, original_context, status as "status: TokenStatus"
+                        SET lang_id = $1, orthography_seq = $2, max_gap = $3, match_by_lemma = $4, definition = $5, notes = $6, original_context = $7, status = $8
+                        WHERE id = $9
+                        RETURNING id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status as "status: TokenStatus"
                     "#,
                     phrase.lang_id.as_i64()?,
                     &phrase.orthography_seq,
+                    phrase.max_gap,
+                    phrase.match_by_lemma,
                     phrase.definition,
                     phrase.notes,
                     phrase.original_context,
@@ -358,7 +460,7 @@ impl DB {
                     r#"
                         DELETE FROM phrase
                         WHERE id = $1
-                        RETURNING id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, definition, notes, original_context, status as "status: TokenStatus"
+                        RETURNING id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", orthography_seq, max_gap, match_by_lemma, definition, notes, original_context, status as "status: TokenStatus"
                     "#,
                     id.as_i64()?
                 )
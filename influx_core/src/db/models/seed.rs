@@ -16,6 +16,7 @@ use tracing::{debug, info};
 
 fn create_language(
     name: &str,
+    language_code: &str,
     deepl_source_lang: &str,
     spacy_model: Option<&str>,
     parser: &str,
@@ -28,6 +29,7 @@ fn create_language(
     Language {
         id: None,
         name: name.to_string(),
+        language_code: language_code.to_string(),
         dicts: vec!["dict:///###".to_string()],
         tts_rate: None,
         tts_pitch: None,
@@ -78,6 +80,8 @@ fn create_phrase(
         id: None,
         lang_id,
         orthography_seq: words.iter().map(|s| s.to_string()).collect(),
+        max_gap: None,
+        match_by_lemma: false,
         definition: definition.to_string(),
         notes: notes.to_string(),
         original_context: context.to_string(),
@@ -101,6 +105,7 @@ impl DB {
         let en_lang = self
             .create_language(create_language(
                 "English",
+                "en",
                 "EN",
                 Some("en_core_web_sm"),
                 "base_spacy",
@@ -109,17 +114,25 @@ impl DB {
         let fr_lang = self
             .create_language(create_language(
                 "French",
+                "fr",
                 "FR",
                 Some("fr_core_news_sm"),
                 "base_spacy",
             ))
             .await?;
         let ja_lang = self
-            .create_language(create_language("Japanese", "JA", None, "enhanced_japanese"))
+            .create_language(create_language(
+                "Japanese",
+                "ja",
+                "JA",
+                None,
+                "enhanced_japanese",
+            ))
             .await?;
         let zh_lang = self
             .create_language(create_language(
                 "Mandarin",
+                "zh-Hant",
                 "ZH",
                 Some("zh_core_web_sm"),
                 "base_spacy",
@@ -0,0 +1,321 @@
+///! token status transitions, recorded by a database trigger on the token table
+use super::*;
+use crate::db::InfluxResourceId;
+use crate::prelude::*;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc};
+use std::collections::{BTreeMap, HashMap};
+use vocab::TokenStatus;
+use DB::*;
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq)]
+pub struct TokenStatusChange {
+    pub id: Option<InfluxResourceId>,
+    pub lang_id: InfluxResourceId,
+    pub token_id: InfluxResourceId,
+    pub orthography: String,
+    /// None when the token was created
+    pub from_status: Option<TokenStatus>,
+    /// UNMARKED when the token was deleted
+    pub to_status: TokenStatus,
+    pub is_backfilled: bool,
+    pub changed_ts: DateTime<Utc>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, Copy, PartialEq, Eq, Hash, ElmDerives!)]
+pub enum TimelineGranularity {
+    Day,
+    Week,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct VocabTimelineBucket {
+    pub bucket_start: DateTime<Utc>,
+    /// number of tokens in each status at the end of the bucket, keyed by status name
+    pub status_counts: BTreeMap<String, i64>,
+    /// tokens created during the bucket
+    pub new_terms: i64,
+    /// tokens that moved to KNOWN during the bucket
+    pub words_learned: i64,
+}
+
+impl DB {
+    pub async fn query_token_status_history(
+        &self,
+        lang_id: InfluxResourceId,
+    ) -> Result<Vec<TokenStatusChange>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query_as!(
+                    TokenStatusChange,
+                    r#"
+                        SELECT id as "id: Option<InfluxResourceId>", lang_id as "lang_id: InfluxResourceId", token_id as "token_id: InfluxResourceId", orthography, from_status as "from_status: TokenStatus", to_status as "to_status: TokenStatus", is_backfilled, changed_ts
+                        FROM token_status_history
+                        WHERE lang_id = $1
+                        ORDER BY changed_ts ASC, id ASC
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(records)
+            }
+        }
+    }
+
+    /// synthesise history for tokens that have none, from their created_ts and updated_ts
+    /// - a token is created at created_ts. one updated since starts UNMARKED and reaches its current status at
+    ///   updated_ts, so it is not counted as learned the day it was created
+    /// - the migration that added the history did this for every existing token, so this only finds
+    ///   tokens written while the trigger was not recording
+    /// - returns the number of history entries inserted
+    pub async fn backfill_token_status_history(&self, lang_id: InfluxResourceId) -> Result<u64> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let result = sqlx::query!(
+                    r#"
+                        INSERT INTO token_status_history (lang_id, token_id, orthography, from_status, to_status, is_backfilled, changed_ts)
+                        SELECT t.lang_id, t.id, t.orthography, entry.from_status, entry.to_status, TRUE, entry.changed_ts
+                        FROM token t
+                        CROSS JOIN LATERAL (
+                            VALUES
+                                (NULL::token_status, CASE WHEN t.updated_ts > t.created_ts THEN 'UNMARKED'::token_status ELSE t.status END, t.created_ts),
+                                ('UNMARKED'::token_status, t.status, t.updated_ts)
+                        ) AS entry (from_status, to_status, changed_ts)
+                        WHERE t.lang_id = $1
+                          AND NOT EXISTS (SELECT 1 FROM token_status_history h WHERE h.token_id = t.id)
+                          AND (entry.from_status IS NULL OR (t.updated_ts > t.created_ts AND t.status <> 'UNMARKED'))
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .execute(pool.as_ref())
+                .await?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    }
+}
+
+fn bucket_start_of(ts: DateTime<Utc>, granularity: TimelineGranularity) -> DateTime<Utc> {
+    let date = ts.date_naive();
+    let date = match granularity {
+        TimelineGranularity::Day => date,
+        TimelineGranularity::Week => {
+            date - Duration::days(date.weekday().num_days_from_monday() as i64)
+        }
+    };
+    Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN))
+}
+
+fn bucket_length(granularity: TimelineGranularity) -> Duration {
+    match granularity {
+        TimelineGranularity::Day => Duration::days(1),
+        TimelineGranularity::Week => Duration::weeks(1),
+    }
+}
+
+/// replay status changes into contiguous buckets from the first change up to and including `until`
+/// - requires that changes are sorted by changed_ts
+pub fn build_vocab_timeline(
+    changes: &[TokenStatusChange],
+    granularity: TimelineGranularity,
+    until: DateTime<Utc>,
+) -> Vec<VocabTimelineBucket> {
+    let Some(first_change) = changes.first() else {
+        return vec![];
+    };
+
+    let mut current_status: HashMap<InfluxResourceId, TokenStatus> = HashMap::new();
+    let mut buckets = vec![];
+    let mut pending = changes.iter().peekable();
+    let mut bucket_start = bucket_start_of(first_change.changed_ts, granularity);
+    let last_bucket_start = bucket_start_of(until.max(first_change.changed_ts), granularity);
+
+    while bucket_start <= last_bucket_start {
+        let bucket_end = bucket_start + bucket_length(granularity);
+        let mut new_terms = 0;
+        let mut words_learned = 0;
+
+        while let Some(change) = pending.next_if(|change| change.changed_ts < bucket_end) {
+            if change.from_status.is_none() {
+                new_terms += 1;
+            }
+            if change.to_status == TokenStatus::KNOWN
+                && change.from_status != Some(TokenStatus::KNOWN)
+            {
+                words_learned += 1;
+            }
+            match change.to_status {
+                TokenStatus::UNMARKED => current_status.remove(&change.token_id),
+                _ => current_status.insert(change.token_id.clone(), change.to_status.clone()),
+            };
+        }
+
+        let mut status_counts: BTreeMap<String, i64> = BTreeMap::new();
+        for status in current_status.values() {
+            *status_counts.entry(format!("{:?}", status)).or_insert(0) += 1;
+        }
+
+        buckets.push(VocabTimelineBucket {
+            bucket_start,
+            status_counts,
+            new_terms,
+            words_learned,
+        });
+        bucket_start = bucket_end;
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    fn mk_change(
+        token_id: i64,
+        from_status: Option<TokenStatus>,
+        to_status: TokenStatus,
+        changed_ts: &str,
+    ) -> TokenStatusChange {
+        TokenStatusChange {
+            id: None,
+            lang_id: InfluxResourceId::SerialId(1),
+            token_id: InfluxResourceId::SerialId(token_id),
+            orthography: format!("token{}", token_id),
+            from_status,
+            to_status,
+            is_backfilled: false,
+            changed_ts: changed_ts.parse().unwrap(),
+        }
+    }
+
+    fn render(buckets: &[VocabTimelineBucket]) -> String {
+        buckets
+            .iter()
+            .map(|bucket| {
+                format!(
+                    "{} new={} learned={} {:?}",
+                    bucket.bucket_start.format("%Y-%m-%d"),
+                    bucket.new_terms,
+                    bucket.words_learned,
+                    bucket.status_counts
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_build_vocab_timeline() {
+        use TokenStatus::*;
+        let changes = vec![
+            mk_change(1, None, L1, "2025-01-06T10:00:00Z"),
+            mk_change(2, None, L1, "2025-01-06T11:00:00Z"),
+            mk_change(1, Some(L1), KNOWN, "2025-01-07T09:00:00Z"),
+            mk_change(3, None, KNOWN, "2025-01-09T09:00:00Z"),
+            mk_change(2, Some(L1), UNMARKED, "2025-01-14T09:00:00Z"),
+        ];
+
+        let daily = build_vocab_timeline(
+            &changes[..4],
+            TimelineGranularity::Day,
+            "2025-01-09T23:00:00Z".parse().unwrap(),
+        );
+        expect![[r#"
+            2025-01-06 new=2 learned=0 {"L1": 2}
+            2025-01-07 new=0 learned=1 {"KNOWN": 1, "L1": 1}
+            2025-01-08 new=0 learned=0 {"KNOWN": 1, "L1": 1}
+            2025-01-09 new=1 learned=1 {"KNOWN": 2, "L1": 1}"#]]
+        .assert_eq(&render(&daily));
+
+        let weekly = build_vocab_timeline(
+            &changes,
+            TimelineGranularity::Week,
+            "2025-01-20T00:00:00Z".parse().unwrap(),
+        );
+        expect![[r#"
+            2025-01-06 new=3 learned=2 {"KNOWN": 2, "L1": 1}
+            2025-01-13 new=0 learned=0 {"KNOWN": 2}
+            2025-01-20 new=0 learned=0 {"KNOWN": 2}"#]]
+        .assert_eq(&render(&weekly));
+
+        assert!(build_vocab_timeline(&[], TimelineGranularity::Day, Utc::now()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backfill_token_status_history() {
+        use crate::db::models::vocab::Token;
+        use crate::test_utils::{test_language, TestDb};
+        use TokenStatus::*;
+
+        let test_db = TestDb::new().await.unwrap();
+        let db = &test_db.db;
+        let lang_id = db
+            .create_language(test_language("English", "en", "unicode"))
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+        for (orthography, status) in [("known", KNOWN), ("fresh", L1)] {
+            db.create_token(Token::fancier_token(
+                lang_id.clone(),
+                orthography,
+                "",
+                "",
+                status,
+            ))
+            .await
+            .unwrap();
+        }
+        // as if the tokens predate the history: "known" was created on the 6th and last updated on the 9th,
+        // "fresh" was never updated
+        let (Postgres { pool } | EmbeddedPostgres { pool, .. }) = db;
+        let mut tx = pool.begin().await.unwrap();
+        for statement in [
+            "ALTER TABLE token DISABLE TRIGGER set_updated_ts_token",
+            "UPDATE token SET created_ts = '2025-01-06T10:00:00Z', updated_ts = '2025-01-06T10:00:00Z'",
+            "UPDATE token SET updated_ts = '2025-01-09T10:00:00Z' WHERE orthography = 'known'",
+            "ALTER TABLE token ENABLE TRIGGER set_updated_ts_token",
+            "DELETE FROM token_status_history",
+        ] {
+            sqlx::query(statement).execute(&mut *tx).await.unwrap();
+        }
+        tx.commit().await.unwrap();
+
+        assert_eq!(
+            db.backfill_token_status_history(lang_id.clone())
+                .await
+                .unwrap(),
+            3
+        );
+        let mut history = db
+            .query_token_status_history(lang_id.clone())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|change| {
+                format!(
+                    "{} {} {:?} -> {:?}",
+                    change.changed_ts.format("%Y-%m-%d"),
+                    change.orthography,
+                    change.from_status,
+                    change.to_status
+                )
+            })
+            .collect::<Vec<_>>();
+        history.sort();
+        expect![[r#"
+            [
+                "2025-01-06 fresh None -> L1",
+                "2025-01-06 known None -> UNMARKED",
+                "2025-01-09 known Some(UNMARKED) -> KNOWN",
+            ]"#]]
+        .assert_eq(&format!("{:#?}", history));
+
+        // tokens with history are left alone
+        assert_eq!(db.backfill_token_status_history(lang_id).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,316 @@
+///! background tokenisation jobs, persisted so that queued work survives restarts
+///! - one row per document, re-queued whenever the document or its language's parser config changes
+///! - a job re-queued while it runs is picked up again, since finishing only applies to RUNNING jobs
+use super::*;
+use crate::db::InfluxResourceId;
+use crate::prelude::*;
+use chrono::{DateTime, Utc};
+use DB::*;
+
+#[derive(Debug, SerdeDerives!, Clone, Copy, PartialEq, Eq, Hash, ElmDerives!, sqlx::Type)]
+#[sqlx(type_name = "tokenisation_job_status")]
+pub enum TokenisationJobStatus {
+    QUEUED,
+    RUNNING,
+    DONE,
+    FAILED,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct TokenisationJob {
+    pub id: InfluxResourceId,
+    pub document_id: InfluxResourceId,
+    pub status: TokenisationJobStatus,
+    /// runs since the job was last queued
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_ts: DateTime<Utc>,
+    pub updated_ts: DateTime<Utc>,
+}
+
+impl DB {
+    pub async fn enqueue_tokenisation_job(
+        &self,
+        document_id: InfluxResourceId,
+    ) -> Result<TokenisationJob> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let record = sqlx::query_as!(
+                    TokenisationJob,
+                    r#"
+                        INSERT INTO tokenisation_job (document_id)
+                        VALUES ($1)
+                        ON CONFLICT (document_id)
+                        DO UPDATE SET status = 'QUEUED', attempts = 0, last_error = NULL
+                        RETURNING id as "id: InfluxResourceId", document_id as "document_id: InfluxResourceId", status as "status: TokenisationJobStatus", attempts, last_error, created_ts, updated_ts
+                    "#,
+                    document_id.as_i64()?
+                )
+                .fetch_one(pool.as_ref())
+                .await?;
+
+                Ok(record)
+            }
+        }
+    }
+
+    /// queue every document of the language, returns the number of jobs queued
+    pub async fn enqueue_tokenisation_jobs_for_lang(
+        &self,
+        lang_id: InfluxResourceId,
+    ) -> Result<u64> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let result = sqlx::query!(
+                    r#"
+                        INSERT INTO tokenisation_job (document_id)
+                        SELECT id FROM document WHERE lang_id = $1
+                        ON CONFLICT (document_id)
+                        DO UPDATE SET status = 'QUEUED', attempts = 0, last_error = NULL
+                    "#,
+                    lang_id.as_i64()?
+                )
+                .execute(pool.as_ref())
+                .await?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    }
+
+    /// mark the oldest queued job as running and return it. concurrent workers never claim the same job
+    pub async fn claim_next_tokenisation_job(&self) -> Result<Option<TokenisationJob>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let record = sqlx::query_as!(
+                    TokenisationJob,
+                    r#"
+                        UPDATE tokenisation_job
+                        SET status = 'RUNNING', attempts = attempts + 1
+                        WHERE id = (
+                            SELECT id FROM tokenisation_job
+                            WHERE status = 'QUEUED'
+                            ORDER BY updated_ts ASC, id ASC
+                            LIMIT 1
+                            FOR UPDATE SKIP LOCKED
+                        )
+                        RETURNING id as "id: InfluxResourceId", document_id as "document_id: InfluxResourceId", status as "status: TokenisationJobStatus", attempts, last_error, created_ts, updated_ts
+                    "#
+                )
+                .fetch_optional(pool.as_ref())
+                .await?;
+
+                Ok(record)
+            }
+        }
+    }
+
+    /// set the outcome of a running job. a job that was re-queued in the meantime stays queued
+    pub async fn finish_tokenisation_job(
+        &self,
+        job_id: InfluxResourceId,
+        status: TokenisationJobStatus,
+        error: Option<String>,
+    ) -> Result<()> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                sqlx::query!(
+                    r#"
+                        UPDATE tokenisation_job
+                        SET status = $2, last_error = $3
+                        WHERE id = $1 AND status = 'RUNNING'
+                    "#,
+                    job_id.as_i64()?,
+                    status as TokenisationJobStatus,
+                    error
+                )
+                .execute(pool.as_ref())
+                .await?;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// jobs left running by a server that stopped are queued again
+    pub async fn requeue_running_tokenisation_jobs(&self) -> Result<u64> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let result = sqlx::query!(
+                    r#"
+                        UPDATE tokenisation_job
+                        SET status = 'QUEUED'
+                        WHERE status = 'RUNNING'
+                    "#
+                )
+                .execute(pool.as_ref())
+                .await?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    }
+
+    /// re-queue failed jobs, all of them when job_id is None. returns the jobs re-queued
+    pub async fn retry_failed_tokenisation_jobs(
+        &self,
+        job_id: Option<InfluxResourceId>,
+    ) -> Result<Vec<TokenisationJob>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let job_id_i64 = match &job_id {
+                    Some(id) => Some(id.as_i64()?),
+                    None => None,
+                };
+                let records = sqlx::query_as!(
+                    TokenisationJob,
+                    r#"
+                        UPDATE tokenisation_job
+                        SET status = 'QUEUED', attempts = 0
+                        WHERE status = 'FAILED' AND ($1::bigint IS NULL OR id = $1)
+                        RETURNING id as "id: InfluxResourceId", document_id as "document_id: InfluxResourceId", status as "status: TokenisationJobStatus", attempts, last_error, created_ts, updated_ts
+                    "#,
+                    job_id_i64
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(records)
+            }
+        }
+    }
+
+    pub async fn query_tokenisation_jobs(
+        &self,
+        document_id: Option<InfluxResourceId>,
+        status: Option<TokenisationJobStatus>,
+    ) -> Result<Vec<TokenisationJob>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let document_id_i64 = match &document_id {
+                    Some(id) => Some(id.as_i64()?),
+                    None => None,
+                };
+                let records = sqlx::query_as!(
+                    TokenisationJob,
+                    r#"
+                        SELECT id as "id: InfluxResourceId", document_id as "document_id: InfluxResourceId", status as "status: TokenisationJobStatus", attempts, last_error, created_ts, updated_ts
+                        FROM tokenisation_job
+                        WHERE ($1::bigint IS NULL OR document_id = $1)
+                          AND ($2::tokenisation_job_status IS NULL OR status = $2)
+                        ORDER BY updated_ts DESC, id DESC
+                    "#,
+                    document_id_i64,
+                    status as Option<TokenisationJobStatus>
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+
+                Ok(records)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::document::DocumentCreateRequest;
+    use crate::test_utils::{test_language, TestDb};
+    use expect_test::expect;
+
+    fn render(jobs: &[TokenisationJob]) -> String {
+        jobs.iter()
+            .map(|job| {
+                format!(
+                    "doc {} {:?} attempts={} error={:?}",
+                    job.document_id, job.status, job.attempts, job.last_error
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[tokio::test]
+    async fn test_tokenisation_job_lifecycle() {
+        let test_db = TestDb::new().await.unwrap();
+        let db = &test_db.db;
+        let lang = db
+            .create_language(test_language("English", "en", "unicode"))
+            .await
+            .unwrap();
+        let mut document_ids = vec![];
+        for title in ["first", "second"] {
+            let document = db
+                .create_document(DocumentCreateRequest {
+                    lang_id: lang.id.clone().unwrap(),
+                    title: title.to_string(),
+                    content: "Some text.".to_string(),
+                    doc_type: "Text".to_string(),
+                    tags: vec![],
+                })
+                .await
+                .unwrap();
+            document_ids.push(document.id.unwrap());
+        }
+
+        db.enqueue_tokenisation_job(document_ids[0].clone())
+            .await
+            .unwrap();
+        let job = db.claim_next_tokenisation_job().await.unwrap().unwrap();
+        assert_eq!(job.status, TokenisationJobStatus::RUNNING);
+        assert!(db.claim_next_tokenisation_job().await.unwrap().is_none());
+
+        // the document is edited while its job runs, so the job must run again
+        db.enqueue_tokenisation_job(document_ids[0].clone())
+            .await
+            .unwrap();
+        db.finish_tokenisation_job(job.id.clone(), TokenisationJobStatus::DONE, None)
+            .await
+            .unwrap();
+        let job = db.claim_next_tokenisation_job().await.unwrap().unwrap();
+        db.finish_tokenisation_job(
+            job.id.clone(),
+            TokenisationJobStatus::FAILED,
+            Some("parser exploded".to_string()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            db.enqueue_tokenisation_jobs_for_lang(lang.id.clone().unwrap())
+                .await
+                .unwrap(),
+            2
+        );
+        // a restart puts the interrupted job at the back of the queue
+        let interrupted = db.claim_next_tokenisation_job().await.unwrap().unwrap();
+        assert_eq!(interrupted.document_id, document_ids[0]);
+        db.requeue_running_tokenisation_jobs().await.unwrap();
+        let job = db.claim_next_tokenisation_job().await.unwrap().unwrap();
+        assert_eq!(job.document_id, document_ids[1]);
+        db.finish_tokenisation_job(
+            job.id.clone(),
+            TokenisationJobStatus::FAILED,
+            Some("parser exploded".to_string()),
+        )
+        .await
+        .unwrap();
+        expect![[r#"
+            doc InfluxResourceId(2) FAILED attempts=1 error=Some("parser exploded")
+            doc InfluxResourceId(1) QUEUED attempts=1 error=None"#]]
+        .assert_eq(&render(
+            &db.query_tokenisation_jobs(None, None).await.unwrap(),
+        ));
+
+        let retried = db.retry_failed_tokenisation_jobs(None).await.unwrap();
+        assert_eq!(retried.len(), 1);
+        expect![[r#"
+            doc InfluxResourceId(2) QUEUED attempts=0 error=Some("parser exploded")"#]]
+        .assert_eq(&render(
+            &db.query_tokenisation_jobs(Some(document_ids[1].clone()), None)
+                .await
+                .unwrap(),
+        ));
+    }
+}
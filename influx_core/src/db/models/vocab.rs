@@ -315,6 +315,58 @@ impl DB {
         }
     }
 
+    /// tokens of the language, only those with the status if one is given, by orthography
+    pub async fn query_tokens_by_lang_id(
+        &self,
+        lang_id: InfluxResourceId,
+        status: Option<TokenStatus>,
+    ) -> Result<Vec<Token>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query_as!(
+                    Token,
+                    r#"
+                        SELECT id as "id: Option<InfluxResourceId>", orthography, phonetic, definition, notes, original_context, status as "status: TokenStatus", lang_id
+                        FROM token
+                        WHERE lang_id = $1 AND ($2::token_status IS NULL OR status = $2)
+                        ORDER BY orthography;
+                    "#,
+                    lang_id.as_i64()?,
+                    status as Option<TokenStatus>
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+                Ok(records)
+            }
+        }
+    }
+
+    /// tokens of the language with the status whose definition is empty or only whitespace
+    pub async fn query_tokens_without_definition(
+        &self,
+        lang_id: InfluxResourceId,
+        status: TokenStatus,
+    ) -> Result<Vec<Token>> {
+        match self {
+            Postgres { pool } | EmbeddedPostgres { pool, .. } => {
+                let records = sqlx::query_as!(
+                    Token,
+                    r#"
+                        SELECT id as "id: Option<InfluxResourceId>", orthography, phonetic, definition, notes, original_context, status as "status: TokenStatus", lang_id
+                        FROM token
+                        WHERE lang_id = $1 AND status = $2 AND TRIM(definition) = ''
+                        ORDER BY orthography;
+                    "#,
+                    lang_id.as_i64()?,
+                    status as TokenStatus
+                )
+                .fetch_all(pool.as_ref())
+                .await?;
+                Ok(records)
+            }
+        }
+    }
+
     pub async fn delete_token_and_return_deleted(&self, token: Token) -> Result<Token> {
         let id = token.id.ok_or(anyhow::anyhow!("cannot delete if no id"))?;
 
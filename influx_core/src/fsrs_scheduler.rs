@@ -506,6 +506,7 @@ mod integration_tests {
         let language = Language {
             id: None,
             name: "Test Language".to_string(),
+            language_code: "en".to_string(),
             dicts: vec![],
             tts_rate: Some(1.0),
             tts_pitch: Some(1.0),
@@ -654,6 +655,7 @@ mod integration_tests {
         let language = Language {
             id: None,
             name: "Test".to_string(),
+            language_code: "en".to_string(),
             dicts: vec![],
             tts_rate: Some(1.0),
             tts_pitch: Some(1.0),
@@ -793,6 +795,7 @@ mod integration_tests {
         let language = Language {
             id: None,
             name: "French".to_string(),
+            language_code: "fr".to_string(),
             dicts: vec![],
             tts_rate: Some(1.2),
             tts_pitch: Some(0.9),
@@ -930,6 +933,7 @@ mod integration_tests {
         let language = Language {
             id: None,
             name: "Spanish".to_string(),
+            language_code: "es".to_string(),
             dicts: vec![],
             tts_rate: Some(1.0),
             tts_pitch: Some(1.0),
@@ -1,7 +1,13 @@
+use crate::db::models::dictionary;
+use crate::db::models::document;
+use crate::db::models::frequency;
 use crate::db::models::fsrs;
 use crate::db::models::phrase::Phrase;
-use crate::db::models::vocab::Token;
+use crate::db::models::token_history;
+use crate::db::models::tokenisation_job;
+use crate::db::models::vocab::{Token, TokenStatus};
 use crate::db::InfluxResourceId;
+use crate::integration::dictionary_registry::InstalledDictionary;
 use crate::nlp;
 use crate::prelude::*;
 use axum::http::StatusCode;
@@ -70,18 +76,66 @@ pub struct GetDocsRequest {
     pub language_id: Option<InfluxResourceId>,
 }
 
-#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, Hash, ElmDerives!)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash, ElmDerives!)]
 pub struct TermEditRequest {
     pub requested_action: TermEditAction,
     pub term: Term,
     pub document_id: Option<InfluxResourceId>,
+    #[serde(default)]
+    pub fill_definition: bool, // on CreateTerm, fill an empty token definition from the language's top dictionary
+}
+
+/// the sentences of a document that a term edit may have changed, annotated again
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct AnnotatedDocDelta {
+    /// replace the document's sentences with the same start_char
+    pub changed_segments: Vec<nlp::DocSegV2>,
+    /// replace the phrase matches within the changed sentences
+    pub phrase_matches: Vec<nlp::PhraseMatch>,
+    /// entries for the terms of the changed sentences, to merge into the document's TermDictionary
+    pub term_dict: nlp::TermDictionary,
 }
 
 #[derive(Debug, SerdeDerives!, Clone, ElmDerives!)]
 pub struct TermEditResponse {
     pub performed_action: TermEditAction,
     pub term: Term,
-    pub updated_annotated_doc: Option<nlp::AnnotatedDocV2>,
+    pub annotated_doc_delta: Option<AnnotatedDocDelta>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct TermListRequest {
+    pub lang_id: InfluxResourceId,
+    pub status: Option<TokenStatus>, // every status when None
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct RankedToken {
+    pub token: Token,
+    pub frequency_rank: Option<i32>, // None when the orthography is not in the language's frequency list
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct TermListResponse {
+    pub tokens: Vec<RankedToken>, // most frequent first, then the unranked by orthography
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct PhraseSuggestionsRequest {
+    pub lang_id: InfluxResourceId,
+    pub min_count: Option<usize>,
+    pub max_suggestions: Option<usize>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct PhraseSuggestionsResponse {
+    pub suggestions: Vec<nlp::collocations::PhraseSuggestion>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct AcceptPhraseSuggestionRequest {
+    pub lang_id: InfluxResourceId,
+    pub suggestion: nlp::collocations::PhraseSuggestion,
 }
 
 // DOCUMENT
@@ -91,6 +145,28 @@ pub struct GetDocResponse {
     pub doc_package: crate::db::models::document::DocPackage,
     pub annotated_doc: nlp::AnnotatedDocV2,
     pub term_dict: nlp::TermDictionary,
+    /// the NLP service was down, so annotated_doc is the last cached annotation, possibly of older text
+    pub stale_annotation: bool,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct GetDocRangeRequest {
+    pub document_id: InfluxResourceId,
+    pub sentence_start: usize,
+    pub sentence_count: usize,
+}
+
+#[derive(SerdeDerives!, Debug, Clone, ElmDerives!)]
+pub struct GetDocRangeResponse {
+    pub doc_package: crate::db::models::document::DocPackage,
+    /// only the requested sentences, char offsets still relative to the whole document
+    pub annotated_doc: nlp::AnnotatedDocV2,
+    pub term_dict: nlp::TermDictionary,
+    pub stale_annotation: bool,
+    /// the range actually served, clamped to the document
+    pub sentence_start: usize,
+    pub sentence_end: usize,
+    pub total_sentences: usize,
 }
 
 #[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
@@ -160,3 +236,156 @@ pub struct SetCardStateRequest {
 pub struct SetCardStateResponse {
     pub updated_card: fsrs::Card,
 }
+
+// STATS
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct VocabTimelineRequest {
+    pub lang_id: InfluxResourceId,
+    pub granularity: token_history::TimelineGranularity,
+    pub backfill: bool, // synthesise history for tokens that have none before building the timeline
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct VocabTimelineResponse {
+    pub buckets: Vec<token_history::VocabTimelineBucket>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct SetFrequencyListRequest {
+    pub lang_id: InfluxResourceId,
+    pub lemmas: Vec<String>, // most frequent first
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct SetFrequencyListResponse {
+    pub lemma_count: u64,
+    pub documents_rescored: usize,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct FrequentUnknownLemmasRequest {
+    pub lang_id: InfluxResourceId,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct FrequentUnknownLemmasResponse {
+    pub lemmas: Vec<frequency::RankedLemma>, // most frequent first
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct RecommendDocumentsRequest {
+    pub lang_id: InfluxResourceId,
+    pub max_unknown_token_ratio: Option<f64>,
+    pub limit: Option<usize>,
+    pub refresh: bool, // rescore the language's documents first, since unknown tokens change as the learner marks them
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct RecommendDocumentsResponse {
+    pub recommended: Vec<document::DocumentDifficulty>,
+}
+
+// CACHE
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct AnnotationCacheStatsResponse {
+    pub total_entries: i64,
+    pub total_bytes: i64,
+    pub languages: Vec<document::LanguageCacheStats>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct InvalidateAnnotationCacheResponse {
+    pub removed: i64,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct AnnotationCacheSettings {
+    pub bypass_cache: bool,
+}
+
+// DICTIONARY
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct LanguageDictionariesRequest {
+    pub lang_id: InfluxResourceId,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct LanguageDictionaryEntry {
+    pub dictionary: dictionary::LanguageDictionary,
+    pub installed: Option<InstalledDictionary>, // None when the dictionary's files are gone
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct LanguageDictionariesResponse {
+    pub dictionaries: Vec<LanguageDictionaryEntry>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct LanguageDictionarySetting {
+    pub dict_path: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct SetLanguageDictionariesRequest {
+    pub lang_id: InfluxResourceId,
+    pub dictionaries: Vec<LanguageDictionarySetting>, // highest priority first
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, ElmDerives!)]
+pub struct LanguageDictionaryLookupRequest {
+    pub lang_id: InfluxResourceId,
+    pub query: String,
+    pub attributes: Option<nlp::SegAttribute>, // the parser's lemma and conjugation chain for the query, tried when it is not found
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct FillDefinitionsRequest {
+    pub lang_id: InfluxResourceId,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct FillDefinitionsResponse {
+    pub filled: Vec<Token>,
+    pub not_found: Vec<String>, // orthographies the top dictionary has no entry for
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct RemoveDictionaryRequest {
+    pub directory_name: String,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct RenameDictionaryRequest {
+    pub directory_name: String,
+    pub new_directory_name: String,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub enum DictionaryCacheAction {
+    Clear,   // delete the cache, it is rebuilt on the next lookup
+    Rebuild, // delete the cache and load the dictionary straight away
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct DictionaryCacheRequest {
+    pub dict_path: String,
+    pub action: DictionaryCacheAction,
+}
+
+// JOBS
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct TokenisationJobsRequest {
+    pub document_id: Option<InfluxResourceId>,
+    pub status: Option<tokenisation_job::TokenisationJobStatus>,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct RetryTokenisationJobsRequest {
+    pub job_id: Option<InfluxResourceId>, // None retries every failed job
+}
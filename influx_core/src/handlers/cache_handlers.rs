@@ -0,0 +1,57 @@
+use super::ServerError;
+use crate::db::models::document::AnnotationCacheScope;
+use crate::handlers::api_interfaces::*;
+use crate::ServerState;
+use axum::extract::State;
+use axum::Json;
+use std::sync::atomic::Ordering;
+use tracing::{debug, info};
+
+pub async fn get_annotation_cache_stats(
+    State(ServerState { db, .. }): State<ServerState>,
+) -> Result<Json<AnnotationCacheStatsResponse>, ServerError> {
+    let languages = db.get_annotated_document_cache_stats().await?;
+    Ok(Json(AnnotationCacheStatsResponse {
+        total_entries: languages.iter().map(|language| language.entries).sum(),
+        total_bytes: languages.iter().map(|language| language.bytes).sum(),
+        languages,
+    }))
+}
+
+pub async fn invalidate_annotation_cache(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(scope): Json<AnnotationCacheScope>,
+) -> Result<Json<InvalidateAnnotationCacheResponse>, ServerError> {
+    debug!(scope = ?scope, "Invalidating annotated document cache");
+    let removed = db.invalidate_annotated_document_cache(scope).await?;
+    info!(removed, "Invalidated annotated document cache entries");
+    Ok(Json(InvalidateAnnotationCacheResponse {
+        removed: removed as i64,
+    }))
+}
+
+pub async fn get_annotation_cache_settings(
+    State(ServerState {
+        bypass_annotation_cache,
+        ..
+    }): State<ServerState>,
+) -> Json<AnnotationCacheSettings> {
+    Json(AnnotationCacheSettings {
+        bypass_cache: bypass_annotation_cache.load(Ordering::Relaxed),
+    })
+}
+
+pub async fn set_annotation_cache_settings(
+    State(ServerState {
+        bypass_annotation_cache,
+        ..
+    }): State<ServerState>,
+    Json(settings): Json<AnnotationCacheSettings>,
+) -> Json<AnnotationCacheSettings> {
+    info!(
+        bypass_cache = settings.bypass_cache,
+        "Updating annotated document cache settings"
+    );
+    bypass_annotation_cache.store(settings.bypass_cache, Ordering::Relaxed);
+    Json(settings)
+}
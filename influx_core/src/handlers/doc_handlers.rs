@@ -1,11 +1,14 @@
 use super::api_interfaces::*;
 use super::ServerError;
 use crate::db::models::document::{DocPackage, Document, DocumentCreateRequest};
-use crate::db::models::phrase::mk_phrase_trie;
+use crate::db::models::lang::Language;
 use crate::db::models::phrase::Phrase;
 use crate::db::models::vocab::Token;
 use crate::db::InfluxResourceId;
+use crate::integration::nlp_client::NlpError;
 use crate::nlp;
+use crate::nlp::difficulty;
+use crate::nlp::term_delta::AffectedTerms;
 use crate::ServerState;
 use axum::{
     extract::{Path, State},
@@ -17,9 +20,8 @@ use axum::{
 use md5;
 use serde_json::json;
 use std::collections::{BTreeMap, BTreeSet};
-use tracing::{debug, info};
-
-const USE_CACHE: bool = false;
+use std::sync::atomic::Ordering;
+use tracing::{debug, info, warn};
 
 pub async fn get_docs_list(
     State(ServerState { db, .. }): State<ServerState>,
@@ -47,9 +49,10 @@ async fn load_cached_nlp_data(
     document_id: InfluxResourceId,
     text_checksum: &str,
     parser_config: &crate::db::models::lang::ParserConfig,
+    parser_version: Option<&str>,
 ) -> Result<Option<nlp::AnnotatedDocV2>, anyhow::Error> {
     if let Some(cached_json) = db
-        .get_annotated_document_cache(document_id.clone(), text_checksum)
+        .get_annotated_document_cache(document_id.clone(), text_checksum, parser_version)
         .await?
     {
         let cached_doc: nlp::AnnotatedDocV2 = serde_json::from_value(cached_json)?;
@@ -69,10 +72,207 @@ async fn load_cached_nlp_data(
     }
 }
 
-pub(crate) async fn get_annotated_doc_logic(
+/// the last annotation cached for the document, whatever text or parser config it was made from
+async fn load_stale_nlp_data(
+    db: &crate::db::DB,
+    document_id: InfluxResourceId,
+) -> Result<Option<nlp::AnnotatedDocV2>, anyhow::Error> {
+    Ok(db
+        .get_latest_annotated_document_cache(document_id)
+        .await?
+        .and_then(|cached_json| serde_json::from_value(cached_json).ok()))
+}
+
+/// the NLP service is down or too slow to answer, so a stale annotation is better than none
+pub(crate) fn is_nlp_service_down(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<NlpError>().is_some_and(|nlp_error| {
+        nlp_error.is_service_down() || matches!(nlp_error, NlpError::Timeout { .. })
+    })
+}
+
+/// None when the tokeniser can't say, e.g. the NLP service is down, in which case cache entries of any version are used.
+/// the NLP client remembers the answer for a while, so this does not cost a request per document
+async fn current_parser_version(state: &ServerState, lang_entry: &Language) -> Option<String> {
+    match nlp::parser_version(&lang_entry.parser_config, &state.nlp_client).await {
+        // an empty version is what entries cached without one are stored under, so it can't be trusted
+        Ok(version) if version.is_empty() => None,
+        Ok(version) => Some(version),
+        Err(err) => {
+            warn!("Could not get parser version: {}", err);
+            None
+        }
+    }
+}
+
+/// run the tokenisation pipeline on the text and cache the result under its checksum
+async fn tokenise_and_cache(
     state: &ServerState,
     document_id: InfluxResourceId,
-) -> Result<GetDocResponse, ServerError> {
+    text: &str,
+    lang_entry: &Language,
+    parser_version: Option<&str>,
+) -> anyhow::Result<nlp::AnnotatedDocV2> {
+    let text_checksum = text_checksum(text.to_string());
+    let tokenised_doc = nlp::tokenise_pipeline(
+        text,
+        // language code for tokenisation pipeline
+        lang_entry.language_code.clone(),
+        lang_entry.parser_config.clone(),
+        &state.nlp_client,
+    )
+    .await?;
+    let serialized_json = serde_json::to_value(&tokenised_doc)?;
+    state
+        .db
+        .set_annotated_document_cache(
+            document_id.clone(),
+            &text_checksum,
+            // an entry without a version is replaced the next time the version is known
+            parser_version.unwrap_or_default(),
+            &serialized_json,
+        )
+        .await?;
+    info!(
+        "Cached NLP data in database for document_id: {:?}, checksum: {}",
+        document_id, text_checksum
+    );
+    Ok(tokenised_doc)
+}
+
+/// make sure the cache holds an annotation of the document's current text and parser config.
+/// used by the background tokenisation jobs, so that opening the document is fast
+pub(crate) async fn pretokenise_document(
+    state: &ServerState,
+    document_id: InfluxResourceId,
+) -> anyhow::Result<()> {
+    let document = state
+        .db
+        .get_document_by_id(document_id.clone())
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+    let lang_entry = state
+        .db
+        .get_language(document.lang_id.clone())
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Language not found for document"))?;
+
+    let text_checksum = text_checksum(document.content.clone());
+    let parser_version = current_parser_version(state, &lang_entry).await;
+    let tokenised_doc = match load_cached_nlp_data(
+        &state.db,
+        document_id.clone(),
+        &text_checksum,
+        &lang_entry.parser_config,
+        parser_version.as_deref(),
+    )
+    .await?
+    {
+        Some(cached_doc) => {
+            debug!(document_id = ?document_id, "Document already tokenised");
+            cached_doc
+        }
+        None => {
+            tokenise_and_cache(
+                state,
+                document_id.clone(),
+                &document.content,
+                &lang_entry,
+                parser_version.as_deref(),
+            )
+            .await?
+        }
+    };
+    // the annotation is what the job is for, so a failed score is only logged
+    if let Err(err) = score_document_difficulties(
+        &state.db,
+        document.lang_id,
+        &[(document_id.clone(), &tokenised_doc)],
+    )
+    .await
+    {
+        warn!(document_id = ?document_id, "Could not score document difficulty: {}", err);
+    }
+    Ok(())
+}
+
+/// store the documents' difficulty for the learner, see nlp::difficulty.
+/// the documents share a language, so their tokens and lemma ranks are read in one query each
+async fn score_document_difficulties(
+    db: &crate::db::DB,
+    lang_id: InfluxResourceId,
+    tokenised_docs: &[(InfluxResourceId, &nlp::AnnotatedDocV2)],
+) -> anyhow::Result<()> {
+    let orthographies = tokenised_docs
+        .iter()
+        .flat_map(|(_, tokenised_doc)| tokenised_doc.orthography_set.iter().cloned())
+        .collect::<BTreeSet<String>>();
+    // orthographies without a token count as unknown, as UNMARKED ones do
+    let term_dict = db
+        .query_tokens_by_orthographies(lang_id.clone(), &orthographies)
+        .await?
+        .into_iter()
+        .map(|token| (token.orthography.clone(), token))
+        .collect::<BTreeMap<String, Token>>();
+    let lemma_ranks = match db.frequency_list_size(lang_id.clone()).await? {
+        0 => None,
+        _ => {
+            let lemmas = tokenised_docs
+                .iter()
+                .flat_map(|(_, tokenised_doc)| difficulty::document_lemmas(tokenised_doc))
+                .collect();
+            Some(db.get_lemma_ranks(lang_id, &lemmas).await?)
+        }
+    };
+    let config = difficulty::DifficultyConfig::default();
+    let difficulties = tokenised_docs
+        .iter()
+        .map(|(document_id, tokenised_doc)| {
+            let features = difficulty::difficulty_features(
+                tokenised_doc,
+                &term_dict,
+                lemma_ranks.as_ref(),
+                &config,
+            );
+            (
+                document_id.clone(),
+                difficulty::difficulty_score(&features, &config),
+                features,
+            )
+        })
+        .collect::<Vec<_>>();
+    db.set_document_difficulties(&difficulties).await?;
+    Ok(())
+}
+
+/// rescore the language's documents from their last cached annotation, returns how many were scored.
+/// documents never tokenised are left for their tokenisation job
+pub(crate) async fn rescore_document_difficulties(
+    db: &crate::db::DB,
+    lang_id: InfluxResourceId,
+) -> anyhow::Result<usize> {
+    let tokenised_docs = db
+        .get_annotated_document_caches_by_lang(lang_id.clone())
+        .await?
+        .into_iter()
+        .filter_map(|(document_id, cached)| {
+            serde_json::from_value::<nlp::AnnotatedDocV2>(cached)
+                .ok()
+                .map(|tokenised_doc| (document_id, tokenised_doc))
+        })
+        .collect::<Vec<_>>();
+    let tokenised_docs = tokenised_docs
+        .iter()
+        .map(|(document_id, tokenised_doc)| (document_id.clone(), tokenised_doc))
+        .collect::<Vec<_>>();
+    score_document_difficulties(db, lang_id, &tokenised_docs).await?;
+    Ok(tokenised_docs.len())
+}
+
+/// the document, its language and its tokenised text, from the cache when possible
+async fn load_tokenised_doc(
+    state: &ServerState,
+    document_id: InfluxResourceId,
+) -> Result<(DocPackage, nlp::AnnotatedDocV2, bool), ServerError> {
     info!("getting doc: document_id = {:?}", document_id);
 
     // Get document from database
@@ -93,16 +293,6 @@ pub(crate) async fn get_annotated_doc_logic(
         .clone()
         .ok_or_else(|| ServerError(anyhow::anyhow!("Language entry missing ID")))?;
 
-    // Derive language code from name for tokenisation pipeline
-    let lang_code = match lang_entry.name.as_str() {
-        "French" => "fr",
-        "English" => "en",
-        "Japanese" => "ja",
-        "Mandarin" => "zh-hant",
-        _ => "en", // fallback to English
-    }
-    .to_string();
-
     let text = document.content.clone();
 
     // Create DocPackage
@@ -114,83 +304,166 @@ pub(crate) async fn get_annotated_doc_logic(
     };
 
     let text_checksum: String = text_checksum(text.clone());
+    let parser_version = current_parser_version(state, &lang_entry).await;
+    let bypass_cache = state.bypass_annotation_cache.load(Ordering::Relaxed);
 
-    let tokenised_doc: nlp::AnnotatedDocV2 = match load_cached_nlp_data(
+    let (tokenised_doc, stale_annotation): (nlp::AnnotatedDocV2, bool) = match load_cached_nlp_data(
         &state.db,
         document_id.clone(),
         &text_checksum,
         &lang_entry.parser_config,
+        parser_version.as_deref(),
     )
     .await
     {
-        Ok(Some(cached_doc)) if USE_CACHE => {
+        Ok(Some(cached_doc)) if !bypass_cache => {
             info!(
                 "Using cached NLP data for document_id: {:?}, checksum: {}",
                 document_id, text_checksum
             );
-            cached_doc
+            (cached_doc, false)
         }
         _ => {
-            // run tokenisation pipeline and cache it
-            let it = nlp::tokenise_pipeline(
-                text.as_str(),
-                lang_code.clone(),
-                lang_entry.parser_config.clone(),
-                &state.nlp_url,
+            match tokenise_and_cache(
+                state,
+                document_id.clone(),
+                &text,
+                &lang_entry,
+                parser_version.as_deref(),
             )
-            .await?;
-            let serialized_json = serde_json::to_value(&it)?;
-            state
-                .db
-                .set_annotated_document_cache(document_id.clone(), &text_checksum, &serialized_json)
-                .await?;
-            info!(
-                "Cached NLP data in database for document_id: {:?}, checksum: {}",
-                document_id, text_checksum
-            );
-            it
+            .await
+            {
+                Ok(it) => (it, false),
+                // serve whatever was cached last rather than nothing while the service is down
+                Err(err) if is_nlp_service_down(&err) => {
+                    match load_stale_nlp_data(&state.db, document_id.clone()).await? {
+                        Some(stale_doc) => {
+                            warn!(
+                                "NLP service is down, serving stale annotation for document_id: {:?}: {}",
+                                document_id, err
+                            );
+                            (stale_doc, true)
+                        }
+                        None => return Err(err.into()),
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
         }
     };
 
+    Ok((doc_package, tokenised_doc, stale_annotation))
+}
+
+/// look up the terms of a tokenised doc and fit phrases onto it
+async fn annotate_tokenised_doc(
+    state: &ServerState,
+    lang_id: InfluxResourceId,
+    tokenised_doc: nlp::AnnotatedDocV2,
+) -> Result<(nlp::AnnotatedDocV2, nlp::TermDictionary), ServerError> {
+    let orthographies = tokenised_doc
+        .orthography_set
+        .union(&tokenised_doc.lemma_set)
+        .cloned()
+        .collect::<BTreeSet<String>>();
+    let frequency_ranks = state
+        .db
+        .get_lemma_ranks(lang_id.clone(), &orthographies)
+        .await?;
     let tokens_dict: BTreeMap<String, Token> = state
         .db
-        .get_dict_from_orthography_set(
-            lang_id.clone(),
-            tokenised_doc
-                .orthography_set
-                .union(&tokenised_doc.lemma_set)
-                .cloned()
-                .collect::<BTreeSet<String>>(),
-        )
+        .get_dict_from_orthography_set(lang_id.clone(), orthographies)
         .await?
         .into_iter()
         .collect();
 
-    let potential_phrases: Vec<Phrase> = state
-        .db
-        .query_phrase_by_onset_orthographies(lang_id.clone(), tokenised_doc.orthography_set.clone())
+    // every phrase of the language, so the index is shared by all documents until a phrase changes
+    let phrase_index = state
+        .phrase_indexes
+        .get_or_build(&state.db, lang_id.clone())
         .await?;
-    let phrase_dict: BTreeMap<String, Phrase> = potential_phrases
-        .iter()
-        .map(|phrase| {
-            let key = phrase.orthography_seq.join(" ");
-            (key, phrase.clone())
-        })
-        .collect();
-    let phrase_trie = mk_phrase_trie(potential_phrases);
-    let annotated_doc = nlp::phrase_fit_pipeline(tokenised_doc, phrase_trie);
+    let annotated_doc = phrase_index.fit(tokenised_doc);
+    let phrase_dict: BTreeMap<String, Phrase> = phrase_index.matched_phrases(&annotated_doc);
 
-    let result = GetDocResponse {
-        doc_package,
+    Ok((
         annotated_doc,
-        term_dict: nlp::TermDictionary {
+        nlp::TermDictionary {
             token_dict: tokens_dict,
             phrase_dict,
+            frequency_ranks,
         },
+    ))
+}
+
+pub(crate) async fn get_annotated_doc_logic(
+    state: &ServerState,
+    document_id: InfluxResourceId,
+) -> Result<GetDocResponse, ServerError> {
+    let (doc_package, tokenised_doc, stale_annotation) =
+        load_tokenised_doc(state, document_id).await?;
+    let (annotated_doc, term_dict) =
+        annotate_tokenised_doc(state, doc_package.language_id.clone(), tokenised_doc).await?;
+
+    let result = GetDocResponse {
+        doc_package,
+        annotated_doc,
+        term_dict,
+        stale_annotation,
     };
     Ok(result)
 }
 
+/// annotate again only the sentences of a document that a term edit may have changed
+pub(crate) async fn get_annotated_doc_delta(
+    state: &ServerState,
+    document_id: InfluxResourceId,
+    affected: &AffectedTerms,
+) -> Result<AnnotatedDocDelta, ServerError> {
+    let (doc_package, tokenised_doc, _) = load_tokenised_doc(state, document_id).await?;
+    let affected_doc = nlp::term_delta::affected_sentences(&tokenised_doc, affected);
+    debug!(
+        changed_sentences = affected_doc.segments.len(),
+        "Re-annotating sentences affected by a term edit"
+    );
+    let (annotated_doc, term_dict) =
+        annotate_tokenised_doc(state, doc_package.language_id, affected_doc).await?;
+
+    Ok(AnnotatedDocDelta {
+        changed_segments: annotated_doc.segments,
+        phrase_matches: annotated_doc.phrase_matches,
+        term_dict,
+    })
+}
+
+/// annotate only a range of sentences, so long documents can be read page by page.
+/// tokenisation still covers the whole document, and is cached, but term lookup and phrase fitting don't
+pub(crate) async fn get_annotated_doc_range_logic(
+    state: &ServerState,
+    request: GetDocRangeRequest,
+) -> Result<GetDocRangeResponse, ServerError> {
+    let (doc_package, tokenised_doc, stale_annotation) =
+        load_tokenised_doc(state, request.document_id).await?;
+    let total_sentences = nlp::chunking::count_sentences(&tokenised_doc);
+    let sentence_start = request.sentence_start.min(total_sentences);
+    let sentence_end = sentence_start
+        .saturating_add(request.sentence_count)
+        .min(total_sentences);
+    let sliced_doc =
+        nlp::chunking::slice_by_sentence_range(&tokenised_doc, sentence_start, sentence_end);
+    let (annotated_doc, term_dict) =
+        annotate_tokenised_doc(state, doc_package.language_id.clone(), sliced_doc).await?;
+
+    Ok(GetDocRangeResponse {
+        doc_package,
+        annotated_doc,
+        term_dict,
+        stale_annotation,
+        sentence_start,
+        sentence_end,
+        total_sentences,
+    })
+}
+
 pub async fn get_doc(
     State(state): State<ServerState>,
     Path(id): Path<String>,
@@ -203,20 +476,48 @@ pub async fn get_doc(
     Ok(Json(response))
 }
 
+pub async fn get_doc_range(
+    State(state): State<ServerState>,
+    Json(request): Json<GetDocRangeRequest>,
+) -> Result<Json<GetDocRangeResponse>, ServerError> {
+    Ok(Json(get_annotated_doc_range_logic(&state, request).await?))
+}
+
+/// tokenise in the background. failing to queue is not fatal, the document is tokenised when opened
+async fn queue_pretokenisation(state: &ServerState, document: &Document) {
+    let Some(document_id) = document.id.clone() else {
+        return;
+    };
+    if let Err(err) = state
+        .tokenisation_queue
+        .enqueue_document(&state.db, document_id.clone())
+        .await
+    {
+        warn!(
+            "Failed to queue tokenisation for document_id {:?}: {}",
+            document_id, err
+        );
+    }
+}
+
 pub async fn create_document(
-    State(ServerState { db, .. }): State<ServerState>,
+    State(state): State<ServerState>,
     Json(payload): Json<DocumentCreateRequest>,
 ) -> Result<Json<Document>, ServerError> {
     debug!(title = %payload.title, "Creating document");
-    Ok(Json(db.create_document(payload).await?))
+    let document = state.db.create_document(payload).await?;
+    queue_pretokenisation(&state, &document).await;
+    Ok(Json(document))
 }
 
 pub async fn update_document(
-    State(ServerState { db, .. }): State<ServerState>,
+    State(state): State<ServerState>,
     Json(payload): Json<Document>,
 ) -> Result<Json<Document>, ServerError> {
     debug!(document_id = ?payload.id, title = %payload.title, "Updating document");
-    Ok(Json(db.update_document(payload).await?))
+    let document = state.db.update_document(payload).await?;
+    queue_pretokenisation(&state, &document).await;
+    Ok(Json(document))
 }
 
 pub async fn delete_document(
@@ -1,16 +1,25 @@
+use super::api_interfaces::*;
 use super::ServerError;
 use crate::data_dir;
+use crate::db::InfluxResourceId;
 use crate::integration;
+use crate::integration::definition_html;
+use crate::integration::dictionary_install;
+use crate::integration::dictionary_reader::DictionaryEntry;
+use crate::integration::dictionary_registry::{self, InstalledDictionary};
+use crate::integration::stardict::DictionaryLoadStats;
 use crate::integration::ExternalDict;
 use crate::integration::ExternalTranslator;
+use crate::nlp::lookup_candidates::{lookup_candidates, LookupCandidate, LookupCandidateSource};
 use crate::prelude::*;
 use crate::ServerState;
 use axum::extract::Path;
 use axum::extract::Query;
 use axum::extract::State;
+use axum::http::StatusCode;
 use axum::Json;
 use serde::Deserialize;
-use tracing::debug;
+use tracing::{debug, warn};
 
 pub async fn lookup_in_macos_dict(
     State(ServerState { db, .. }): State<ServerState>,
@@ -70,6 +79,25 @@ pub struct StardictLookupQuery {
     pub query: String,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionMode {
+    /// headwords starting with the query, for suggestions while typing
+    Prefix,
+    /// headwords a few edits away, only when the query itself is not a headword
+    Fuzzy,
+}
+
+#[derive(Deserialize)]
+pub struct DictionarySuggestQuery {
+    pub dict_path: String,
+    pub query: String,
+    pub mode: SuggestionMode,
+    pub limit: Option<usize>,
+}
+
+const DEFAULT_SUGGESTION_LIMIT: usize = 20;
+
 #[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
 pub enum StardictType {
     Html,
@@ -95,7 +123,37 @@ pub struct DictionaryInfo {
 #[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
 pub struct WordDefinitionSegment {
     pub types: StardictType,
+    /// html is sanitized and its resource links point at the server, see integration::definition_html
     pub text: String,
+    /// the text without markup, e.g. for card backs
+    pub plain_text: String,
+}
+
+impl WordDefinitionSegment {
+    fn from_stardict(types: String, text: String, resource_base_url: &str) -> Self {
+        let types = StardictType::from(types);
+        match &types {
+            StardictType::Html => {
+                let text = definition_html::sanitize_definition_html(&text, resource_base_url);
+                Self {
+                    plain_text: definition_html::html_to_plain_text(&text),
+                    types,
+                    text,
+                }
+            }
+            // xdxf and pango markup are not rendered, but their tags still have to go from the plain text
+            StardictType::Other(type_str) if type_str == "x" || type_str == "g" => Self {
+                plain_text: definition_html::html_to_plain_text(&text),
+                types,
+                text,
+            },
+            StardictType::Other(_) => Self {
+                plain_text: text.clone(),
+                types,
+                text,
+            },
+        }
+    }
 }
 
 #[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
@@ -103,20 +161,19 @@ pub struct WordDefinition {
     pub word: String,
     pub segments: Vec<WordDefinitionSegment>,
     pub dictionary_info: DictionaryInfo,
+    /// the form that was found, when the lookup tried several, see nlp::lookup_candidates
+    pub matched_candidate: Option<LookupCandidate>,
 }
 
 impl WordDefinition {
-    fn from_stardict_with_metadata(def: stardict::WordDefinition, dict_path: &str) -> Self {
+    fn from_entry_with_metadata(def: DictionaryEntry, dict_path: &str, server_url: &str) -> Self {
         // Extract directory name from dict_path
         // dict_path format: "French - English/French - English.ifo"
         let directory_name = dict_path.split('/').next().unwrap_or("unknown").to_string();
 
         // Create base URL for resources
-        // Use the directory name directly since it should be safe for URLs
-        let base_url = format!(
-            "http://127.0.0.1:3000/influx_app_data/dictionaries/stardicts/{}/res",
-            directory_name.replace(" ", "%20") // Simple space encoding
-        );
+        // The directory name is percent-encoded, it often has spaces and may be in any script
+        let base_url = definition_html::resource_base_url(server_url, &directory_name);
 
         let dictionary_info = DictionaryInfo {
             name: directory_name.clone(),
@@ -129,12 +186,16 @@ impl WordDefinition {
             segments: def
                 .segments
                 .into_iter()
-                .map(|seg| WordDefinitionSegment {
-                    types: seg.types.into(),
-                    text: seg.text,
+                .map(|seg| {
+                    WordDefinitionSegment::from_stardict(
+                        seg.types,
+                        seg.text,
+                        &dictionary_info.base_url,
+                    )
                 })
                 .collect(),
             dictionary_info,
+            matched_candidate: None,
         }
     }
 }
@@ -145,64 +206,483 @@ pub async fn stardict_lookup(
 ) -> Result<Json<Vec<WordDefinition>>, ServerError> {
     debug!(dict_path = %query.dict_path, query = %query.query, "Looking up word in stardict");
 
-    // Resolve relative path to absolute path
-    let absolute_path = data_dir::resolve_dict_path(&query.dict_path)
-        .map_err(|e| ServerError(e))?
-        .to_string_lossy()
-        .to_string();
+    // Resolve relative path to absolute path. only installed dictionaries can be looked up, by their dict_path
+    let absolute_path = data_dir::resolve_dict_path(&query.dict_path).map_err(ServerError)?;
+    if !absolute_path.is_file() {
+        return Err(anyhow::anyhow!("Dictionary not installed: {}", query.dict_path).into());
+    }
+    let absolute_path = absolute_path.to_string_lossy().to_string();
 
-    // TODO maybe can do rwlock instead, but mutex should be fine for now
-    let mut stardict_manager = state.stardict_manager.lock().await;
-    let result = stardict_manager.lookup_word(absolute_path, &query.query)?;
+    // DONE maybe can do rwlock instead, but mutex should be fine for now
+    // each dictionary has its own lock now, and lookups run on the blocking pool, see StardictManager
+    let result = state
+        .stardict_manager
+        .lookup_word(absolute_path, &query.query)
+        .await?;
 
     match result {
         Some(definitions) => Ok(Json(
             definitions
                 .into_iter()
-                .map(|def| WordDefinition::from_stardict_with_metadata(def, &query.dict_path))
+                .map(|def| {
+                    WordDefinition::from_entry_with_metadata(
+                        def,
+                        &query.dict_path,
+                        &state.server_url,
+                    )
+                })
                 .collect(),
         )),
         None => Ok(Json(vec![])),
     }
 }
 
+/// ranked headwords of one dictionary for the query, see integration::headword_index
+pub async fn dictionary_suggest(
+    State(state): State<ServerState>,
+    Query(query): Query<DictionarySuggestQuery>,
+) -> Result<Json<Vec<String>>, ServerError> {
+    debug!(dict_path = %query.dict_path, query = %query.query, mode = ?query.mode, "Suggesting headwords");
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    dictionary_registry::read_installed_dictionary(&dictionaries_dir, &query.dict_path)?;
+    let absolute_path =
+        dictionary_registry::dictionary_file_path(&dictionaries_dir, &query.dict_path)?;
+    let index = state
+        .stardict_manager
+        .headword_index(absolute_path.to_string_lossy().to_string())
+        .await?;
+    let limit = query.limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT);
+    Ok(Json(match query.mode {
+        SuggestionMode::Prefix => index.prefix_matches(&query.query, limit),
+        SuggestionMode::Fuzzy => index.fuzzy_suggestions(&query.query, limit),
+    }))
+}
+
 pub async fn list_dictionaries() -> Result<Json<Vec<String>>, ServerError> {
     let dictionaries_dir = data_dir::get_dictionaries_dir().map_err(|e| ServerError(e))?;
+    Ok(Json(
+        dictionary_registry::list_installed_dictionaries(&dictionaries_dir)?
+            .into_iter()
+            .map(|dictionary| dictionary.dict_path)
+            .collect(),
+    ))
+}
+
+pub async fn dictionary_load_stats(
+    State(state): State<ServerState>,
+) -> Result<Json<Vec<DictionaryLoadStats>>, ServerError> {
+    Ok(Json(state.stardict_manager.load_stats()))
+}
 
-    let mut dictionary_names = Vec::new();
-
-    if let Ok(entries) = std::fs::read_dir(&dictionaries_dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_dir() {
-                    if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
-                        // Look for any .ifo files in the directory
-                        if let Ok(dir_entries) = std::fs::read_dir(&path) {
-                            for dir_entry in dir_entries {
-                                if let Ok(dir_entry) = dir_entry {
-                                    let file_path = dir_entry.path();
-                                    if let Some(extension) = file_path.extension() {
-                                        if extension == "ifo" {
-                                            if let Some(file_name) =
-                                                file_path.file_name().and_then(|n| n.to_str())
-                                            {
-                                                dictionary_names
-                                                    .push(format!("{}/{}", dir_name, file_name));
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+/// e.g. after a dictionary's files were replaced, or when its cache is corrupt
+pub async fn dictionary_cache(
+    State(state): State<ServerState>,
+    Json(request): Json<DictionaryCacheRequest>,
+) -> Result<StatusCode, ServerError> {
+    debug!(dict_path = %request.dict_path, action = ?request.action, "Updating dictionary cache");
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    dictionary_registry::read_installed_dictionary(&dictionaries_dir, &request.dict_path)?;
+    let absolute_path =
+        dictionary_registry::dictionary_file_path(&dictionaries_dir, &request.dict_path)?
+            .to_string_lossy()
+            .to_string();
+    match request.action {
+        DictionaryCacheAction::Clear => state.stardict_manager.clear_cache(absolute_path).await?,
+        DictionaryCacheAction::Rebuild => {
+            state.stardict_manager.rebuild_cache(absolute_path).await?
+        }
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_installed_dictionaries() -> Result<Json<Vec<InstalledDictionary>>, ServerError> {
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    Ok(Json(dictionary_registry::list_installed_dictionaries(
+        &dictionaries_dir,
+    )?))
+}
+
+#[derive(Deserialize)]
+pub struct InstallDictionaryQuery {
+    pub directory_name: Option<String>,
+    pub lang_id: Option<i64>, // enable the dictionary for this language after its others
+}
+
+/// dictionary bundles are far larger than the default 2MB request body limit
+const MAX_DICTIONARY_ARCHIVE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// write the request body to the file as it arrives, failing once it passes max_bytes. returns its size
+async fn save_upload(
+    body: axum::body::Body,
+    file: std::fs::File,
+    max_bytes: u64,
+) -> anyhow::Result<u64> {
+    use axum::body::HttpBody;
+    use tokio::io::AsyncWriteExt;
+
+    let mut body = body;
+    let mut file = tokio::fs::File::from_std(file);
+    let mut size = 0;
+    while let Some(frame) =
+        std::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_frame(cx)).await
+    {
+        let Ok(data) = frame?.into_data() else {
+            continue;
+        };
+        size += data.len() as u64;
+        if size > max_bytes {
+            anyhow::bail!("the upload is larger than {} bytes", max_bytes);
+        }
+        file.write_all(&data).await?;
+    }
+    file.flush().await?;
+    Ok(size)
+}
+
+/// the request body is the raw .zip, .tar.gz or .tar.bz2 bundle, see integration::dictionary_install.
+/// it is streamed to a temporary file next to the dictionaries rather than held in memory
+pub async fn install_dictionary(
+    State(ServerState { db, .. }): State<ServerState>,
+    Query(query): Query<InstallDictionaryQuery>,
+    body: axum::body::Body,
+) -> Result<Json<InstalledDictionary>, ServerError> {
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    std::fs::create_dir_all(&dictionaries_dir)?;
+    let archive = tempfile::Builder::new()
+        .prefix(".upload-")
+        .tempfile_in(&dictionaries_dir)?;
+    let size = save_upload(body, archive.reopen()?, MAX_DICTIONARY_ARCHIVE_BYTES).await?;
+    debug!(size, directory_name = ?query.directory_name, "Installing dictionary");
+    let installed = tokio::task::spawn_blocking(move || {
+        dictionary_install::install_dictionary(
+            &dictionaries_dir,
+            archive.path(),
+            query.directory_name.as_deref(),
+        )
+    })
+    .await??;
+    if let Some(lang_id) = query.lang_id {
+        db.append_language_dictionary(InfluxResourceId::SerialId(lang_id), &installed.dict_path)
+            .await?;
+    }
+    Ok(Json(installed))
+}
+
+/// close the directory's dictionaries and delete their caches before it is removed or renamed.
+/// caches are keyed by path, so after either they would never be used again
+async fn unload_directory(
+    state: &ServerState,
+    dictionaries_dir: &std::path::Path,
+    directory_name: &str,
+) -> anyhow::Result<()> {
+    for dictionary in dictionary_registry::list_installed_dictionaries(dictionaries_dir)?
+        .into_iter()
+        .filter(|dictionary| dictionary.directory_name == directory_name)
+    {
+        let absolute_path = dictionaries_dir.join(&dictionary.dict_path);
+        state
+            .stardict_manager
+            .clear_cache(absolute_path.to_string_lossy().to_string())
+            .await?;
+    }
+    state
+        .stardict_manager
+        .unload_directory(&dictionaries_dir.join(directory_name));
+    Ok(())
+}
+
+pub async fn remove_dictionary(
+    State(state): State<ServerState>,
+    Json(request): Json<RemoveDictionaryRequest>,
+) -> Result<StatusCode, ServerError> {
+    debug!(directory_name = %request.directory_name, "Removing dictionary");
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    dictionary_install::validate_directory_name(&request.directory_name)?;
+    unload_directory(&state, &dictionaries_dir, &request.directory_name).await?;
+    dictionary_install::remove_dictionary(&dictionaries_dir, &request.directory_name)?;
+    state
+        .db
+        .remove_dictionary_directory(&request.directory_name)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn rename_dictionary(
+    State(state): State<ServerState>,
+    Json(request): Json<RenameDictionaryRequest>,
+) -> Result<Json<Vec<InstalledDictionary>>, ServerError> {
+    debug!(directory_name = %request.directory_name, new_directory_name = %request.new_directory_name, "Renaming dictionary");
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    // checked first, so a rename that cannot happen leaves the dictionary's caches alone
+    dictionary_install::validate_rename(
+        &dictionaries_dir,
+        &request.directory_name,
+        &request.new_directory_name,
+    )?;
+    unload_directory(&state, &dictionaries_dir, &request.directory_name).await?;
+    let renamed = dictionary_install::rename_dictionary(
+        &dictionaries_dir,
+        &request.directory_name,
+        &request.new_directory_name,
+    )?;
+    state
+        .db
+        .rename_dictionary_directory(&request.directory_name, &request.new_directory_name)
+        .await?;
+    Ok(Json(renamed))
+}
+
+async fn language_dictionary_entries(
+    db: &crate::db::DB,
+    lang_id: InfluxResourceId,
+) -> Result<Vec<LanguageDictionaryEntry>, ServerError> {
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    Ok(db
+        .get_language_dictionaries(lang_id)
+        .await?
+        .into_iter()
+        .map(|dictionary| LanguageDictionaryEntry {
+            installed: dictionary_registry::read_installed_dictionary(
+                &dictionaries_dir,
+                &dictionary.dict_path,
+            )
+            .ok(),
+            dictionary,
+        })
+        .collect())
+}
+
+pub async fn get_language_dictionaries(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<LanguageDictionariesRequest>,
+) -> Result<Json<LanguageDictionariesResponse>, ServerError> {
+    Ok(Json(LanguageDictionariesResponse {
+        dictionaries: language_dictionary_entries(&db, request.lang_id).await?,
+    }))
+}
+
+/// only installed dictionaries can be enabled, so a dict_path never points outside the dictionaries directory
+pub async fn set_language_dictionaries(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<SetLanguageDictionariesRequest>,
+) -> Result<Json<LanguageDictionariesResponse>, ServerError> {
+    debug!(lang_id = ?request.lang_id, count = request.dictionaries.len(), "Setting language dictionaries");
+    let installed =
+        dictionary_registry::list_installed_dictionaries(&data_dir::get_dictionaries_dir()?)?;
+    if let Some(unknown) = request.dictionaries.iter().find(|setting| {
+        !installed
+            .iter()
+            .any(|dictionary| dictionary.dict_path == setting.dict_path)
+    }) {
+        return Err(anyhow::anyhow!("Dictionary not installed: {}", unknown.dict_path).into());
+    }
+    db.set_language_dictionaries(
+        request.lang_id.clone(),
+        &request
+            .dictionaries
+            .into_iter()
+            .map(|setting| (setting.dict_path, setting.enabled))
+            .collect::<Vec<_>>(),
+    )
+    .await?;
+    Ok(Json(LanguageDictionariesResponse {
+        dictionaries: language_dictionary_entries(&db, request.lang_id).await?,
+    }))
+}
+
+/// the language's enabled dictionaries that are installed, in priority order, with their metadata.
+/// a dictionary whose files are gone is logged and skipped
+async fn enabled_language_dictionaries(
+    state: &ServerState,
+    lang_id: InfluxResourceId,
+) -> anyhow::Result<Vec<InstalledDictionary>> {
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    let mut installed = vec![];
+    for dictionary in state.db.get_language_dictionaries(lang_id).await? {
+        if !dictionary.enabled {
+            continue;
+        }
+        match dictionary_registry::read_installed_dictionary(
+            &dictionaries_dir,
+            &dictionary.dict_path,
+        ) {
+            Ok(dictionary) => installed.push(dictionary),
+            Err(err) => {
+                warn!(dict_path = %dictionary.dict_path, "Skipping dictionary: {:#}", err)
+            }
+        }
+    }
+    Ok(installed)
+}
+
+/// look the query up in each dictionary, in order, and append what is new to `merged`
+/// - a dictionary that fails is logged and skipped, so the others still answer
+/// - a definition identical to one already found, e.g. from a dictionary installed twice, is left out
+async fn lookup_in_dictionaries(
+    state: &ServerState,
+    dictionaries: &[InstalledDictionary],
+    query: &str,
+    matched_candidate: Option<&LookupCandidate>,
+    merged: &mut Vec<WordDefinition>,
+) -> anyhow::Result<()> {
+    let dictionaries_dir = data_dir::get_dictionaries_dir()?;
+    for dictionary in dictionaries {
+        let absolute_path = dictionaries_dir
+            .join(&dictionary.dict_path)
+            .to_string_lossy()
+            .to_string();
+        // a diacritic-stripped form is rarely a headword itself, so the headwords that fold to it are looked up
+        let headwords = match matched_candidate.map(|candidate| &candidate.source) {
+            Some(LookupCandidateSource::DiacriticsStripped) => match state
+                .stardict_manager
+                .headword_index(absolute_path.clone())
+                .await
+            {
+                Ok(index) => index.folded_matches(query),
+                Err(err) => {
+                    warn!(dict_path = %dictionary.dict_path, "Could not read headwords: {:#}", err);
+                    continue;
+                }
+            },
+            _ => vec![query.to_string()],
+        };
+        for headword in headwords {
+            let definitions = match state
+                .stardict_manager
+                .lookup_word(absolute_path.clone(), &headword)
+                .await
+            {
+                Ok(definitions) => definitions.unwrap_or_default(),
+                Err(err) => {
+                    warn!(dict_path = %dictionary.dict_path, "Dictionary lookup failed: {:#}", err);
+                    continue;
+                }
+            };
+            for def in definitions {
+                let mut definition = WordDefinition::from_entry_with_metadata(
+                    def,
+                    &dictionary.dict_path,
+                    &state.server_url,
+                );
+                definition.dictionary_info.name = dictionary.bookname.clone();
+                definition.matched_candidate = matched_candidate.cloned();
+                if !merged.iter().any(|existing| {
+                    existing.word == definition.word && existing.segments == definition.segments
+                }) {
+                    merged.push(definition);
                 }
             }
         }
     }
+    Ok(())
+}
+
+/// look a word up under each of its lookup candidates, keeping what each adds in candidate order.
+/// the case-folded and diacritic-stripped fallbacks are only tried when nothing else was found
+pub(crate) async fn lookup_with_fallbacks(
+    state: &ServerState,
+    lang_id: InfluxResourceId,
+    surface: &str,
+    attributes: Option<&crate::nlp::SegAttribute>,
+) -> anyhow::Result<Vec<WordDefinition>> {
+    let dictionaries = enabled_language_dictionaries(state, lang_id).await?;
+    lookup_candidates_in(state, &dictionaries, surface, attributes).await
+}
+
+async fn lookup_candidates_in(
+    state: &ServerState,
+    dictionaries: &[InstalledDictionary],
+    surface: &str,
+    attributes: Option<&crate::nlp::SegAttribute>,
+) -> anyhow::Result<Vec<WordDefinition>> {
+    let mut merged = vec![];
+    for candidate in lookup_candidates(surface, attributes) {
+        if candidate.is_fallback() && !merged.is_empty() {
+            break;
+        }
+        debug!(candidate = %candidate.text, source = ?candidate.source, "Trying lookup candidate");
+        lookup_in_dictionaries(
+            state,
+            dictionaries,
+            &candidate.text,
+            Some(&candidate),
+            &mut merged,
+        )
+        .await?;
+    }
+    Ok(merged)
+}
 
-    dictionary_names.sort();
-    Ok(Json(dictionary_names))
+/// what a new token's definition and phonetic are filled with
+pub(crate) struct DefinitionFill {
+    pub definition: String,
+    pub phonetic: Option<String>,
+}
+
+/// the first sense of the first entry the language's top-priority dictionary has for the word, as plain text
+/// - the phonetic is the entry's transcription segment, its reading, e.g. the たべる of 食べる【たべる】,
+///   or else a pronunciation the definition gives before the sense
+/// - None when the language has no dictionary or the top one has no entry
+pub(crate) async fn definition_fill(
+    state: &ServerState,
+    lang_id: InfluxResourceId,
+    orthography: &str,
+) -> anyhow::Result<Option<DefinitionFill>> {
+    let dictionaries = enabled_language_dictionaries(state, lang_id).await?;
+    let Some(top) = dictionaries.first() else {
+        return Ok(None);
+    };
+    let definitions =
+        lookup_candidates_in(state, std::slice::from_ref(top), orthography, None).await?;
+    let Some(definition) = definitions.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let (headword, reading) = match definition.word.split_once('【') {
+        Some((expression, reading)) => (expression, Some(reading.trim_end_matches('】'))),
+        None => (definition.word.as_str(), None),
+    };
+    let is_transcription =
+        |segment: &&WordDefinitionSegment| segment.types == StardictType::Other("t".to_string());
+    let transcription = definition
+        .segments
+        .iter()
+        .find(is_transcription)
+        .map(|segment| segment.plain_text.trim());
+    let plain_text = definition
+        .segments
+        .iter()
+        .filter(|segment| !is_transcription(segment))
+        .map(|segment| segment.plain_text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let Some(first) = definition_html::first_sense(&plain_text, headword) else {
+        return Ok(None);
+    };
+    Ok(Some(DefinitionFill {
+        definition: first.sense,
+        phonetic: transcription
+            .or(reading)
+            .map(str::to_string)
+            .or(first.pronunciation)
+            .filter(|phonetic| !phonetic.trim().is_empty()),
+    }))
+}
+
+pub async fn language_dictionary_lookup(
+    State(state): State<ServerState>,
+    Json(request): Json<LanguageDictionaryLookupRequest>,
+) -> Result<Json<Vec<WordDefinition>>, ServerError> {
+    debug!(lang_id = ?request.lang_id, query = %request.query, "Looking up word in language dictionaries");
+    Ok(Json(
+        lookup_with_fallbacks(
+            &state,
+            request.lang_id,
+            &request.query,
+            request.attributes.as_ref(),
+        )
+        .await?,
+    ))
 }
 
 pub async fn open_app_data_dir() -> Result<Json<()>, ServerError> {
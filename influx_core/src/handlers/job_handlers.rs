@@ -0,0 +1,31 @@
+use super::ServerError;
+use crate::db::models::tokenisation_job::TokenisationJob;
+use crate::handlers::api_interfaces::*;
+use crate::ServerState;
+use axum::extract::State;
+use axum::Json;
+use tracing::debug;
+
+pub async fn get_tokenisation_jobs(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<TokenisationJobsRequest>,
+) -> Result<Json<Vec<TokenisationJob>>, ServerError> {
+    Ok(Json(
+        db.query_tokenisation_jobs(request.document_id, request.status)
+            .await?,
+    ))
+}
+
+pub async fn retry_tokenisation_jobs(
+    State(ServerState {
+        db,
+        tokenisation_queue,
+        ..
+    }): State<ServerState>,
+    Json(request): Json<RetryTokenisationJobsRequest>,
+) -> Result<Json<Vec<TokenisationJob>>, ServerError> {
+    let retried = db.retry_failed_tokenisation_jobs(request.job_id).await?;
+    debug!(retried = retried.len(), "Retrying failed tokenisation jobs");
+    tokenisation_queue.wake();
+    Ok(Json(retried))
+}
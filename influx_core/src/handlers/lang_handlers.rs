@@ -11,13 +11,14 @@ use crate::ServerState;
 use crate::db::InfluxResourceId;
 use axum::extract::State;
 use axum::http::StatusCode;
-use tracing::debug;
+use tracing::{debug, warn};
 
 #[derive(
     serde::Deserialize, serde::Serialize, elm_rs::Elm, elm_rs::ElmEncode, elm_rs::ElmDecode,
 )]
 pub struct LanguageCreateRequest {
     pub name: String,
+    pub language_code: String,
     pub dicts: Vec<String>,
     pub tts_rate: Option<f64>,
     pub tts_pitch: Option<f64>,
@@ -32,6 +33,7 @@ impl From<LanguageCreateRequest> for Language {
         Language {
             id: None,
             name: req.name,
+            language_code: req.language_code,
             dicts: req.dicts,
             tts_rate: req.tts_rate,
             tts_pitch: req.tts_pitch,
@@ -67,11 +69,36 @@ pub async fn get_language_by_id(
 }
 
 pub async fn update_language(
-    State(ServerState { db, .. }): State<ServerState>,
+    State(ServerState {
+        db,
+        tokenisation_queue,
+        ..
+    }): State<ServerState>,
     Json(payload): Json<Language>,
 ) -> Result<Json<Language>, ServerError> {
     debug!(language_id = ?payload.id, name = %payload.name, "Updating language");
-    Ok(Json(db.update_language(payload).await?))
+    let previous_parser_config = match payload.id.clone() {
+        Some(lang_id) => db
+            .get_language(lang_id)
+            .await?
+            .map(|language| language.parser_config),
+        None => None,
+    };
+    let language = db.update_language(payload).await?;
+
+    // cached annotations were made with the old parser, so re-tokenise every document in the background
+    if let (Some(previous), Some(lang_id)) = (previous_parser_config, language.id.clone()) {
+        if previous != language.parser_config {
+            match tokenisation_queue.enqueue_language(&db, lang_id).await {
+                Ok(queued) => debug!(queued, "Queued tokenisation after parser config change"),
+                Err(err) => warn!(
+                    "Failed to queue tokenisation after parser config change: {}",
+                    err
+                ),
+            }
+        }
+    }
+    Ok(Json(language))
 }
 
 pub async fn create_language(
@@ -84,7 +111,9 @@ pub async fn create_language(
 }
 
 pub async fn delete_language(
-    State(ServerState { db, .. }): State<ServerState>,
+    State(ServerState {
+        db, phrase_indexes, ..
+    }): State<ServerState>,
     Path(id): Path<String>,
 ) -> Result<StatusCode, ServerError> {
     let language_id = InfluxResourceId::SerialId(
@@ -92,6 +121,7 @@ pub async fn delete_language(
             .map_err(|_| ServerError(anyhow::anyhow!("Invalid language ID: {}", id)))?,
     );
     debug!(language_id = ?language_id, "Deleting language");
-    db.delete_language(language_id).await?;
+    db.delete_language(language_id.clone()).await?;
+    phrase_indexes.invalidate(&language_id);
     Ok(StatusCode::NO_CONTENT)
 }
@@ -1,13 +1,27 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use crate::ServerState;
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 
 pub mod api_interfaces;
 pub use api_interfaces::*;
+pub mod cache_handlers;
 pub mod doc_handlers;
 pub mod fsrs_handlers;
 pub mod integration_handlers;
+pub mod job_handlers;
 pub mod lang_handlers;
+pub mod stats_handlers;
 pub mod term_handlers;
 
 pub async fn connection_test() -> impl IntoResponse {
     StatusCode::OK
 }
+
+/// 200 when the NLP service answers, 503 with the reason when it does not
+pub async fn nlp_health(
+    State(ServerState { nlp_client, .. }): State<ServerState>,
+) -> impl IntoResponse {
+    match nlp_client.health_check().await {
+        Ok(()) => (StatusCode::OK, String::new()),
+        Err(err) => (StatusCode::SERVICE_UNAVAILABLE, err.to_string()),
+    }
+}
@@ -0,0 +1,119 @@
+use super::doc_handlers::rescore_document_difficulties;
+use super::ServerError;
+use crate::db::models::token_history::build_vocab_timeline;
+use crate::db::InfluxResourceId;
+use crate::handlers::api_interfaces::*;
+use crate::integration::frequency_list::{self, FrequencyListFormat};
+use crate::nlp::difficulty::recommend_documents;
+use crate::ServerState;
+use axum::extract::{Query, State};
+use axum::Json;
+use chrono::Utc;
+use serde::Deserialize;
+use tracing::debug;
+
+/// about one unknown word in twelve, where reading is still comfortable without constant lookups
+const DEFAULT_MAX_UNKNOWN_TOKEN_RATIO: f64 = 0.08;
+const DEFAULT_RECOMMENDATION_LIMIT: usize = 10;
+const DEFAULT_FREQUENT_UNKNOWN_LIMIT: i64 = 50;
+
+pub async fn get_vocab_timeline(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<VocabTimelineRequest>,
+) -> Result<Json<VocabTimelineResponse>, ServerError> {
+    debug!(lang_id = ?request.lang_id, granularity = ?request.granularity, "Building vocab timeline");
+    if request.backfill {
+        let inserted = db
+            .backfill_token_status_history(request.lang_id.clone())
+            .await?;
+        debug!(inserted, "Backfilled token status history");
+    }
+    let changes = db.query_token_status_history(request.lang_id).await?;
+    Ok(Json(VocabTimelineResponse {
+        buckets: build_vocab_timeline(&changes, request.granularity, Utc::now()),
+    }))
+}
+
+/// replace the language's lemma frequency list and rescore its documents with it
+pub async fn set_frequency_list(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<SetFrequencyListRequest>,
+) -> Result<Json<SetFrequencyListResponse>, ServerError> {
+    debug!(lang_id = ?request.lang_id, lemmas = request.lemmas.len(), "Setting frequency list");
+    let lemma_count = db
+        .replace_frequency_list(request.lang_id.clone(), &request.lemmas)
+        .await?;
+    let documents_rescored = rescore_document_difficulties(&db, request.lang_id).await?;
+    Ok(Json(SetFrequencyListResponse {
+        lemma_count,
+        documents_rescored,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct ImportFrequencyListQuery {
+    pub lang_id: i64,
+    pub format: Option<FrequencyListFormat>, // detected from the file when left out
+}
+
+/// the request body is the raw list file, see integration::frequency_list. replaces the language's
+/// frequency list and rescores its documents like set_frequency_list
+pub async fn import_frequency_list(
+    State(ServerState { db, .. }): State<ServerState>,
+    Query(query): Query<ImportFrequencyListQuery>,
+    file: axum::body::Bytes,
+) -> Result<Json<SetFrequencyListResponse>, ServerError> {
+    debug!(lang_id = query.lang_id, format = ?query.format, size = file.len(), "Importing frequency list");
+    let format = query.format;
+    let ranked =
+        tokio::task::spawn_blocking(move || frequency_list::parse_frequency_list(&file, format))
+            .await??;
+    let lang_id = InfluxResourceId::SerialId(query.lang_id);
+    let lemma_count = db
+        .replace_ranked_frequency_list(lang_id.clone(), &ranked)
+        .await?;
+    let documents_rescored = rescore_document_difficulties(&db, lang_id).await?;
+    Ok(Json(SetFrequencyListResponse {
+        lemma_count,
+        documents_rescored,
+    }))
+}
+
+/// the lemmas most worth learning next: the most frequent the learner has not marked yet
+pub async fn get_frequent_unknown_lemmas(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<FrequentUnknownLemmasRequest>,
+) -> Result<Json<FrequentUnknownLemmasResponse>, ServerError> {
+    debug!(lang_id = ?request.lang_id, limit = ?request.limit, "Querying frequent unknown lemmas");
+    let limit = request
+        .limit
+        .unwrap_or(DEFAULT_FREQUENT_UNKNOWN_LIMIT)
+        .max(0);
+    Ok(Json(FrequentUnknownLemmasResponse {
+        lemmas: db
+            .query_frequent_unknown_lemmas(request.lang_id, limit)
+            .await?,
+    }))
+}
+
+/// the texts to read next, by their stored difficulty
+pub async fn get_recommended_documents(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<RecommendDocumentsRequest>,
+) -> Result<Json<RecommendDocumentsResponse>, ServerError> {
+    debug!(lang_id = ?request.lang_id, refresh = request.refresh, "Recommending documents");
+    if request.refresh {
+        let rescored = rescore_document_difficulties(&db, request.lang_id.clone()).await?;
+        debug!(rescored, "Rescored document difficulties");
+    }
+    let difficulties = db.get_document_difficulties(request.lang_id).await?;
+    Ok(Json(RecommendDocumentsResponse {
+        recommended: recommend_documents(
+            difficulties,
+            request
+                .max_unknown_token_ratio
+                .unwrap_or(DEFAULT_MAX_UNKNOWN_TOKEN_RATIO),
+            request.limit.unwrap_or(DEFAULT_RECOMMENDATION_LIMIT),
+        ),
+    }))
+}
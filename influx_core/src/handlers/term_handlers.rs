@@ -1,11 +1,17 @@
+use super::integration_handlers::definition_fill;
 use super::ServerError;
 use crate::db::models::phrase::Phrase;
-use crate::db::models::vocab::Token;
+use crate::db::models::vocab::{Token, TokenStatus};
+use crate::db::DB;
 use crate::handlers::api_interfaces::*;
+use crate::nlp::collocations::{mine_collocations, CollocationMinerConfig};
+use crate::nlp::term_delta::AffectedTerms;
+use crate::nlp::AnnotatedDocV2;
 use crate::ServerState;
 use axum::extract::State;
 use axum::Json;
-use tracing::debug;
+use std::collections::BTreeSet;
+use tracing::{debug, warn};
 
 pub async fn create_token(
     State(ServerState { db, .. }): State<ServerState>,
@@ -16,11 +22,23 @@ pub async fn create_token(
 }
 
 async fn create_phrase(
-    State(ServerState { db, .. }): State<ServerState>,
+    State(ServerState {
+        db, phrase_indexes, ..
+    }): State<ServerState>,
     Json(payload): Json<Phrase>,
 ) -> Result<Json<Phrase>, ServerError> {
     debug!(phrase_id = ?payload.id, orthography_seq = ?payload.orthography_seq, "Creating phrase");
-    Ok(Json(db.create_phrase(payload).await?))
+    let phrase = db.create_phrase(payload).await?;
+    phrase_indexes.phrase_saved(None, &phrase);
+    Ok(Json(phrase))
+}
+
+/// the stored phrase an update replaces, whose orthography_seq may be about to change
+async fn previous_phrase(db: &DB, phrase: &Phrase) -> anyhow::Result<Option<Phrase>> {
+    match &phrase.id {
+        Some(id) => db.query_phrase_by_id(id.clone()).await,
+        None => Ok(None),
+    }
 }
 
 pub async fn update_token(
@@ -32,11 +50,16 @@ pub async fn update_token(
 }
 
 pub async fn update_phrase(
-    State(ServerState { db, .. }): State<ServerState>,
+    State(ServerState {
+        db, phrase_indexes, ..
+    }): State<ServerState>,
     Json(payload): Json<Phrase>,
 ) -> Result<Json<Phrase>, ServerError> {
     debug!(phrase_id = ?payload.id, orthography_seq = ?payload.orthography_seq, "Updating phrase");
-    Ok(Json(db.update_phrase(payload).await?))
+    let previous = previous_phrase(&db, &payload).await?;
+    let phrase = db.update_phrase(payload).await?;
+    phrase_indexes.phrase_saved(previous.as_ref(), &phrase);
+    Ok(Json(phrase))
 }
 
 pub async fn delete_token(
@@ -48,11 +71,137 @@ pub async fn delete_token(
 }
 
 pub async fn delete_phrase(
-    State(ServerState { db, .. }): State<ServerState>,
+    State(ServerState {
+        db, phrase_indexes, ..
+    }): State<ServerState>,
     Json(payload): Json<Phrase>,
 ) -> Result<Json<Phrase>, ServerError> {
     debug!(phrase_id = ?payload.id, orthography_seq = ?payload.orthography_seq, "Deleting phrase");
-    Ok(Json(db.delete_phrase_and_return_deleted(payload).await?))
+    let phrase = db.delete_phrase_and_return_deleted(payload).await?;
+    phrase_indexes.phrase_deleted(&phrase);
+    Ok(Json(phrase))
+}
+
+/// the language's tokens with their frequency ranks, most frequent first
+pub async fn list_terms(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<TermListRequest>,
+) -> Result<Json<TermListResponse>, ServerError> {
+    debug!(lang_id = ?request.lang_id, status = ?request.status, "Listing terms");
+    let tokens = db
+        .query_tokens_by_lang_id(request.lang_id.clone(), request.status)
+        .await?;
+    let ranks = db
+        .get_lemma_ranks(
+            request.lang_id,
+            &tokens
+                .iter()
+                .map(|token| token.orthography.clone())
+                .collect(),
+        )
+        .await?;
+    let mut tokens = tokens
+        .into_iter()
+        .map(|token| RankedToken {
+            frequency_rank: ranks.get(&token.orthography).copied(),
+            token,
+        })
+        .collect::<Vec<_>>();
+    // tokens come by orthography, and the sort is stable
+    tokens.sort_by_key(|ranked| (ranked.frequency_rank.is_none(), ranked.frequency_rank));
+    Ok(Json(TermListResponse { tokens }))
+}
+
+/// mine collocations from the cached annotated documents of a language
+/// - documents that have never been opened have no cache and are not mined
+pub async fn get_phrase_suggestions(
+    State(ServerState { db, .. }): State<ServerState>,
+    Json(request): Json<PhraseSuggestionsRequest>,
+) -> Result<Json<PhraseSuggestionsResponse>, ServerError> {
+    debug!(lang_id = ?request.lang_id, "Mining phrase suggestions");
+    let docs = db
+        .get_annotated_document_caches_by_lang(request.lang_id.clone())
+        .await?
+        .into_iter()
+        .filter_map(|(_, cached)| serde_json::from_value::<AnnotatedDocV2>(cached).ok())
+        .collect::<Vec<_>>();
+    let existing = db
+        .query_phrases_by_lang_id(request.lang_id)
+        .await?
+        .into_iter()
+        .map(|phrase| phrase.orthography_seq)
+        .collect::<BTreeSet<_>>();
+
+    let default_config = CollocationMinerConfig::default();
+    let config = CollocationMinerConfig {
+        min_count: request.min_count.unwrap_or(default_config.min_count),
+        max_suggestions: request
+            .max_suggestions
+            .unwrap_or(default_config.max_suggestions),
+        ..default_config
+    };
+    let suggestions = mine_collocations(&docs, &existing, &config);
+    debug!(
+        documents = docs.len(),
+        suggestions = suggestions.len(),
+        "Mined phrase suggestions"
+    );
+    Ok(Json(PhraseSuggestionsResponse { suggestions }))
+}
+
+pub async fn accept_phrase_suggestion(
+    State(ServerState {
+        db, phrase_indexes, ..
+    }): State<ServerState>,
+    Json(request): Json<AcceptPhraseSuggestionRequest>,
+) -> Result<Json<Phrase>, ServerError> {
+    let suggestion = request.suggestion;
+    debug!(orthography_seq = ?suggestion.orthography_seq, "Accepting phrase suggestion");
+    let orthography_seq = suggestion
+        .orthography_seq
+        .iter()
+        .map(|x| x.to_lowercase())
+        .collect::<Vec<_>>();
+    if db
+        .phrase_exists(request.lang_id.clone(), orthography_seq.clone())
+        .await?
+    {
+        return Err(anyhow::anyhow!("Phrase {:?} already exists", orthography_seq).into());
+    }
+    let phrase = Phrase {
+        id: None,
+        lang_id: request.lang_id,
+        orthography_seq,
+        max_gap: None,
+        match_by_lemma: suggestion.match_by_lemma,
+        definition: "".to_string(),
+        notes: "".to_string(),
+        original_context: suggestion
+            .example_sentences
+            .first()
+            .cloned()
+            .unwrap_or_default(),
+        status: TokenStatus::L1,
+    };
+    let phrase = db.create_phrase(phrase).await?;
+    phrase_indexes.phrase_saved(None, &phrase);
+    Ok(Json(phrase))
+}
+
+/// fill the token's definition, and its phonetic if that is empty too, from the language's top dictionary.
+/// returns whether it was filled. the token is left unchanged when the dictionary has nothing for it, or on error
+async fn fill_token_definition(state: &ServerState, token: &mut Token) -> anyhow::Result<bool> {
+    let Some(fill) = definition_fill(state, token.lang_id.clone(), &token.orthography).await?
+    else {
+        return Ok(false);
+    };
+    token.definition = fill.definition;
+    if token.phonetic.trim().is_empty() {
+        if let Some(phonetic) = fill.phonetic {
+            token.phonetic = phonetic;
+        }
+    }
+    Ok(true)
 }
 
 pub async fn edit_term(
@@ -62,22 +211,52 @@ pub async fn edit_term(
     debug!(action = ?request.requested_action, document_id = ?request.document_id, "Processing term edit request");
     use Term::*;
     use TermEditAction::*;
+    // an update may have moved the phrase to another onset, whose sentences change too
+    let mut replaced_phrase = None;
     let term_becomes = match (&request.requested_action, request.term) {
-        (CreateTerm, TokenTerm(token)) => TokenTerm(state.db.create_token(token).await?),
-        (CreateTerm, PhraseTerm(phrase)) => PhraseTerm(state.db.create_phrase(phrase).await?),
+        (CreateTerm, TokenTerm(mut token)) => {
+            // the term is saved unfilled rather than lost when the dictionary cannot be read
+            if request.fill_definition && token.definition.trim().is_empty() {
+                if let Err(err) = fill_token_definition(&state, &mut token).await {
+                    warn!(orthography = %token.orthography, "Filling definition failed: {:#}", err);
+                }
+            }
+            TokenTerm(state.db.create_token(token).await?)
+        }
+        (CreateTerm, PhraseTerm(phrase)) => {
+            let phrase = state.db.create_phrase(phrase).await?;
+            state.phrase_indexes.phrase_saved(None, &phrase);
+            PhraseTerm(phrase)
+        }
         (UpdateTerm, TokenTerm(token)) => TokenTerm(state.db.update_token(token).await?),
-        (UpdateTerm, PhraseTerm(phrase)) => PhraseTerm(state.db.update_phrase(phrase).await?),
+        (UpdateTerm, PhraseTerm(phrase)) => {
+            let previous = previous_phrase(&state.db, &phrase).await?;
+            let phrase = state.db.update_phrase(phrase).await?;
+            state
+                .phrase_indexes
+                .phrase_saved(previous.as_ref(), &phrase);
+            replaced_phrase = previous;
+            PhraseTerm(phrase)
+        }
         (DeleteTerm, TokenTerm(token)) => {
             TokenTerm(state.db.delete_token_and_return_unmarked(token).await?)
         }
         (DeleteTerm, PhraseTerm(phrase)) => {
-            PhraseTerm(state.db.delete_phrase_and_return_unmarked(phrase).await?)
+            let unmarked = state.db.delete_phrase_and_return_unmarked(phrase).await?;
+            state.phrase_indexes.phrase_deleted(&unmarked);
+            PhraseTerm(unmarked)
         }
     };
 
-    let updated_annotated_doc = if let Some(document_id) = request.document_id {
-        let response = super::doc_handlers::get_annotated_doc_logic(&state, document_id).await?;
-        Some(response.annotated_doc)
+    let mut affected = match &term_becomes {
+        TokenTerm(token) => AffectedTerms::of_token(token),
+        PhraseTerm(phrase) => AffectedTerms::of_phrase(phrase),
+    };
+    if let Some(previous) = &replaced_phrase {
+        affected.extend(AffectedTerms::of_phrase(previous));
+    }
+    let annotated_doc_delta = if let Some(document_id) = request.document_id {
+        Some(super::doc_handlers::get_annotated_doc_delta(&state, document_id, &affected).await?)
     } else {
         None
     };
@@ -85,6 +264,36 @@ pub async fn edit_term(
     Ok(Json(TermEditResponse {
         term: term_becomes,
         performed_action: request.requested_action,
-        updated_annotated_doc,
+        annotated_doc_delta,
     }))
 }
+
+/// fill the definition of every L1 token of the language whose definition is empty, see edit_term's fill_definition
+/// - a token the dictionary fails on is logged and left for the next run
+pub async fn fill_definitions(
+    State(state): State<ServerState>,
+    Json(request): Json<FillDefinitionsRequest>,
+) -> Result<Json<FillDefinitionsResponse>, ServerError> {
+    debug!(lang_id = ?request.lang_id, "Filling empty L1 definitions from dictionary");
+    let tokens = state
+        .db
+        .query_tokens_without_definition(request.lang_id.clone(), TokenStatus::L1)
+        .await?;
+    let mut filled = vec![];
+    let mut not_found = vec![];
+    for mut token in tokens {
+        match fill_token_definition(&state, &mut token).await {
+            Ok(true) => filled.push(state.db.update_token(token).await?),
+            Ok(false) => not_found.push(token.orthography),
+            Err(err) => {
+                warn!(orthography = %token.orthography, "Filling definition failed: {:#}", err)
+            }
+        }
+    }
+    debug!(
+        filled = filled.len(),
+        not_found = not_found.len(),
+        "Filled empty L1 definitions"
+    );
+    Ok(Json(FillDefinitionsResponse { filled, not_found }))
+}
@@ -0,0 +1,438 @@
+///! preparing StarDict html definitions for the client
+///! - sanitized against an allow-list of tags and attributes, since dictionaries come from anywhere
+///! - relative src/href values point into the dictionary's res/ folder, served under /influx_app_data
+///! - bword:// cross-references become influx://lookup/ links the client can look up in place
+///! - a plain-text rendering, for card backs and anywhere markup can't go
+///! - the first sense and pronunciation of a plain-text definition, to fill in new terms with
+use ammonia::{Builder, UrlRelative};
+use maplit::hashset;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::borrow::Cow;
+
+/// characters escaped in a path segment, on top of controls and non-ascii
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'\\')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+pub const LOOKUP_LINK_PREFIX: &str = "influx://lookup/";
+
+fn encode_segment(segment: &str) -> String {
+    utf8_percent_encode(
+        &percent_decode_str(segment).decode_utf8_lossy(),
+        PATH_SEGMENT,
+    )
+    .to_string()
+}
+
+/// e.g. http://127.0.0.1:3000/influx_app_data/dictionaries/stardicts/French%20-%20English/res
+pub fn resource_base_url(server_url: &str, directory_name: &str) -> String {
+    format!(
+        "{}/influx_app_data/dictionaries/stardicts/{}/res",
+        server_url.trim_end_matches('/'),
+        encode_segment(directory_name)
+    )
+}
+
+/// link the client follows by looking the word up
+pub fn lookup_link(word: &str) -> String {
+    format!("{}{}", LOOKUP_LINK_PREFIX, encode_segment(word))
+}
+
+/// a relative resource path inside res/, or None for one that would climb out of it
+fn resource_url(resource_base_url: &str, relative: &str) -> Option<String> {
+    // keep the fragment, e.g. for sprite sheets, but not a query, which static files ignore anyway
+    let (path, fragment) = match relative.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (relative, None),
+    };
+    let path = path.split('?').next().unwrap_or_default();
+    let mut segments = vec![];
+    for segment in path.split(['/', '\\']) {
+        match percent_decode_str(segment).decode_utf8_lossy().as_ref() {
+            "" | "." => {}
+            ".." => return None,
+            _ => segments.push(encode_segment(segment)),
+        }
+    }
+    let mut url = format!("{}/{}", resource_base_url, segments.join("/"));
+    if let Some(fragment) = fragment {
+        url.push('#');
+        url.push_str(fragment);
+    }
+    Some(url)
+}
+
+/// resolves relative urls against the res/ folder, keeping in-page anchors as they are
+fn relative_url_evaluator(
+    resource_base_url: String,
+) -> impl Fn(&str) -> Option<Cow<'_, str>> + Send + Sync {
+    move |relative| {
+        if relative.starts_with('#') {
+            return Some(Cow::Borrowed(relative));
+        }
+        resource_url(&resource_base_url, relative).map(Cow::Owned)
+    }
+}
+
+/// no scheme or host, so the url is resolved against res/ by relative_url_evaluator
+fn is_relative_url(url: &str) -> bool {
+    let url = url.trim();
+    let before_path = url.split(['/', '?', '#']).next().unwrap_or_default();
+    !url.starts_with("//") && !url.starts_with('\\') && !before_path.contains(':')
+}
+
+/// sanitize a definition, pointing its resources at resource_base_url, see resource_base_url
+pub fn sanitize_definition_html(html: &str, resource_base_url: &str) -> String {
+    let mut builder = Builder::default();
+    builder
+        .add_tags(hashset![
+            "font",
+            "link",
+            "rt",
+            "rp",
+            "ruby",
+            "rb",
+            "big",
+            "small",
+            "u",
+            "s",
+            "sub",
+            "sup",
+            "figure",
+            "figcaption",
+            "details",
+            "summary",
+            "audio",
+            "source",
+        ])
+        // dictionaries lean on inline styles and classes for their own stylesheets
+        .add_generic_attributes(hashset![
+            "class",
+            "style",
+            "lang",
+            "title",
+            "data-sc-content"
+        ])
+        .add_tag_attributes("font", hashset!["color", "face", "size"])
+        .add_tag_attributes("link", hashset!["rel", "href", "type"])
+        .add_tag_attributes("audio", hashset!["src", "controls"])
+        .add_tag_attributes("source", hashset!["src", "type"])
+        // bword is only let through for the filter below to turn into lookup links
+        .add_url_schemes(hashset!["influx", "bword"])
+        .url_relative(UrlRelative::Custom(Box::new(relative_url_evaluator(
+            resource_base_url.to_string(),
+        ))))
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            (_, "href") if value.trim_start().starts_with("bword://") => Some(Cow::Owned(
+                lookup_link(value.trim_start().trim_start_matches("bword://")),
+            )),
+            (_, _) if value.trim_start().starts_with("bword://") => None,
+            // a stylesheet may only come from the dictionary's own res/ folder, a remote one could track lookups
+            ("link", "href") if !is_relative_url(value) => None,
+            // url() would load from anywhere, and expression() runs script in old engines
+            (_, "style")
+                if value.to_lowercase().contains("url(")
+                    || value.to_lowercase().contains("expression(") =>
+            {
+                None
+            }
+            _ => Some(Cow::Borrowed(value)),
+        });
+    builder.clean(html).to_string()
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// the text of an html definition, a line per block element, without markup
+pub fn html_to_plain_text(html: &str) -> String {
+    const BLOCK_TAGS: [&str; 14] = [
+        "br",
+        "p",
+        "div",
+        "li",
+        "ul",
+        "ol",
+        "tr",
+        "table",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "blockquote",
+        "hr",
+    ];
+    // the text inside these is not part of the definition. ruby readings would run into their base text
+    const SKIPPED_TAGS: [&str; 4] = ["script", "style", "rt", "rp"];
+
+    let mut text = String::new();
+    let mut rest = html;
+    let mut skipping: Option<String> = None;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => {
+                let Some(end) = rest.find('>') else {
+                    break;
+                };
+                let tag = rest[1..end].trim();
+                let is_closing = tag.starts_with('/');
+                let name = tag
+                    .trim_start_matches('/')
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_lowercase();
+                match &skipping {
+                    Some(skipped) if is_closing && name == *skipped => skipping = None,
+                    Some(_) => {}
+                    None if SKIPPED_TAGS.contains(&name.as_str())
+                        && !is_closing
+                        && !tag.ends_with('/') =>
+                    {
+                        skipping = Some(name);
+                    }
+                    None if BLOCK_TAGS.contains(&name.as_str())
+                        && !text.is_empty()
+                        && !text.ends_with('\n') =>
+                    {
+                        text.push('\n');
+                    }
+                    None => {}
+                }
+                rest = &rest[end + 1..];
+            }
+            '&' if skipping.is_none() => {
+                let decoded = rest[1..]
+                    .find(';')
+                    .filter(|end| *end <= 10)
+                    .and_then(|end| decode_entity(&rest[1..end + 1]).map(|c| (c, end + 2)));
+                match decoded {
+                    Some((c, length)) => {
+                        text.push(c);
+                        rest = &rest[length..];
+                    }
+                    None => {
+                        text.push('&');
+                        rest = &rest[1..];
+                    }
+                }
+            }
+            _ => {
+                if skipping.is_none() {
+                    text.push(c);
+                }
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// a definition's first sense, with the pronunciation given before it if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirstSense {
+    pub sense: String,
+    pub pronunciation: Option<String>,
+}
+
+/// the first /.../ or [...] in text, e.g. the "ʃa" of "chat [ʃa] n.m."
+fn bracketed_pronunciation(text: &str) -> Option<String> {
+    [('/', '/'), ('[', ']')]
+        .into_iter()
+        .filter_map(|(open, close)| {
+            let start = text.find(open)?;
+            let length = text[start + 1..].find(close)?;
+            Some((start, text[start + 1..start + 1 + length].trim()))
+        })
+        .filter(|(_, pronunciation)| !pronunciation.is_empty())
+        .min_by_key(|(start, _)| *start)
+        .map(|(_, pronunciation)| pronunciation.to_string())
+}
+
+/// "1. ", "2) ", "①" and the like at the start of a sense
+fn strip_sense_number(line: &str) -> &str {
+    let line = line.trim_start_matches(|c: char| ('\u{2460}'..='\u{2473}').contains(&c));
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let line = match line[digits..].chars().next() {
+        Some(c @ ('.' | ')' | ':')) if digits > 0 => &line[digits + c.len_utf8()..],
+        _ => line,
+    };
+    line.trim()
+}
+
+/// the first sense of a plain-text definition, see html_to_plain_text
+/// - lines starting with the headword introduce the entry, they only give its pronunciation
+/// - a line that is only a pronunciation, e.g. "/ʃa/", is not a sense either
+pub fn first_sense(plain_text: &str, headword: &str) -> Option<FirstSense> {
+    let headword = headword.trim().to_lowercase();
+    let mut pronunciation = None;
+    for line in plain_text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let lowercase = line.to_lowercase();
+        let introduces_headword = !headword.is_empty()
+            && lowercase.strip_prefix(&headword).is_some_and(|rest| {
+                rest.is_empty()
+                    || rest.starts_with(|c: char| c.is_whitespace() || c == '/' || c == '[')
+            });
+        let only_pronunciation = (line.starts_with('/') && line.ends_with('/'))
+            || (line.starts_with('[') && line.ends_with(']'));
+        if introduces_headword || only_pronunciation {
+            if pronunciation.is_none() {
+                pronunciation = bracketed_pronunciation(line);
+            }
+            continue;
+        }
+        let sense = strip_sense_number(line);
+        if !sense.is_empty() {
+            return Some(FirstSense {
+                sense: sense.to_string(),
+                pronunciation,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    const BASE: &str = "http://127.0.0.1:3000/influx_app_data/dictionaries/stardicts/fr%20en/res";
+
+    #[test]
+    fn test_resource_urls() {
+        assert_eq!(resource_base_url("http://127.0.0.1:3000/", "fr en"), BASE);
+        expect!["http://127.0.0.1:3000/influx_app_data/dictionaries/stardicts/fr%20en/res/img/%C3%A9t%C3%A9%20d'hiver.png"]
+            .assert_eq(&resource_url(BASE, "./img/été d'hiver.png").unwrap());
+        expect!["http://127.0.0.1:3000/influx_app_data/dictionaries/stardicts/fr%20en/res/a%20b.png#top"]
+            .assert_eq(&resource_url(BASE, "a%20b.png?v=2#top").unwrap());
+        assert_eq!(resource_url(BASE, "../../secret.txt"), None);
+        assert_eq!(resource_url(BASE, "img/%2e%2e/x.png"), None);
+        assert_eq!(
+            lookup_link("être en train"),
+            "influx://lookup/%C3%AAtre%20en%20train"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_definition_html() {
+        let sanitized = sanitize_definition_html(
+            r#"<link rel="stylesheet" href="style.css"><div class="sense" onclick="steal()">voir <a href="bword://être">être</a> <img src="../x.png"><img src="pic.png"><script>steal()</script><span style="background: url(http://tracker)">x</span></div>"#,
+            BASE,
+        );
+        assert!(
+            sanitized.contains(&format!(r#"href="{}/style.css""#, BASE)),
+            "{}",
+            sanitized
+        );
+        assert!(sanitized.contains(r#"class="sense""#), "{}", sanitized);
+        assert!(
+            sanitized.contains(r#"href="influx://lookup/%C3%AAtre""#),
+            "{}",
+            sanitized
+        );
+        assert!(
+            sanitized.contains(&format!(r#"src="{}/pic.png""#, BASE)),
+            "{}",
+            sanitized
+        );
+        for removed in ["onclick", "script", "steal", "../x.png", "tracker"] {
+            assert!(!sanitized.contains(removed), "{} in {}", removed, sanitized);
+        }
+
+        for remote in [
+            "https://tracker.example/style.css",
+            "//tracker.example/style.css",
+            "HTTP:tracker.example/style.css",
+        ] {
+            let sanitized = sanitize_definition_html(
+                &format!(r#"<link rel="stylesheet" href="{}">"#, remote),
+                BASE,
+            );
+            assert_eq!(sanitized, r#"<link rel="stylesheet">"#, "{}", remote);
+        }
+        assert_eq!(
+            sanitize_definition_html(r#"<img src="bword://chat">"#, BASE),
+            "<img>"
+        );
+    }
+
+    #[test]
+    fn test_html_to_plain_text() {
+        expect![[r#"
+            chat [ʃa] n.m.
+            1. cat
+            2. tom & co
+            漢字"#]]
+        .assert_eq(&html_to_plain_text(
+            "<style>.x { color: red }</style><div><b>chat</b> [ʃa] <i>n.m.</i></div><ol><li>1. cat</li><li>2. tom &amp; co&nbsp;</li></ol><ruby>漢<rp>(</rp><rt>かん</rt><rp>)</rp></ruby><ruby>字<rt>じ</rt></ruby><br/>",
+        ));
+        assert_eq!(html_to_plain_text("a &unknown; b < c"), "a &unknown; b");
+        assert_eq!(html_to_plain_text("&#233;t&#xE9;"), "été");
+    }
+
+    #[test]
+    fn test_first_sense() {
+        let plain_text = html_to_plain_text(
+            "<div><b>chat</b> [ʃa] <i>n.m.</i></div><ol><li>1. cat</li><li>2. tom</li></ol>",
+        );
+        assert_eq!(
+            first_sense(&plain_text, "chat"),
+            Some(FirstSense {
+                sense: "cat".to_string(),
+                pronunciation: Some("ʃa".to_string()),
+            })
+        );
+        assert_eq!(
+            first_sense("/ʃa/\n1.\ncat; tomcat\nchatte", "Chat"),
+            Some(FirstSense {
+                sense: "cat; tomcat".to_string(),
+                pronunciation: Some("ʃa".to_string()),
+            })
+        );
+        // chatte only starts with chat, it is not the headword
+        assert_eq!(
+            first_sense("chatte: she-cat", "chat").map(|first| first.sense),
+            Some("chatte: she-cat".to_string())
+        );
+        assert_eq!(
+            first_sense("食べる\n①to eat\n②to live on", "食べる").map(|first| first.sense),
+            Some("to eat".to_string())
+        );
+        assert_eq!(first_sense("chat [ʃa]\n2)", "chat"), None);
+    }
+}
@@ -0,0 +1,431 @@
+///! installing StarDict bundles into the dictionaries directory, and removing and renaming them
+///! - a bundle is a .zip, .tar.gz or .tar.bz2 holding one dictionary: its .ifo, .idx(.gz) and .dict(.dz),
+///!   and optionally a res/ folder of resources next to them, possibly inside a top level folder
+///! - bundles are unpacked into a temporary directory inside the dictionaries directory and moved into
+///!   place once validated, so a failed install leaves nothing behind
+///! - unpacking stops at MAX_UNPACKED_BYTES, so a small archive can't fill the disk
+use super::dictionary_registry::{self, InstalledDictionary};
+use anyhow::{Context, Result};
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::{Path, PathBuf};
+
+/// total size of the unpacked files of a bundle. dictionaries with many images run to a few gigabytes
+const MAX_UNPACKED_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+    TarBz2,
+}
+
+impl ArchiveFormat {
+    /// by the archive's magic bytes, since uploads carry no reliable file name
+    pub fn detect(archive: &[u8]) -> Result<Self> {
+        if archive.starts_with(b"PK\x03\x04") {
+            Ok(ArchiveFormat::Zip)
+        } else if archive.starts_with(&[0x1f, 0x8b]) {
+            Ok(ArchiveFormat::TarGz)
+        } else if archive.starts_with(b"BZh") {
+            Ok(ArchiveFormat::TarBz2)
+        } else {
+            anyhow::bail!("unsupported archive, expected a .zip, .tar.gz or .tar.bz2")
+        }
+    }
+}
+
+/// a directory name directly under the dictionaries directory, so it can't escape it or hide as a dotfile
+pub fn validate_directory_name(name: &str) -> Result<()> {
+    if name.trim().is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\'])
+        || name.chars().any(char::is_control)
+    {
+        anyhow::bail!("invalid dictionary directory name {:?}", name);
+    }
+    Ok(())
+}
+
+/// check that directory_name is installed and new_directory_name is free, before anything is unloaded for the rename
+pub fn validate_rename(
+    dictionaries_dir: &Path,
+    directory_name: &str,
+    new_directory_name: &str,
+) -> Result<()> {
+    validate_directory_name(directory_name)?;
+    validate_directory_name(new_directory_name)?;
+    if !dictionaries_dir.join(directory_name).is_dir() {
+        anyhow::bail!("no dictionary named {:?} is installed", directory_name);
+    }
+    if dictionaries_dir.join(new_directory_name).exists() {
+        anyhow::bail!(
+            "a dictionary named {:?} is already installed",
+            new_directory_name
+        );
+    }
+    Ok(())
+}
+
+/// count the bytes unpacked so far, failing once they pass the limit
+struct UnpackBudget {
+    remaining: u64,
+}
+
+impl UnpackBudget {
+    fn spend(&mut self, bytes: u64) -> Result<()> {
+        self.remaining = self
+            .remaining
+            .checked_sub(bytes)
+            .ok_or_else(|| anyhow::anyhow!("the archive unpacks to more than the allowed size"))?;
+        Ok(())
+    }
+}
+
+fn unpack_zip(archive: impl Read + Seek, dest: &Path, budget: &mut UnpackBudget) -> Result<()> {
+    let mut zip = zip::ZipArchive::new(archive)?;
+    for i in 0..zip.len() {
+        let file = zip.by_index(i)?;
+        let relative = file
+            .enclosed_name()
+            .ok_or_else(|| anyhow::anyhow!("unsafe path in archive: {}", file.name()))?;
+        let out_path = dest.join(relative);
+        if file.is_dir() {
+            std::fs::create_dir_all(&out_path)?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // the sizes in the zip's headers can lie, so what is actually read is counted
+        let written = std::io::copy(
+            &mut file.take(budget.remaining + 1),
+            &mut File::create(&out_path)?,
+        )?;
+        budget.spend(written)?;
+    }
+    Ok(())
+}
+
+fn unpack_tar(reader: impl Read, dest: &Path, budget: &mut UnpackBudget) -> Result<()> {
+    let mut tar = tar::Archive::new(reader);
+    tar.set_preserve_permissions(false);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        // links could point outside the dictionary, and StarDict bundles have no use for them
+        if matches!(
+            entry.header().entry_type(),
+            tar::EntryType::Symlink | tar::EntryType::Link
+        ) {
+            continue;
+        }
+        // a tar entry is exactly as long as its header says
+        budget.spend(entry.size())?;
+        // unpack_in refuses paths that would land outside dest
+        if !entry.unpack_in(dest)? {
+            anyhow::bail!("unsafe path in archive: {}", entry.path()?.display());
+        }
+    }
+    Ok(())
+}
+
+fn unpack(archive: &Path, dest: &Path, max_bytes: u64) -> Result<()> {
+    let mut file = File::open(archive)?;
+    let mut magic = [0; 4];
+    let magic_len = file.read(&mut magic)?;
+    file.rewind()?;
+    let budget = &mut UnpackBudget {
+        remaining: max_bytes,
+    };
+    match ArchiveFormat::detect(&magic[..magic_len])? {
+        ArchiveFormat::Zip => unpack_zip(file, dest, budget),
+        ArchiveFormat::TarGz => unpack_tar(GzDecoder::new(BufReader::new(file)), dest, budget),
+        ArchiveFormat::TarBz2 => unpack_tar(BzDecoder::new(BufReader::new(file)), dest, budget),
+    }
+}
+
+fn find_ifo_files(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_ifo_files(&path, found)?;
+        } else if path.extension().is_some_and(|extension| extension == "ifo") {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// the .ifo of the one dictionary in the unpacked bundle, checked to have its index and data files
+fn find_dictionary(unpacked: &Path) -> Result<PathBuf> {
+    let mut ifo_files = vec![];
+    find_ifo_files(unpacked, &mut ifo_files)?;
+    let ifo_path = match ifo_files.as_slice() {
+        [ifo_path] => ifo_path.clone(),
+        [] => anyhow::bail!("no .ifo file in the archive"),
+        _ => anyhow::bail!(
+            "the archive holds {} dictionaries, install them one at a time",
+            ifo_files.len()
+        ),
+    };
+
+    let dir = ifo_path.parent().unwrap_or(unpacked);
+    let stem = ifo_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| anyhow::anyhow!("invalid .ifo file name"))?;
+    let has_any = |extensions: &[&str]| {
+        extensions
+            .iter()
+            .any(|extension| dir.join(format!("{}.{}", stem, extension)).is_file())
+    };
+    if !has_any(&["idx", "idx.gz"]) {
+        anyhow::bail!("{}.idx is missing from the archive", stem);
+    }
+    if !has_any(&["dict", "dict.dz"]) {
+        anyhow::bail!(
+            "{}.dict or {}.dict.dz is missing from the archive",
+            stem,
+            stem
+        );
+    }
+    dictionary_registry::parse_ifo(&std::fs::read_to_string(&ifo_path)?)
+        .with_context(|| format!("invalid {}.ifo", stem))?;
+    Ok(ifo_path)
+}
+
+/// unpack the bundle in the archive file into its own directory under dictionaries_dir
+/// - the directory is named directory_name, or else after the bundle's top level folder or its .ifo
+/// - fails if that directory already exists
+pub fn install_dictionary(
+    dictionaries_dir: &Path,
+    archive: &Path,
+    directory_name: Option<&str>,
+) -> Result<InstalledDictionary> {
+    install_dictionary_within(
+        dictionaries_dir,
+        archive,
+        directory_name,
+        MAX_UNPACKED_BYTES,
+    )
+}
+
+fn install_dictionary_within(
+    dictionaries_dir: &Path,
+    archive: &Path,
+    directory_name: Option<&str>,
+    max_unpacked_bytes: u64,
+) -> Result<InstalledDictionary> {
+    std::fs::create_dir_all(dictionaries_dir)?;
+    let unpacked = tempfile::Builder::new()
+        .prefix(".installing-")
+        .tempdir_in(dictionaries_dir)?;
+    unpack(archive, unpacked.path(), max_unpacked_bytes).context("failed to unpack the archive")?;
+
+    let ifo_path = find_dictionary(unpacked.path())?;
+    let dictionary_root = ifo_path.parent().unwrap_or(unpacked.path());
+    let name = match directory_name {
+        Some(name) => name.to_string(),
+        None if dictionary_root != unpacked.path() => dictionary_root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        None => ifo_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    validate_directory_name(&name)?;
+    let target = dictionaries_dir.join(&name);
+    if target.exists() {
+        anyhow::bail!("a dictionary named {:?} is already installed", name);
+    }
+    // the temporary directory is dropped afterwards, taking whatever was outside the dictionary with it
+    std::fs::rename(dictionary_root, &target)?;
+
+    let ifo_name = ifo_path
+        .file_name()
+        .and_then(|ifo_name| ifo_name.to_str())
+        .unwrap_or_default();
+    dictionary_registry::read_installed_dictionary(
+        dictionaries_dir,
+        &format!("{}/{}", name, ifo_name),
+    )
+}
+
+pub fn remove_dictionary(dictionaries_dir: &Path, directory_name: &str) -> Result<()> {
+    validate_directory_name(directory_name)?;
+    let dir = dictionaries_dir.join(directory_name);
+    if !dir.is_dir() {
+        anyhow::bail!("no dictionary named {:?} is installed", directory_name);
+    }
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+/// move a dictionary's directory, returning the dictionaries it now holds
+pub fn rename_dictionary(
+    dictionaries_dir: &Path,
+    directory_name: &str,
+    new_directory_name: &str,
+) -> Result<Vec<InstalledDictionary>> {
+    validate_rename(dictionaries_dir, directory_name, new_directory_name)?;
+    std::fs::rename(
+        dictionaries_dir.join(directory_name),
+        dictionaries_dir.join(new_directory_name),
+    )?;
+    Ok(
+        dictionary_registry::list_installed_dictionaries(dictionaries_dir)?
+            .into_iter()
+            .filter(|dictionary| dictionary.directory_name == new_directory_name)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    const IFO: &str = "StarDict's dict ifo file\nversion=3.0.0\nbookname=Test Dictionary\nwordcount=1\nsametypesequence=h\n";
+
+    fn mk_zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn mk_tar_gz(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// installs read the bundle from a file, as uploads are streamed to one
+    fn archive_file(archive: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(archive).unwrap();
+        file
+    }
+
+    fn listing(dictionaries_dir: &Path) -> Vec<String> {
+        let mut paths = vec![];
+        let mut stack = vec![dictionaries_dir.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    stack.push(path);
+                } else {
+                    paths.push(
+                        path.strip_prefix(dictionaries_dir)
+                            .unwrap()
+                            .to_string_lossy()
+                            .to_string(),
+                    );
+                }
+            }
+        }
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_install_remove_rename() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = archive_file(&mk_zip(&[
+            ("fr-en/fr-en.ifo", IFO),
+            ("fr-en/fr-en.idx", ""),
+            ("fr-en/fr-en.dict.dz", ""),
+            ("fr-en/res/style.css", "body {}"),
+            ("README", "not part of the dictionary"),
+        ]));
+        let installed = install_dictionary(dir.path(), bundle.path(), None).unwrap();
+        assert_eq!(installed.dict_path, "fr-en/fr-en.ifo");
+        assert_eq!(installed.bookname, "Test Dictionary");
+
+        let flat_bundle = archive_file(&mk_tar_gz(&[
+            ("jmdict.ifo", IFO),
+            ("jmdict.idx", ""),
+            ("jmdict.dict", ""),
+        ]));
+        let installed = install_dictionary(dir.path(), flat_bundle.path(), Some("JMdict")).unwrap();
+        assert_eq!(installed.dict_path, "JMdict/jmdict.ifo");
+        assert_eq!(
+            listing(dir.path()),
+            vec![
+                "JMdict/jmdict.dict",
+                "JMdict/jmdict.idx",
+                "JMdict/jmdict.ifo",
+                "fr-en/fr-en.dict.dz",
+                "fr-en/fr-en.idx",
+                "fr-en/fr-en.ifo",
+                "fr-en/res/style.css",
+            ]
+        );
+
+        let err = install_dictionary(dir.path(), bundle.path(), None).unwrap_err();
+        assert!(err.to_string().contains("already installed"), "{}", err);
+        let err =
+            install_dictionary(dir.path(), flat_bundle.path(), Some("../escape")).unwrap_err();
+        assert!(err.to_string().contains("invalid"), "{}", err);
+        let missing_dict = archive_file(&mk_zip(&[("a/a.ifo", IFO), ("a/a.idx", "")]));
+        let err = install_dictionary(dir.path(), missing_dict.path(), None).unwrap_err();
+        assert!(err.to_string().contains("a.dict"), "{}", err);
+        let not_an_archive = archive_file(b"not an archive");
+        let err = install_dictionary(dir.path(), not_an_archive.path(), None).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("unsupported archive"),
+            "{:#}",
+            err
+        );
+        // unpacking stops once the files add up to more than allowed, whatever the archive claims
+        let large = "x".repeat(1000);
+        for oversized in [
+            archive_file(&mk_zip(&[("b/b.ifo", IFO), ("b/b.dict", &large)])),
+            archive_file(&mk_tar_gz(&[("b/b.ifo", IFO), ("b/b.dict", &large)])),
+        ] {
+            let err =
+                install_dictionary_within(dir.path(), oversized.path(), None, 1000).unwrap_err();
+            assert!(format!("{:#}", err).contains("allowed size"), "{:#}", err);
+        }
+        // failed installs leave nothing behind
+        assert_eq!(listing(dir.path()).len(), 7);
+
+        let renamed = rename_dictionary(dir.path(), "fr-en", "French").unwrap();
+        assert_eq!(renamed.len(), 1);
+        assert_eq!(renamed[0].dict_path, "French/fr-en.ifo");
+        assert!(rename_dictionary(dir.path(), "fr-en", "Other").is_err());
+        let err = validate_rename(dir.path(), "French", "JMdict").unwrap_err();
+        assert!(err.to_string().contains("already installed"), "{}", err);
+
+        remove_dictionary(dir.path(), "French").unwrap();
+        assert!(remove_dictionary(dir.path(), "French").is_err());
+        assert!(remove_dictionary(dir.path(), "..").is_err());
+        assert_eq!(
+            listing(dir.path()),
+            vec![
+                "JMdict/jmdict.dict",
+                "JMdict/jmdict.idx",
+                "JMdict/jmdict.ifo"
+            ]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+///! dictionaries in any of the supported formats, read behind one trait
+///! - StarDict (.ifo with its .idx and .dict), ABBYY Lingvo DSL (.dsl or .dsl.dz), MDict (.mdx) and
+///!   Yomitan/Yomichan (.zip with an index.json and term banks)
+///! - entries carry StarDict segment type codes, so every format reaches the client as the same
+///!   WordDefinition: "h" for html, "m" for plain text
+use super::headword_index;
+use crate::prelude::*;
+use anyhow::Result;
+use std::path::Path;
+
+#[derive(Debug, SerdeDerives!, Clone, Copy, PartialEq, Eq, ElmDerives!)]
+pub enum DictionaryFormat {
+    StarDict,
+    Dsl,
+    MDict,
+    Yomitan,
+}
+
+impl DictionaryFormat {
+    /// by the file a dict_path points at. DSL resource archives (.files.zip) are not dictionaries
+    pub fn from_path(path: &str) -> Option<Self> {
+        let lowercase = path.to_lowercase();
+        if lowercase.ends_with(".ifo") {
+            Some(DictionaryFormat::StarDict)
+        } else if lowercase.ends_with(".dsl") || lowercase.ends_with(".dsl.dz") {
+            Some(DictionaryFormat::Dsl)
+        } else if lowercase.ends_with(".mdx") {
+            Some(DictionaryFormat::MDict)
+        } else if lowercase.ends_with(".zip") && !lowercase.ends_with(".files.zip") {
+            Some(DictionaryFormat::Yomitan)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictionarySegment {
+    /// StarDict type code, e.g. "h" for html
+    pub types: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DictionaryEntry {
+    pub word: String,
+    pub segments: Vec<DictionarySegment>,
+}
+
+impl DictionaryEntry {
+    pub fn html(word: impl Into<String>, html: impl Into<String>) -> Self {
+        Self {
+            word: word.into(),
+            segments: vec![DictionarySegment {
+                types: "h".to_string(),
+                text: html.into(),
+            }],
+        }
+    }
+}
+
+pub trait Dictionary: Send {
+    /// entries whose headword is exactly the word, None when there are none
+    fn lookup(&mut self, word: &str) -> Result<Option<Vec<DictionaryEntry>>>;
+
+    /// every headword, for prefix and fuzzy search
+    fn headwords(&mut self) -> Result<Vec<String>>;
+}
+
+/// a StarDict dictionary, read by the stardict crate with or without its sled cache
+pub struct StarDictDictionary {
+    dict: Box<dyn stardict::StarDict + Send>,
+    ifo_path: String,
+}
+
+impl StarDictDictionary {
+    pub fn new(dict: Box<dyn stardict::StarDict + Send>, ifo_path: &str) -> Self {
+        Self {
+            dict,
+            ifo_path: ifo_path.to_string(),
+        }
+    }
+}
+
+impl Dictionary for StarDictDictionary {
+    fn lookup(&mut self, word: &str) -> Result<Option<Vec<DictionaryEntry>>> {
+        let definitions = self
+            .dict
+            .lookup(word)
+            .map_err(|e| anyhow::anyhow!("Failed to lookup word '{}': {}", word, e))?;
+        Ok(definitions.map(|definitions| {
+            definitions
+                .into_iter()
+                .map(|definition| DictionaryEntry {
+                    word: definition.word,
+                    segments: definition
+                        .segments
+                        .into_iter()
+                        .map(|segment| DictionarySegment {
+                            types: segment.types,
+                            text: segment.text,
+                        })
+                        .collect(),
+                })
+                .collect()
+        }))
+    }
+
+    fn headwords(&mut self) -> Result<Vec<String>> {
+        headword_index::read_stardict_headwords(Path::new(&self.ifo_path))
+    }
+}
+
+/// open a dictionary in one of the formats read without the stardict crate
+pub fn open_dictionary(path: &Path) -> Result<Box<dyn Dictionary>> {
+    match DictionaryFormat::from_path(&path.to_string_lossy()) {
+        Some(DictionaryFormat::Dsl) => Ok(Box::new(super::dsl::DslDictionary::open(path)?)),
+        Some(DictionaryFormat::MDict) => Ok(Box::new(super::mdict::MdictDictionary::open(path)?)),
+        Some(DictionaryFormat::Yomitan) => {
+            Ok(Box::new(super::yomitan::YomitanDictionary::open(path)?))
+        }
+        Some(DictionaryFormat::StarDict) | None => {
+            anyhow::bail!("not a DSL, MDict or Yomitan dictionary: {}", path.display())
+        }
+    }
+}
+
+/// escape text for html
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dictionary_format_from_path() {
+        let formats = [
+            "fr-en/fr-en.ifo",
+            "ru-en/ru-en.dsl",
+            "ru-en/ru-en.DSL.dz",
+            "ru-en/ru-en.dsl.files.zip",
+            "oald/oald.mdx",
+            "oald/oald.mdd",
+            "jitendex/jitendex.zip",
+        ]
+        .map(DictionaryFormat::from_path);
+        assert_eq!(
+            formats,
+            [
+                Some(DictionaryFormat::StarDict),
+                Some(DictionaryFormat::Dsl),
+                Some(DictionaryFormat::Dsl),
+                None,
+                Some(DictionaryFormat::MDict),
+                None,
+                Some(DictionaryFormat::Yomitan),
+            ]
+        );
+        assert_eq!(
+            escape_html(r#"<a href="x">&'"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+///! dictionaries installed under the dictionaries directory, found by their StarDict .ifo, DSL, MDict
+///! .mdx or Yomitan .zip files
+///! - a dictionary is identified by its dict_path, the file's path relative to the dictionaries directory,
+///!   e.g. "French - English/French - English.ifo", which is also how languages refer to it
+///! - each directory under the dictionaries directory holds one dictionary, with its resources in res/
+use super::dictionary_reader::DictionaryFormat;
+use super::{dsl, mdict, yomitan};
+use crate::prelude::*;
+use anyhow::{Context, Result};
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+const IFO_MAGIC: &str = "StarDict's dict ifo file";
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct InstalledDictionary {
+    pub dict_path: String,
+    pub directory_name: String,
+    pub format: DictionaryFormat,
+    pub bookname: String,
+    pub wordcount: usize,
+    pub author: String,
+    /// type of every definition segment when the dictionary leaves it out of the data, e.g. "h" for html
+    pub sametypesequence: Option<String>,
+}
+
+/// the .ifo fields shown to the user. the rest are only needed to read the dictionary.
+/// other formats fill in what they record, see dictionary_reader
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DictionaryMetadata {
+    pub bookname: String,
+    pub wordcount: usize,
+    pub author: String,
+    pub sametypesequence: Option<String>,
+}
+
+/// parse the key=value lines of a .ifo file, after its magic first line
+pub fn parse_ifo(content: &str) -> Result<DictionaryMetadata> {
+    let mut lines = content.lines();
+    match lines
+        .next()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+    {
+        Some(IFO_MAGIC) => {}
+        other => anyhow::bail!("not a StarDict .ifo file, starts with {:?}", other),
+    }
+
+    let mut metadata = DictionaryMetadata::default();
+    let mut has_bookname = false;
+    for line in lines {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "bookname" => {
+                has_bookname = true;
+                metadata.bookname = value;
+            }
+            "wordcount" => {
+                metadata.wordcount = value
+                    .parse()
+                    .with_context(|| format!("invalid wordcount {:?}", value))?
+            }
+            "author" => metadata.author = value,
+            "sametypesequence" if !value.is_empty() => metadata.sametypesequence = Some(value),
+            _ => {}
+        }
+    }
+    if !has_bookname {
+        anyhow::bail!(".ifo file has no bookname");
+    }
+    Ok(metadata)
+}
+
+/// absolute path of the dictionary file at dict_path. dict_path comes from clients, so only a file in a
+/// directory directly under dictionaries_dir is accepted, as list_installed_dictionaries finds them;
+/// "..", absolute paths, anything nested deeper and the installer's hidden work directories are rejected
+pub fn dictionary_file_path(dictionaries_dir: &Path, dict_path: &str) -> Result<PathBuf> {
+    match Path::new(dict_path)
+        .components()
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [Component::Normal(dir_name), Component::Normal(_)] if !is_hidden(Path::new(dir_name)) => {
+            Ok(dictionaries_dir.join(dict_path))
+        }
+        _ => anyhow::bail!(
+            "not a dictionary in the dictionaries directory: {:?}",
+            dict_path
+        ),
+    }
+}
+
+/// dot-prefixed entries, like the installer's .installing-* and .upload-* work files, are never dictionaries
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+/// read the metadata of the dictionary at dict_path, relative to dictionaries_dir
+pub fn read_installed_dictionary(
+    dictionaries_dir: &Path,
+    dict_path: &str,
+) -> Result<InstalledDictionary> {
+    let path = dictionary_file_path(dictionaries_dir, dict_path)?;
+    let format = DictionaryFormat::from_path(dict_path)
+        .with_context(|| format!("not a dictionary file: {}", dict_path))?;
+    let metadata = match format {
+        DictionaryFormat::StarDict => {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            parse_ifo(&content)
+        }
+        DictionaryFormat::Dsl => dsl::read_metadata(&path),
+        DictionaryFormat::MDict => mdict::read_metadata(&path),
+        DictionaryFormat::Yomitan => yomitan::read_metadata(&path),
+    }
+    .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(InstalledDictionary {
+        dict_path: dict_path.to_string(),
+        directory_name: dict_path.split('/').next().unwrap_or_default().to_string(),
+        format,
+        bookname: metadata.bookname,
+        wordcount: metadata.wordcount,
+        author: metadata.author,
+        sametypesequence: metadata.sametypesequence,
+    })
+}
+
+/// every dictionary in the directories directly under dictionaries_dir, sorted by dict_path.
+/// unreadable dictionary files are logged and skipped so one broken dictionary does not hide the rest
+pub fn list_installed_dictionaries(dictionaries_dir: &Path) -> Result<Vec<InstalledDictionary>> {
+    let mut dictionaries = vec![];
+    if !dictionaries_dir.exists() {
+        return Ok(dictionaries);
+    }
+    for entry in std::fs::read_dir(dictionaries_dir)? {
+        let dir_path = entry?.path();
+        let Some(dir_name) = dir_path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !dir_path.is_dir() || is_hidden(&dir_path) {
+            continue;
+        }
+        for file in std::fs::read_dir(&dir_path)? {
+            let file_path = file?.path();
+            let Some(file_name) = file_path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if DictionaryFormat::from_path(file_name).is_none() {
+                continue;
+            }
+            let dict_path = format!("{}/{}", dir_name, file_name);
+            match read_installed_dictionary(dictionaries_dir, &dict_path) {
+                Ok(dictionary) => dictionaries.push(dictionary),
+                Err(err) => warn!(dict_path = %dict_path, "Skipping dictionary: {:#}", err),
+            }
+        }
+    }
+    dictionaries.sort_by(|a, b| a.dict_path.cmp(&b.dict_path));
+    Ok(dictionaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+    use std::fs;
+
+    const FRA_ENG_IFO: &str = indoc::indoc! {"
+        StarDict's dict ifo file
+        version=3.0.0
+        bookname=French-English FreeDict Dictionary (fr-en)
+        wordcount=8505
+        idxfilesize=147979
+        author=FreeDict
+        sametypesequence=h
+    "};
+
+    #[test]
+    fn test_parse_ifo() {
+        assert_eq!(
+            parse_ifo(FRA_ENG_IFO).unwrap(),
+            DictionaryMetadata {
+                bookname: "French-English FreeDict Dictionary (fr-en)".to_string(),
+                wordcount: 8505,
+                author: "FreeDict".to_string(),
+                sametypesequence: Some("h".to_string()),
+            }
+        );
+        assert!(parse_ifo("bookname=missing magic").is_err());
+        assert!(parse_ifo("StarDict's dict ifo file\nwordcount=1\n").is_err());
+        assert!(parse_ifo("StarDict's dict ifo file\nbookname=x\nwordcount=many\n").is_err());
+    }
+
+    #[test]
+    fn test_dictionary_file_path() {
+        let dir = Path::new("/data/dictionaries");
+        assert_eq!(
+            dictionary_file_path(dir, "fr-en/fr-en.ifo").unwrap(),
+            dir.join("fr-en/fr-en.ifo")
+        );
+        for dict_path in [
+            "../fr-en/fr-en.ifo",
+            "fr-en/../../secret.ifo",
+            "/etc/fr-en.ifo",
+            "fr-en.ifo",
+            "fr-en/res/fr-en.ifo",
+            ".installing-1/fr-en.ifo",
+            "",
+        ] {
+            assert!(
+                dictionary_file_path(dir, dict_path).is_err(),
+                "{} was accepted",
+                dict_path
+            );
+        }
+    }
+
+    #[test]
+    fn test_list_installed_dictionaries() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("fr-en/res")).unwrap();
+        fs::write(dir.path().join("fr-en/fr-en.ifo"), FRA_ENG_IFO).unwrap();
+        fs::write(dir.path().join("fr-en/fr-en.idx"), "").unwrap();
+        fs::create_dir_all(dir.path().join("broken")).unwrap();
+        fs::write(dir.path().join("broken/broken.ifo"), "garbage").unwrap();
+        fs::write(dir.path().join("stray.ifo"), FRA_ENG_IFO).unwrap();
+        // left behind by an install that crashed
+        fs::create_dir_all(dir.path().join(".installing-1")).unwrap();
+        fs::write(dir.path().join(".installing-1/fr-en.ifo"), FRA_ENG_IFO).unwrap();
+        fs::create_dir_all(dir.path().join("ru-en")).unwrap();
+        fs::write(
+            dir.path().join("ru-en/ru-en.dsl"),
+            "#NAME \"Test (Ru-En)\"\n\nкот\n\tcat\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("ru-en/ru-en.dsl.files.zip"), "").unwrap();
+
+        let rendered = list_installed_dictionaries(dir.path())
+            .unwrap()
+            .iter()
+            .map(|dictionary| {
+                format!(
+                    "{} [{}] {:?} {:?} words={} author={:?} types={:?}",
+                    dictionary.dict_path,
+                    dictionary.directory_name,
+                    dictionary.format,
+                    dictionary.bookname,
+                    dictionary.wordcount,
+                    dictionary.author,
+                    dictionary.sametypesequence
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect![[r#"
+            fr-en/fr-en.ifo [fr-en] StarDict "French-English FreeDict Dictionary (fr-en)" words=8505 author="FreeDict" types=Some("h")
+            ru-en/ru-en.dsl [ru-en] Dsl "Test (Ru-En)" words=0 author="" types=None"#]]
+        .assert_eq(&rendered);
+        assert_eq!(
+            list_installed_dictionaries(&dir.path().join("missing")).unwrap(),
+            vec![]
+        );
+    }
+}
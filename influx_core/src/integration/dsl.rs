@@ -0,0 +1,425 @@
+///! ABBYY Lingvo DSL dictionaries, .dsl or dictzipped .dsl.dz, read whole into memory
+///! - a card is one or more headword lines starting at the first column, then body lines indented by
+///!   whitespace. the header before the first card holds #NAME and the languages
+///! - body markup ([b], [m1], [ref], <<x>>, [s] media, ...) becomes html, cross-references become
+///!   bword:// links, which definition_html turns into lookup links like StarDict's
+///! - headwords are indexed without their {unsorted parts}, and with and without (optional parts)
+use super::dictionary_reader::{escape_html, Dictionary, DictionaryEntry};
+use super::dictionary_registry::DictionaryMetadata;
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "bmp", "svg"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DslCard {
+    headwords: Vec<String>,
+    body: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DslDictionary {
+    name: String,
+    cards: Vec<DslCard>,
+    /// indexed form of a headword to the cards it heads, with the headword as displayed
+    index: HashMap<String, Vec<(usize, String)>>,
+}
+
+/// DSL files are usually UTF-16LE, sometimes UTF-16BE or UTF-8, with or without a byte order mark
+pub fn decode_text(bytes: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units = bytes
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    };
+    match bytes {
+        [0xff, 0xfe, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xfe, 0xff, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xef, 0xbb, 0xbf, rest @ ..] => String::from_utf8_lossy(rest).to_string(),
+        // ascii text in UTF-16LE has every other byte zero
+        [_, 0, ..] => utf16(bytes, u16::from_le_bytes),
+        [0, _, ..] => utf16(bytes, u16::from_be_bytes),
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+fn read_text(path: &Path) -> Result<String> {
+    let raw = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let bytes = if path.to_string_lossy().to_lowercase().ends_with(".dz") {
+        let mut bytes = vec![];
+        GzDecoder::new(raw.as_slice()).read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        raw
+    };
+    Ok(decode_text(&bytes))
+}
+
+/// the value of a header line like #NAME "Universal (Ru-En)"
+fn header_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix('#')?.strip_prefix(key)?;
+    Some(rest.trim().trim_matches('"'))
+}
+
+/// the headword as displayed, and its indexed forms
+fn parse_headword(line: &str) -> (String, Vec<String>) {
+    let mut display = String::new();
+    let mut without_optional = String::new();
+    let mut with_optional = String::new();
+    let mut in_unsorted = false;
+    let mut in_optional = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        let (c, escaped) = match c {
+            '\\' => match chars.next() {
+                Some(next) => (next, true),
+                None => break,
+            },
+            _ => (c, false),
+        };
+        match (c, escaped) {
+            ('{', false) => in_unsorted = true,
+            ('}', false) => in_unsorted = false,
+            ('(', false) => in_optional = true,
+            (')', false) => in_optional = false,
+            _ => {
+                display.push(c);
+                if !in_unsorted {
+                    with_optional.push(c);
+                    if !in_optional {
+                        without_optional.push(c);
+                    }
+                }
+            }
+        }
+    }
+    let normalise = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut indexed = vec![normalise(&without_optional)];
+    let with_optional = normalise(&with_optional);
+    if !indexed.contains(&with_optional) {
+        indexed.push(with_optional);
+    }
+    indexed.retain(|form| !form.is_empty());
+    (normalise(&display), indexed)
+}
+
+/// a css colour name or hex code, so a [c] argument can't break out of the style attribute
+fn is_colour(text: &str) -> bool {
+    let hex = text.strip_prefix('#');
+    match hex {
+        Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !text.is_empty() && text.chars().all(|c| c.is_ascii_alphabetic()),
+    }
+}
+
+/// text up to the closing [/name], unescaped, and what follows it
+fn until_closing<'a>(text: &'a str, name: &str) -> (String, &'a str) {
+    let closing = format!("[/{}]", name);
+    let (inner, rest) = match text.find(&closing) {
+        Some(end) => (&text[..end], &text[end + closing.len()..]),
+        None => (text, ""),
+    };
+    let mut unescaped = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+    (unescaped, rest)
+}
+
+fn media_html(file: &str) -> String {
+    let extension = file.rsplit('.').next().unwrap_or_default().to_lowercase();
+    if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        format!(r#"<img src="{}">"#, escape_html(file))
+    } else {
+        format!(r#"<audio controls src="{}"></audio>"#, escape_html(file))
+    }
+}
+
+/// one body line of DSL markup as html, closing whatever it leaves open
+pub fn markup_to_html(line: &str) -> String {
+    let mut html = String::new();
+    // tag name and the html closing it, innermost last
+    let mut open: Vec<(String, &str)> = vec![];
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("{{") {
+            rest = after.find("}}").map(|end| &after[end + 2..]).unwrap_or("");
+        } else if let Some(after) = rest.strip_prefix("<<") {
+            let (word, after) = match after.find(">>") {
+                Some(end) => (&after[..end], &after[end + 2..]),
+                None => (after, ""),
+            };
+            html.push_str(&format!(
+                r#"<a href="bword://{}">{}</a>"#,
+                escape_html(word),
+                escape_html(word)
+            ));
+            rest = after;
+        } else if c == '\\' {
+            let mut chars = rest.chars();
+            chars.next();
+            if let Some(escaped) = chars.next() {
+                html.push_str(&escape_html(&escaped.to_string()));
+            }
+            rest = chars.as_str();
+        } else if c == '[' {
+            let Some(end) = rest.find(']') else {
+                html.push_str(&escape_html(rest));
+                break;
+            };
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                if let Some(position) = open.iter().rposition(|(open_name, _)| open_name == name) {
+                    for (_, closing) in open.drain(position..).rev() {
+                        html.push_str(closing);
+                    }
+                }
+                continue;
+            }
+            let (name, argument) = match tag.split_once(' ') {
+                Some((name, argument)) => (name, argument.trim()),
+                None => (tag, ""),
+            };
+            match name {
+                "ref" | "url" | "s" => {
+                    let (inner, after) = until_closing(rest, name);
+                    rest = after;
+                    html.push_str(&match name {
+                        "ref" => format!(
+                            r#"<a href="bword://{}">{}</a>"#,
+                            escape_html(&inner),
+                            escape_html(&inner)
+                        ),
+                        "url" => format!(
+                            r#"<a href="{}">{}</a>"#,
+                            escape_html(&inner),
+                            escape_html(&inner)
+                        ),
+                        _ => media_html(&inner),
+                    });
+                }
+                "b" | "i" | "u" | "sub" | "sup" => {
+                    html.push_str(&format!("<{}>", name));
+                    let closing = match name {
+                        "b" => "</b>",
+                        "i" => "</i>",
+                        "u" => "</u>",
+                        "sub" => "</sub>",
+                        _ => "</sup>",
+                    };
+                    open.push((name.to_string(), closing));
+                }
+                "c" => {
+                    let colour = match is_colour(argument) {
+                        true => argument,
+                        false => "green",
+                    };
+                    html.push_str(&format!(
+                        r#"<span class="dsl-c" style="color: {}">"#,
+                        colour
+                    ));
+                    open.push((name.to_string(), "</span>"));
+                }
+                "p" | "ex" | "com" | "trn" | "trn1" | "!trs" | "trs" | "*" | "t" | "'" | "lang" => {
+                    let class = match name {
+                        "!trs" => "trs",
+                        "*" => "opt",
+                        "'" => "stress",
+                        _ => name,
+                    };
+                    html.push_str(&format!(r#"<span class="dsl-{}">"#, class));
+                    open.push((name.to_string(), "</span>"));
+                }
+                // margins are handled per line, and anything unknown is left out
+                _ => {}
+            }
+        } else {
+            html.push_str(&escape_html(&c.to_string()));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    for (_, closing) in open.into_iter().rev() {
+        html.push_str(closing);
+    }
+    html
+}
+
+/// a card's body as html, a div per line, indented by its [m] margin
+pub fn body_to_html(body: &[String]) -> String {
+    body.iter()
+        .map(|line| {
+            let line = line.trim();
+            let (margin, line) = match line.strip_prefix("[m") {
+                Some(after) => match after.find(']') {
+                    Some(end) if after[..end].chars().all(|c| c.is_ascii_digit()) => {
+                        (after[..end].parse().unwrap_or(0), &after[end + 1..])
+                    }
+                    _ => (0, line),
+                },
+                None => (0u32, line),
+            };
+            let line = line.trim_end().trim_end_matches("[/m]");
+            match margin {
+                0 => format!("<div>{}</div>", markup_to_html(line)),
+                _ => format!(
+                    r#"<div style="margin-left: {}em">{}</div>"#,
+                    margin,
+                    markup_to_html(line)
+                ),
+            }
+        })
+        .collect()
+}
+
+impl DslDictionary {
+    pub fn parse(text: &str) -> Self {
+        let mut dictionary = DslDictionary::default();
+        let mut card: Option<DslCard> = None;
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if card.is_none() && line.starts_with('#') {
+                if let Some(name) = header_value(line, "NAME") {
+                    dictionary.name = name.to_string();
+                }
+                continue;
+            }
+            if line.starts_with([' ', '\t']) {
+                if let Some(card) = card.as_mut() {
+                    card.body.push(line.to_string());
+                }
+                continue;
+            }
+            match card.as_mut() {
+                // consecutive headword lines share a card
+                Some(current) if current.body.is_empty() => {
+                    current.headwords.push(line.to_string())
+                }
+                _ => {
+                    dictionary.push_card(card.take());
+                    card = Some(DslCard {
+                        headwords: vec![line.to_string()],
+                        body: vec![],
+                    });
+                }
+            }
+        }
+        dictionary.push_card(card);
+        dictionary
+    }
+
+    fn push_card(&mut self, card: Option<DslCard>) {
+        let Some(card) = card else {
+            return;
+        };
+        let card_index = self.cards.len();
+        for headword in &card.headwords {
+            let (display, indexed) = parse_headword(headword);
+            for form in indexed {
+                self.index
+                    .entry(form)
+                    .or_default()
+                    .push((card_index, display.clone()));
+            }
+        }
+        self.cards.push(card);
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self::parse(&read_text(path)?))
+    }
+}
+
+/// the #NAME of a DSL file, from its header
+pub fn read_metadata(path: &Path) -> Result<DictionaryMetadata> {
+    let text = read_text(path)?;
+    let bookname = text
+        .lines()
+        .take_while(|line| line.starts_with('#') || line.trim().is_empty())
+        .find_map(|line| header_value(line, "NAME"))
+        .context("DSL file has no #NAME")?;
+    Ok(DictionaryMetadata {
+        bookname: bookname.to_string(),
+        ..Default::default()
+    })
+}
+
+impl Dictionary for DslDictionary {
+    fn lookup(&mut self, word: &str) -> Result<Option<Vec<DictionaryEntry>>> {
+        Ok(self.index.get(word).map(|cards| {
+            cards
+                .iter()
+                .map(|(card_index, display)| {
+                    DictionaryEntry::html(display, body_to_html(&self.cards[*card_index].body))
+                })
+                .collect()
+        }))
+    }
+
+    fn headwords(&mut self) -> Result<Vec<String>> {
+        Ok(self.index.keys().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    const DSL: &str = "#NAME \"Test (Ru-En)\"\n#INDEX_LANGUAGE \"Russian\"\n#CONTENTS_LANGUAGE \"English\"\n\nкот\nкошка\n\t[m1][b]1.[/b] [p]n[/p] cat [c red]tom[/c][/m]\n\t[m2][ex]кот {{comment}}ловит мышь[/ex] see <<кошка>>[/m]\n\n{по}бежать (домой)\n\t[m1]to run [ref]бег[/ref] [s]run.wav[/s] \\[sic\\][/m]\n";
+
+    #[test]
+    fn test_dsl_dictionary() {
+        let mut dictionary = DslDictionary::parse(DSL);
+        assert_eq!(dictionary.name, "Test (Ru-En)");
+        let mut headwords = dictionary.headwords().unwrap();
+        headwords.sort();
+        expect![[r#"["бежать", "бежать домой", "кот", "кошка"]"#]]
+            .assert_eq(&format!("{:?}", headwords));
+
+        let entries = dictionary.lookup("кошка").unwrap().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].word, "кошка");
+        expect![[r#"<div style="margin-left: 1em"><b>1.</b> <span class="dsl-p">n</span> cat <span class="dsl-c" style="color: red">tom</span></div><div style="margin-left: 2em"><span class="dsl-ex">кот ловит мышь</span> see <a href="bword://кошка">кошка</a></div>"#]]
+            .assert_eq(&entries[0].segments[0].text);
+
+        let entries = dictionary.lookup("бежать домой").unwrap().unwrap();
+        assert_eq!(entries[0].word, "побежать домой");
+        expect![[r#"<div style="margin-left: 1em">to run <a href="bword://бег">бег</a> <audio controls src="run.wav"></audio> [sic]</div>"#]]
+            .assert_eq(&entries[0].segments[0].text);
+        assert_eq!(dictionary.lookup("собака").unwrap(), None);
+    }
+
+    #[test]
+    fn test_markup_to_html() {
+        expect![[r#"<b><i>unclosed &lt;tag&gt;</i></b>"#]]
+            .assert_eq(&markup_to_html("[b][i]unclosed <tag>"));
+        expect![[r#"<span class="dsl-c" style="color: green">x</span> y"#]]
+            .assert_eq(&markup_to_html(r#"[c "red" onclick]x[/c] [unknown]y"#));
+    }
+
+    #[test]
+    fn test_decode_text() {
+        let utf16 = "#NAME \"x\""
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decode_text(&[&[0xff, 0xfe][..], &utf16].concat()),
+            "#NAME \"x\""
+        );
+        assert_eq!(decode_text(&utf16), "#NAME \"x\"");
+        assert_eq!(decode_text("é".as_bytes()), "é");
+    }
+}
@@ -0,0 +1,350 @@
+///! frequency list files, read into ranked lemmas for DB::replace_ranked_frequency_list
+///! - Counts: word<TAB>count lines, in any order, ranked by position once sorted
+///! - Ranks: one word per line from the most frequent, or word<TAB>rank / rank<TAB>word lines whose ranks are kept
+///! - Yomitan: a frequency dictionary .zip, whose term_meta_bank_*.json rows are [term, "freq", data]
+///! - text may be UTF-8 or UTF-16, lines starting with # are comments
+use super::dsl::decode_text;
+use crate::db::models::frequency::RankedLemma;
+use crate::prelude::*;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+#[derive(Debug, SerdeDerives!, Clone, Copy, PartialEq, Eq, ElmDerives!)]
+pub enum FrequencyListFormat {
+    Counts,
+    Ranks,
+    Yomitan,
+}
+
+impl FrequencyListFormat {
+    /// a zip is a Yomitan dictionary. text with a number beside each word is taken as counts, unless the
+    /// numbers are whole and ascend from 1 in line order, which is how rank lists are written
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"PK\x03\x04") {
+            return FrequencyListFormat::Yomitan;
+        }
+        let text = decode_text(bytes);
+        let numbers = entry_lines(&text)
+            .map(|line| split_number(line).map(|(_, number)| number))
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default();
+        if numbers.is_empty() || is_rank_sequence(&numbers) {
+            FrequencyListFormat::Ranks
+        } else {
+            FrequencyListFormat::Counts
+        }
+    }
+}
+
+/// whole numbers from 1 that never go down, e.g. 1 2 2 4, allowing for ties and gaps
+fn is_rank_sequence(numbers: &[f64]) -> bool {
+    numbers.first() == Some(&1.0)
+        && numbers.iter().all(|number| number.fract() == 0.0)
+        && numbers.windows(2).all(|pair| pair[0] <= pair[1])
+}
+
+/// the words ranked by their position, from 1
+fn ranked_by_position(words: impl IntoIterator<Item = String>) -> Vec<RankedLemma> {
+    words
+        .into_iter()
+        .zip(1..)
+        .map(|(lemma, rank)| RankedLemma { lemma, rank })
+        .collect()
+}
+
+/// a rank read from a file, as the closest rank the database can hold
+fn to_rank(number: f64) -> i32 {
+    number.round().clamp(1.0, i32::MAX as f64) as i32
+}
+
+fn entry_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// the word and the number of a line with one tab-separated number column, first or last
+fn split_number(line: &str) -> Option<(&str, f64)> {
+    let (first, last) = line.split_once('\t').or_else(|| line.rsplit_once(' '))?;
+    match (first.trim().parse::<f64>(), last.trim().parse::<f64>()) {
+        (_, Ok(number)) => Some((first.trim(), number)),
+        (Ok(number), _) => Some((last.trim(), number)),
+        _ => None,
+    }
+    .filter(|(word, number)| !word.is_empty() && number.is_finite())
+}
+
+/// words by descending count. lines without a count, e.g. a header, are skipped
+pub fn parse_counts(text: &str) -> Vec<String> {
+    let mut counts = entry_lines(text)
+        .filter_map(split_number)
+        .collect::<Vec<_>>();
+    counts.sort_by(|a, b| b.1.total_cmp(&a.1));
+    counts
+        .into_iter()
+        .map(|(word, _)| word.to_string())
+        .collect()
+}
+
+/// words with the ranks the lines give them, by ascending rank, or ranked in line order when the lines have no ranks
+pub fn parse_ranks(text: &str) -> Vec<RankedLemma> {
+    let lines = entry_lines(text).collect::<Vec<_>>();
+    let ranked = lines
+        .iter()
+        .filter_map(|line| split_number(line))
+        .collect::<Vec<_>>();
+    if ranked.len() < lines.len() {
+        return ranked_by_position(lines.into_iter().map(str::to_string));
+    }
+    let mut ranked = ranked;
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    ranked
+        .into_iter()
+        .map(|(word, rank)| RankedLemma {
+            lemma: word.to_string(),
+            rank: to_rank(rank),
+        })
+        .collect()
+}
+
+/// the number in a frequency entry's data: a number, a string starting with one, an object with a value
+/// or displayValue, or an object with a reading and one of those as its frequency
+fn yomitan_frequency(data: &Value) -> Option<f64> {
+    match data {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .next()
+            .and_then(|number| number.parse().ok()),
+        Value::Object(object) => object
+            .get("frequency")
+            .and_then(yomitan_frequency)
+            .or_else(|| object.get("value").and_then(yomitan_frequency))
+            .or_else(|| object.get("displayValue").and_then(yomitan_frequency)),
+        _ => None,
+    }
+}
+
+fn is_term_meta_bank(name: &str) -> bool {
+    name.strip_prefix("term_meta_bank_")
+        .and_then(|rest| rest.strip_suffix(".json"))
+        .is_some_and(|number| number.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// terms of a Yomitan frequency dictionary from the most frequent. frequencies are ranks, which are kept,
+/// unless index.json says they are occurrence counts. a term listed under several readings keeps its best
+pub fn parse_yomitan(bytes: &[u8]) -> Result<Vec<RankedLemma>> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut index = String::new();
+    zip.by_name("index.json")
+        .context("Yomitan dictionary has no index.json")?
+        .read_to_string(&mut index)?;
+    let index: Value = serde_json::from_str(&index)?;
+    let occurrence_based =
+        index.get("frequencyMode").and_then(Value::as_str) == Some("occurrence-based");
+
+    let bank_names = zip
+        .file_names()
+        .filter(|name| is_term_meta_bank(name))
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    if bank_names.is_empty() {
+        anyhow::bail!(
+            "Yomitan dictionary has no term_meta_bank files, is it a frequency dictionary?"
+        );
+    }
+    // ranks, or negated counts, so smaller is always more frequent
+    let mut best: HashMap<String, (f64, usize)> = HashMap::new();
+    let mut order = 0;
+    for name in bank_names {
+        let mut content = String::new();
+        zip.by_name(&name)?.read_to_string(&mut content)?;
+        let rows: Vec<Value> =
+            serde_json::from_str(&content).with_context(|| format!("failed to parse {}", name))?;
+        for row in rows {
+            let Some(row) = row.as_array() else {
+                continue;
+            };
+            let (Some(term), Some("freq")) = (
+                row.first().and_then(Value::as_str),
+                row.get(1).and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            let Some(frequency) = row.get(2).and_then(yomitan_frequency) else {
+                continue;
+            };
+            let key = if occurrence_based {
+                -frequency
+            } else {
+                frequency
+            };
+            order += 1;
+            best.entry(term.to_string())
+                .and_modify(|current| {
+                    if key < current.0 {
+                        current.0 = key;
+                    }
+                })
+                .or_insert((key, order));
+        }
+    }
+    let mut terms = best.into_iter().collect::<Vec<_>>();
+    terms.sort_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    if occurrence_based {
+        return Ok(ranked_by_position(terms.into_iter().map(|(term, _)| term)));
+    }
+    Ok(terms
+        .into_iter()
+        .map(|(term, (rank, _))| RankedLemma {
+            lemma: term,
+            rank: to_rank(rank),
+        })
+        .collect())
+}
+
+/// the ranked lemmas of a frequency list file, from the most frequent. format is detected when None
+pub fn parse_frequency_list(
+    bytes: &[u8],
+    format: Option<FrequencyListFormat>,
+) -> Result<Vec<RankedLemma>> {
+    let lemmas = match format.unwrap_or_else(|| FrequencyListFormat::detect(bytes)) {
+        FrequencyListFormat::Counts => ranked_by_position(parse_counts(&decode_text(bytes))),
+        FrequencyListFormat::Ranks => parse_ranks(&decode_text(bytes)),
+        FrequencyListFormat::Yomitan => parse_yomitan(bytes)?,
+    };
+    if lemmas.is_empty() {
+        anyhow::bail!("the frequency list has no entries");
+    }
+    Ok(lemmas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn render(ranked: &[RankedLemma]) -> Vec<String> {
+        ranked
+            .iter()
+            .map(|ranked| format!("{} {}", ranked.lemma, ranked.rank))
+            .collect()
+    }
+
+    fn mk_zip(files: &[(&str, Value)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.to_string().as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_parse_text_lists() {
+        let counts = "# word\tcount\nword\tcount\nof\t300\nthe\t500\nice cream\t2\nbe\t400\n";
+        assert_eq!(
+            FrequencyListFormat::detect(b"the\t5\nof\t3\n"),
+            FrequencyListFormat::Counts
+        );
+        assert_eq!(parse_counts(counts), vec!["the", "be", "of", "ice cream"]);
+        assert_eq!(
+            render(&parse_frequency_list(b"the\nbe\n\nof\n", None).unwrap()),
+            vec!["the 1", "be 2", "of 3"]
+        );
+        assert_eq!(
+            render(&parse_ranks("3\tof\n1\tthe\n2\tbe\n")),
+            vec!["the 1", "be 2", "of 3"]
+        );
+        assert_eq!(
+            render(&parse_ranks("of 3\nthe 1\nbe 2\n")),
+            vec!["the 1", "be 2", "of 3"]
+        );
+        // a list of words some of which are numbers is still in line order
+        assert_eq!(
+            render(&parse_ranks("the\n1 000\nbe\n")),
+            vec!["the 1", "1 000 2", "be 3"]
+        );
+        let utf16 = [0xff, 0xfe]
+            .into_iter()
+            .chain("été\t7\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect::<Vec<u8>>();
+        assert_eq!(
+            render(&parse_frequency_list(&utf16, None).unwrap()),
+            vec!["été 1"]
+        );
+        assert!(parse_frequency_list(b"# nothing\n", None).is_err());
+    }
+
+    #[test]
+    fn test_unlabelled_rank_file() {
+        // word<TAB>rank lines look like counts, but ranks ascend from 1, so the file keeps its order and ranks
+        let ranks = b"the\t1\nof\t2\nand\t3\nto\t5\n";
+        assert_eq!(
+            FrequencyListFormat::detect(ranks),
+            FrequencyListFormat::Ranks
+        );
+        assert_eq!(
+            render(&parse_frequency_list(ranks, None).unwrap()),
+            vec!["the 1", "of 2", "and 3", "to 5"]
+        );
+        for counts in [
+            &b"the\t500\nof\t300\n"[..],
+            b"the\t5\nof\t1\n",
+            b"a\t1.5\nb\t2\n",
+        ] {
+            assert_eq!(
+                FrequencyListFormat::detect(counts),
+                FrequencyListFormat::Counts
+            );
+        }
+        // the format can still be given when a list of counts happens to look like ranks
+        assert_eq!(
+            render(
+                &parse_frequency_list(b"rare\t1\ncommon\t2\n", Some(FrequencyListFormat::Counts))
+                    .unwrap()
+            ),
+            vec!["common 1", "rare 2"]
+        );
+    }
+
+    #[test]
+    fn test_parse_yomitan() {
+        let bank = json!([
+            ["食べる", "freq", {"reading": "たべる", "frequency": {"value": 250, "displayValue": "250㋕"}}],
+            ["猫", "freq", 1200],
+            ["の", "freq", "3"],
+            ["食べる", "freq", {"reading": "くべる", "frequency": 90000}],
+            ["猫", "pitch", {"reading": "ねこ", "pitches": []}],
+            ["犬", "freq", {"value": 900}]
+        ]);
+        let ranked = mk_zip(&[
+            ("index.json", json!({"title": "Freq", "format": 3})),
+            ("term_meta_bank_1.json", bank.clone()),
+        ]);
+        assert_eq!(
+            FrequencyListFormat::detect(&ranked),
+            FrequencyListFormat::Yomitan
+        );
+        assert_eq!(
+            render(&parse_frequency_list(&ranked, None).unwrap()),
+            vec!["の 3", "食べる 250", "犬 900", "猫 1200"]
+        );
+        let counted = mk_zip(&[
+            (
+                "index.json",
+                json!({"title": "Counts", "frequencyMode": "occurrence-based"}),
+            ),
+            ("term_meta_bank_1.json", bank),
+        ]);
+        assert_eq!(
+            render(&parse_yomitan(&counted).unwrap()),
+            vec!["食べる 1", "猫 2", "犬 3", "の 4"]
+        );
+        assert!(parse_yomitan(&mk_zip(&[("index.json", json!({"title": "Terms"}))])).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+///! the headwords of a dictionary, for suggestions while typing and for near misses when a word is not
+///! found. StarDict headwords are read from its .idx, other formats list their own, see dictionary_reader
+///! - headwords are matched folded, see nlp::lookup_candidates::fold_for_lookup, so "ete" finds "été"
+///! - fuzzy matching is by edit distance, allowing 1 edit for short queries and 2 for longer ones
+use crate::nlp::lookup_candidates::fold_for_lookup;
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::Path;
+
+pub const SHORT_QUERY_CHARS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HeadwordIndex {
+    /// (folded, headword), sorted by folded form then headword, without repeats
+    entries: Vec<(String, String)>,
+}
+
+/// the headwords in the .idx data: each a nul-terminated word followed by its offset and size in the .dict
+pub fn parse_idx(data: &[u8], offset_bits: u32) -> Result<Vec<String>> {
+    let entry_tail = match offset_bits {
+        32 => 8,
+        64 => 12,
+        other => anyhow::bail!("unsupported idxoffsetbits {}", other),
+    };
+    let mut headwords = vec![];
+    let mut rest = data;
+    while !rest.is_empty() {
+        let nul = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .context("truncated .idx entry")?;
+        if rest.len() < nul + 1 + entry_tail {
+            anyhow::bail!("truncated .idx entry");
+        }
+        headwords.push(String::from_utf8_lossy(&rest[..nul]).to_string());
+        rest = &rest[nul + 1 + entry_tail..];
+    }
+    Ok(headwords)
+}
+
+/// the headwords of a StarDict dictionary, from the index next to its .ifo, either stem.idx or stem.idx.gz
+pub fn read_stardict_headwords(ifo_path: &Path) -> Result<Vec<String>> {
+    let ifo = std::fs::read_to_string(ifo_path)
+        .with_context(|| format!("failed to read {}", ifo_path.display()))?;
+    let offset_bits = ifo
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "idxoffsetbits")
+        .map(|(_, value)| value.trim().parse())
+        .transpose()
+        .context("invalid idxoffsetbits")?
+        .unwrap_or(32);
+
+    let idx_path = ifo_path.with_extension("idx");
+    let data = if idx_path.is_file() {
+        std::fs::read(&idx_path)?
+    } else {
+        let gz_path = ifo_path.with_extension("idx.gz");
+        let mut data = vec![];
+        GzDecoder::new(
+            std::fs::File::open(&gz_path)
+                .with_context(|| format!("failed to read {}", gz_path.display()))?,
+        )
+        .read_to_end(&mut data)?;
+        data
+    };
+    parse_idx(&data, offset_bits)
+}
+
+/// Levenshtein distance in chars, or None once it is certain to exceed max_distance
+fn edit_distance_within(a: &[char], b: &[char], max_distance: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max_distance {
+        return None;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().min().is_some_and(|min| *min > max_distance) {
+            return None;
+        }
+        previous = current;
+    }
+    Some(previous[b.len()]).filter(|distance| *distance <= max_distance)
+}
+
+impl HeadwordIndex {
+    pub fn new(headwords: impl IntoIterator<Item = String>) -> Self {
+        let mut entries = headwords
+            .into_iter()
+            .filter(|headword| !headword.trim().is_empty())
+            .map(|headword| (fold_for_lookup(&headword), headword))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries.dedup();
+        Self { entries }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// the entries whose folded form is the query's
+    fn folded_entries(&self, query: &str) -> &[(String, String)] {
+        let folded = fold_for_lookup(query.trim());
+        let start = self
+            .entries
+            .partition_point(|(entry, _)| entry.as_str() < folded.as_str());
+        let end = self
+            .entries
+            .partition_point(|(entry, _)| entry.as_str() <= folded.as_str());
+        &self.entries[start..end]
+    }
+
+    /// whether the query is a headword, up to folding
+    pub fn contains(&self, query: &str) -> bool {
+        !self.folded_entries(query).is_empty()
+    }
+
+    /// the headwords that fold to the same form as the query, e.g. "ete" and "été" for "Ete"
+    pub fn folded_matches(&self, query: &str) -> Vec<String> {
+        self.folded_entries(query)
+            .iter()
+            .map(|(_, headword)| headword.clone())
+            .collect()
+    }
+
+    /// "did you mean" headwords for a query that found nothing: headwords it only misses by accents or case,
+    /// e.g. "fête" for "fete", then near misses by fuzzy_matches
+    pub fn fuzzy_suggestions(&self, query: &str, limit: usize) -> Vec<String> {
+        let mut suggestions = self
+            .folded_matches(query)
+            .into_iter()
+            .filter(|headword| headword != query.trim())
+            .collect::<Vec<_>>();
+        for headword in self.fuzzy_matches(query, limit) {
+            if !suggestions.contains(&headword) {
+                suggestions.push(headword);
+            }
+        }
+        suggestions.truncate(limit);
+        suggestions
+    }
+
+    /// headwords starting with the query, shortest first
+    pub fn prefix_matches(&self, query: &str, limit: usize) -> Vec<String> {
+        let folded = fold_for_lookup(query.trim());
+        if folded.is_empty() {
+            return vec![];
+        }
+        let start = self
+            .entries
+            .partition_point(|(entry, _)| entry.as_str() < folded.as_str());
+        let mut matches = self.entries[start..]
+            .iter()
+            .take_while(|(entry, _)| entry.starts_with(&folded))
+            .map(|(entry, headword)| (entry.chars().count(), headword))
+            .collect::<Vec<_>>();
+        // stable, so headwords of the same length stay in index order
+        matches.sort_by_key(|(length, _)| *length);
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, headword)| headword.clone())
+            .collect()
+    }
+
+    /// headwords within a few edits of the query, closest first, excluding exact matches
+    pub fn fuzzy_matches(&self, query: &str, limit: usize) -> Vec<String> {
+        let folded = fold_for_lookup(query.trim()).chars().collect::<Vec<_>>();
+        if folded.is_empty() {
+            return vec![];
+        }
+        let max_distance = match folded.len() <= SHORT_QUERY_CHARS {
+            true => 1,
+            false => 2,
+        };
+        let mut matches = self
+            .entries
+            .iter()
+            .filter_map(|(entry, headword)| {
+                let entry = entry.chars().collect::<Vec<_>>();
+                edit_distance_within(&folded, &entry, max_distance)
+                    .filter(|distance| *distance > 0)
+                    .map(|distance| (distance, entry.len().abs_diff(folded.len()), headword))
+            })
+            .collect::<Vec<_>>();
+        matches.sort();
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, headword)| headword.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    fn idx(headwords: &[&str]) -> Vec<u8> {
+        let mut data = vec![];
+        for (i, headword) in headwords.iter().enumerate() {
+            data.extend_from_slice(headword.as_bytes());
+            data.push(0);
+            data.extend_from_slice(&(i as u32 * 10).to_be_bytes());
+            data.extend_from_slice(&10u32.to_be_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_idx() {
+        assert_eq!(
+            parse_idx(&idx(&["a", "été"]), 32).unwrap(),
+            vec!["a".to_string(), "été".to_string()]
+        );
+        assert!(parse_idx(&idx(&["a"])[..5], 32).is_err());
+        assert!(parse_idx(&idx(&["a"]), 16).is_err());
+    }
+
+    #[test]
+    fn test_headword_index() {
+        let index = HeadwordIndex::new(
+            [
+                "été",
+                "étage",
+                "étang",
+                "état",
+                "États-Unis",
+                "être",
+                "ete",
+                "tête",
+                "fête",
+                "chat",
+                "chats",
+                "château",
+                "",
+            ]
+            .into_iter()
+            .map(|headword| headword.to_string()),
+        );
+        assert_eq!(index.len(), 12);
+        assert!(index.contains("Été"));
+        assert!(!index.contains("chien"));
+        assert_eq!(index.folded_matches("Ete"), vec!["ete", "été"]);
+        assert_eq!(index.folded_matches("etre"), vec!["être"]);
+        assert_eq!(index.folded_matches("chien"), Vec::<String>::new());
+
+        expect![[r#"["ete", "été", "état", "être", "étage"]"#]]
+            .assert_eq(&format!("{:?}", index.prefix_matches("et", 5)));
+        expect![[r#"["chat", "chats", "château"]"#]]
+            .assert_eq(&format!("{:?}", index.prefix_matches("CHA", 10)));
+        expect![[r#"["tête", "ete", "été"]"#]]
+            .assert_eq(&format!("{:?}", index.fuzzy_matches("fete", 10)));
+        expect![[r#"["chats"]"#]].assert_eq(&format!("{:?}", index.fuzzy_matches("chts", 10)));
+        expect![[r#"["château"]"#]]
+            .assert_eq(&format!("{:?}", index.fuzzy_matches("chateaux", 10)));
+        assert_eq!(index.fuzzy_matches("", 10), Vec::<String>::new());
+        // lookups match exactly, so a missing accent is the first thing to suggest
+        expect![[r#"["fête", "tête", "ete", "été"]"#]]
+            .assert_eq(&format!("{:?}", index.fuzzy_suggestions("fete", 10)));
+        expect![[r#"["ete", "été"]"#]]
+            .assert_eq(&format!("{:?}", index.fuzzy_suggestions("Ete", 2)));
+        expect![[r#"["ete", "fête"]"#]]
+            .assert_eq(&format!("{:?}", index.fuzzy_suggestions("été", 2)));
+        assert_eq!(index.prefix_matches("zz", 10), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,517 @@
+///! MDict .mdx dictionaries, version 2.0 files with UTF-8 or UTF-16 text
+///! - keys are read into memory on open; a record block is only read and inflated when a lookup needs it
+///! - blocks may be stored or zlib compressed. LZO compressed and encrypted files are refused
+///! - @@@LINK= records redirect to another key, entry:// links become bword:// cross-references and
+///!   sound:// links point at files next to the dictionary. resources packed in .mdd files are not read
+use super::dictionary_reader::{Dictionary, DictionaryEntry};
+use super::dictionary_registry::DictionaryMetadata;
+use anyhow::{Context, Result};
+use flate2::read::ZlibDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const MAX_LINK_HOPS: usize = 5;
+const LINK_PREFIX: &str = "@@@LINK=";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecordBlock {
+    file_offset: u64,
+    compressed_size: u64,
+    decompressed_offset: u64,
+    decompressed_size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MdictHeader {
+    attributes: HashMap<String, String>,
+    /// where the keyword section starts
+    end: u64,
+}
+
+pub struct MdictDictionary {
+    file: File,
+    utf16: bool,
+    /// keys in file order, with where their record starts in the inflated records
+    keys: Vec<(String, u64)>,
+    index: HashMap<String, Vec<usize>>,
+    record_blocks: Vec<RecordBlock>,
+    records_size: u64,
+    /// the last record block inflated, since neighbouring lookups often share one
+    cached_block: Option<(usize, Vec<u8>)>,
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// the name="value" attributes of the header's one xml element
+fn header_attributes(xml: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut rest = xml;
+    while let Some(equals) = rest.find("=\"") {
+        let name = rest[..equals]
+            .rsplit(|c: char| c.is_whitespace() || c == '<')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let after = &rest[equals + 2..];
+        let Some(end) = after.find('"') else {
+            break;
+        };
+        attributes.insert(name, unescape_xml(&after[..end]));
+        rest = &after[end + 1..];
+    }
+    attributes
+}
+
+fn read_u64(bytes: &[u8], at: &mut usize) -> Result<u64> {
+    let value = bytes
+        .get(*at..*at + 8)
+        .context("truncated MDict file")?
+        .try_into()
+        .map(u64::from_be_bytes)?;
+    *at += 8;
+    Ok(value)
+}
+
+fn read_u16(bytes: &[u8], at: &mut usize) -> Result<u16> {
+    let value = bytes
+        .get(*at..*at + 2)
+        .context("truncated MDict file")?
+        .try_into()
+        .map(u16::from_be_bytes)?;
+    *at += 2;
+    Ok(value)
+}
+
+/// offsets and lengths are read from the file itself, so they are checked against its size before
+/// anything is allocated, or a corrupt length could ask for gigabytes
+fn read_exact_at(file: &mut File, offset: u64, length: u64) -> Result<Vec<u8>> {
+    let file_size = file.metadata()?.len();
+    if offset.checked_add(length).is_none_or(|end| end > file_size) {
+        anyhow::bail!(
+            "truncated MDict file, {} bytes at {} are past its end at {}",
+            length,
+            offset,
+            file_size
+        );
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = vec![0; length as usize];
+    file.read_exact(&mut bytes)
+        .context("truncated MDict file")?;
+    Ok(bytes)
+}
+
+/// a block is a 4 byte compression type, a 4 byte checksum, then the data
+fn decompress_block(block: &[u8]) -> Result<Vec<u8>> {
+    let (kind, data) = match block {
+        [a, b, c, d, _, _, _, _, data @ ..] => ([*a, *b, *c, *d], data),
+        _ => anyhow::bail!("truncated MDict block"),
+    };
+    match kind {
+        [0, 0, 0, 0] => Ok(data.to_vec()),
+        [2, 0, 0, 0] => {
+            let mut decompressed = vec![];
+            ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+            Ok(decompressed)
+        }
+        [1, 0, 0, 0] => anyhow::bail!("LZO compressed MDict files are not supported"),
+        _ => anyhow::bail!("unknown MDict block compression {:?}", kind),
+    }
+}
+
+fn read_header(file: &mut File) -> Result<MdictHeader> {
+    let length = u32::from_be_bytes(read_exact_at(file, 0, 4)?.try_into().unwrap()) as u64;
+    let bytes = read_exact_at(file, 4, length)?;
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect::<Vec<_>>();
+    let attributes = header_attributes(&String::from_utf16_lossy(&units));
+
+    let version = attributes
+        .get("GeneratedByEngineVersion")
+        .and_then(|version| version.trim().parse::<f32>().ok())
+        .context("MDict header has no engine version")?;
+    if version < 2.0 {
+        anyhow::bail!(
+            "MDict version {} is not supported, only 2.0 and later",
+            version
+        );
+    }
+    let encrypted = attributes
+        .get("Encrypted")
+        .map(|encrypted| encrypted.trim())
+        .unwrap_or("0");
+    if !matches!(encrypted, "0" | "" | "No") {
+        anyhow::bail!("encrypted MDict files are not supported");
+    }
+    // the header is followed by its 4 byte checksum
+    Ok(MdictHeader {
+        attributes,
+        end: 4 + length + 4,
+    })
+}
+
+fn decode(bytes: &[u8], utf16: bool) -> String {
+    if utf16 {
+        let units = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(bytes).to_string()
+    }
+}
+
+/// a key block's entries: a record offset, then the key up to its nul terminator
+fn parse_key_block(block: &[u8], utf16: bool, keys: &mut Vec<(String, u64)>) -> Result<()> {
+    let unit = if utf16 { 2 } else { 1 };
+    let mut at = 0;
+    while at < block.len() {
+        let offset = read_u64(block, &mut at)?;
+        let mut end = at;
+        while end + unit <= block.len() && block[end..end + unit].iter().any(|byte| *byte != 0) {
+            end += unit;
+        }
+        keys.push((decode(&block[at..end], utf16), offset));
+        at = end + unit;
+    }
+    Ok(())
+}
+
+impl MdictDictionary {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file =
+            File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let header = read_header(&mut file)?;
+        let utf16 = header
+            .attributes
+            .get("Encoding")
+            .is_some_and(|encoding| encoding.to_uppercase().starts_with("UTF-16"));
+        if let Some(encoding) = header.attributes.get("Encoding") {
+            let encoding = encoding.to_uppercase();
+            if !(encoding.is_empty() || encoding == "UTF-8" || encoding.starts_with("UTF-16")) {
+                anyhow::bail!("MDict encoding {} is not supported", encoding);
+            }
+        }
+
+        // keyword section: block count, entry count, key block info sizes and total key block size
+        let section = read_exact_at(&mut file, header.end, 40)?;
+        let mut at = 0;
+        let key_block_count = read_u64(&section, &mut at)?;
+        let _entry_count = read_u64(&section, &mut at)?;
+        let _key_info_decompressed_size = read_u64(&section, &mut at)?;
+        let key_info_size = read_u64(&section, &mut at)?;
+        let key_blocks_size = read_u64(&section, &mut at)?;
+        let key_info_offset = header.end + 40 + 4;
+
+        let key_info =
+            decompress_block(&read_exact_at(&mut file, key_info_offset, key_info_size)?)?;
+        let unit = if utf16 { 2 } else { 1 };
+        let mut key_block_sizes = vec![];
+        let mut at = 0;
+        for _ in 0..key_block_count {
+            let _entries = read_u64(&key_info, &mut at)?;
+            // first and last keys of the block, as a length and the text with its terminator
+            for _ in 0..2 {
+                let length = read_u16(&key_info, &mut at)? as usize;
+                at += (length + 1) * unit;
+            }
+            let compressed_size = read_u64(&key_info, &mut at)?;
+            let _decompressed_size = read_u64(&key_info, &mut at)?;
+            key_block_sizes.push(compressed_size);
+        }
+
+        let key_blocks_offset = key_info_offset + key_info_size;
+        let key_blocks = read_exact_at(&mut file, key_blocks_offset, key_blocks_size)?;
+        let mut keys = vec![];
+        let mut at = 0usize;
+        for size in key_block_sizes {
+            let block = key_blocks
+                .get(at..at + size as usize)
+                .context("truncated MDict key block")?;
+            parse_key_block(&decompress_block(block)?, utf16, &mut keys)?;
+            at += size as usize;
+        }
+
+        // record section: block count, entry count, block info size and total record block size
+        let records_offset = key_blocks_offset + key_blocks_size;
+        let section = read_exact_at(&mut file, records_offset, 32)?;
+        let mut at = 0;
+        let record_block_count = read_u64(&section, &mut at)?;
+        let _entry_count = read_u64(&section, &mut at)?;
+        let record_info_size = read_u64(&section, &mut at)?;
+        let _record_blocks_size = read_u64(&section, &mut at)?;
+        let record_info = read_exact_at(&mut file, records_offset + 32, record_info_size)?;
+        let mut file_offset = records_offset + 32 + record_info_size;
+        let mut decompressed_offset = 0;
+        let mut record_blocks = vec![];
+        let mut at = 0;
+        for _ in 0..record_block_count {
+            let compressed_size = read_u64(&record_info, &mut at)?;
+            let decompressed_size = read_u64(&record_info, &mut at)?;
+            record_blocks.push(RecordBlock {
+                file_offset,
+                compressed_size,
+                decompressed_offset,
+                decompressed_size,
+            });
+            file_offset += compressed_size;
+            decompressed_offset += decompressed_size;
+        }
+
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (position, (key, _)) in keys.iter().enumerate() {
+            index.entry(key.clone()).or_default().push(position);
+        }
+        Ok(Self {
+            file,
+            utf16,
+            keys,
+            index,
+            record_blocks,
+            records_size: decompressed_offset,
+            cached_block: None,
+        })
+    }
+
+    fn record_block(&mut self, block_index: usize) -> Result<&[u8]> {
+        if self
+            .cached_block
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != block_index)
+        {
+            let block = &self.record_blocks[block_index];
+            let compressed =
+                read_exact_at(&mut self.file, block.file_offset, block.compressed_size)?;
+            self.cached_block = Some((block_index, decompress_block(&compressed)?));
+        }
+        Ok(&self.cached_block.as_ref().unwrap().1)
+    }
+
+    /// the text of the record of the key at position, without its terminator
+    fn record(&mut self, position: usize) -> Result<String> {
+        let start = self.keys[position].1;
+        let end = self
+            .keys
+            .get(position + 1)
+            .map(|(_, offset)| *offset)
+            .filter(|offset| *offset > start)
+            .unwrap_or(self.records_size);
+        let block_index = self
+            .record_blocks
+            .partition_point(|block| block.decompressed_offset + block.decompressed_size <= start);
+        let block_start = self
+            .record_blocks
+            .get(block_index)
+            .context("MDict record is outside the record blocks")?
+            .decompressed_offset;
+        let utf16 = self.utf16;
+        let block = self.record_block(block_index)?;
+        let from = (start - block_start) as usize;
+        let to = ((end - block_start) as usize).min(block.len());
+        let text = decode(block.get(from..to).unwrap_or_default(), utf16);
+        Ok(text.trim_end_matches(['\0', '\r', '\n']).to_string())
+    }
+
+    /// the html for a key, following @@@LINK= redirects
+    fn resolve(&mut self, word: &str) -> Result<Option<Vec<String>>> {
+        let mut word = word.to_string();
+        for _ in 0..MAX_LINK_HOPS {
+            let Some(positions) = self.index.get(&word).cloned() else {
+                return Ok(None);
+            };
+            let records = positions
+                .into_iter()
+                .map(|position| self.record(position))
+                .collect::<Result<Vec<_>>>()?;
+            match records
+                .first()
+                .and_then(|record| record.trim().strip_prefix(LINK_PREFIX))
+            {
+                Some(target) => word = target.trim().to_string(),
+                None => return Ok(Some(records)),
+            }
+        }
+        anyhow::bail!("too many @@@LINK redirects from {}", word)
+    }
+}
+
+/// entry:// links are cross-references and sound:// links name files next to the dictionary
+fn rewrite_links(html: &str) -> String {
+    html.replace("\"entry://", "\"bword://")
+        .replace("'entry://", "'bword://")
+        .replace("\"sound://", "\"")
+        .replace("'sound://", "'")
+}
+
+/// the title and entry count of an .mdx, from its header and keyword section
+pub fn read_metadata(path: &Path) -> Result<DictionaryMetadata> {
+    let mut file =
+        File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let header = read_header(&mut file)?;
+    let section = read_exact_at(&mut file, header.end, 16)?;
+    let mut at = 8;
+    let entry_count = read_u64(&section, &mut at)?;
+    let title = header
+        .attributes
+        .get("Title")
+        .map(|title| title.trim())
+        // MDict's builder leaves this placeholder when no title is set
+        .filter(|title| !title.is_empty() && !title.starts_with("Title (No HTML code allowed)"))
+        .map(|title| title.to_string())
+        .or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+        })
+        .unwrap_or_default();
+    Ok(DictionaryMetadata {
+        bookname: title,
+        wordcount: entry_count as usize,
+        ..Default::default()
+    })
+}
+
+impl Dictionary for MdictDictionary {
+    fn lookup(&mut self, word: &str) -> Result<Option<Vec<DictionaryEntry>>> {
+        Ok(self.resolve(word)?.map(|records| {
+            records
+                .into_iter()
+                .map(|record| DictionaryEntry::html(word, rewrite_links(&record)))
+                .collect()
+        }))
+    }
+
+    fn headwords(&mut self) -> Result<Vec<String>> {
+        Ok(self.index.keys().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn zlib_block(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(data).unwrap();
+        // checksums are not verified, so they are left as zeros
+        [&[2, 0, 0, 0, 0, 0, 0, 0][..], &encoder.finish().unwrap()].concat()
+    }
+
+    /// a version 2.0 UTF-8 .mdx with one key block and one record block
+    fn mk_mdx(entries: &[(&str, &str)]) -> Vec<u8> {
+        let header = r#"<Dictionary GeneratedByEngineVersion="2.0" RequiredEngineVersion="2.0" Encrypted="0" Encoding="UTF-8" Format="Html" Title="Cats &amp; Kittens"/>"#
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut keys = vec![];
+        let mut records = vec![];
+        for (key, record) in entries {
+            keys.extend((records.len() as u64).to_be_bytes());
+            keys.extend(key.as_bytes());
+            keys.push(0);
+            records.extend(record.as_bytes());
+            records.push(0);
+        }
+        let key_block = zlib_block(&keys);
+        let mut key_info = vec![];
+        key_info.extend((entries.len() as u64).to_be_bytes());
+        for key in [entries[0].0, entries[entries.len() - 1].0] {
+            key_info.extend((key.len() as u16).to_be_bytes());
+            key_info.extend(key.as_bytes());
+            key_info.push(0);
+        }
+        key_info.extend((key_block.len() as u64).to_be_bytes());
+        key_info.extend((keys.len() as u64).to_be_bytes());
+        let compressed_key_info = zlib_block(&key_info);
+        let record_block = zlib_block(&records);
+
+        let mut mdx = vec![];
+        mdx.extend((header.len() as u32).to_be_bytes());
+        mdx.extend(&header);
+        mdx.extend([0; 4]);
+        for number in [
+            1,
+            entries.len() as u64,
+            key_info.len() as u64,
+            compressed_key_info.len() as u64,
+            key_block.len() as u64,
+        ] {
+            mdx.extend(number.to_be_bytes());
+        }
+        mdx.extend([0; 4]);
+        mdx.extend(&compressed_key_info);
+        mdx.extend(&key_block);
+        for number in [
+            1,
+            entries.len() as u64,
+            16,
+            record_block.len() as u64,
+            record_block.len() as u64,
+            records.len() as u64,
+        ] {
+            mdx.extend(number.to_be_bytes());
+        }
+        mdx.extend(&record_block);
+        mdx
+    }
+
+    #[test]
+    fn test_mdict_dictionary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cats.mdx");
+        std::fs::write(
+            &path,
+            mk_mdx(&[
+                ("cat", r#"<b>cat</b> see <a href="entry://kitten">kitten</a> <a href="sound://cat.mp3">♪</a>"#),
+                ("kitten", "<b>kitten</b> a young cat\r\n"),
+                ("kitty", "@@@LINK=kitten"),
+                ("loop", "@@@LINK=loop"),
+            ]),
+        )
+        .unwrap();
+
+        let metadata = read_metadata(&path).unwrap();
+        assert_eq!(metadata.bookname, "Cats & Kittens");
+        assert_eq!(metadata.wordcount, 4);
+
+        let mut dictionary = MdictDictionary::open(&path).unwrap();
+        let mut headwords = dictionary.headwords().unwrap();
+        headwords.sort();
+        assert_eq!(headwords, vec!["cat", "kitten", "kitty", "loop"]);
+        assert_eq!(
+            dictionary.lookup("cat").unwrap(),
+            Some(vec![DictionaryEntry::html(
+                "cat",
+                r#"<b>cat</b> see <a href="bword://kitten">kitten</a> <a href="cat.mp3">♪</a>"#
+            )])
+        );
+        assert_eq!(
+            dictionary.lookup("kitty").unwrap(),
+            Some(vec![DictionaryEntry::html(
+                "kitty",
+                "<b>kitten</b> a young cat"
+            )])
+        );
+        assert_eq!(dictionary.lookup("dog").unwrap(), None);
+        assert!(dictionary.lookup("loop").is_err());
+
+        // a header claiming 4GB is refused rather than allocated
+        let corrupt = dir.path().join("corrupt.mdx");
+        std::fs::write(&corrupt, [0xff, 0xff, 0xff, 0xff, b'<']).unwrap();
+        let err = read_metadata(&corrupt).unwrap_err();
+        assert!(format!("{:#}", err).contains("truncated"), "{:#}", err);
+    }
+}
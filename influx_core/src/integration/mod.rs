@@ -4,7 +4,17 @@ use reqwest::Client;
 use serde_json::{json, Value};
 use std::process::Command;
 
+pub mod definition_html;
+pub mod dictionary_install;
+pub mod dictionary_reader;
+pub mod dictionary_registry;
+pub mod dsl;
+pub mod frequency_list;
+pub mod headword_index;
+pub mod mdict;
+pub mod nlp_client;
 pub mod stardict;
+pub mod yomitan;
 
 #[async_trait]
 pub trait ExternalDict {
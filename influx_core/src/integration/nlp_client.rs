@@ -0,0 +1,341 @@
+///! client for the python NLP service, created once and shared through ServerState
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+#[derive(Debug, Clone)]
+pub struct NlpClientConfig {
+    pub connect_timeout: Duration,
+    /// time allowed between reads of the response. the first request for a model can be slow while it loads
+    pub read_timeout: Duration,
+    /// retries after the first attempt, only when the service looks down
+    pub max_retries: u32,
+    /// doubled after every retry
+    pub initial_backoff: Duration,
+    /// how long the service's parser version, or its failure to give one, is trusted before asking again
+    pub version_ttl: Duration,
+}
+
+impl Default for NlpClientConfig {
+    fn default() -> Self {
+        NlpClientConfig {
+            connect_timeout: Duration::from_secs(3),
+            read_timeout: Duration::from_secs(120),
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(250),
+            version_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum NlpError {
+    #[error("NLP service at {url} is unreachable: {source}")]
+    Unreachable {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("NLP service at {url} accepted the request but did not answer in time: {source}")]
+    Timeout {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("NLP service at {url} returned {status}: {body}")]
+    ServiceError {
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("failed to decode NLP service response from {url}: {source}\n{body}")]
+    DecodeFailure {
+        url: String,
+        body: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl NlpError {
+    /// a timeout after connecting means the service is up but slow, so the error is a Timeout and is not retried
+    fn from_request(url: &str, source: reqwest::Error) -> Self {
+        let url = url.to_string();
+        if source.is_timeout() && !source.is_connect() {
+            NlpError::Timeout { url, source }
+        } else {
+            NlpError::Unreachable { url, source }
+        }
+    }
+
+    /// the service could not be reached or said it is unavailable, so another attempt may succeed
+    pub fn is_service_down(&self) -> bool {
+        match self {
+            NlpError::Unreachable { .. } => true,
+            // another attempt would wait just as long
+            NlpError::Timeout { .. } => false,
+            NlpError::ServiceError { status, .. } => matches!(
+                *status,
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            NlpError::DecodeFailure { .. } => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NlpClient {
+    client: Client,
+    base_url: String,
+    config: NlpClientConfig,
+    /// shared by every clone, so handlers and jobs ask the service at most once per version_ttl
+    cached_version: Arc<Mutex<Option<CachedVersion>>>,
+}
+
+/// when the version was asked for, and the answer, None when the service could not say
+type CachedVersion = (Instant, Option<String>);
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    version: String,
+}
+
+impl NlpClient {
+    pub fn new(base_url: impl Into<String>, config: NlpClientConfig) -> anyhow::Result<Self> {
+        let client = Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .read_timeout(config.read_timeout)
+            .build()?;
+        Ok(NlpClient {
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            config,
+            cached_version: Default::default(),
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// the service answers GET / as soon as it is up, without loading any models
+    pub async fn health_check(&self) -> Result<(), NlpError> {
+        let url = format!("{}/", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|source| NlpError::from_request(&url, source))?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(NlpError::ServiceError {
+                url,
+                status,
+                body: response.text().await.unwrap_or_default(),
+            })
+        }
+    }
+
+    /// opaque string that changes whenever the service may parse the same text differently.
+    /// None when the service could not tell, which is remembered too so that a service that is down
+    /// is not asked again on every document open
+    pub async fn parser_version(&self) -> Option<String> {
+        // held while fetching, so concurrent callers wait for one request instead of each sending their own
+        let mut cached_version = self.cached_version.lock().await;
+        if let Some((fetched_at, version)) = cached_version.as_ref() {
+            if fetched_at.elapsed() < self.config.version_ttl {
+                return version.clone();
+            }
+        }
+        let version = match self.fetch_parser_version().await {
+            Ok(version) => Some(version),
+            Err(err) => {
+                warn!("Could not get the NLP parser version: {}", err);
+                None
+            }
+        };
+        *cached_version = Some((Instant::now(), version.clone()));
+        version
+    }
+
+    async fn fetch_parser_version(&self) -> Result<String, NlpError> {
+        let url = format!("{}/version", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|source| NlpError::from_request(&url, source))?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(NlpError::ServiceError { url, status, body });
+        }
+        let VersionResponse { version } = serde_json::from_str(&body)
+            .map_err(|source| NlpError::DecodeFailure { url, body, source })?;
+        Ok(version)
+    }
+
+    async fn post_json_once<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        payload: &Value,
+    ) -> Result<T, NlpError> {
+        let request_error = |source| NlpError::from_request(url, source);
+        let response = self
+            .client
+            .post(url)
+            .json(payload)
+            .send()
+            .await
+            .map_err(request_error)?;
+        let status = response.status();
+        let body = response.text().await.map_err(request_error)?;
+        debug!(%status, %url, "NLP service response received");
+
+        if !status.is_success() {
+            return Err(NlpError::ServiceError {
+                url: url.to_string(),
+                status,
+                body,
+            });
+        }
+        serde_json::from_str(&body).map_err(|source| NlpError::DecodeFailure {
+            url: url.to_string(),
+            body,
+            source,
+        })
+    }
+
+    /// POST a JSON payload to `path` and decode the JSON response, retrying with backoff while the service is down
+    pub async fn post_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        payload: &Value,
+    ) -> Result<T, NlpError> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut backoff = self.config.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            match self.post_json_once(&url, payload).await {
+                Err(err) if err.is_service_down() && attempt < self.config.max_retries => {
+                    attempt += 1;
+                    warn!(%url, attempt, error = %err, "NLP service request failed, retrying");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode as AxumStatusCode, routing::post, Router};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
+
+    fn fast_config() -> NlpClientConfig {
+        NlpClientConfig {
+            connect_timeout: Duration::from_millis(200),
+            read_timeout: Duration::from_millis(500),
+            max_retries: 1,
+            initial_backoff: Duration::from_millis(1),
+            version_ttl: Duration::from_secs(60),
+        }
+    }
+
+    static SLOW_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+    static VERSION_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+    #[tokio::test]
+    async fn test_nlp_client_errors() {
+        let app = Router::new()
+            .route(
+                "/broken",
+                post(|| async { (AxumStatusCode::INTERNAL_SERVER_ERROR, "parser exploded") }),
+            )
+            .route("/garbled", post(|| async { "not json" }))
+            .route("/ok", post(|| async { "[1, 2]" }))
+            .route(
+                "/slow",
+                post(|| async {
+                    SLOW_REQUESTS.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    "[]"
+                }),
+            )
+            .route(
+                "/version",
+                axum::routing::get(|| async {
+                    VERSION_REQUESTS.fetch_add(1, Ordering::SeqCst);
+                    r#"{"version": "spacy=3.8"}"#
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = NlpClient::new(format!("http://{}", addr), fast_config()).unwrap();
+        let ok: Vec<i32> = client.post_json("/ok", &json!({})).await.unwrap();
+        assert_eq!(ok, vec![1, 2]);
+        assert_eq!(client.parser_version().await.as_deref(), Some("spacy=3.8"));
+        // clones share the cached version, so only the first call reaches the service
+        assert_eq!(
+            client.clone().parser_version().await.as_deref(),
+            Some("spacy=3.8")
+        );
+        assert_eq!(VERSION_REQUESTS.load(Ordering::SeqCst), 1);
+
+        match client.post_json::<Value>("/broken", &json!({})).await {
+            Err(err @ NlpError::ServiceError { .. }) => {
+                assert!(!err.is_service_down());
+                assert!(err.to_string().contains("parser exploded"));
+            }
+            other => panic!("expected a service error, got {:?}", other),
+        }
+        assert!(matches!(
+            client.post_json::<Value>("/garbled", &json!({})).await,
+            Err(NlpError::DecodeFailure { .. })
+        ));
+
+        // a connected request that outlasts read_timeout fails once instead of being retried
+        let err = client
+            .post_json::<Value>("/slow", &json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, NlpError::Timeout { .. }));
+        assert!(!err.is_service_down());
+        assert_eq!(SLOW_REQUESTS.load(Ordering::SeqCst), 1);
+
+        // nothing listens on a port we bound and released
+        let closed_addr = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let down_client = NlpClient::new(format!("http://{}", closed_addr), fast_config()).unwrap();
+        let err = down_client
+            .post_json::<Value>("/tokeniser", &json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, NlpError::Unreachable { .. }));
+        assert!(err.is_service_down());
+        assert!(down_client.health_check().await.is_err());
+        assert_eq!(down_client.parser_version().await, None);
+    }
+}
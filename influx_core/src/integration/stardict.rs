@@ -1,52 +1,295 @@
-use stardict::{no_cache, with_sled, StarDict};
+///! StarDict dictionaries opened on demand and shared between concurrent lookups
+///! - DSL, MDict and Yomitan dictionaries are managed the same way, through dictionary_reader
+///! - each dictionary has its own lock, so lookups in different dictionaries run in parallel and a slow
+///!   load only holds up lookups in the dictionary being loaded
+///! - loads and lookups read files, so they run on tokio's blocking thread pool
+///! - at most max_loaded dictionaries stay open, the least recently used is closed to make room. a dictionary
+///!   in use is never closed, or the next lookup would open it a second time while sled still holds its lock
+use super::dictionary_reader::{
+    open_dictionary, Dictionary, DictionaryEntry, DictionaryFormat, StarDictDictionary,
+};
+use super::headword_index::HeadwordIndex;
+use crate::prelude::*;
+use stardict::{no_cache, with_sled};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
 
+pub const DEFAULT_MAX_LOADED_DICTIONARIES: usize = 8;
+
+/// an open dictionary, or None while it has not been loaded yet
+struct DictionarySlot {
+    dict: Mutex<Option<Box<dyn Dictionary>>>,
+    /// read on the first search, and closed along with the dictionary
+    headwords: Mutex<Option<Arc<HeadwordIndex>>>,
+    last_used: AtomicU64,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct DictionaryLoadStats {
+    pub ifo_path: String,
+    pub loads: u64,
+    pub last_load_millis: u64,
+    pub total_load_millis: u64,
+    pub loaded: bool,
+}
+
+#[derive(Clone)]
 pub struct StardictManager {
-    dictionaries: HashMap<String, Box<dyn StarDict + Send>>,
-    use_cache: bool,
+    dictionaries: Arc<Mutex<HashMap<String, Arc<DictionarySlot>>>>,
+    load_stats: Arc<Mutex<HashMap<String, DictionaryLoadStats>>>,
+    /// incremented on every lookup, so last_used orders the slots by recency
+    clock: Arc<AtomicU64>,
+    /// where sled caches go, None to read dictionaries without caching
+    cache_dir: Option<PathBuf>,
+    max_loaded: usize,
+}
+
+/// the least recently used slot other than keep, to close when more than max_loaded are open
+fn least_recently_used<'a>(
+    last_used: impl Iterator<Item = (&'a String, u64)>,
+    keep: &str,
+) -> Option<String> {
+    last_used
+        .filter(|(ifo_path, _)| ifo_path.as_str() != keep)
+        .min_by_key(|(_, last_used)| *last_used)
+        .map(|(ifo_path, _)| ifo_path.clone())
 }
 
 impl StardictManager {
     pub fn new() -> Self {
         Self {
-            dictionaries: HashMap::new(),
-            use_cache: false,
+            dictionaries: Default::default(),
+            load_stats: Default::default(),
+            clock: Default::default(),
+            cache_dir: None,
+            max_loaded: DEFAULT_MAX_LOADED_DICTIONARIES,
         }
     }
 
-    pub fn new_with_cache() -> Self {
+    /// cache each dictionary in its own sled database under cache_dir
+    pub fn new_with_cache(cache_dir: PathBuf) -> Self {
         Self {
-            dictionaries: HashMap::new(),
-            use_cache: true,
+            cache_dir: Some(cache_dir),
+            ..Self::new()
         }
     }
 
-    pub fn load_dictionary(&mut self, ifo_path: String) -> &mut Box<dyn StarDict + Send> {
-        if !self.dictionaries.contains_key(&ifo_path) {
-            let path_buf = PathBuf::from(&ifo_path);
+    pub fn with_max_loaded(self, max_loaded: usize) -> Self {
+        Self {
+            max_loaded: max_loaded.max(1),
+            ..self
+        }
+    }
 
-            let dict: Box<dyn StarDict + Send> = if self.use_cache {
-                // TODO cache name is definitely not right. maybe use the path library to get our app data dir?
-                Box::new(with_sled(path_buf, "influx_stardict").unwrap())
-            } else {
-                Box::new(no_cache(path_buf).unwrap())
+    /// the dictionary's slot, marked as just used, making room for it if it is new
+    fn slot(&self, ifo_path: &str) -> Arc<DictionarySlot> {
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        let mut dictionaries = self.dictionaries.lock().unwrap();
+        let slot = dictionaries
+            .entry(ifo_path.to_string())
+            .or_insert_with(|| {
+                Arc::new(DictionarySlot {
+                    dict: Mutex::new(None),
+                    headwords: Mutex::new(None),
+                    last_used: AtomicU64::new(now),
+                })
+            })
+            .clone();
+        slot.last_used.store(now, Ordering::Relaxed);
+
+        while dictionaries.len() > self.max_loaded {
+            // slots held outside the map are in use, so more than max_loaded stay open until they are released
+            let Some(evicted) = least_recently_used(
+                dictionaries
+                    .iter()
+                    .filter(|(_, slot)| Arc::strong_count(slot) == 1)
+                    .map(|(path, slot)| (path, slot.last_used.load(Ordering::Relaxed))),
+                ifo_path,
+            ) else {
+                break;
             };
-            self.dictionaries.insert(ifo_path.clone(), dict);
+            debug!(ifo_path = %evicted, "Closing least recently used dictionary");
+            dictionaries.remove(&evicted);
         }
+        slot
+    }
 
-        self.dictionaries.get_mut(&ifo_path).unwrap()
+    /// the dictionary's own sled cache directory, named after its file and a hash of its full path so
+    /// dictionaries with the same file name in different directories don't share one
+    fn cache_path(cache_dir: &Path, ifo_path: &str) -> PathBuf {
+        let stem = Path::new(ifo_path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("dictionary");
+        cache_dir.join(format!("{}-{:x}", stem, md5::compute(ifo_path)))
     }
 
-    pub fn lookup_word(
-        &mut self,
+    /// only StarDict dictionaries are cached, the other formats are read straight from their files
+    fn open(&self, ifo_path: &str) -> anyhow::Result<Box<dyn Dictionary>> {
+        let path_buf = PathBuf::from(ifo_path);
+        let started = Instant::now();
+        if DictionaryFormat::from_path(ifo_path) != Some(DictionaryFormat::StarDict) {
+            let dict = open_dictionary(&path_buf)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", ifo_path, e))?;
+            self.record_load(ifo_path, started.elapsed());
+            return Ok(dict);
+        }
+        let dict: Box<dyn stardict::StarDict + Send> = match &self.cache_dir {
+            // DONE cache name is definitely not right. maybe use the path library to get our app data dir?
+            // caches now live under data_dir, see data_dir::get_stardict_cache_dir
+            Some(cache_dir) => {
+                let cache_path = Self::cache_path(cache_dir, ifo_path);
+                Box::new(
+                    with_sled(path_buf, &cache_path.to_string_lossy()).map_err(|e| {
+                        anyhow::anyhow!("Failed to load {} with its cache: {}", ifo_path, e)
+                    })?,
+                )
+            }
+            None => Box::new(
+                no_cache(path_buf)
+                    .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", ifo_path, e))?,
+            ),
+        };
+        self.record_load(ifo_path, started.elapsed());
+        Ok(Box::new(StarDictDictionary::new(dict, ifo_path)))
+    }
+
+    fn record_load(&self, ifo_path: &str, load_time: Duration) {
+        info!(ifo_path = %ifo_path, load_ms = load_time.as_millis() as u64, "Loaded dictionary");
+        let mut load_stats = self.load_stats.lock().unwrap();
+        let stats = load_stats
+            .entry(ifo_path.to_string())
+            .or_insert_with(|| DictionaryLoadStats {
+                ifo_path: ifo_path.to_string(),
+                loads: 0,
+                last_load_millis: 0,
+                total_load_millis: 0,
+                loaded: false,
+            });
+        stats.loads += 1;
+        stats.last_load_millis = load_time.as_millis() as u64;
+        stats.total_load_millis += stats.last_load_millis;
+    }
+
+    /// blocking: open the dictionary if needed and run f on it, holding only its own lock
+    fn with_dictionary<T>(
+        &self,
+        ifo_path: &str,
+        f: impl FnOnce(&mut Box<dyn Dictionary>) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let slot = self.slot(ifo_path);
+        let mut dict = slot.dict.lock().unwrap();
+        if dict.is_none() {
+            match self.open(ifo_path) {
+                Ok(opened) => *dict = Some(opened),
+                Err(err) => {
+                    // so a dictionary that can't be opened does not take the place of one that can
+                    let mut dictionaries = self.dictionaries.lock().unwrap();
+                    if dictionaries
+                        .get(ifo_path)
+                        .is_some_and(|current| Arc::ptr_eq(current, &slot))
+                    {
+                        dictionaries.remove(ifo_path);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        f(dict.as_mut().unwrap())
+    }
+
+    pub async fn lookup_word(
+        &self,
         ifo_path: String,
         word: &str,
-    ) -> anyhow::Result<Option<Vec<stardict::WordDefinition>>> {
-        let dict: &mut Box<dyn StarDict + Send> = self.load_dictionary(ifo_path);
+    ) -> anyhow::Result<Option<Vec<DictionaryEntry>>> {
+        let manager = self.clone();
+        let word = word.to_string();
+        tokio::task::spawn_blocking(move || {
+            manager.with_dictionary(&ifo_path, |dict| dict.lookup(&word))
+        })
+        .await?
+    }
+
+    /// the dictionary's headwords, for prefix and fuzzy search
+    pub async fn headword_index(&self, ifo_path: String) -> anyhow::Result<Arc<HeadwordIndex>> {
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let slot = manager.slot(&ifo_path);
+            let mut headwords = slot.headwords.lock().unwrap();
+            if let Some(index) = headwords.as_ref() {
+                return Ok(index.clone());
+            }
+            let started = Instant::now();
+            let index = Arc::new(HeadwordIndex::new(
+                manager.with_dictionary(&ifo_path, |dict| dict.headwords())?,
+            ));
+            debug!(ifo_path = %ifo_path, headwords = index.len(), read_ms = started.elapsed().as_millis() as u64, "Read headword index");
+            *headwords = Some(index.clone());
+            Ok(index)
+        })
+        .await?
+    }
 
-        dict.lookup(word)
-            .map_err(|e| anyhow::anyhow!("Failed to lookup word '{}': {}", word, e))
+    /// forget every loaded dictionary under dir, so a removed or renamed dictionary is not served
+    /// from stale file handles and is reloaded from its new path on the next lookup
+    pub fn unload_directory(&self, dir: &Path) {
+        self.dictionaries
+            .lock()
+            .unwrap()
+            .retain(|ifo_path, _| !Path::new(ifo_path).starts_with(dir));
+    }
+
+    /// close the dictionary and delete its cache, so the next lookup reads the dictionary afresh.
+    /// does nothing to the files when caching is off
+    pub async fn clear_cache(&self, ifo_path: String) -> anyhow::Result<()> {
+        let unloaded = self.dictionaries.lock().unwrap().remove(&ifo_path);
+        let cache_path = self
+            .cache_dir
+            .as_ref()
+            .map(|cache_dir| Self::cache_path(cache_dir, &ifo_path));
+        debug!(ifo_path = %ifo_path, cache_path = ?cache_path, "Clearing dictionary cache");
+        tokio::task::spawn_blocking(move || {
+            // wait for a lookup still using the dictionary, then close it so its cache can go
+            if let Some(slot) = unloaded {
+                drop(slot.dict.lock().unwrap().take());
+            }
+            match cache_path.map(std::fs::remove_dir_all) {
+                Some(Err(err)) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            }
+        })
+        .await??;
+        Ok(())
+    }
+
+    /// clear the dictionary's cache and load it again straight away, building a fresh cache
+    pub async fn rebuild_cache(&self, ifo_path: String) -> anyhow::Result<()> {
+        self.clear_cache(ifo_path.clone()).await?;
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || manager.with_dictionary(&ifo_path, |_| Ok(()))).await?
+    }
+
+    /// how long each dictionary took to load, for every dictionary loaded since startup
+    pub fn load_stats(&self) -> Vec<DictionaryLoadStats> {
+        let dictionaries = self.dictionaries.lock().unwrap();
+        let mut load_stats = self
+            .load_stats
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .map(|stats| DictionaryLoadStats {
+                loaded: dictionaries.contains_key(&stats.ifo_path),
+                ..stats
+            })
+            .collect::<Vec<_>>();
+        load_stats.sort_by(|a, b| a.ifo_path.cmp(&b.ifo_path));
+        load_stats
     }
 }
 
@@ -54,6 +297,7 @@ impl StardictManager {
 mod tests {
     use super::*;
     use insta::assert_debug_snapshot;
+    use stardict::StarDict;
 
     fn get_jitendex_path() -> String {
         "/Users/chaosarium/Documents/Repos/Influx/dictionaries/jitendex/jitendex.ifo".to_string()
@@ -83,6 +327,27 @@ mod tests {
         })
     }
 
+    fn truncated_entries(
+        entries: anyhow::Result<Option<Vec<DictionaryEntry>>>,
+    ) -> anyhow::Result<Option<Vec<DictionaryEntry>>> {
+        entries.map(|opt| {
+            opt.map(|entries| {
+                entries
+                    .into_iter()
+                    .map(|mut entry| {
+                        for segment in &mut entry.segments {
+                            if segment.text.len() > 15 {
+                                segment.text.truncate(15);
+                                segment.text.push_str("...");
+                            }
+                        }
+                        entry
+                    })
+                    .collect()
+            })
+        })
+    }
+
     #[test]
     fn test_direct_stardict_interface() {
         let path = get_fra_eng_path();
@@ -184,20 +449,151 @@ mod tests {
     }
 
     #[test]
-    fn test_add_jitendex_dictionary() {
-        let mut manager = StardictManager::new();
+    fn test_least_recently_used() {
+        let last_used = [
+            ("a".to_string(), 3),
+            ("b".to_string(), 1),
+            ("c".to_string(), 2),
+        ];
+        let lru = |keep| least_recently_used(last_used.iter().map(|(path, t)| (path, *t)), keep);
+        assert_eq!(lru("a"), Some("b".to_string()));
+        assert_eq!(lru("b"), Some("c".to_string()));
+        assert_eq!(
+            least_recently_used(
+                [("a".to_string(), 0)].iter().map(|(path, t)| (path, *t)),
+                "a"
+            ),
+            None
+        );
+    }
 
-        let _ = manager.load_dictionary(get_jitendex_path());
-        let definitions = manager.lookup_word(get_jitendex_path(), "じんぶんちり");
+    /// a one word StarDict dictionary with one synonym, written to dir
+    fn write_tiny_stardict(dir: &Path) -> String {
+        let definition = b"a small dictionary";
+        let mut idx = b"tiny\0".to_vec();
+        idx.extend_from_slice(&0u32.to_be_bytes());
+        idx.extend_from_slice(&(definition.len() as u32).to_be_bytes());
+        let mut syn = b"wee\0".to_vec();
+        syn.extend_from_slice(&0u32.to_be_bytes());
+        std::fs::write(
+            dir.join("tiny.ifo"),
+            format!(
+                "StarDict's dict ifo file\nversion=3.0.0\nbookname=Tiny\nwordcount=1\nsynwordcount=1\nidxfilesize={}\nsametypesequence=m\n",
+                idx.len()
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.join("tiny.idx"), idx).unwrap();
+        std::fs::write(dir.join("tiny.syn"), syn).unwrap();
+        std::fs::write(dir.join("tiny.dict"), definition).unwrap();
+        dir.join("tiny.ifo").to_string_lossy().to_string()
+    }
 
-        assert_debug_snapshot!(truncated_definitions(definitions), @r#"
+    #[test]
+    fn test_cache_path() {
+        let cache_dir = Path::new("/cache");
+        let cache_path = StardictManager::cache_path(cache_dir, "/dictionaries/fr-en/fr-en.ifo");
+        assert_eq!(
+            cache_path,
+            cache_dir.join(format!(
+                "fr-en-{:x}",
+                md5::compute("/dictionaries/fr-en/fr-en.ifo")
+            ))
+        );
+        // the same file name in another directory gets a cache of its own
+        assert_ne!(
+            cache_path,
+            StardictManager::cache_path(cache_dir, "/dictionaries/French/fr-en.ifo")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clear_and_rebuild_cache() {
+        let dictionary_dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let ifo_path = write_tiny_stardict(dictionary_dir.path());
+        let cache_path = StardictManager::cache_path(cache_dir.path(), &ifo_path);
+        let manager = StardictManager::new_with_cache(cache_dir.path().to_path_buf());
+        let loaded = |manager: &StardictManager| {
+            manager
+                .load_stats()
+                .into_iter()
+                .map(|stats| (stats.loads, stats.loaded))
+                .collect::<Vec<_>>()
+        };
+
+        let definitions = manager
+            .lookup_word(ifo_path.clone(), "tiny")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(definitions[0].segments[0].text, "a small dictionary");
+        assert!(cache_path.exists());
+        assert_eq!(loaded(&manager), vec![(1, true)]);
+
+        manager.clear_cache(ifo_path.clone()).await.unwrap();
+        assert!(!cache_path.exists());
+        assert_eq!(loaded(&manager), vec![(1, false)]);
+        // clearing a cache that is already gone is fine
+        manager.clear_cache(ifo_path.clone()).await.unwrap();
+
+        manager.rebuild_cache(ifo_path.clone()).await.unwrap();
+        assert!(cache_path.exists());
+        assert_eq!(loaded(&manager), vec![(2, true)]);
+    }
+
+    #[test]
+    fn test_slots_in_use_are_not_evicted() {
+        let manager = StardictManager::new().with_max_loaded(1);
+        let loaded = |manager: &StardictManager| {
+            let mut paths = manager
+                .dictionaries
+                .lock()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        };
+        let in_use = manager.slot("a.ifo");
+        drop(manager.slot("b.ifo"));
+        assert_eq!(loaded(&manager), vec!["a.ifo", "b.ifo"]);
+        drop(manager.slot("c.ifo"));
+        assert_eq!(loaded(&manager), vec!["a.ifo", "c.ifo"]);
+        // the same slot is handed out again rather than a second one for the same dictionary
+        assert!(Arc::ptr_eq(&in_use, &manager.slot("a.ifo")));
+        drop(in_use);
+        drop(manager.slot("d.ifo"));
+        assert_eq!(loaded(&manager), vec!["d.ifo"]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_dictionary_is_not_kept() {
+        let manager = StardictManager::new().with_max_loaded(1);
+        assert!(manager
+            .lookup_word("/nonexistent/missing.ifo".to_string(), "word")
+            .await
+            .is_err());
+        assert_eq!(manager.load_stats(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_add_jitendex_dictionary() {
+        let manager = StardictManager::new();
+
+        let definitions = manager
+            .lookup_word(get_jitendex_path(), "じんぶんちり")
+            .await;
+
+        assert_debug_snapshot!(truncated_entries(definitions), @r#"
         Ok(
             Some(
                 [
-                    WordDefinition {
+                    DictionaryEntry {
                         word: "@jitendex-1369180",
                         segments: [
-                            WordDefinitionSegment {
+                            DictionarySegment {
                                 types: "h",
                                 text: "<link rel='styl...",
                             },
@@ -0,0 +1,351 @@
+///! Yomitan (formerly Yomichan) dictionaries: a .zip with an index.json and term_bank_*.json files
+///! - term banks of format 3 have a glossary array, format 1 lists glossary strings after the score
+///! - structured-content glossaries are rendered to html; ?query= links become bword:// cross-references
+///! - entries are found by their expression or their reading. images are left out since they stay
+///!   inside the zip, as are kanji and frequency banks
+use super::dictionary_reader::{escape_html, Dictionary, DictionaryEntry};
+use super::dictionary_registry::DictionaryMetadata;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// structured-content tags kept as html, the rest render only their content
+const STRUCTURED_CONTENT_TAGS: &[&str] = &[
+    "ruby", "rt", "rp", "table", "thead", "tbody", "tfoot", "tr", "td", "th", "span", "div", "ol",
+    "ul", "li", "details", "summary",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct YomitanTerm {
+    expression: String,
+    reading: String,
+    html: String,
+}
+
+pub struct YomitanDictionary {
+    terms: Vec<YomitanTerm>,
+    /// expressions and readings to positions in terms
+    index: HashMap<String, Vec<usize>>,
+}
+
+fn read_index(zip: &mut zip::ZipArchive<File>) -> Result<Value> {
+    let mut content = String::new();
+    zip.by_name("index.json")
+        .context("Yomitan dictionary has no index.json")?
+        .read_to_string(&mut content)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn is_term_bank(name: &str) -> bool {
+    name.strip_prefix("term_bank_")
+        .and_then(|rest| rest.strip_suffix(".json"))
+        .is_some_and(|number| number.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// the word a ?query=word&... link searches for
+fn query_link_word(href: &str) -> Option<String> {
+    href.strip_prefix('?')?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("query="))
+        .map(|word| {
+            percent_encoding::percent_decode_str(word)
+                .decode_utf8_lossy()
+                .to_string()
+        })
+}
+
+fn structured_content_to_html(content: &Value, html: &mut String) {
+    match content {
+        Value::String(text) => html.push_str(&escape_html(text).replace('\n', "<br>")),
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| structured_content_to_html(item, html)),
+        Value::Object(element) => {
+            let tag = element
+                .get("tag")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let inner = element.get("content").unwrap_or(&Value::Null);
+            match tag {
+                "br" => html.push_str("<br>"),
+                "img" => {}
+                "a" => {
+                    let href = element
+                        .get("href")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+                    let href = match query_link_word(href) {
+                        Some(word) => format!("bword://{}", word),
+                        None => href.to_string(),
+                    };
+                    html.push_str(&format!("<a href=\"{}\">", escape_html(&href)));
+                    structured_content_to_html(inner, html);
+                    html.push_str("</a>");
+                }
+                tag if STRUCTURED_CONTENT_TAGS.contains(&tag) => {
+                    html.push('<');
+                    html.push_str(tag);
+                    for (attribute, name) in [
+                        ("lang", "lang"),
+                        ("title", "title"),
+                        ("colSpan", "colspan"),
+                        ("rowSpan", "rowspan"),
+                    ] {
+                        if let Some(value) = element.get(attribute).filter(|value| !value.is_null())
+                        {
+                            let value = value
+                                .as_str()
+                                .map_or_else(|| value.to_string(), str::to_string);
+                            html.push_str(&format!(" {}=\"{}\"", name, escape_html(&value)));
+                        }
+                    }
+                    html.push('>');
+                    structured_content_to_html(inner, html);
+                    html.push_str(&format!("</{}>", tag));
+                }
+                _ => structured_content_to_html(inner, html),
+            }
+        }
+        _ => {}
+    }
+}
+
+/// one glossary item: a string, or a text or structured-content object
+fn glossary_item_to_html(item: &Value) -> Option<String> {
+    match item {
+        Value::String(text) => Some(escape_html(text)),
+        Value::Object(object) => match object.get("type").and_then(Value::as_str) {
+            Some("text") => object.get("text").and_then(Value::as_str).map(escape_html),
+            Some("structured-content") => {
+                let mut html = String::new();
+                structured_content_to_html(
+                    object.get("content").unwrap_or(&Value::Null),
+                    &mut html,
+                );
+                Some(html)
+            }
+            _ => None,
+        },
+        // deinflection rules and images have nothing to show
+        _ => None,
+    }
+}
+
+fn term_html(definition_tags: &str, glossary: &[Value]) -> String {
+    let mut html = String::new();
+    if !definition_tags.trim().is_empty() {
+        html.push_str(&format!(
+            "<div class=\"yomitan-tags\">{}</div>",
+            escape_html(definition_tags.trim())
+        ));
+    }
+    html.push_str("<ul class=\"yomitan-glossary\">");
+    for item in glossary.iter().filter_map(glossary_item_to_html) {
+        html.push_str(&format!("<li>{}</li>", item));
+    }
+    html.push_str("</ul>");
+    html
+}
+
+/// a term bank row: [expression, reading, definition tags, rules, score, glossary...]
+fn parse_term(row: &Value, format: u64) -> Option<YomitanTerm> {
+    let row = row.as_array()?;
+    let expression = row.first()?.as_str()?.to_string();
+    let reading = row
+        .get(1)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let definition_tags = row.get(2).and_then(Value::as_str).unwrap_or_default();
+    let glossary = if format >= 3 {
+        row.get(5)
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default()
+    } else {
+        row.get(5..).map(|rest| rest.to_vec()).unwrap_or_default()
+    };
+    Some(YomitanTerm {
+        expression,
+        reading,
+        html: term_html(definition_tags, &glossary),
+    })
+}
+
+impl YomitanDictionary {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mut zip = zip::ZipArchive::new(file)?;
+        let index = read_index(&mut zip)?;
+        let format = index
+            .get("format")
+            .or_else(|| index.get("version"))
+            .and_then(Value::as_u64)
+            .unwrap_or(3);
+
+        let mut bank_names = zip
+            .file_names()
+            .filter(|name| is_term_bank(name))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        bank_names.sort();
+        let mut terms = vec![];
+        for name in bank_names {
+            let mut content = String::new();
+            zip.by_name(&name)?.read_to_string(&mut content)?;
+            let rows: Vec<Value> = serde_json::from_str(&content)
+                .with_context(|| format!("failed to parse {}", name))?;
+            terms.extend(rows.iter().filter_map(|row| parse_term(row, format)));
+        }
+
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (position, term) in terms.iter().enumerate() {
+            index
+                .entry(term.expression.clone())
+                .or_default()
+                .push(position);
+            if !term.reading.is_empty() && term.reading != term.expression {
+                index
+                    .entry(term.reading.clone())
+                    .or_default()
+                    .push(position);
+            }
+        }
+        Ok(Self { terms, index })
+    }
+}
+
+/// the title and author of a Yomitan dictionary. counting its terms would mean parsing every term bank
+pub fn read_metadata(path: &Path) -> Result<DictionaryMetadata> {
+    let file = File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut zip = zip::ZipArchive::new(file)?;
+    let index = read_index(&mut zip)?;
+    let title = index
+        .get("title")
+        .and_then(Value::as_str)
+        .context("Yomitan index.json has no title")?;
+    Ok(DictionaryMetadata {
+        bookname: title.to_string(),
+        author: index
+            .get("author")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        ..Default::default()
+    })
+}
+
+impl Dictionary for YomitanDictionary {
+    fn lookup(&mut self, word: &str) -> Result<Option<Vec<DictionaryEntry>>> {
+        Ok(self.index.get(word).map(|positions| {
+            positions
+                .iter()
+                .map(|position| {
+                    let term = &self.terms[*position];
+                    let word = if term.reading.is_empty() || term.reading == term.expression {
+                        term.expression.clone()
+                    } else {
+                        format!("{}【{}】", term.expression, term.reading)
+                    };
+                    DictionaryEntry::html(word, term.html.clone())
+                })
+                .collect()
+        }))
+    }
+
+    fn headwords(&mut self) -> Result<Vec<String>> {
+        Ok(self.index.keys().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, files: &[(&str, Value)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.to_string().as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_yomitan_dictionary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.zip");
+        write_zip(
+            &path,
+            &[
+                (
+                    "index.json",
+                    json!({"title": "Test Dict", "format": 3, "revision": "1", "author": "Someone"}),
+                ),
+                (
+                    "term_bank_1.json",
+                    json!([
+                        ["食べる", "たべる", "v1", "v1", 0, ["to eat", {"type": "text", "text": "to <consume>"}], 1, ""],
+                        ["猫", "ねこ", "", "", 0, [{"type": "structured-content", "content": [
+                            {"tag": "ruby", "content": ["猫", {"tag": "rt", "content": "ねこ"}]},
+                            " cat",
+                            {"tag": "br"},
+                            {"tag": "a", "href": "?query=%E7%8A%AC&wildcards=off", "content": "犬"},
+                            {"tag": "img", "path": "cat.png"},
+                            {"tag": "td", "colSpan": 2, "style": {"fontWeight": "bold"}, "content": "x"}
+                        ]}], 2, ""],
+                        ["ねこ", "", "", "", 0, [["deinflected", ["v1"]], "kitty"], 3, ""]
+                    ]),
+                ),
+                (
+                    "kanji_bank_1.json",
+                    json!([["猫", "ビョウ", "ねこ", "", [], [], {}]]),
+                ),
+            ],
+        );
+
+        let metadata = read_metadata(&path).unwrap();
+        assert_eq!(metadata.bookname, "Test Dict");
+        assert_eq!(metadata.author, "Someone");
+
+        let mut dictionary = YomitanDictionary::open(&path).unwrap();
+        let mut headwords = dictionary.headwords().unwrap();
+        headwords.sort();
+        assert_eq!(headwords, vec!["たべる", "ねこ", "猫", "食べる"]);
+        assert_eq!(
+            dictionary.lookup("たべる").unwrap(),
+            Some(vec![DictionaryEntry::html(
+                "食べる【たべる】",
+                "<div class=\"yomitan-tags\">v1</div><ul class=\"yomitan-glossary\"><li>to eat</li><li>to &lt;consume&gt;</li></ul>"
+            )])
+        );
+        assert_eq!(
+            dictionary.lookup("ねこ").unwrap(),
+            Some(vec![
+                DictionaryEntry::html(
+                    "猫【ねこ】",
+                    "<ul class=\"yomitan-glossary\"><li><ruby>猫<rt>ねこ</rt></ruby> cat<br><a href=\"bword://犬\">犬</a><td colspan=\"2\">x</td></li></ul>"
+                ),
+                DictionaryEntry::html("ねこ", "<ul class=\"yomitan-glossary\"><li>kitty</li></ul>"),
+            ])
+        );
+        assert_eq!(dictionary.lookup("犬").unwrap(), None);
+    }
+
+    #[test]
+    fn test_format_1_term_bank() {
+        let term = parse_term(&json!(["chat", "", "n", "", 0, "cat", "tomcat"]), 1).unwrap();
+        assert_eq!(term.expression, "chat");
+        assert_eq!(
+            term.html,
+            "<div class=\"yomitan-tags\">n</div><ul class=\"yomitan-glossary\"><li>cat</li><li>tomcat</li></ul>"
+        );
+    }
+}
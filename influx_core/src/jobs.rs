@@ -0,0 +1,118 @@
+///! background pre-tokenisation, so that documents open without waiting for the NLP service
+///! - jobs live in the tokenisation_job table, handlers queue them and wake the worker
+///! - the worker also polls, so jobs queued before a restart or while it slept are not forgotten
+use crate::db::models::tokenisation_job::{TokenisationJob, TokenisationJobStatus};
+use crate::db::{InfluxResourceId, DB};
+use crate::handlers::doc_handlers;
+use crate::ServerState;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// wait before trying again when the NLP service is down, instead of failing every queued job
+const SERVICE_DOWN_BACKOFF: Duration = Duration::from_secs(30);
+/// runs of a job while the NLP service is down before it is marked failed
+const MAX_ATTEMPTS: i32 = 5;
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenisationQueue {
+    wake: Arc<Notify>,
+}
+
+impl TokenisationQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn enqueue_document(
+        &self,
+        db: &DB,
+        document_id: InfluxResourceId,
+    ) -> anyhow::Result<TokenisationJob> {
+        let job = db.enqueue_tokenisation_job(document_id).await?;
+        self.wake.notify_one();
+        Ok(job)
+    }
+
+    pub async fn enqueue_language(
+        &self,
+        db: &DB,
+        lang_id: InfluxResourceId,
+    ) -> anyhow::Result<u64> {
+        let queued = db.enqueue_tokenisation_jobs_for_lang(lang_id).await?;
+        self.wake.notify_one();
+        Ok(queued)
+    }
+
+    /// tell the worker there may be queued jobs, e.g. after failed jobs were retried
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+pub fn spawn_tokenisation_worker(state: ServerState) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        match state.db.requeue_running_tokenisation_jobs().await {
+            Ok(0) => {}
+            Ok(requeued) => info!(requeued, "Re-queued interrupted tokenisation jobs"),
+            Err(err) => warn!("Failed to re-queue interrupted tokenisation jobs: {}", err),
+        }
+        loop {
+            let idle = match state.db.claim_next_tokenisation_job().await {
+                Ok(Some(job)) => {
+                    run_tokenisation_job(&state, job).await;
+                    None
+                }
+                Ok(None) => Some(POLL_INTERVAL),
+                Err(err) => {
+                    warn!("Failed to claim tokenisation job: {}", err);
+                    Some(POLL_INTERVAL)
+                }
+            };
+            if let Some(timeout) = idle {
+                tokio::select! {
+                    _ = state.tokenisation_queue.wake.notified() => {}
+                    _ = tokio::time::sleep(timeout) => {}
+                }
+            }
+        }
+    })
+}
+
+async fn run_tokenisation_job(state: &ServerState, job: TokenisationJob) {
+    debug!(job_id = ?job.id, document_id = ?job.document_id, attempt = job.attempts, "Running tokenisation job");
+    let (status, error) =
+        match doc_handlers::pretokenise_document(state, job.document_id.clone()).await {
+            Ok(()) => (TokenisationJobStatus::DONE, None),
+            Err(err) if doc_handlers::is_nlp_service_down(&err) && job.attempts < MAX_ATTEMPTS => {
+                warn!(
+                    "NLP service is down, tokenisation of document_id {:?} will be retried: {}",
+                    job.document_id, err
+                );
+                (TokenisationJobStatus::QUEUED, Some(err.to_string()))
+            }
+            Err(err) => {
+                warn!(
+                    "Tokenisation job for document_id {:?} failed: {}",
+                    job.document_id, err
+                );
+                (TokenisationJobStatus::FAILED, Some(err.to_string()))
+            }
+        };
+    if let Err(err) = state
+        .db
+        .finish_tokenisation_job(job.id.clone(), status, error)
+        .await
+    {
+        warn!(
+            "Failed to record outcome of tokenisation job {:?}: {}",
+            job.id, err
+        );
+    }
+    if status == TokenisationJobStatus::QUEUED {
+        tokio::time::sleep(SERVICE_DOWN_BACKOFF).await;
+    }
+}
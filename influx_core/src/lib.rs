@@ -1,5 +1,6 @@
 #![allow(unused_variables, dead_code)]
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{get, post},
     Router,
 };
@@ -7,7 +8,7 @@ use clap::{Parser, ValueEnum};
 use tokio::net::TcpListener;
 use tower_http::cors::CorsLayer;
 use tower_http::services::ServeDir;
-use tracing::info;
+use tracing::{info, warn};
 
 pub mod data_dir;
 pub mod db;
@@ -15,6 +16,7 @@ pub mod embedded_db;
 pub mod fsrs_scheduler;
 mod handlers;
 pub mod integration;
+pub mod jobs;
 mod nlp;
 mod prelude;
 mod utils;
@@ -22,9 +24,12 @@ mod utils;
 pub mod test_utils;
 
 use db::DB;
+use integration::nlp_client::{NlpClient, NlpClientConfig};
 use integration::stardict::StardictManager;
+use jobs::TokenisationQueue;
+use nlp::phrase_index::{PhraseIndexCache, PhraseTrieStore};
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 
 #[macro_use]
 extern crate macro_rules_attribute;
@@ -57,23 +62,51 @@ pub struct InfluxCoreArgs {
     /// URL of the NLP service
     #[arg(short, long, default_value = "http://127.0.0.1:3001")]
     pub nlp_url: String,
+
+    /// Seconds to wait between reads of an NLP service response before giving up
+    #[arg(long, default_value_t = 120)]
+    pub nlp_read_timeout_secs: u64,
+
+    /// Cache dictionaries in sled databases under the data directory, for faster lookups in large dictionaries
+    #[arg(long, default_value_t = false)]
+    pub stardict_cache: bool,
+
+    /// Always re-tokenise documents instead of serving cached annotations. can be changed at runtime
+    #[arg(long, default_value_t = false)]
+    pub bypass_annotation_cache: bool,
 }
 
 #[derive(Clone)]
 pub struct ServerState {
     pub db: DB,
-    pub nlp_url: String,
-    pub stardict_manager: Arc<Mutex<StardictManager>>,
+    pub nlp_client: NlpClient,
+    pub stardict_manager: StardictManager,
+    /// where clients reach this server, e.g. http://127.0.0.1:3000, for links to its own resources
+    pub server_url: String,
+    pub tokenisation_queue: TokenisationQueue,
+    /// when set, documents are re-tokenised on every request. the cache is still written
+    pub bypass_annotation_cache: Arc<AtomicBool>,
+    /// each language's phrases indexed for phrase fitting, dropped when a phrase changes
+    pub phrase_indexes: PhraseIndexCache,
 }
 
+/// frequency lists are far larger than the default 2MB request body limit, a Yomitan frequency
+/// dictionary can be tens of megabytes. dictionary bundles are streamed, see install_dictionary
+const MAX_FREQUENCY_LIST_BYTES: usize = 256 * 1024 * 1024;
+
 pub fn create_app_router(state: ServerState) -> Router {
     // Get the data directory for file serving
     let data_dir = data_dir::get_data_dir().expect("Failed to get data directory");
 
     Router::new()
         .route("/connection_test", get(handlers::connection_test))
+        .route("/nlp/health", get(handlers::nlp_health))
         .route("/docs", post(handlers::doc_handlers::get_docs_list))
         .route("/doc/{id}", get(handlers::doc_handlers::get_doc))
+        .route(
+            "/doc/annotated_range",
+            post(handlers::doc_handlers::get_doc_range),
+        )
         .route("/doc/create", post(handlers::doc_handlers::create_document))
         .route("/doc/edit", post(handlers::doc_handlers::update_document))
         .route(
@@ -81,6 +114,19 @@ pub fn create_app_router(state: ServerState) -> Router {
             post(handlers::doc_handlers::delete_document),
         )
         .route("/term/edit", post(handlers::term_handlers::edit_term))
+        .route("/term/list", post(handlers::term_handlers::list_terms))
+        .route(
+            "/term/fill_definitions",
+            post(handlers::term_handlers::fill_definitions),
+        )
+        .route(
+            "/phrase/suggestions",
+            post(handlers::term_handlers::get_phrase_suggestions),
+        )
+        .route(
+            "/phrase/suggestions/accept",
+            post(handlers::term_handlers::accept_phrase_suggestion),
+        )
         .route("/lang", get(handlers::lang_handlers::get_language_list))
         .route(
             "/lang/{lang_id}",
@@ -95,6 +141,48 @@ pub fn create_app_router(state: ServerState) -> Router {
             "/lang/delete/{id}",
             post(handlers::lang_handlers::delete_language),
         )
+        .route(
+            "/cache/annotated_docs/stats",
+            get(handlers::cache_handlers::get_annotation_cache_stats),
+        )
+        .route(
+            "/cache/annotated_docs/invalidate",
+            post(handlers::cache_handlers::invalidate_annotation_cache),
+        )
+        .route(
+            "/cache/annotated_docs/settings",
+            get(handlers::cache_handlers::get_annotation_cache_settings)
+                .post(handlers::cache_handlers::set_annotation_cache_settings),
+        )
+        .route(
+            "/jobs/tokenisation",
+            post(handlers::job_handlers::get_tokenisation_jobs),
+        )
+        .route(
+            "/jobs/tokenisation/retry",
+            post(handlers::job_handlers::retry_tokenisation_jobs),
+        )
+        .route(
+            "/stats/vocab_timeline",
+            post(handlers::stats_handlers::get_vocab_timeline),
+        )
+        .route(
+            "/stats/frequency_list",
+            post(handlers::stats_handlers::set_frequency_list),
+        )
+        .route(
+            "/stats/frequency_list/import",
+            post(handlers::stats_handlers::import_frequency_list)
+                .layer(DefaultBodyLimit::max(MAX_FREQUENCY_LIST_BYTES)),
+        )
+        .route(
+            "/stats/frequent_unknown_lemmas",
+            post(handlers::stats_handlers::get_frequent_unknown_lemmas),
+        )
+        .route(
+            "/stats/recommended_documents",
+            post(handlers::stats_handlers::get_recommended_documents),
+        )
         .route(
             "/extern/macos_dict/{language_identifier}/{orthography}",
             get(handlers::integration_handlers::lookup_in_macos_dict),
@@ -107,10 +195,50 @@ pub fn create_app_router(state: ServerState) -> Router {
             "/dictionary/lookup",
             get(handlers::integration_handlers::stardict_lookup),
         )
+        .route(
+            "/dictionary/suggest",
+            get(handlers::integration_handlers::dictionary_suggest),
+        )
         .route(
             "/dictionary/list",
             get(handlers::integration_handlers::list_dictionaries),
         )
+        .route(
+            "/dictionary/installed",
+            get(handlers::integration_handlers::list_installed_dictionaries),
+        )
+        .route(
+            "/dictionary/load_stats",
+            get(handlers::integration_handlers::dictionary_load_stats),
+        )
+        .route(
+            "/dictionary/cache",
+            post(handlers::integration_handlers::dictionary_cache),
+        )
+        .route(
+            "/dictionary/install",
+            post(handlers::integration_handlers::install_dictionary),
+        )
+        .route(
+            "/dictionary/remove",
+            post(handlers::integration_handlers::remove_dictionary),
+        )
+        .route(
+            "/dictionary/rename",
+            post(handlers::integration_handlers::rename_dictionary),
+        )
+        .route(
+            "/dictionary/language",
+            post(handlers::integration_handlers::get_language_dictionaries),
+        )
+        .route(
+            "/dictionary/language/set",
+            post(handlers::integration_handlers::set_language_dictionaries),
+        )
+        .route(
+            "/dictionary/language/lookup",
+            post(handlers::integration_handlers::language_dictionary_lookup),
+        )
         .route(
             "/open_influx_app_data_dir",
             get(handlers::integration_handlers::open_app_data_dir),
@@ -132,14 +260,39 @@ pub async fn launch(args: InfluxCoreArgs) -> anyhow::Result<()> {
         let _ = db.seed_all_tables().await;
     }
 
-    let app = create_app_router(ServerState {
-        db,
-        nlp_url: args.nlp_url.clone(),
-        stardict_manager: Arc::new(Mutex::new(StardictManager::new())),
-    });
+    let nlp_client = NlpClient::new(
+        args.nlp_url.clone(),
+        NlpClientConfig {
+            read_timeout: std::time::Duration::from_secs(args.nlp_read_timeout_secs),
+            ..Default::default()
+        },
+    )?;
+    if let Err(err) = nlp_client.health_check().await {
+        warn!("NLP service is not available yet: {}", err);
+    }
 
+    // bound before the state is built, so links in responses use the real address and port
     let bind_addr = format!("127.0.0.1:{}", args.port);
     let listener = TcpListener::bind(&bind_addr).await?;
+    let server_url = format!("http://{}", listener.local_addr()?);
+
+    let state = ServerState {
+        db,
+        nlp_client,
+        server_url,
+        stardict_manager: match args.stardict_cache {
+            true => StardictManager::new_with_cache(data_dir::get_stardict_cache_dir()?),
+            false => StardictManager::new(),
+        },
+        tokenisation_queue: TokenisationQueue::new(),
+        bypass_annotation_cache: Arc::new(AtomicBool::new(args.bypass_annotation_cache)),
+        phrase_indexes: PhraseIndexCache::with_store(PhraseTrieStore::new(
+            data_dir::get_phrase_tries_dir()?,
+        )),
+    };
+    jobs::spawn_tokenisation_worker(state.clone());
+    let app = create_app_router(state);
+
     info!(
         "Starting Influx server at http://{:?}",
         listener.local_addr()?
@@ -182,8 +335,15 @@ mod tests {
                 handlers::Term,
                 handlers::TermEditAction,
                 handlers::GetDocResponse,
+                handlers::GetDocRangeRequest,
+                handlers::GetDocRangeResponse,
                 handlers::TermEditRequest,
                 handlers::TermEditResponse,
+                handlers::AnnotatedDocDelta,
+                handlers::PhraseSuggestionsRequest,
+                handlers::PhraseSuggestionsResponse,
+                handlers::AcceptPhraseSuggestionRequest,
+                nlp::collocations::PhraseSuggestion,
                 handlers::GetDocsRequest,
                 handlers::ReviewableCardId,
                 handlers::CardWithTerm,
@@ -195,12 +355,58 @@ mod tests {
                 handlers::UpdateFSRSConfigResponse,
                 handlers::SetCardStateRequest,
                 handlers::SetCardStateResponse,
+                db::models::token_history::TimelineGranularity,
+                db::models::token_history::VocabTimelineBucket,
+                handlers::VocabTimelineRequest,
+                handlers::VocabTimelineResponse,
+                handlers::SetFrequencyListRequest,
+                handlers::SetFrequencyListResponse,
+                handlers::FrequentUnknownLemmasRequest,
+                handlers::FrequentUnknownLemmasResponse,
+                db::models::frequency::RankedLemma,
+                integration::frequency_list::FrequencyListFormat,
+                handlers::TermListRequest,
+                handlers::RankedToken,
+                handlers::TermListResponse,
+                handlers::RecommendDocumentsRequest,
+                handlers::RecommendDocumentsResponse,
+                db::models::tokenisation_job::TokenisationJobStatus,
+                db::models::tokenisation_job::TokenisationJob,
+                handlers::TokenisationJobsRequest,
+                handlers::RetryTokenisationJobsRequest,
+                db::models::document::AnnotationCacheScope,
+                db::models::document::DocumentCacheStats,
+                db::models::document::DifficultyFeatures,
+                db::models::document::DocumentDifficulty,
+                db::models::document::LanguageCacheStats,
+                handlers::AnnotationCacheStatsResponse,
+                handlers::InvalidateAnnotationCacheResponse,
+                handlers::AnnotationCacheSettings,
                 handlers::integration_handlers::StardictType,
                 handlers::integration_handlers::WordDefinition,
                 handlers::integration_handlers::WordDefinitionSegment,
                 handlers::integration_handlers::DictionaryInfo,
+                nlp::lookup_candidates::LookupCandidate,
+                nlp::lookup_candidates::LookupCandidateSource,
+                integration::dictionary_reader::DictionaryFormat,
+                integration::dictionary_registry::InstalledDictionary,
+                integration::stardict::DictionaryLoadStats,
+                db::models::dictionary::LanguageDictionary,
+                handlers::LanguageDictionariesRequest,
+                handlers::LanguageDictionaryEntry,
+                handlers::LanguageDictionariesResponse,
+                handlers::LanguageDictionarySetting,
+                handlers::SetLanguageDictionariesRequest,
+                handlers::LanguageDictionaryLookupRequest,
+                handlers::FillDefinitionsRequest,
+                handlers::FillDefinitionsResponse,
+                handlers::RemoveDictionaryRequest,
+                handlers::RenameDictionaryRequest,
+                handlers::DictionaryCacheAction,
+                handlers::DictionaryCacheRequest,
                 nlp::TermDictionary,
                 nlp::AnnotatedDocV2,
+                nlp::PhraseMatch,
                 nlp::DocSegV2,
                 nlp::DocSegVariants,
                 nlp::SentSegV2,
@@ -227,8 +433,15 @@ mod tests {
                 handlers::Term,
                 handlers::TermEditAction,
                 handlers::GetDocResponse,
+                handlers::GetDocRangeRequest,
+                handlers::GetDocRangeResponse,
                 handlers::TermEditRequest,
                 handlers::TermEditResponse,
+                handlers::AnnotatedDocDelta,
+                handlers::PhraseSuggestionsRequest,
+                handlers::PhraseSuggestionsResponse,
+                handlers::AcceptPhraseSuggestionRequest,
+                nlp::collocations::PhraseSuggestion,
                 handlers::GetDocsRequest,
                 handlers::ReviewableCardId,
                 handlers::CardWithTerm,
@@ -240,12 +453,58 @@ mod tests {
                 handlers::UpdateFSRSConfigResponse,
                 handlers::SetCardStateRequest,
                 handlers::SetCardStateResponse,
+                db::models::token_history::TimelineGranularity,
+                db::models::token_history::VocabTimelineBucket,
+                handlers::VocabTimelineRequest,
+                handlers::VocabTimelineResponse,
+                handlers::SetFrequencyListRequest,
+                handlers::SetFrequencyListResponse,
+                handlers::FrequentUnknownLemmasRequest,
+                handlers::FrequentUnknownLemmasResponse,
+                db::models::frequency::RankedLemma,
+                integration::frequency_list::FrequencyListFormat,
+                handlers::TermListRequest,
+                handlers::RankedToken,
+                handlers::TermListResponse,
+                handlers::RecommendDocumentsRequest,
+                handlers::RecommendDocumentsResponse,
+                db::models::tokenisation_job::TokenisationJobStatus,
+                db::models::tokenisation_job::TokenisationJob,
+                handlers::TokenisationJobsRequest,
+                handlers::RetryTokenisationJobsRequest,
+                db::models::document::AnnotationCacheScope,
+                db::models::document::DocumentCacheStats,
+                db::models::document::DifficultyFeatures,
+                db::models::document::DocumentDifficulty,
+                db::models::document::LanguageCacheStats,
+                handlers::AnnotationCacheStatsResponse,
+                handlers::InvalidateAnnotationCacheResponse,
+                handlers::AnnotationCacheSettings,
                 handlers::integration_handlers::StardictType,
                 handlers::integration_handlers::WordDefinition,
                 handlers::integration_handlers::WordDefinitionSegment,
                 handlers::integration_handlers::DictionaryInfo,
+                nlp::lookup_candidates::LookupCandidate,
+                nlp::lookup_candidates::LookupCandidateSource,
+                integration::dictionary_reader::DictionaryFormat,
+                integration::dictionary_registry::InstalledDictionary,
+                integration::stardict::DictionaryLoadStats,
+                db::models::dictionary::LanguageDictionary,
+                handlers::LanguageDictionariesRequest,
+                handlers::LanguageDictionaryEntry,
+                handlers::LanguageDictionariesResponse,
+                handlers::LanguageDictionarySetting,
+                handlers::SetLanguageDictionariesRequest,
+                handlers::LanguageDictionaryLookupRequest,
+                handlers::FillDefinitionsRequest,
+                handlers::FillDefinitionsResponse,
+                handlers::RemoveDictionaryRequest,
+                handlers::RenameDictionaryRequest,
+                handlers::DictionaryCacheAction,
+                handlers::DictionaryCacheRequest,
                 nlp::TermDictionary,
                 nlp::AnnotatedDocV2,
+                nlp::PhraseMatch,
                 nlp::DocSegV2,
                 nlp::DocSegVariants,
                 nlp::SentSegV2,
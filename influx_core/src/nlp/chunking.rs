@@ -0,0 +1,438 @@
+///! chunked tokenisation for long documents, and sentence range slicing for progressive loading and partial re-annotation
+///! - text is split on paragraph boundaries, chunks are tokenised concurrently and stitched back into one document
+///! - stitching shifts char offsets, sentence_idx and token idx so that they stay ordered and unique across chunks
+use super::tokeniser::Tokeniser;
+use super::*;
+use crate::db::models::lang::ParserConfig;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+#[derive(Debug, Clone)]
+pub struct ChunkingConfig {
+    /// chunks are cut at the first paragraph boundary after reaching this many bytes
+    pub target_chunk_bytes: usize,
+    pub max_concurrent_chunks: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        ChunkingConfig {
+            target_chunk_bytes: 20_000,
+            max_concurrent_chunks: 4,
+        }
+    }
+}
+
+/// byte offsets where paragraphs start, i.e. just after a run of whitespace containing two or more newlines
+fn paragraph_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![];
+    let mut i = 0;
+    while let Some(rel) = text[i..].find('\n') {
+        let after_newline = i + rel + 1;
+        let rest = &text[after_newline..];
+        let whitespace_len = rest.len() - rest.trim_start().len();
+        let next = after_newline + whitespace_len;
+        if rest[..whitespace_len].contains('\n') && next < text.len() {
+            starts.push(next);
+        }
+        i = next;
+    }
+    starts
+}
+
+/// split text into (char offset, chunk) on paragraph boundaries. the chunks concatenate back to the text
+pub fn split_into_paragraph_chunks(text: &str, target_chunk_bytes: usize) -> Vec<(usize, &str)> {
+    let mut chunks = vec![];
+    let mut chunk_start = 0;
+    let mut chunk_start_char = 0;
+    for paragraph_start in paragraph_starts(text) {
+        if paragraph_start - chunk_start >= target_chunk_bytes {
+            let chunk = &text[chunk_start..paragraph_start];
+            chunks.push((chunk_start_char, chunk));
+            chunk_start_char += chunk.chars().count();
+            chunk_start = paragraph_start;
+        }
+    }
+    if chunk_start < text.len() || chunks.is_empty() {
+        chunks.push((chunk_start_char, &text[chunk_start..]));
+    }
+    chunks
+}
+
+fn shift_sent_seg(
+    seg: &mut SentSegV2,
+    char_offset: usize,
+    sentence_offset: usize,
+    token_offset: usize,
+) {
+    seg.start_char += char_offset;
+    seg.end_char += char_offset;
+    seg.sentence_idx += sentence_offset;
+    if let Some((head, _)) = seg.attributes.dependency.as_mut() {
+        *head += token_offset;
+    }
+    match &mut seg.inner {
+        SentSegVariants::TokenSeg { idx, .. } => *idx += token_offset,
+        SentSegVariants::PhraseSeg {
+            components, gaps, ..
+        } => {
+            for inner_seg in components.iter_mut().chain(gaps.iter_mut()) {
+                shift_sent_seg(inner_seg, char_offset, sentence_offset, token_offset);
+            }
+        }
+        SentSegVariants::WhitespaceSeg | SentSegVariants::PunctuationSeg => {}
+    }
+}
+
+/// one past the largest sentence_idx and token idx (including dependency heads) used in the document
+fn sentence_and_token_extent(doc: &AnnotatedDocV2) -> (usize, usize) {
+    fn visit(seg: &SentSegV2, extent: &mut (usize, usize)) {
+        extent.0 = extent.0.max(seg.sentence_idx + 1);
+        if let Some((head, _)) = &seg.attributes.dependency {
+            extent.1 = extent.1.max(head + 1);
+        }
+        match &seg.inner {
+            SentSegVariants::TokenSeg { idx, .. } => extent.1 = extent.1.max(idx + 1),
+            SentSegVariants::PhraseSeg {
+                components, gaps, ..
+            } => components
+                .iter()
+                .chain(gaps.iter())
+                .for_each(|inner_seg| visit(inner_seg, extent)),
+            SentSegVariants::WhitespaceSeg | SentSegVariants::PunctuationSeg => {}
+        }
+    }
+    let mut extent = (0, 0);
+    for doc_seg in &doc.segments {
+        if let DocSegVariants::Sentence { segments } = &doc_seg.inner {
+            segments.iter().for_each(|seg| visit(seg, &mut extent));
+        }
+    }
+    extent
+}
+
+/// join documents tokenised from consecutive chunks of `text`, given with the char offset of each chunk
+pub fn stitch_chunks(
+    text: &str,
+    chunks: Vec<(usize, AnnotatedDocV2)>,
+    parser_config: ParserConfig,
+) -> AnnotatedDocV2 {
+    let mut stitched_segments: Vec<DocSegV2> = vec![];
+    let mut orthography_set = BTreeSet::new();
+    let mut lemma_set = BTreeSet::new();
+    let mut sentence_offset = 0;
+    let mut token_offset = 0;
+
+    for (char_offset, chunk) in chunks {
+        let (chunk_sentences, chunk_tokens) = sentence_and_token_extent(&chunk);
+        for mut doc_seg in chunk.segments {
+            doc_seg.start_char += char_offset;
+            doc_seg.end_char += char_offset;
+            match &mut doc_seg.inner {
+                DocSegVariants::Sentence { segments } => segments.iter_mut().for_each(|seg| {
+                    shift_sent_seg(seg, char_offset, sentence_offset, token_offset)
+                }),
+                DocSegVariants::DocumentWhitespace => {
+                    // whitespace at the end of one chunk and the start of the next is one run
+                    if let Some(previous) = stitched_segments.last_mut().filter(|previous| {
+                        matches!(previous.inner, DocSegVariants::DocumentWhitespace)
+                    }) {
+                        previous.text.push_str(&doc_seg.text);
+                        previous.end_char = doc_seg.end_char;
+                        continue;
+                    }
+                }
+            }
+            stitched_segments.push(doc_seg);
+        }
+        orthography_set.extend(chunk.orthography_set);
+        lemma_set.extend(chunk.lemma_set);
+        sentence_offset += chunk_sentences;
+        token_offset += chunk_tokens;
+    }
+
+    AnnotatedDocV2 {
+        text: text.to_string(),
+        segments: stitched_segments,
+        orthography_set,
+        lemma_set,
+        parser_config,
+        phrase_matches: vec![],
+    }
+}
+
+/// tokenise chunk by chunk with at most max_concurrent_chunks in flight. short texts are a single chunk
+pub async fn chunked_tokenise(
+    tokeniser: Arc<dyn Tokeniser>,
+    text: &str,
+    language_code: &str,
+    parser_config: &ParserConfig,
+    config: &ChunkingConfig,
+) -> anyhow::Result<AnnotatedDocV2> {
+    let chunks = split_into_paragraph_chunks(text, config.target_chunk_bytes);
+    if chunks.len() == 1 {
+        return tokeniser.tokenise(text, language_code, parser_config).await;
+    }
+    debug!(
+        chunks = chunks.len(),
+        text_len = text.len(),
+        "Tokenising document in chunks"
+    );
+
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrent_chunks.max(1)));
+    let mut join_set = JoinSet::new();
+    for (chunk_idx, (char_offset, chunk_text)) in chunks.into_iter().enumerate() {
+        let semaphore = semaphore.clone();
+        let tokeniser = tokeniser.clone();
+        let chunk_text = chunk_text.to_string();
+        let language_code = language_code.to_string();
+        let parser_config = parser_config.clone();
+        join_set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            let chunk_doc = tokeniser
+                .tokenise(&chunk_text, &language_code, &parser_config)
+                .await?;
+            anyhow::Ok((chunk_idx, char_offset, chunk_doc))
+        });
+    }
+
+    // returning early drops the join set, which aborts the remaining chunks
+    let mut tokenised_chunks = vec![];
+    while let Some(joined) = join_set.join_next().await {
+        tokenised_chunks.push(joined??);
+    }
+    tokenised_chunks.sort_by_key(|(chunk_idx, _, _)| *chunk_idx);
+
+    Ok(stitch_chunks(
+        text,
+        tokenised_chunks
+            .into_iter()
+            .map(|(_, char_offset, chunk_doc)| (char_offset, chunk_doc))
+            .collect(),
+        parser_config.clone(),
+    ))
+}
+
+pub fn count_sentences(doc: &AnnotatedDocV2) -> usize {
+    doc.segments
+        .iter()
+        .filter(|doc_seg| matches!(doc_seg.inner, DocSegVariants::Sentence { .. }))
+        .count()
+}
+
+/// the sentences [sentence_start, sentence_end) of a document, counting sentences in order, and the
+/// document whitespace between them
+/// - char offsets stay relative to the whole document, while text is only the covered part
+/// - orthography_set and lemma_set only cover the slice
+pub fn slice_by_sentence_range(
+    doc: &AnnotatedDocV2,
+    sentence_start: usize,
+    sentence_end: usize,
+) -> AnnotatedDocV2 {
+    let sentence_positions = doc
+        .segments
+        .iter()
+        .enumerate()
+        .filter(|(_, doc_seg)| matches!(doc_seg.inner, DocSegVariants::Sentence { .. }))
+        .map(|(position, _)| position)
+        .collect::<Vec<usize>>();
+    let sentence_end = sentence_end.min(sentence_positions.len());
+
+    let segments = if sentence_start < sentence_end {
+        doc.segments[sentence_positions[sentence_start]..=sentence_positions[sentence_end - 1]]
+            .to_vec()
+    } else {
+        vec![]
+    };
+    let (orthography_set, lemma_set) = lexical_sets(&segments);
+
+    let text = match (segments.first(), segments.last()) {
+        (Some(first), Some(last)) => doc
+            .text
+            .chars()
+            .skip(first.start_char)
+            .take(last.end_char - first.start_char)
+            .collect(),
+        _ => String::new(),
+    };
+
+    AnnotatedDocV2 {
+        text,
+        segments,
+        orthography_set,
+        lemma_set,
+        parser_config: doc.parser_config.clone(),
+        phrase_matches: vec![],
+    }
+}
+
+/// the sentences of a document for which keep holds, without the document whitespace between them
+/// - like slice_by_sentence_range, offsets stay relative to the whole document and the sets only cover the selection
+/// - text is left empty, since the selected sentences need not be adjacent
+pub fn select_sentences(
+    doc: &AnnotatedDocV2,
+    keep: impl Fn(&[SentSegV2]) -> bool,
+) -> AnnotatedDocV2 {
+    let segments = doc
+        .segments
+        .iter()
+        .filter(|doc_seg| match &doc_seg.inner {
+            DocSegVariants::Sentence { segments } => keep(segments),
+            DocSegVariants::DocumentWhitespace => false,
+        })
+        .cloned()
+        .collect::<Vec<DocSegV2>>();
+    let (orthography_set, lemma_set) = lexical_sets(&segments);
+
+    AnnotatedDocV2 {
+        text: String::new(),
+        segments,
+        orthography_set,
+        lemma_set,
+        parser_config: doc.parser_config.clone(),
+        phrase_matches: vec![],
+    }
+}
+
+/// orthographies (including punctuation) and lowercase lemmas of the sentences among segments
+fn lexical_sets(segments: &[DocSegV2]) -> (BTreeSet<String>, BTreeSet<String>) {
+    let mut orthography_set = BTreeSet::new();
+    let mut lemma_set = BTreeSet::new();
+    for doc_seg in segments {
+        if let DocSegVariants::Sentence { segments } = &doc_seg.inner {
+            for seg in segments {
+                match &seg.inner {
+                    SentSegVariants::TokenSeg { orthography, .. } => {
+                        orthography_set.insert(orthography.clone());
+                    }
+                    SentSegVariants::PunctuationSeg => {
+                        orthography_set.insert(seg.text.to_lowercase());
+                    }
+                    _ => continue,
+                }
+                if let Some(lemma) = &seg.attributes.lemma {
+                    lemma_set.insert(lemma.to_lowercase());
+                }
+            }
+        }
+    }
+    (orthography_set, lemma_set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nlp::tokeniser::{unicode_tokenise, UnicodeTokeniser, UNICODE_PARSER};
+    use expect_test::expect;
+
+    const TEXT: &str = "The first paragraph. It has two sentences.\n\nA second one!\n  \n\nAnd a third, after a gap.\n";
+
+    fn token_idx_mut(doc: &mut AnnotatedDocV2) -> impl Iterator<Item = &mut usize> {
+        doc.segments
+            .iter_mut()
+            .filter_map(|doc_seg| match &mut doc_seg.inner {
+                DocSegVariants::Sentence { segments } => Some(segments.iter_mut()),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|seg| match &mut seg.inner {
+                SentSegVariants::TokenSeg { idx, .. } => Some(idx),
+                _ => None,
+            })
+    }
+
+    fn token_idx(doc: &AnnotatedDocV2) -> Vec<usize> {
+        token_idx_mut(&mut doc.clone()).map(|idx| *idx).collect()
+    }
+
+    /// the document with its token idx renumbered 0, 1, 2, ... in order
+    fn with_dense_token_idx(mut doc: AnnotatedDocV2) -> AnnotatedDocV2 {
+        token_idx_mut(&mut doc)
+            .enumerate()
+            .for_each(|(position, idx)| *idx = position);
+        doc
+    }
+
+    fn unicode_config() -> ParserConfig {
+        ParserConfig {
+            which_parser: UNICODE_PARSER.to_string(),
+            parser_args: hashmap! {},
+        }
+    }
+
+    #[test]
+    fn test_split_into_paragraph_chunks() {
+        let chunks = split_into_paragraph_chunks(TEXT, 10);
+        expect![[r#"
+            [
+                (
+                    0,
+                    "The first paragraph. It has two sentences.\n\n",
+                ),
+                (
+                    44,
+                    "A second one!\n  \n\n",
+                ),
+                (
+                    62,
+                    "And a third, after a gap.\n",
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(&chunks);
+        assert_eq!(split_into_paragraph_chunks(TEXT, 10_000).len(), 1);
+        assert_eq!(split_into_paragraph_chunks("", 10).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_chunked_tokenise_matches_whole_document() {
+        let chunked = chunked_tokenise(
+            Arc::new(UnicodeTokeniser),
+            TEXT,
+            "en",
+            &unicode_config(),
+            &ChunkingConfig {
+                target_chunk_bytes: 10,
+                max_concurrent_chunks: 2,
+            },
+        )
+        .await
+        .unwrap();
+        // the unicode tokeniser numbers punctuation too, so a chunk that ends in punctuation leaves a gap
+        // in the stitched token idx. they only have to stay ordered and unique
+        let chunked_idx = token_idx(&chunked);
+        assert!(chunked_idx.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(
+            with_dense_token_idx(chunked),
+            with_dense_token_idx(unicode_tokenise(TEXT, unicode_config()))
+        );
+    }
+
+    #[test]
+    fn test_slice_by_sentence_range() {
+        let doc = unicode_tokenise(TEXT, unicode_config());
+        assert_eq!(count_sentences(&doc), 4);
+        let slice = slice_by_sentence_range(&doc, 1, 3);
+        let rendered = slice
+            .segments
+            .iter()
+            .map(|doc_seg| {
+                format!(
+                    "{}..{} {:?}",
+                    doc_seg.start_char, doc_seg.end_char, doc_seg.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect![[r#"
+            21..42 "It has two sentences."
+            42..44 "\n\n"
+            44..57 "A second one!""#]]
+        .assert_eq(&rendered);
+        assert_eq!(slice.text, "It has two sentences.\n\nA second one!");
+        assert!(slice.orthography_set.contains("second"));
+        assert!(!slice.orthography_set.contains("third"));
+        assert!(slice_by_sentence_range(&doc, 4, 10).segments.is_empty());
+    }
+}
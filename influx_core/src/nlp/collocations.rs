@@ -0,0 +1,335 @@
+///! collocation mining over annotated documents, used to suggest phrases to the user
+///! n-grams within sentences are scored by Dunning's log-likelihood ratio, treating an n-gram as its
+///! (n-1)-gram prefix followed by its last word, and boosted when the parser links its tokens by dependency
+use super::*;
+use std::cmp::Ordering;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, ElmDerives!)]
+pub struct PhraseSuggestion {
+    /// lowercase orthographies, or lemmas when match_by_lemma is set
+    pub orthography_seq: Vec<String>,
+    pub match_by_lemma: bool,
+    pub score: f64,
+    pub count: usize,
+    /// share of occurrences where the tokens form a connected dependency subtree
+    pub dependency_linked_ratio: f64,
+    pub example_sentences: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CollocationMinerConfig {
+    pub max_n: usize,
+    pub min_count: usize,
+    pub max_suggestions: usize,
+    pub max_examples: usize,
+}
+
+impl Default for CollocationMinerConfig {
+    fn default() -> Self {
+        CollocationMinerConfig {
+            max_n: 3,
+            min_count: 2,
+            max_suggestions: 50,
+            max_examples: 3,
+        }
+    }
+}
+
+struct LexToken {
+    orthography: String,
+    lemma: String,
+    idx: usize,
+    head: Option<usize>,
+}
+
+#[derive(Default)]
+struct NgramStats {
+    count: usize,
+    linked_count: usize,
+    examples: Vec<String>,
+}
+
+fn collect_lex_tokens(segments: &[SentSegV2], runs: &mut Vec<Vec<LexToken>>) {
+    for seg in segments {
+        match &seg.inner {
+            SentSegVariants::TokenSeg { idx, orthography } => {
+                if runs.is_empty() {
+                    runs.push(vec![]);
+                }
+                runs.last_mut().unwrap().push(LexToken {
+                    orthography: orthography.clone(),
+                    lemma: seg
+                        .attributes
+                        .lemma
+                        .as_ref()
+                        .map(|lemma| lemma.to_lowercase())
+                        .unwrap_or_else(|| orthography.clone()),
+                    idx: *idx,
+                    head: seg.attributes.dependency.as_ref().map(|(head, _)| *head),
+                })
+            }
+            // n-grams do not cross punctuation
+            SentSegVariants::PunctuationSeg => runs.push(vec![]),
+            SentSegVariants::PhraseSeg {
+                components, gaps, ..
+            } => {
+                let mut inner = components
+                    .iter()
+                    .chain(gaps.iter())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                inner.sort_by_key(|x| x.start_char);
+                collect_lex_tokens(&inner, runs);
+            }
+            SentSegVariants::WhitespaceSeg => {}
+        }
+    }
+}
+
+/// (sentence text, runs of tokens between punctuation) for every sentence in the documents
+fn sentence_runs(docs: &[AnnotatedDocV2]) -> Vec<(String, Vec<Vec<LexToken>>)> {
+    docs.iter()
+        .flat_map(|doc| doc.segments.iter())
+        .filter_map(|doc_seg| match &doc_seg.inner {
+            DocSegVariants::Sentence { segments } => {
+                let mut runs = vec![];
+                collect_lex_tokens(segments, &mut runs);
+                Some((doc_seg.text.trim().to_string(), runs))
+            }
+            DocSegVariants::DocumentWhitespace => None,
+        })
+        .collect()
+}
+
+/// whether the tokens form a connected dependency subtree, i.e. have at least n-1 internal edges
+fn is_dependency_linked(window: &[LexToken]) -> bool {
+    let internal_edges = window
+        .iter()
+        .filter(|token| {
+            token.head.is_some_and(|head| {
+                head != token.idx && window.iter().any(|other| other.idx == head)
+            })
+        })
+        .count();
+    internal_edges + 1 >= window.len()
+}
+
+fn x_log_x(x: f64) -> f64 {
+    if x <= 0.0 {
+        0.0
+    } else {
+        x * x.ln()
+    }
+}
+
+fn entropy(elements: &[f64]) -> f64 {
+    x_log_x(elements.iter().sum()) - elements.iter().map(|x| x_log_x(*x)).sum::<f64>()
+}
+
+/// Dunning's log-likelihood ratio for a 2x2 contingency table
+pub fn log_likelihood_ratio(k11: f64, k12: f64, k21: f64, k22: f64) -> f64 {
+    let row_entropy = entropy(&[k11 + k12, k21 + k22]);
+    let column_entropy = entropy(&[k11 + k21, k12 + k22]);
+    let matrix_entropy = entropy(&[k11, k12, k21, k22]);
+    (2.0 * (row_entropy + column_entropy - matrix_entropy)).max(0.0)
+}
+
+fn mine_with_key(
+    runs: &[(String, Vec<Vec<LexToken>>)],
+    key: fn(&LexToken) -> &String,
+    existing: &BTreeSet<Vec<String>>,
+    config: &CollocationMinerConfig,
+    match_by_lemma: bool,
+) -> Vec<PhraseSuggestion> {
+    let mut stats: HashMap<Vec<String>, NgramStats> = HashMap::new();
+    let mut totals: Vec<usize> = vec![0; config.max_n + 1];
+
+    for (sentence_text, sentence_runs) in runs {
+        for run in sentence_runs {
+            let longest = config.max_n.min(run.len());
+            for (n, total) in totals.iter_mut().enumerate().take(longest + 1).skip(1) {
+                for window in run.windows(n) {
+                    *total += 1;
+                    let entry = stats
+                        .entry(window.iter().map(|token| key(token).clone()).collect())
+                        .or_default();
+                    entry.count += 1;
+                    if n > 1 {
+                        if is_dependency_linked(window) {
+                            entry.linked_count += 1;
+                        }
+                        if entry.examples.len() < config.max_examples
+                            && !entry.examples.contains(sentence_text)
+                        {
+                            entry.examples.push(sentence_text.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    stats
+        .iter()
+        .filter(|(seq, ngram_stats)| {
+            seq.len() > 1 && ngram_stats.count >= config.min_count && !existing.contains(*seq)
+        })
+        .filter_map(|(seq, ngram_stats)| {
+            let count_of = |x: &[String]| stats.get(x).map(|s| s.count).unwrap_or(0) as f64;
+            let n = seq.len();
+            let k11 = ngram_stats.count as f64;
+            let k12 = (count_of(&seq[..n - 1]) - k11).max(0.0);
+            let k21 = (count_of(&seq[n - 1..]) - k11).max(0.0);
+            let k22 = (totals[n] as f64 - k11 - k12 - k21).max(0.0);
+            // only positive associations
+            if k11 * k22 <= k12 * k21 {
+                return None;
+            }
+            let dependency_linked_ratio = ngram_stats.linked_count as f64 / k11;
+            Some(PhraseSuggestion {
+                orthography_seq: seq.clone(),
+                match_by_lemma,
+                score: log_likelihood_ratio(k11, k12, k21, k22) * (1.0 + dependency_linked_ratio),
+                count: ngram_stats.count,
+                dependency_linked_ratio,
+                example_sentences: ngram_stats.examples.clone(),
+            })
+        })
+        .collect()
+}
+
+/// mine ranked phrase suggestions from token and lemma n-grams
+/// - n-grams already in `existing` are skipped
+/// - a lemma n-gram is only suggested when no orthography n-gram has the same sequence
+pub fn mine_collocations(
+    docs: &[AnnotatedDocV2],
+    existing: &BTreeSet<Vec<String>>,
+    config: &CollocationMinerConfig,
+) -> Vec<PhraseSuggestion> {
+    let runs = sentence_runs(docs);
+
+    let mut suggestions: BTreeMap<Vec<String>, PhraseSuggestion> = BTreeMap::new();
+    for suggestion in mine_with_key(&runs, |token| &token.orthography, existing, config, false) {
+        suggestions.insert(suggestion.orthography_seq.clone(), suggestion);
+    }
+    for suggestion in mine_with_key(&runs, |token| &token.lemma, existing, config, true) {
+        suggestions
+            .entry(suggestion.orthography_seq.clone())
+            .or_insert(suggestion);
+    }
+
+    let mut suggestions = suggestions.into_values().collect::<Vec<_>>();
+    suggestions.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then(b.count.cmp(&a.count))
+            .then(a.orthography_seq.cmp(&b.orthography_seq))
+    });
+    suggestions.truncate(config.max_suggestions);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+    use maplit::btreeset;
+
+    /// words are (text, lemma, dependency head as an index into the sentence)
+    fn mk_doc(sentences: &[&[(&str, &str, usize)]]) -> AnnotatedDocV2 {
+        let mut segments = vec![];
+        let mut token_offset = 0;
+        for (sentence_idx, words) in sentences.iter().enumerate() {
+            let sent_segs = words
+                .iter()
+                .enumerate()
+                .map(|(i, (text, lemma, head))| SentSegV2 {
+                    sentence_idx,
+                    text: text.to_string(),
+                    start_char: 0,
+                    end_char: 0,
+                    inner: SentSegVariants::TokenSeg {
+                        idx: token_offset + i,
+                        orthography: text.to_lowercase(),
+                    },
+                    attributes: SegAttribute {
+                        lemma: Some(lemma.to_string()),
+                        upos: None,
+                        xpos: None,
+                        dependency: Some((token_offset + head, "dep".to_string())),
+                        misc: btreemap! {},
+                        conjugation_chain: None,
+                    },
+                })
+                .collect::<Vec<_>>();
+            token_offset += words.len();
+            segments.push(DocSegV2 {
+                text: words
+                    .iter()
+                    .map(|(text, _, _)| *text)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                start_char: 0,
+                end_char: 0,
+                inner: DocSegVariants::Sentence {
+                    segments: sent_segs,
+                },
+            });
+        }
+        AnnotatedDocV2 {
+            text: "".to_string(),
+            segments,
+            orthography_set: btreeset! {},
+            lemma_set: btreeset! {},
+            parser_config: Default::default(),
+            phrase_matches: vec![],
+        }
+    }
+
+    fn render(suggestions: &[PhraseSuggestion]) -> String {
+        suggestions
+            .iter()
+            .map(|s| {
+                format!(
+                    "{:?} lemma={} count={} linked={:.2} score={:.2} examples={:?}",
+                    s.orthography_seq,
+                    s.match_by_lemma,
+                    s.count,
+                    s.dependency_linked_ratio,
+                    s.score,
+                    s.example_sentences
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_log_likelihood_ratio() {
+        assert!((log_likelihood_ratio(1.0, 0.0, 0.0, 1.0) - 4.0 * 2f64.ln()).abs() < 1e-9);
+        assert_eq!(log_likelihood_ratio(10.0, 0.0, 0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_mine_collocations() {
+        let doc = mk_doc(&[
+            &[("She", "she", 1), ("took", "take", 1), ("off", "off", 1)],
+            &[("He", "he", 1), ("takes", "take", 1), ("off", "off", 1)],
+            &[
+                ("The", "the", 1),
+                ("plane", "plane", 2),
+                ("took", "take", 2),
+                ("off", "off", 2),
+            ],
+            &[("The", "the", 1), ("cat", "cat", 2), ("sat", "sit", 2)],
+            &[("The", "the", 1), ("cat", "cat", 2), ("slept", "sleep", 2)],
+        ]);
+        let existing = btreeset! {vec!["the".to_string(), "cat".to_string()]};
+        let suggestions = mine_collocations(&[doc], &existing, &Default::default());
+        expect![[r#"
+            ["take", "off"] lemma=true count=3 linked=1.00 score=25.78 examples=["She took off", "He takes off", "The plane took off"]
+            ["took", "off"] lemma=false count=2 linked=1.00 score=13.22 examples=["She took off", "The plane took off"]"#]]
+        .assert_eq(&render(&suggestions));
+    }
+}
@@ -0,0 +1,465 @@
+///! difficulty of a document for the learner, estimated from its tokenised AnnotatedDocV2
+///! - sentence length in lexical tokens, the share of rare lemmas by the language's frequency list,
+///!   dependency depth from SegAttribute.dependency, and the share of tokens the learner has not marked
+///! - each feature is scaled to 0..1 between an easy and a hard value, and the score is their weighted mean,
+///!   over the features available for the language and parser, out of 100
+use super::*;
+use crate::db::models::document::{DifficultyFeatures, DocumentDifficulty};
+use crate::db::models::vocab::TokenStatus;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone)]
+pub struct DifficultyConfig {
+    /// lemmas ranked after this, or not ranked at all, count as rare
+    pub rare_rank_threshold: i32,
+    pub easy_sentence_length: f64,
+    pub hard_sentence_length: f64,
+    pub hard_rare_lemma_ratio: f64,
+    pub easy_dependency_depth: f64,
+    pub hard_dependency_depth: f64,
+    pub hard_unknown_token_ratio: f64,
+    pub sentence_length_weight: f64,
+    pub rare_lemma_weight: f64,
+    pub dependency_depth_weight: f64,
+    pub unknown_token_weight: f64,
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        DifficultyConfig {
+            rare_rank_threshold: 5000,
+            easy_sentence_length: 5.0,
+            hard_sentence_length: 30.0,
+            hard_rare_lemma_ratio: 0.3,
+            easy_dependency_depth: 2.0,
+            hard_dependency_depth: 8.0,
+            hard_unknown_token_ratio: 0.3,
+            sentence_length_weight: 0.2,
+            rare_lemma_weight: 0.25,
+            dependency_depth_weight: 0.15,
+            unknown_token_weight: 0.4,
+        }
+    }
+}
+
+struct LexToken {
+    orthography: String,
+    lemma: String,
+    idx: usize,
+    /// whether the parser gave the token a dependency at all
+    parsed: bool,
+    /// None for the root of the sentence
+    head: Option<usize>,
+}
+
+fn collect_lex_tokens(segments: &[SentSegV2], tokens: &mut Vec<LexToken>) {
+    for seg in segments {
+        match &seg.inner {
+            SentSegVariants::TokenSeg { idx, orthography } => tokens.push(LexToken {
+                orthography: orthography.clone(),
+                lemma: seg
+                    .attributes
+                    .lemma
+                    .as_ref()
+                    .map(|lemma| lemma.to_lowercase())
+                    .unwrap_or_else(|| orthography.clone()),
+                idx: *idx,
+                parsed: seg.attributes.dependency.is_some(),
+                head: seg
+                    .attributes
+                    .dependency
+                    .as_ref()
+                    .filter(|(head, relation)| {
+                        *head != *idx && !relation.eq_ignore_ascii_case("root")
+                    })
+                    .map(|(head, _)| *head),
+            }),
+            SentSegVariants::PhraseSeg {
+                components, gaps, ..
+            } => {
+                let inner = components
+                    .iter()
+                    .chain(gaps.iter())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                collect_lex_tokens(&inner, tokens);
+            }
+            SentSegVariants::PunctuationSeg | SentSegVariants::WhitespaceSeg => {}
+        }
+    }
+}
+
+/// lexical tokens of each sentence with any
+fn sentence_tokens(doc: &AnnotatedDocV2) -> Vec<Vec<LexToken>> {
+    doc.segments
+        .iter()
+        .filter_map(|doc_seg| match &doc_seg.inner {
+            DocSegVariants::Sentence { segments } => {
+                let mut tokens = vec![];
+                collect_lex_tokens(segments, &mut tokens);
+                Some(tokens).filter(|tokens| !tokens.is_empty())
+            }
+            DocSegVariants::DocumentWhitespace => None,
+        })
+        .collect()
+}
+
+/// lowercase lemmas of the document's tokens, or their orthography when they have none, to rank by frequency
+pub fn document_lemmas(doc: &AnnotatedDocV2) -> BTreeSet<String> {
+    sentence_tokens(doc)
+        .into_iter()
+        .flatten()
+        .map(|token| token.lemma)
+        .collect()
+}
+
+/// distance from the root to the deepest token, following heads within the sentence
+/// - None when the parser gave no dependencies
+/// - a head outside the sentence ends the path, and the walk is bounded in case the heads form a cycle
+fn dependency_depth(sentence: &[LexToken]) -> Option<usize> {
+    if !sentence.iter().any(|token| token.parsed) {
+        return None;
+    }
+    let heads: HashMap<usize, Option<usize>> = sentence
+        .iter()
+        .map(|token| (token.idx, token.head))
+        .collect();
+    sentence
+        .iter()
+        .map(|token| {
+            let mut curr = token.idx;
+            let mut depth = 0;
+            while let Some(Some(head)) = heads.get(&curr) {
+                if depth == sentence.len() {
+                    break;
+                }
+                curr = *head;
+                depth += 1;
+            }
+            depth
+        })
+        .max()
+}
+
+/// - term_dict maps orthographies to the learner's tokens, a missing orthography counts as UNMARKED
+/// - lemma_ranks is None when the language has no frequency list
+pub fn difficulty_features(
+    doc: &AnnotatedDocV2,
+    term_dict: &BTreeMap<String, Token>,
+    lemma_ranks: Option<&BTreeMap<String, i32>>,
+    config: &DifficultyConfig,
+) -> DifficultyFeatures {
+    let sentences = sentence_tokens(doc);
+    let tokens = sentences.iter().flatten().collect::<Vec<_>>();
+    let ratio = |count: usize| match tokens.len() {
+        0 => 0.0,
+        total => count as f64 / total as f64,
+    };
+
+    let depths = sentences
+        .iter()
+        .filter_map(|sentence| dependency_depth(sentence))
+        .collect::<Vec<_>>();
+    DifficultyFeatures {
+        mean_sentence_length: match sentences.len() {
+            0 => 0.0,
+            count => tokens.len() as f64 / count as f64,
+        },
+        rare_lemma_ratio: lemma_ranks.map(|ranks| {
+            ratio(
+                tokens
+                    .iter()
+                    .filter(|token| {
+                        ranks
+                            .get(&token.lemma)
+                            .is_none_or(|rank| *rank > config.rare_rank_threshold)
+                    })
+                    .count(),
+            )
+        }),
+        mean_dependency_depth: match depths.len() {
+            0 => None,
+            count => Some(depths.iter().sum::<usize>() as f64 / count as f64),
+        },
+        unknown_token_ratio: ratio(
+            tokens
+                .iter()
+                .filter(|token| {
+                    term_dict
+                        .get(&token.orthography)
+                        .is_none_or(|term| term.status == TokenStatus::UNMARKED)
+                })
+                .count(),
+        ),
+    }
+}
+
+fn scale(value: f64, easy: f64, hard: f64) -> f64 {
+    ((value - easy) / (hard - easy)).clamp(0.0, 1.0)
+}
+
+/// 0 for the easiest text to 100 for the hardest, with the weights of missing features shared by the rest
+pub fn difficulty_score(features: &DifficultyFeatures, config: &DifficultyConfig) -> f64 {
+    let scaled = [
+        Some((
+            scale(
+                features.mean_sentence_length,
+                config.easy_sentence_length,
+                config.hard_sentence_length,
+            ),
+            config.sentence_length_weight,
+        )),
+        features.rare_lemma_ratio.map(|ratio| {
+            (
+                scale(ratio, 0.0, config.hard_rare_lemma_ratio),
+                config.rare_lemma_weight,
+            )
+        }),
+        features.mean_dependency_depth.map(|depth| {
+            (
+                scale(
+                    depth,
+                    config.easy_dependency_depth,
+                    config.hard_dependency_depth,
+                ),
+                config.dependency_depth_weight,
+            )
+        }),
+        Some((
+            scale(
+                features.unknown_token_ratio,
+                0.0,
+                config.hard_unknown_token_ratio,
+            ),
+            config.unknown_token_weight,
+        )),
+    ];
+    let (weighted, total_weight) =
+        scaled
+            .iter()
+            .flatten()
+            .fold((0.0, 0.0), |(weighted, total_weight), (value, weight)| {
+                (weighted + value * weight, total_weight + weight)
+            });
+    match total_weight > 0.0 {
+        true => 100.0 * weighted / total_weight,
+        false => 0.0,
+    }
+}
+
+/// documents to read next, at most `limit`
+/// - first those with at most max_unknown_token_ratio unknown tokens, hardest first, so the learner is stretched
+///   without being lost
+/// - then the rest, fewest unknown tokens first, so there is always something to suggest
+pub fn recommend_documents(
+    mut difficulties: Vec<DocumentDifficulty>,
+    max_unknown_token_ratio: f64,
+    limit: usize,
+) -> Vec<DocumentDifficulty> {
+    let within = |difficulty: &DocumentDifficulty| {
+        difficulty.features.unknown_token_ratio <= max_unknown_token_ratio
+    };
+    difficulties.sort_by(|a, b| match (within(a), within(b)) {
+        (true, true) => b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal),
+        (false, false) => a
+            .features
+            .unknown_token_ratio
+            .partial_cmp(&b.features.unknown_token_ratio)
+            .unwrap_or(Ordering::Equal),
+        (within_a, within_b) => within_b.cmp(&within_a),
+    });
+    difficulties.truncate(limit);
+    difficulties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use expect_test::expect;
+    use maplit::btreeset;
+
+    /// words are (text, lemma, dependency head as an index into the sentence, relation)
+    fn mk_doc(sentences: &[&[(&str, &str, usize, &str)]]) -> AnnotatedDocV2 {
+        let mut segments = vec![];
+        let mut token_offset = 0;
+        for (sentence_idx, words) in sentences.iter().enumerate() {
+            let mut sent_segs = words
+                .iter()
+                .enumerate()
+                .map(|(i, (text, lemma, head, relation))| SentSegV2 {
+                    sentence_idx,
+                    text: text.to_string(),
+                    start_char: 0,
+                    end_char: 0,
+                    inner: SentSegVariants::TokenSeg {
+                        idx: token_offset + i,
+                        orthography: text.to_lowercase(),
+                    },
+                    attributes: SegAttribute {
+                        lemma: Some(lemma.to_string()),
+                        upos: None,
+                        xpos: None,
+                        dependency: Some((token_offset + head, relation.to_string())),
+                        misc: btreemap! {},
+                        conjugation_chain: None,
+                    },
+                })
+                .collect::<Vec<_>>();
+            sent_segs.push(SentSegV2 {
+                sentence_idx,
+                text: ".".to_string(),
+                start_char: 0,
+                end_char: 0,
+                inner: SentSegVariants::PunctuationSeg,
+                attributes: SegAttribute {
+                    lemma: None,
+                    upos: None,
+                    xpos: None,
+                    dependency: None,
+                    misc: btreemap! {},
+                    conjugation_chain: None,
+                },
+            });
+            token_offset += words.len();
+            segments.push(DocSegV2 {
+                text: String::new(),
+                start_char: 0,
+                end_char: 0,
+                inner: DocSegVariants::Sentence {
+                    segments: sent_segs,
+                },
+            });
+        }
+        AnnotatedDocV2 {
+            text: "".to_string(),
+            segments,
+            orthography_set: btreeset! {},
+            lemma_set: btreeset! {},
+            parser_config: Default::default(),
+            phrase_matches: vec![],
+        }
+    }
+
+    fn render(features: &DifficultyFeatures, config: &DifficultyConfig) -> String {
+        format!(
+            "length={:.2} rare={:?} depth={:?} unknown={:.2} score={:.1}",
+            features.mean_sentence_length,
+            features.rare_lemma_ratio.map(|x| format!("{:.2}", x)),
+            features.mean_dependency_depth.map(|x| format!("{:.2}", x)),
+            features.unknown_token_ratio,
+            difficulty_score(features, config)
+        )
+    }
+
+    #[test]
+    fn test_difficulty_features() {
+        let lang_id = InfluxResourceId::SerialId(1);
+        let doc = mk_doc(&[
+            // took <- she, took <- off, took is the root
+            &[
+                ("She", "she", 1, "nsubj"),
+                ("took", "take", 1, "ROOT"),
+                ("off", "off", 1, "prt"),
+            ],
+            // sat <- cat <- the, sat <- on <- mat <- the
+            &[
+                ("The", "the", 1, "det"),
+                ("cat", "cat", 2, "nsubj"),
+                ("sat", "sit", 2, "ROOT"),
+                ("on", "on", 2, "prep"),
+                ("the", "the", 5, "det"),
+                ("mat", "mat", 3, "pobj"),
+            ],
+        ]);
+        assert_eq!(
+            document_lemmas(&doc),
+            ["cat", "mat", "off", "on", "she", "sit", "take", "the"]
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect()
+        );
+
+        let mut term_dict = ["she", "took", "the", "cat", "sat"]
+            .into_iter()
+            .map(|orthography| {
+                (
+                    orthography.to_string(),
+                    Token::essential_token(lang_id.clone(), orthography),
+                )
+            })
+            .collect::<BTreeMap<String, Token>>();
+        term_dict.get_mut("cat").unwrap().status = TokenStatus::UNMARKED;
+        let ranks = ["the", "she", "on", "take", "sit", "off", "cat"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, lemma)| (lemma.to_string(), i as i32 + 1))
+            .collect::<BTreeMap<String, i32>>();
+        let config = DifficultyConfig {
+            rare_rank_threshold: 5,
+            ..Default::default()
+        };
+
+        expect!["length=4.50 rare=Some(\"0.33\") depth=Some(\"2.00\") unknown=0.44 score=65.0"]
+            .assert_eq(&render(
+                &difficulty_features(&doc, &term_dict, Some(&ranks), &config),
+                &config,
+            ));
+        // without a frequency list or a parse, the rest of the features share the weight
+        let mut unparsed = doc.clone();
+        for doc_seg in unparsed.segments.iter_mut() {
+            if let DocSegVariants::Sentence { segments } = &mut doc_seg.inner {
+                for seg in segments.iter_mut() {
+                    seg.attributes.dependency = None;
+                }
+            }
+        }
+        expect!["length=4.50 rare=None depth=None unknown=0.44 score=66.7"].assert_eq(&render(
+            &difficulty_features(&unparsed, &term_dict, None, &config),
+            &config,
+        ));
+        expect!["length=0.00 rare=Some(\"0.00\") depth=None unknown=0.00 score=0.0"].assert_eq(
+            &render(
+                &difficulty_features(&mk_doc(&[]), &term_dict, Some(&ranks), &config),
+                &config,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_dependency_depth_with_cycle() {
+        let doc = mk_doc(&[&[("a", "a", 1, "dep"), ("b", "b", 0, "dep")]]);
+        let sentences = sentence_tokens(&doc);
+        assert_eq!(dependency_depth(&sentences[0]), Some(2));
+    }
+
+    #[test]
+    fn test_recommend_documents() {
+        let mk_difficulty =
+            |document_id: i64, score: f64, unknown_token_ratio: f64| DocumentDifficulty {
+                document_id: InfluxResourceId::SerialId(document_id),
+                title: format!("doc {}", document_id),
+                score,
+                features: DifficultyFeatures {
+                    mean_sentence_length: 10.0,
+                    rare_lemma_ratio: None,
+                    mean_dependency_depth: None,
+                    unknown_token_ratio,
+                },
+                updated_ts: Utc::now(),
+            };
+        let difficulties = vec![
+            mk_difficulty(1, 20.0, 0.01),
+            mk_difficulty(2, 45.0, 0.05),
+            mk_difficulty(3, 70.0, 0.30),
+            mk_difficulty(4, 60.0, 0.12),
+            mk_difficulty(5, 35.0, 0.02),
+        ];
+        let recommended = recommend_documents(difficulties.clone(), 0.08, 4)
+            .into_iter()
+            .map(|difficulty| difficulty.title)
+            .collect::<Vec<_>>();
+        assert_eq!(recommended, vec!["doc 2", "doc 5", "doc 1", "doc 4"]);
+        assert_eq!(recommend_documents(difficulties, 0.08, 0), vec![]);
+    }
+}
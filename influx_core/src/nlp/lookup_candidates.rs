@@ -0,0 +1,173 @@
+///! forms to look a word up under in the dictionaries, since inflected forms are rarely headwords
+///! - the surface form, its lemma, and each result of its conjugation chain, from the parser's SegAttribute
+///! - then case-folded and diacritic-stripped variants of those, which only serve as fallbacks
+use super::*;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub enum LookupCandidateSource {
+    Surface,
+    Lemma,
+    /// the result of a step of the conjugation chain, e.g. step 0 is the base form
+    Conjugation {
+        step: u32,
+        form: String,
+    },
+    CaseFolded,
+    DiacriticsStripped,
+}
+
+#[derive(Debug, SerdeDerives!, Clone, PartialEq, Eq, ElmDerives!)]
+pub struct LookupCandidate {
+    pub text: String,
+    pub source: LookupCandidateSource,
+}
+
+impl LookupCandidate {
+    /// variants are looked up only when none of the parser's forms are found
+    pub fn is_fallback(&self) -> bool {
+        matches!(
+            self.source,
+            LookupCandidateSource::CaseFolded | LookupCandidateSource::DiacriticsStripped
+        )
+    }
+}
+
+/// remove combining marks from the Latin, Greek and Cyrillic accents block, e.g. "été" to "ete".
+/// marks outside it, like Japanese dakuten, change the letter and are kept
+fn strip_diacritics(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !('\u{0300}'..='\u{036f}').contains(c))
+        .nfc()
+        .collect()
+}
+
+/// case-folded and diacritic-stripped, the loosest form a word is matched under, e.g. "Été" to "ete"
+pub fn fold_for_lookup(text: &str) -> String {
+    strip_diacritics(&text.to_lowercase())
+}
+
+fn push_candidate(
+    candidates: &mut Vec<LookupCandidate>,
+    text: &str,
+    source: LookupCandidateSource,
+) {
+    let text = text.trim();
+    if !text.is_empty() && !candidates.iter().any(|candidate| candidate.text == text) {
+        candidates.push(LookupCandidate {
+            text: text.to_string(),
+            source,
+        });
+    }
+}
+
+/// candidates in the order they should be tried, without repeats or empty forms
+pub fn lookup_candidates(surface: &str, attributes: Option<&SegAttribute>) -> Vec<LookupCandidate> {
+    let mut candidates: Vec<LookupCandidate> = vec![];
+    push_candidate(&mut candidates, surface, LookupCandidateSource::Surface);
+    if let Some(attributes) = attributes {
+        if let Some(lemma) = &attributes.lemma {
+            push_candidate(&mut candidates, lemma, LookupCandidateSource::Lemma);
+        }
+        for step in attributes.conjugation_chain.iter().flatten() {
+            push_candidate(
+                &mut candidates,
+                &step.result,
+                LookupCandidateSource::Conjugation {
+                    step: step.step,
+                    form: step.form.clone(),
+                },
+            );
+        }
+    }
+
+    let forms = candidates
+        .iter()
+        .map(|candidate| candidate.text.clone())
+        .collect::<Vec<_>>();
+    for form in &forms {
+        push_candidate(
+            &mut candidates,
+            &form.to_lowercase(),
+            LookupCandidateSource::CaseFolded,
+        );
+    }
+    for form in &forms {
+        push_candidate(
+            &mut candidates,
+            &fold_for_lookup(form),
+            LookupCandidateSource::DiacriticsStripped,
+        );
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    fn render(candidates: &[LookupCandidate]) -> String {
+        candidates
+            .iter()
+            .map(|candidate| format!("{} {:?}", candidate.text, candidate.source))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn attributes(lemma: Option<&str>, chain: &[(u32, &str, &str)]) -> SegAttribute {
+        SegAttribute {
+            lemma: lemma.map(|lemma| lemma.to_string()),
+            upos: None,
+            xpos: None,
+            dependency: None,
+            misc: btreemap! {},
+            conjugation_chain: match chain.is_empty() {
+                true => None,
+                false => Some(
+                    chain
+                        .iter()
+                        .map(|(step, form, result)| ConjugationStep {
+                            step: *step,
+                            form: form.to_string(),
+                            result: result.to_string(),
+                        })
+                        .collect(),
+                ),
+            },
+        }
+    }
+
+    #[test]
+    fn test_lookup_candidates() {
+        expect![[r#"
+            Étaient Surface
+            être Lemma
+            étaient CaseFolded
+            etaient DiacriticsStripped
+            etre DiacriticsStripped"#]]
+        .assert_eq(&render(&lookup_candidates(
+            "Étaient",
+            Some(&attributes(Some("être"), &[])),
+        )));
+
+        expect![[r#"
+            殺されるな Surface
+            殺す Lemma
+            殺される Conjugation { step: 1, form: "Passive Form" }"#]]
+        .assert_eq(&render(&lookup_candidates(
+            "殺されるな",
+            Some(&attributes(
+                Some("殺す"),
+                &[
+                    (0, "base", "殺す"),
+                    (1, "Passive Form", "殺される"),
+                    (2, "な Negative Command (Do Not Do)", "殺されるな"),
+                ],
+            )),
+        )));
+
+        expect!["がっこう Surface"].assert_eq(&render(&lookup_candidates(" がっこう ", None)));
+        assert_eq!(lookup_candidates("", None), vec![]);
+    }
+}
@@ -16,7 +16,16 @@ use crate::db::models::phrase::Phrase;
 use crate::db::models::vocab::Token;
 use crate::db::InfluxResourceId;
 use crate::utils::trie::Trie;
+use phrase_fitting::ContiguousPhraseMatcher;
+pub mod chunking;
+pub mod collocations;
+pub mod difficulty;
+pub mod lookup_candidates;
+pub mod phrase_automaton;
 pub mod phrase_fitting;
+pub mod phrase_index;
+pub mod term_delta;
+pub mod tokeniser;
 use crate::prelude::*;
 use reqwest::Client;
 use serde_json::json;
@@ -68,7 +77,11 @@ pub enum SentSegVariants {
     PhraseSeg {
         /// lowercase, with each token orthography separated by a space, to make JavaScript type work out.
         normalised_orthography: String,
+        /// segments that make up the phrase. for gapped phrases these can be non-adjacent
         components: Vec<SentSegV2>,
+        /// segments within the phrase's span that are not part of it, e.g. the object in "put it off"
+        #[serde(default)]
+        gaps: Vec<SentSegV2>,
     },
     WhitespaceSeg,
     PunctuationSeg,
@@ -93,6 +106,22 @@ pub struct AnnotatedDocV2 {
     pub orthography_set: BTreeSet<String>,
     pub lemma_set: BTreeSet<String>,
     pub parser_config: crate::db::models::lang::ParserConfig,
+    /// every saved phrase found in the document, including those hidden by the best-fit segmentation
+    #[serde(default)]
+    pub phrase_matches: Vec<PhraseMatch>,
+}
+
+/// a saved phrase found in a sentence. unlike PhraseSegs in the segmentation, matches may overlap and nest
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, ElmDerives!)]
+pub struct PhraseMatch {
+    pub sentence_idx: usize,
+    pub normalised_orthography: String,
+    pub start_char: usize,
+    pub end_char: usize,
+    /// character spans [start, end) of the phrase's own components, which may be non-adjacent
+    pub component_spans: Vec<(usize, usize)>,
+    /// whether this match is a PhraseSeg in the best-fit segmentation
+    pub in_best_fit: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone, ElmDerives!)]
@@ -100,200 +129,384 @@ pub struct TermDictionary {
     pub token_dict: BTreeMap<String, Token>,
     // JavaScript doesn't support HashMaps with non-string keys, sad. We'll concat the keys into a string for now.
     pub phrase_dict: BTreeMap<String, Phrase>,
+    /// ranks in the language's frequency list of the token_dict keys, unranked keys are left out
+    pub frequency_ranks: BTreeMap<String, i32>,
 }
 
 /// given text and language, return a tokenised document before phrase fitting
+/// - the tokeniser is chosen by parser_config.which_parser, see tokeniser::tokeniser_for
+/// - long texts are tokenised in chunks, see chunking::chunked_tokenise
 pub async fn tokenise_pipeline(
     text: &str,
     language_code: String,
     parser_config: crate::db::models::lang::ParserConfig,
-    nlp_url: &str,
+    nlp_client: &crate::integration::nlp_client::NlpClient,
 ) -> anyhow::Result<AnnotatedDocV2> {
-    let client = Client::new();
-    let url = format!("{}/tokeniser", nlp_url);
-    let payload = json!({
-        "text": text,
-        "parser_config": parser_config
-    });
-    let response = client.post(&url).json(&payload).send().await?;
-
-    info!(
-        status = %response.status(),
-        url = %response.url(),
-        "NLP server response received"
-    );
-
-    if response.status().is_success() {
-        debug!("Request to NLP server succeeded");
-        let response_text = response.text().await?;
-        let res_json: AnnotatedDocV2 = serde_json::from_str(&response_text)
-            .with_context(|| format!("failed to decode NLP server response:\n{}", response_text))?;
-        debug!("NLP server response decode succeeded");
-        debug!(
-            segments_count = res_json.segments.len(),
-            orthography_count = res_json.orthography_set.len(),
-            lemma_count = res_json.lemma_set.len(),
-            "Parsed NLP response"
-        );
+    chunking::chunked_tokenise(
+        tokeniser::tokeniser_for(&parser_config, nlp_client),
+        text,
+        &language_code,
+        &parser_config,
+        &Default::default(),
+    )
+    .await
+}
+
+/// version of the tokeniser behind a parser config, stored alongside cached annotations
+pub async fn parser_version(
+    parser_config: &crate::db::models::lang::ParserConfig,
+    nlp_client: &crate::integration::nlp_client::NlpClient,
+) -> anyhow::Result<String> {
+    tokeniser::tokeniser_for(parser_config, nlp_client)
+        .parser_version()
+        .await
+}
+
+/// a phrase found in a sentence, in terms of indices into the sentence's lexical segments
+struct SentencePhraseMatch {
+    /// lexical indices of the phrase's own components, which may be non-adjacent
+    lex_components: Vec<usize>,
+    /// lexical span [start, end) from the first to the last component
+    lex_span: (usize, usize),
+    normalised_orthography: String,
+}
+
+/// (segment index, orthography) for the lexical segments of a sentence, and their lemma sequence
+fn sentence_lex_sequences(
+    segments: &[SentSegV2],
+) -> (Vec<(usize, String)>, Vec<String>, Vec<String>) {
+    let lex_segments = segments
+        .iter()
+        .enumerate()
+        .filter_map(|(i, x)| match &x.inner {
+            SentSegVariants::TokenSeg { orthography, .. } => Some((i, orthography.clone())),
+            SentSegVariants::PunctuationSeg => Some((i, x.text.clone())),
+            _ => None,
+        })
+        .collect::<Vec<(usize, String)>>();
+    let lex_segment_orthographies = lex_segments
+        .iter()
+        .map(|(_, orthography)| orthography.clone())
+        .collect::<Vec<String>>();
+    // lowercase lemmas, falling back to the orthography where the parser gave no lemma
+    let lex_segment_lemmas = lex_segments
+        .iter()
+        .map(|(i, orthography)| {
+            segments[*i]
+                .attributes
+                .lemma
+                .as_ref()
+                .map(|lemma| lemma.to_lowercase())
+                .unwrap_or_else(|| orthography.clone())
+        })
+        .collect::<Vec<String>>();
+    (lex_segments, lex_segment_orthographies, lex_segment_lemmas)
+}
+
+/// the phrases that dp_best_fit cannot find, grouped by whether they match against lemmas
+/// - lemma phrases first so that they take precedence over patterns
+fn gapped_phrase_passes(extra_phrases: &[Phrase]) -> Vec<(bool, Vec<&Phrase>)> {
+    vec![
+        (
+            true,
+            extra_phrases
+                .iter()
+                .filter(|phrase| phrase.match_by_lemma && !phrase.is_pattern())
+                .collect(),
+        ),
+        (
+            false,
+            extra_phrases
+                .iter()
+                .filter(|phrase| phrase.is_pattern())
+                .collect(),
+        ),
+        (
+            true,
+            extra_phrases
+                .iter()
+                .filter(|phrase| phrase.match_by_lemma && phrase.is_pattern())
+                .collect(),
+        ),
+    ]
+}
 
-        let mut annotated_document: AnnotatedDocV2 = res_json;
-        annotated_document.parser_config = parser_config;
-        Ok(annotated_document)
-    } else {
-        Err(anyhow::anyhow!("Request to NLP server failed"))
+fn gapped_patterns(phrases: &[&Phrase]) -> Vec<phrase_fitting::GappedPattern<String>> {
+    phrases
+        .iter()
+        .map(|phrase| phrase_fitting::GappedPattern {
+            elements: phrase.pattern_elements(),
+            max_gap: phrase.max_gap.unwrap_or(0).max(0) as usize,
+        })
+        .collect()
+}
+
+fn fit_phrases_in_sentence(
+    original_segments: Vec<SentSegV2>,
+    potential_phrases: &impl ContiguousPhraseMatcher<String>,
+    extra_phrases: &[Phrase],
+) -> Vec<SentSegV2> {
+    let (lex_segments, lex_segment_orthographies, lex_segment_lemmas) =
+        sentence_lex_sequences(&original_segments);
+
+    // contiguous phrases by orthography first, then lemma and gapped phrases in whatever is left
+    let mut phrase_matches: Vec<SentencePhraseMatch> = potential_phrases
+        .best_fit(&lex_segment_orthographies)
+        .into_iter()
+        .filter(|(start, end)| end - start > 1) // remove trivial phrases
+        .filter_map(|(lex_start, lex_end)| {
+            let orthographies = &lex_segment_orthographies[lex_start..lex_end];
+            match potential_phrases.contains_phrase(orthographies) {
+                true => Some(SentencePhraseMatch {
+                    lex_components: (lex_start..lex_end).collect(),
+                    lex_span: (lex_start, lex_end),
+                    normalised_orthography: orthographies.join(" "),
+                }),
+                false => {
+                    error!(
+                        "Failed to find phrase for orthographies: {:?}",
+                        orthographies
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+
+    if !extra_phrases.is_empty() {
+        let mut claimed = vec![false; lex_segments.len()];
+        for phrase_match in &phrase_matches {
+            claimed[phrase_match.lex_span.0..phrase_match.lex_span.1]
+                .iter_mut()
+                .for_each(|x| *x = true);
+        }
+
+        for (by_lemma, phrases) in gapped_phrase_passes(extra_phrases) {
+            if phrases.is_empty() {
+                continue;
+            }
+            let lex_seq = if by_lemma {
+                &lex_segment_lemmas
+            } else {
+                &lex_segment_orthographies
+            };
+            let patterns = gapped_patterns(&phrases);
+            let gapped_matches = phrase_fitting::gapped_fit(lex_seq, &patterns, &mut claimed);
+            phrase_matches.extend(gapped_matches.into_iter().map(|gapped_match| {
+                SentencePhraseMatch {
+                    lex_components: gapped_match.components,
+                    lex_span: (gapped_match.span.0, gapped_match.span.1 + 1),
+                    normalised_orthography: phrases[gapped_match.pattern_idx]
+                        .orthography_seq
+                        .join(" "),
+                }
+            }));
+        }
+        phrase_matches.sort_by_key(|phrase_match| phrase_match.lex_span.0);
     }
+
+    if phrase_matches.is_empty() {
+        return original_segments;
+    }
+
+    let mut fitted_segments: Vec<SentSegV2> = Vec::new();
+    let mut cursor = 0;
+    for phrase_match in phrase_matches {
+        let start = lex_segments[phrase_match.lex_span.0].0;
+        let end = lex_segments[phrase_match.lex_span.1 - 1].0 + 1;
+
+        // Add non-phrase segments before the current phrase
+        fitted_segments.extend_from_slice(&original_segments[cursor..start]);
+
+        // Contiguous phrases keep everything in their span as components, gapped phrases only
+        // keep the segments they matched and report the rest as gaps
+        let is_contiguous =
+            phrase_match.lex_components.len() == phrase_match.lex_span.1 - phrase_match.lex_span.0;
+        let (components, gaps): (Vec<SentSegV2>, Vec<SentSegV2>) = if is_contiguous {
+            (original_segments[start..end].to_vec(), vec![])
+        } else {
+            let component_indices = phrase_match
+                .lex_components
+                .iter()
+                .map(|lex_idx| lex_segments[*lex_idx].0)
+                .collect::<BTreeSet<usize>>();
+            let (components, gaps) = original_segments[start..end]
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(offset, seg)| (start + offset, seg))
+                .partition::<Vec<_>, _>(|(i, _)| component_indices.contains(i));
+            (
+                components.into_iter().map(|(_, seg)| seg).collect(),
+                gaps.into_iter().map(|(_, seg)| seg).collect(),
+            )
+        };
+
+        fitted_segments.push(SentSegV2 {
+            sentence_idx: original_segments[start].sentence_idx,
+            text: original_segments[start..end]
+                .iter()
+                .map(|x| x.text.clone())
+                .collect::<Vec<String>>()
+                .join(""),
+            start_char: original_segments[start].start_char,
+            end_char: original_segments[end - 1].end_char,
+            inner: SentSegVariants::PhraseSeg {
+                normalised_orthography: phrase_match.normalised_orthography,
+                components,
+                gaps,
+            },
+            attributes: SegAttribute {
+                lemma: None,
+                upos: None,
+                xpos: None,
+                dependency: None,
+                misc: btreemap! {},
+                conjugation_chain: None,
+            },
+        });
+        cursor = end;
+    }
+
+    // Add non-phrase segments after the last phrase
+    fitted_segments.extend_from_slice(&original_segments[cursor..]);
+    fitted_segments
+}
+
+/// every phrase in the sentence, with overlap and nesting allowed, before any segmentation
+fn find_all_phrases_in_sentence(
+    segments: &[SentSegV2],
+    potential_phrases: &impl ContiguousPhraseMatcher<String>,
+    extra_phrases: &[Phrase],
+) -> Vec<PhraseMatch> {
+    let (lex_segments, lex_segment_orthographies, lex_segment_lemmas) =
+        sentence_lex_sequences(segments);
+
+    let mut found: Vec<SentencePhraseMatch> = vec![];
+    for (start, end) in potential_phrases
+        .find_all(&lex_segment_orthographies)
+        .into_iter()
+        .filter(|(start, end)| end - start > 1)
+    {
+        found.push(SentencePhraseMatch {
+            lex_components: (start..end).collect(),
+            lex_span: (start, end),
+            normalised_orthography: lex_segment_orthographies[start..end].join(" "),
+        });
+    }
+    for (by_lemma, phrases) in gapped_phrase_passes(extra_phrases) {
+        let lex_seq = if by_lemma {
+            &lex_segment_lemmas
+        } else {
+            &lex_segment_orthographies
+        };
+        let patterns = gapped_patterns(&phrases);
+        for gapped_match in phrase_fitting::gapped_find_all(lex_seq, &patterns) {
+            found.push(SentencePhraseMatch {
+                lex_components: gapped_match.components,
+                lex_span: (gapped_match.span.0, gapped_match.span.1 + 1),
+                normalised_orthography: phrases[gapped_match.pattern_idx].orthography_seq.join(" "),
+            });
+        }
+    }
+
+    // a lemma phrase whose lemmas equal its orthographies is found by both passes
+    let mut seen = HashSet::new();
+    found.retain(|phrase_match| {
+        seen.insert((
+            phrase_match.normalised_orthography.clone(),
+            phrase_match.lex_components.clone(),
+        ))
+    });
+    found.sort_by_key(|phrase_match| {
+        (
+            phrase_match.lex_span.0,
+            std::cmp::Reverse(phrase_match.lex_span.1),
+        )
+    });
+
+    found
+        .into_iter()
+        .map(|phrase_match| {
+            let first = &segments[lex_segments[phrase_match.lex_span.0].0];
+            let last = &segments[lex_segments[phrase_match.lex_span.1 - 1].0];
+            PhraseMatch {
+                sentence_idx: first.sentence_idx,
+                normalised_orthography: phrase_match.normalised_orthography,
+                start_char: first.start_char,
+                end_char: last.end_char,
+                component_spans: phrase_match
+                    .lex_components
+                    .iter()
+                    .map(|lex_idx| {
+                        let seg = &segments[lex_segments[*lex_idx].0];
+                        (seg.start_char, seg.end_char)
+                    })
+                    .collect(),
+                in_best_fit: false,
+            }
+        })
+        .collect()
 }
 
 pub fn phrase_fit_pipeline(
     document: AnnotatedDocV2,
     potential_phrases: Trie<String, Phrase>,
 ) -> AnnotatedDocV2 {
+    // phrases that dp_best_fit over orthographies cannot find on its own
+    let extra_phrases: Vec<Phrase> = potential_phrases
+        .get_all_entries()
+        .into_iter()
+        .map(|(_, phrase)| phrase.clone())
+        .filter(|phrase| phrase.is_pattern() || phrase.match_by_lemma)
+        .collect();
+
+    fit_phrases_in_document(document, &potential_phrases, &extra_phrases)
+}
+
+/// contiguous phrases come from potential_phrases, lemma and gapped phrases from extra_phrases
+fn fit_phrases_in_document(
+    document: AnnotatedDocV2,
+    potential_phrases: &impl ContiguousPhraseMatcher<String>,
+    extra_phrases: &[Phrase],
+) -> AnnotatedDocV2 {
+    let mut phrase_matches: Vec<PhraseMatch> = vec![];
     let fitted_doc_seg: Vec<DocSegV2> = document
         .segments
         .into_iter()
-        .map(|document_segment| {
-            match document_segment.inner {
-                DocSegVariants::DocumentWhitespace => DocSegV2 {
+        .map(|document_segment| match document_segment.inner {
+            DocSegVariants::DocumentWhitespace => DocSegV2 {
+                text: document_segment.text,
+                start_char: document_segment.start_char,
+                end_char: document_segment.end_char,
+                inner: DocSegVariants::DocumentWhitespace,
+            },
+            DocSegVariants::Sentence { segments } => {
+                let mut sentence_phrase_matches =
+                    find_all_phrases_in_sentence(&segments, potential_phrases, extra_phrases);
+                let fitted_segments =
+                    fit_phrases_in_sentence(segments, potential_phrases, extra_phrases);
+                for phrase_match in sentence_phrase_matches.iter_mut() {
+                    phrase_match.in_best_fit = fitted_segments.iter().any(|seg| match &seg.inner {
+                        SentSegVariants::PhraseSeg {
+                            normalised_orthography,
+                            ..
+                        } => {
+                            *normalised_orthography == phrase_match.normalised_orthography
+                                && seg.start_char == phrase_match.start_char
+                                && seg.end_char == phrase_match.end_char
+                        }
+                        _ => false,
+                    });
+                }
+                phrase_matches.extend(sentence_phrase_matches);
+                DocSegV2 {
                     text: document_segment.text,
                     start_char: document_segment.start_char,
                     end_char: document_segment.end_char,
-                    inner: DocSegVariants::DocumentWhitespace,
-                },
-                DocSegVariants::Sentence { segments } => {
-                    let original_segments = segments.clone();
-
-                    let lex_segments = segments
-                        .iter()
-                        .enumerate()
-                        .filter_map(|(i, x)| match &x.inner {
-                            SentSegVariants::TokenSeg { orthography, .. } => {
-                                Some((i, orthography.clone()))
-                            }
-                            SentSegVariants::PunctuationSeg => Some((i, x.text.clone())),
-                            _ => None,
-                        })
-                        .collect::<Vec<(usize, String)>>();
-                    let lex_segment_orthographies = lex_segments
-                        .iter()
-                        .map(|(_, orthography)| orthography.clone())
-                        .collect::<Vec<String>>();
-                    let lex_phrase_slices_indices = phrase_fitting::dp_best_fit(
-                        lex_segment_orthographies.clone(),
-                        &potential_phrases,
-                    );
-                    let lex_phrase_slices_indices = lex_phrase_slices_indices
-                        .iter()
-                        .filter(|(start, end)| {
-                            // remove trivial phrases
-                            match end - start {
-                                1 => false,
-                                _ => true,
-                            }
-                        })
-                        .collect::<Vec<&(usize, usize)>>();
-                    if lex_phrase_slices_indices.len() == 0 {
-                        // no phrase
-                        return DocSegV2 {
-                            text: document_segment.text,
-                            start_char: document_segment.start_char,
-                            end_char: document_segment.end_char,
-                            inner: DocSegVariants::Sentence {
-                                segments: original_segments,
-                            },
-                        };
-                    }
-                    let phrase_slices = lex_phrase_slices_indices
-                        .iter()
-                        .map(|(lex_start, lex_end)| {
-                            let start = lex_segments[*lex_start].0;
-                            let end = {
-                                match lex_end {
-                                    0 => 0,
-                                    _ => lex_segments[*lex_end - 1].0 + 1,
-                                }
-                            };
-                            ((start, end), (*lex_start, *lex_end))
-                        })
-                        .collect::<Vec<((usize, usize), (usize, usize))>>();
-
-                    let phrase_non_phrase_sentence: Vec<SentSegV2> = phrase_slices
-                        .iter()
-                        .enumerate()
-                        .flat_map(|(i, ((start, end), (lex_start, lex_end)))| {
-                            let mut segments_to_add: Vec<SentSegV2> = Vec::new();
-
-                            // Add non-phrase tokens before the current phrase
-                            if i == 0 && *start > 0 {
-                                segments_to_add.extend_from_slice(&original_segments[0..*start]);
-                            } else if i > 0 && *start > phrase_slices[i - 1].0 .1 {
-                                segments_to_add.extend_from_slice(
-                                    &original_segments[phrase_slices[i - 1].0 .1..*start],
-                                );
-                            }
-
-                            // Add the phrase token
-                            let phrase = match potential_phrases
-                                .search_for_payload(
-                                    lex_segment_orthographies[*lex_start..*lex_end].to_vec(),
-                                )
-                                .1
-                            {
-                                Some(p) => p,
-                                None => {
-                                    error!(
-                                        "Failed to find phrase in trie for orthographies: {:?}",
-                                        &lex_segment_orthographies[*lex_start..*lex_end]
-                                    );
-                                    return vec![]; // Return empty vec to continue processing
-                                }
-                            };
-                            let phrase_start_char = original_segments[*start].start_char;
-                            let phrase_end_char = original_segments[*end - 1].end_char;
-                            let phrase_text = original_segments[*start..*end]
-                                .iter()
-                                .map(|x| x.text.clone())
-                                .collect::<Vec<String>>()
-                                .join("");
-
-                            segments_to_add.push(SentSegV2 {
-                                sentence_idx: original_segments[*start].sentence_idx,
-                                text: phrase_text,
-                                start_char: phrase_start_char,
-                                end_char: phrase_end_char,
-                                inner: SentSegVariants::PhraseSeg {
-                                    normalised_orthography: lex_segment_orthographies
-                                        [*lex_start..*lex_end]
-                                        .join(" "),
-                                    components: original_segments[*start..*end].to_vec(),
-                                },
-                                attributes: SegAttribute {
-                                    lemma: None,
-                                    upos: None,
-                                    xpos: None,
-                                    dependency: None,
-                                    misc: btreemap! {},
-                                    conjugation_chain: None,
-                                },
-                            });
-
-                            // Add non-phrase tokens after the last phrase
-                            if i == phrase_slices.len() - 1 && *end < original_segments.len() {
-                                segments_to_add.extend_from_slice(
-                                    &original_segments[*end..original_segments.len()],
-                                );
-                            }
-                            segments_to_add
-                        })
-                        .collect();
-
-                    DocSegV2 {
-                        text: document_segment.text,
-                        start_char: document_segment.start_char,
-                        end_char: document_segment.end_char,
-                        inner: DocSegVariants::Sentence {
-                            segments: phrase_non_phrase_sentence,
-                        },
-                    }
+                    inner: DocSegVariants::Sentence {
+                        segments: fitted_segments,
+                    },
                 }
             }
         })
@@ -305,12 +518,14 @@ pub fn phrase_fit_pipeline(
         orthography_set: document.orthography_set,
         lemma_set: document.lemma_set,
         parser_config: document.parser_config,
+        phrase_matches,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::integration::nlp_client::NlpClient;
     use expect_test::{expect, Expect};
 
     #[tokio::test]
@@ -326,7 +541,7 @@ mod tests {
                     "spacy_model".to_string() => "en_core_web_sm".to_string()
                 },
             },
-            "http://127.0.0.1:3001",
+            &NlpClient::new("http://127.0.0.1:3001", Default::default()).unwrap(),
         )
         .await;
         assert!(res.is_ok());
@@ -538,6 +753,7 @@ mod tests {
                         "spacy_model": "en_core_web_sm",
                     },
                 },
+                phrase_matches: [],
             }
         "#]];
         expected.assert_debug_eq(&res);
@@ -556,7 +772,7 @@ mod tests {
                     "spacy_model".to_string() => "en_core_web_sm".to_string()
                 },
             },
-            "http://127.0.0.1:3001",
+            &NlpClient::new("http://127.0.0.1:3001", Default::default()).unwrap(),
         )
         .await;
         assert!(res.is_ok());
@@ -729,6 +945,7 @@ mod tests {
                         "spacy_model": "en_core_web_sm",
                     },
                 },
+                phrase_matches: [],
             }
         "#]];
         expected.assert_debug_eq(&res);
@@ -747,7 +964,7 @@ mod tests {
                     "enable_conjugation_analysis".to_string() => "true".to_string()
                 },
             },
-            "http://127.0.0.1:3001",
+            &NlpClient::new("http://127.0.0.1:3001", Default::default()).unwrap(),
         )
         .await;
         assert!(res.is_ok());
@@ -867,6 +1084,7 @@ mod tests {
                         "enable_conjugation_analysis": "true",
                     },
                 },
+                phrase_matches: [],
             }
         "#]];
         expected.assert_debug_eq(&res);
@@ -972,4 +1190,282 @@ mod tests {
     }
 
     // TODO phrase fitting tests
+
+    fn mk_plain_attributes(lemma: Option<&str>) -> SegAttribute {
+        SegAttribute {
+            lemma: lemma.map(|x| x.to_string()),
+            upos: None,
+            xpos: None,
+            dependency: None,
+            misc: btreemap! {},
+            conjugation_chain: None,
+        }
+    }
+
+    /// a single-sentence document of space separated words, with a trailing full stop
+    fn mk_sentence_doc(words: &[&str]) -> AnnotatedDocV2 {
+        let mut segments = vec![];
+        let mut char_idx = 0;
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 {
+                segments.push(SentSegV2 {
+                    sentence_idx: 0,
+                    text: " ".to_string(),
+                    start_char: char_idx,
+                    end_char: char_idx + 1,
+                    inner: SentSegVariants::WhitespaceSeg,
+                    attributes: mk_plain_attributes(None),
+                });
+                char_idx += 1;
+            }
+            let len = word.chars().count();
+            segments.push(SentSegV2 {
+                sentence_idx: 0,
+                text: word.to_string(),
+                start_char: char_idx,
+                end_char: char_idx + len,
+                inner: SentSegVariants::TokenSeg {
+                    idx: i,
+                    orthography: word.to_lowercase(),
+                },
+                attributes: mk_plain_attributes(Some(word)),
+            });
+            char_idx += len;
+        }
+        segments.push(SentSegV2 {
+            sentence_idx: 0,
+            text: ".".to_string(),
+            start_char: char_idx,
+            end_char: char_idx + 1,
+            inner: SentSegVariants::PunctuationSeg,
+            attributes: mk_plain_attributes(None),
+        });
+        let text = format!("{}.", words.join(" "));
+        AnnotatedDocV2 {
+            segments: vec![DocSegV2 {
+                text: text.clone(),
+                start_char: 0,
+                end_char: char_idx + 1,
+                inner: DocSegVariants::Sentence { segments },
+            }],
+            text,
+            orthography_set: words.iter().map(|x| x.to_lowercase()).collect(),
+            lemma_set: words.iter().map(|x| x.to_string()).collect(),
+            parser_config: Default::default(),
+            phrase_matches: vec![],
+        }
+    }
+
+    /// compact rendering of the lexical structure of fitted sentences
+    fn render_fitted_doc(doc: &AnnotatedDocV2) -> String {
+        fn render_seg(seg: &SentSegV2) -> Option<String> {
+            match &seg.inner {
+                SentSegVariants::TokenSeg { .. } | SentSegVariants::PunctuationSeg => {
+                    Some(seg.text.clone())
+                }
+                SentSegVariants::PhraseSeg {
+                    normalised_orthography,
+                    components,
+                    gaps,
+                } => Some(format!(
+                    "[{}: {} | gaps: {}]",
+                    normalised_orthography,
+                    components
+                        .iter()
+                        .filter_map(render_seg)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    gaps.iter()
+                        .filter_map(render_seg)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )),
+                SentSegVariants::WhitespaceSeg => None,
+            }
+        }
+        doc.segments
+            .iter()
+            .filter_map(|doc_seg| match &doc_seg.inner {
+                DocSegVariants::Sentence { segments } => Some(
+                    segments
+                        .iter()
+                        .filter_map(render_seg)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+                DocSegVariants::DocumentWhitespace => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn mk_test_phrase(orthography_seq: &[&str], max_gap: Option<i32>) -> Phrase {
+        let mut phrase = Phrase::essential_phrase(
+            InfluxResourceId::SerialId(1),
+            orthography_seq.iter().map(|x| x.to_string()).collect(),
+        );
+        phrase.max_gap = max_gap;
+        phrase
+    }
+
+    #[test]
+    fn test_phrase_fit_gapped_phrases() {
+        let doc = mk_sentence_doc(&[
+            "He", "put", "the", "meeting", "off", "but", "put", "it", "off", "again",
+        ]);
+        let phrases = vec![
+            mk_test_phrase(&["put", "off"], Some(2)),
+            mk_test_phrase(&["put", "*", "off"], None),
+        ];
+        let fitted = phrase_fit_pipeline(doc, crate::db::models::phrase::mk_phrase_trie(phrases));
+        expect![[r#"He [put off: put off | gaps: the meeting] but [put * off: put off | gaps: it] again ."#]]
+            .assert_eq(&render_fitted_doc(&fitted));
+    }
+
+    #[test]
+    fn test_gapped_phrase_seg_covers_its_span() {
+        // the client shows a gapped phrase by laying its components and gaps out by start_char
+        let doc = mk_sentence_doc(&["put", "the", "meeting", "off"]);
+        let phrases = vec![mk_test_phrase(&["put", "off"], Some(2))];
+        let fitted = phrase_fit_pipeline(doc, crate::db::models::phrase::mk_phrase_trie(phrases));
+        let DocSegVariants::Sentence { segments } = &fitted.segments[0].inner else {
+            panic!("expected a sentence");
+        };
+        let (phrase_seg, components, gaps) = segments
+            .iter()
+            .find_map(|seg| match &seg.inner {
+                SentSegVariants::PhraseSeg {
+                    components, gaps, ..
+                } => Some((seg, components, gaps)),
+                _ => None,
+            })
+            .unwrap();
+        let mut parts = components
+            .iter()
+            .map(|seg| (seg, "component"))
+            .chain(gaps.iter().map(|seg| (seg, "gap")))
+            .collect::<Vec<_>>();
+        parts.sort_by_key(|(seg, _)| seg.start_char);
+        assert_eq!(
+            parts
+                .iter()
+                .map(|(seg, _)| seg.text.as_str())
+                .collect::<String>(),
+            phrase_seg.text
+        );
+        expect![[r#"
+            0 "put" component
+            3 " " gap
+            4 "the" gap
+            7 " " gap
+            8 "meeting" gap
+            15 " " gap
+            16 "off" component"#]]
+        .assert_eq(
+            &parts
+                .iter()
+                .map(|(seg, part)| format!("{} {:?} {}", seg.start_char, seg.text, part))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+
+    #[test]
+    fn test_phrase_fit_by_lemma() {
+        let mut doc = mk_sentence_doc(&["She", "took", "off", "and", "takes", "it", "off"]);
+        if let DocSegVariants::Sentence { segments } = &mut doc.segments[0].inner {
+            for seg in segments.iter_mut() {
+                if seg.text == "took" || seg.text == "takes" {
+                    seg.attributes.lemma = Some("take".to_string());
+                }
+            }
+        }
+        let mut take_off = mk_test_phrase(&["take", "off"], Some(1));
+        take_off.match_by_lemma = true;
+        let fitted = phrase_fit_pipeline(
+            doc,
+            crate::db::models::phrase::mk_phrase_trie(vec![take_off]),
+        );
+        expect![[r#"She [take off: took off | gaps: ] and [take off: takes off | gaps: it] ."#]]
+            .assert_eq(&render_fitted_doc(&fitted));
+    }
+
+    #[test]
+    fn test_phrase_fit_contiguous_phrase_takes_precedence() {
+        let doc = mk_sentence_doc(&["put", "off", "the", "meeting"]);
+        let phrases = vec![
+            mk_test_phrase(&["put", "off"], Some(3)),
+            mk_test_phrase(&["the", "meeting"], None),
+        ];
+        let fitted = phrase_fit_pipeline(doc, crate::db::models::phrase::mk_phrase_trie(phrases));
+        expect![[r#"[put off: put off | gaps: ] [the meeting: the meeting | gaps: ] ."#]]
+            .assert_eq(&render_fitted_doc(&fitted));
+    }
+
+    #[test]
+    fn test_phrase_index_fits_like_trie() {
+        let doc = tokeniser::unicode_tokenise(
+            "New York is big. They took off for New York City, then took it off.",
+            Default::default(),
+        );
+        let mut took_off = mk_test_phrase(&["took", "off"], Some(1));
+        took_off.match_by_lemma = true;
+        let phrases = vec![
+            mk_test_phrase(&["new", "york"], None),
+            mk_test_phrase(&["new", "york", "city"], None),
+            mk_test_phrase(&["york", "is"], None),
+            mk_test_phrase(&["never", "seen"], None),
+            took_off,
+        ];
+        let index = phrase_index::PhraseIndex::new(phrases.clone());
+        let fitted = index.fit(doc.clone());
+        assert_eq!(
+            fitted,
+            phrase_fit_pipeline(doc, crate::db::models::phrase::mk_phrase_trie(phrases))
+        );
+        expect![[r#"
+            New [york is: York is | gaps: ] big .
+            They [took off: took off | gaps: ] for [new york city: New York City | gaps: ] , then [took off: took off | gaps: it] ."#]]
+            .assert_eq(&render_fitted_doc(&fitted));
+        expect![[r#"["new york", "new york city", "took off", "york is"]"#]].assert_eq(&format!(
+            "{:?}",
+            index
+                .matched_phrases(&fitted)
+                .into_keys()
+                .collect::<Vec<_>>()
+        ));
+    }
+
+    #[test]
+    fn test_phrase_fit_reports_overlapping_and_nested_phrases() {
+        let doc = mk_sentence_doc(&["he", "gave", "up", "on", "it"]);
+        let phrases = vec![
+            mk_test_phrase(&["gave", "up"], None),
+            mk_test_phrase(&["gave", "up", "on"], None),
+            mk_test_phrase(&["up", "on"], None),
+        ];
+        let fitted = phrase_fit_pipeline(doc, crate::db::models::phrase::mk_phrase_trie(phrases));
+        expect![[r#"he [gave up on: gave up on | gaps: ] it ."#]]
+            .assert_eq(&render_fitted_doc(&fitted));
+        let rendered_matches = fitted
+            .phrase_matches
+            .iter()
+            .map(|m| {
+                format!(
+                    "{} {}..{} {:?} best={}",
+                    m.normalised_orthography,
+                    m.start_char,
+                    m.end_char,
+                    m.component_spans,
+                    m.in_best_fit
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        expect![[r#"
+            gave up on 3..13 [(3, 7), (8, 10), (11, 13)] best=true
+            gave up 3..10 [(3, 7), (8, 10)] best=false
+            up on 8..13 [(8, 10), (11, 13)] best=false"#]]
+        .assert_eq(&rendered_matches);
+    }
 }
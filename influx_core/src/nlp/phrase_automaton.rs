@@ -0,0 +1,322 @@
+///! Aho–Corasick automaton over token sequences, finding every phrase in a sentence in a single pass
+///! - built once per language from all of its phrases, see PhraseIndex, instead of a Trie per document
+///! - best_fit gives the same segmentation as dp_best_fit, in time linear in the sentence plus its matches
+use super::phrase_fitting::ContiguousPhraseMatcher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+const ROOT: usize = 0;
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    children: HashMap<T, usize>,
+    /// node of the longest proper suffix of this node's path that is also a path in the automaton
+    fail: usize,
+    depth: usize,
+    /// the pattern ending at this node, as an index into payloads
+    pattern: Option<usize>,
+    /// nearest node on the fail chain, excluding this one, where a pattern ends
+    dict_suffix: Option<usize>,
+}
+
+impl<T> Node<T> {
+    fn new(depth: usize) -> Self {
+        Node {
+            children: HashMap::new(),
+            fail: ROOT,
+            depth,
+            pattern: None,
+            dict_suffix: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PhraseAutomaton<T, S> {
+    nodes: Vec<Node<T>>,
+    payloads: Vec<S>,
+}
+
+impl<T: Eq + Hash + Clone, S> PhraseAutomaton<T, S> {
+    /// empty patterns are ignored, and a repeated pattern keeps its last payload
+    pub fn new(patterns: impl IntoIterator<Item = (Vec<T>, S)>) -> Self {
+        let mut nodes = vec![Node::new(0)];
+        let mut payloads = vec![];
+        for (pattern, payload) in patterns {
+            if pattern.is_empty() {
+                continue;
+            }
+            let mut curr = ROOT;
+            for x in pattern {
+                curr = match nodes[curr].children.get(&x) {
+                    Some(&child) => child,
+                    None => {
+                        let child = nodes.len();
+                        nodes.push(Node::new(nodes[curr].depth + 1));
+                        nodes[curr].children.insert(x, child);
+                        child
+                    }
+                };
+            }
+            match nodes[curr].pattern {
+                Some(existing) => payloads[existing] = payload,
+                None => {
+                    nodes[curr].pattern = Some(payloads.len());
+                    payloads.push(payload);
+                }
+            }
+        }
+
+        // breadth first, so a node's fail link is set before those of its children. nodes at depth 1 fail to the root
+        let mut queue: VecDeque<usize> = nodes[ROOT].children.values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let children = nodes[node]
+                .children
+                .iter()
+                .map(|(x, &child)| (x.clone(), child))
+                .collect::<Vec<(T, usize)>>();
+            for (x, child) in children {
+                let mut fallback = nodes[node].fail;
+                let fail = loop {
+                    if let Some(&next) = nodes[fallback].children.get(&x) {
+                        break next;
+                    }
+                    if fallback == ROOT {
+                        break ROOT;
+                    }
+                    fallback = nodes[fallback].fail;
+                };
+                nodes[child].fail = fail;
+                nodes[child].dict_suffix = match nodes[fail].pattern {
+                    Some(_) => Some(fail),
+                    None => nodes[fail].dict_suffix,
+                };
+                queue.push_back(child);
+            }
+        }
+
+        PhraseAutomaton { nodes, payloads }
+    }
+
+    /// number of distinct patterns
+    pub fn len(&self) -> usize {
+        self.payloads.len()
+    }
+
+    pub fn get(&self, pattern: &[T]) -> Option<&S> {
+        let mut curr = ROOT;
+        for x in pattern {
+            curr = *self.nodes[curr].children.get(x)?;
+        }
+        self.nodes[curr].pattern.map(|idx| &self.payloads[idx])
+    }
+
+    fn step(&self, mut state: usize, x: &T) -> usize {
+        loop {
+            if let Some(&next) = self.nodes[state].children.get(x) {
+                return next;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+
+    /// lengths of the patterns starting at each position of seq, ascending
+    /// - a match is reported when its end is reached, so for a fixed start they come in ascending length
+    fn match_lengths_by_start(&self, seq: &[T]) -> Vec<Vec<usize>> {
+        let mut lengths_by_start = vec![vec![]; seq.len()];
+        let mut state = ROOT;
+        for (i, x) in seq.iter().enumerate() {
+            state = self.step(state, x);
+            let mut output = match self.nodes[state].pattern {
+                Some(_) => Some(state),
+                None => self.nodes[state].dict_suffix,
+            };
+            while let Some(node) = output {
+                let len = self.nodes[node].depth;
+                lengths_by_start[i + 1 - len].push(len);
+                output = self.nodes[node].dict_suffix;
+            }
+        }
+        lengths_by_start
+    }
+}
+
+impl<T: Eq + Hash + Clone, S> ContiguousPhraseMatcher<T> for PhraseAutomaton<T, S> {
+    fn best_fit(&self, seq: &[T]) -> Vec<(usize, usize)> {
+        let lengths_by_start = self.match_lengths_by_start(seq);
+        // fewest segments covering seq[start..], and the length of the first of them
+        let mut cost = vec![0; seq.len() + 1];
+        let mut first_len = vec![1; seq.len() + 1];
+        for start in (0..seq.len()).rev() {
+            cost[start] = cost[start + 1] + 1;
+            // a singleton first, then ascending lengths, keeping the shortest on ties like dp_best_fit
+            for &len in &lengths_by_start[start] {
+                if cost[start + len] + 1 < cost[start] {
+                    cost[start] = cost[start + len] + 1;
+                    first_len[start] = len;
+                }
+            }
+        }
+
+        let mut slices = vec![];
+        let mut start = 0;
+        while start < seq.len() {
+            slices.push((start, start + first_len[start]));
+            start += first_len[start];
+        }
+        slices
+    }
+
+    fn find_all(&self, seq: &[T]) -> Vec<(usize, usize)> {
+        self.match_lengths_by_start(seq)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(start, lengths)| lengths.into_iter().map(move |len| (start, start + len)))
+            .collect()
+    }
+
+    fn contains_phrase(&self, phrase: &[T]) -> bool {
+        self.get(phrase).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nlp::phrase_fitting::dp_best_fit;
+    use crate::utils::trie::Trie;
+    use std::time::Instant;
+
+    /// xorshift, so synthetic inputs are reproducible without a rand dependency
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    /// phrases of 2 to 5 words, and a sentence that mostly repeats pieces of them so matches overlap
+    fn synthetic_input(
+        rng: &mut Rng,
+        vocab_size: usize,
+        phrase_count: usize,
+        sentence_len: usize,
+    ) -> (Vec<Vec<String>>, Vec<String>) {
+        let word = |i: usize| format!("w{}", i);
+        let phrases = (0..phrase_count)
+            .map(|_| {
+                (0..2 + rng.below(4))
+                    .map(|_| word(rng.below(vocab_size)))
+                    .collect()
+            })
+            .collect::<Vec<Vec<String>>>();
+        let mut sentence = vec![];
+        while sentence.len() < sentence_len {
+            if rng.below(3) == 0 {
+                sentence.push(word(rng.below(vocab_size)));
+            } else {
+                let phrase = &phrases[rng.below(phrase_count)];
+                let cut = rng.below(phrase.len()) + 1;
+                sentence.extend_from_slice(&phrase[..cut]);
+            }
+        }
+        sentence.truncate(sentence_len);
+        (phrases, sentence)
+    }
+
+    fn both(phrases: &[Vec<String>]) -> (Trie<String, ()>, PhraseAutomaton<String, ()>) {
+        let entries = phrases
+            .iter()
+            .map(|phrase| (phrase.clone(), ()))
+            .collect::<Vec<_>>();
+        (
+            Trie::new_with_entries_and_payloads(entries.clone()),
+            PhraseAutomaton::new(entries),
+        )
+    }
+
+    #[test]
+    fn test_automaton_find_all_and_best_fit() {
+        let automaton = PhraseAutomaton::new(vec![
+            (vec![1, 2, 3], "a"),
+            (vec![1, 2, 3, 4], "b"),
+            (vec![1, 2, 3, 4, 5], "c"),
+            (vec![6, 7], "d"),
+            (vec![7, 8, 9], "e"),
+            (vec![2, 3], "f"),
+            (vec![], "ignored"),
+        ]);
+        let seq = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
+        assert_eq!(automaton.len(), 6);
+        assert_eq!(automaton.get(&[2, 3]), Some(&"f"));
+        assert_eq!(automaton.get(&[1, 2]), None);
+        assert_eq!(
+            automaton.find_all(&seq),
+            vec![(0, 3), (0, 4), (0, 5), (1, 3), (5, 7), (6, 9)]
+        );
+        assert_eq!(automaton.best_fit(&seq), vec![(0, 5), (5, 6), (6, 9)]);
+        assert_eq!(automaton.best_fit(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_automaton_agrees_with_trie() {
+        let mut rng = Rng(0x5eed);
+        for _ in 0..200 {
+            let (phrases, sentence) = synthetic_input(&mut rng, 12, 20, 40);
+            let (trie, automaton) = both(&phrases);
+            assert_eq!(
+                automaton.best_fit(&sentence),
+                dp_best_fit(sentence.clone(), &trie),
+                "{:?} in {:?}",
+                phrases,
+                sentence
+            );
+            assert_eq!(automaton.find_all(&sentence), trie.find_all(&sentence));
+        }
+    }
+
+    fn bench_best_fit(vocab_size: usize, phrase_count: usize, sentence_len: usize) {
+        let mut rng = Rng(0xbe4c);
+        let (phrases, sentence) = synthetic_input(&mut rng, vocab_size, phrase_count, sentence_len);
+
+        let started = Instant::now();
+        let (trie, automaton) = both(&phrases);
+        let build_time = started.elapsed();
+
+        let started = Instant::now();
+        let dp_fit = dp_best_fit(sentence.clone(), &trie);
+        let dp_time = started.elapsed();
+
+        let started = Instant::now();
+        let automaton_fit = automaton.best_fit(&sentence);
+        let automaton_time = started.elapsed();
+
+        assert_eq!(automaton_fit, dp_fit);
+        println!(
+            "{} phrases over {} tokens: built both in {:?}, dp_best_fit {:?}, automaton {:?}",
+            phrase_count, sentence_len, build_time, dp_time, automaton_time
+        );
+    }
+
+    // cargo test --release bench_ -- --ignored --nocapture
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_best_fit_long_sentence() {
+        bench_best_fit(5_000, 20_000, 1_000);
+        bench_best_fit(5_000, 20_000, 5_000);
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_best_fit_many_phrases() {
+        bench_best_fit(20_000, 50_000, 1_000);
+        bench_best_fit(50_000, 200_000, 1_000);
+    }
+}
@@ -107,6 +107,135 @@ pub fn dp_best_fit<T: Eq + Hash + Clone, S>(seq: Vec<T>, trie: &Trie<T, S>) -> V
     slices.clone()
 }
 
+/// contiguous phrase matching over a sentence's lexical sequence, so phrase fitting can run against
+/// a Trie of the phrases that may occur in a document or a PhraseAutomaton of all of a language's phrases
+pub trait ContiguousPhraseMatcher<T> {
+    /// the fewest segments covering seq, each a single element or a phrase, as (start, end)
+    fn best_fit(&self, seq: &[T]) -> Vec<(usize, usize)>;
+    /// every phrase occurrence as (start, end), ordered by start then ascending length
+    fn find_all(&self, seq: &[T]) -> Vec<(usize, usize)>;
+    fn contains_phrase(&self, phrase: &[T]) -> bool;
+}
+
+impl<T: Eq + Hash + Clone, S> ContiguousPhraseMatcher<T> for Trie<T, S> {
+    fn best_fit(&self, seq: &[T]) -> Vec<(usize, usize)> {
+        dp_best_fit(seq.to_vec(), self)
+    }
+
+    fn find_all(&self, seq: &[T]) -> Vec<(usize, usize)> {
+        (0..seq.len())
+            .flat_map(|start| {
+                self.search_prefixes_by_ref(&seq[start..], false)
+                    .into_iter()
+                    .map(move |prefix| (start, start + prefix.len()))
+            })
+            .collect()
+    }
+
+    fn contains_phrase(&self, phrase: &[T]) -> bool {
+        self.search_for_payload(phrase.iter().cloned()).1.is_some()
+    }
+}
+
+/// a phrase pattern for gapped matching. None elements are wildcard slots that consume exactly one
+/// element, and up to max_gap elements may be skipped before each literal element after the first
+#[derive(Debug, Clone, PartialEq)]
+pub struct GappedPattern<T> {
+    pub elements: Vec<Option<T>>,
+    pub max_gap: usize,
+}
+
+/// a match of a gapped pattern. components are the positions matched by literal elements and may be
+/// non-adjacent; the span runs from the first to the last component, inclusive
+#[derive(Debug, Clone, PartialEq)]
+pub struct GappedMatch {
+    pub pattern_idx: usize,
+    pub components: Vec<usize>,
+    pub span: (usize, usize),
+}
+
+fn match_gapped_pattern_at<T: Eq>(
+    seq: &[T],
+    pattern: &GappedPattern<T>,
+    start: usize,
+    claimed: &[bool],
+) -> Option<Vec<usize>> {
+    let mut elements = pattern.elements.iter();
+    match elements.next()? {
+        Some(first) if seq[start] == *first && !claimed[start] => {}
+        _ => return None,
+    }
+
+    let mut components = vec![start];
+    let mut pos = start;
+    for element in elements {
+        pos = match element {
+            None => pos + 1,
+            Some(x) => (pos + 1..=pos + 1 + pattern.max_gap)
+                .take_while(|&j| j < seq.len() && !claimed[j])
+                .find(|&j| seq[j] == *x)?,
+        };
+        if pos >= seq.len() || claimed[pos] {
+            return None;
+        }
+        if element.is_some() {
+            components.push(pos);
+        }
+    }
+    Some(components)
+}
+
+/// greedy left-to-right matching of gapped patterns. at each position the pattern with the most
+/// elements wins, and each literal element takes its earliest occurrence. matches never overlap
+/// each other or positions that are already claimed, e.g. by contiguous phrases
+pub fn gapped_fit<T: Eq>(
+    seq: &[T],
+    patterns: &[GappedPattern<T>],
+    claimed: &mut [bool],
+) -> Vec<GappedMatch> {
+    debug_assert!(claimed.len() == seq.len());
+    let mut pattern_order = (0..patterns.len()).collect::<Vec<usize>>();
+    pattern_order.sort_by_key(|&i| std::cmp::Reverse(patterns[i].elements.len()));
+
+    let mut matches = vec![];
+    for start in 0..seq.len() {
+        let found = pattern_order.iter().find_map(|&pattern_idx| {
+            match_gapped_pattern_at(seq, &patterns[pattern_idx], start, claimed)
+                .map(|components| (pattern_idx, components))
+        });
+        if let Some((pattern_idx, components)) = found {
+            let span = (start, *components.last().unwrap());
+            claimed[span.0..=span.1].iter_mut().for_each(|x| *x = true);
+            matches.push(GappedMatch {
+                pattern_idx,
+                components,
+                span,
+            });
+        }
+    }
+    matches
+}
+
+/// every occurrence of every pattern, with overlap and nesting allowed. unlike gapped_fit this does not
+/// segment, it reports what is there
+pub fn gapped_find_all<T: Eq>(seq: &[T], patterns: &[GappedPattern<T>]) -> Vec<GappedMatch> {
+    let unclaimed = vec![false; seq.len()];
+    let mut matches = vec![];
+    for start in 0..seq.len() {
+        for (pattern_idx, pattern) in patterns.iter().enumerate() {
+            if let Some(components) = match_gapped_pattern_at(seq, pattern, start, &unclaimed) {
+                let span = (start, *components.last().unwrap());
+                matches.push(GappedMatch {
+                    pattern_idx,
+                    components,
+                    span,
+                });
+            }
+        }
+    }
+    matches
+}
+
 #[cfg(test)]
 mod test {
     #[test]
@@ -164,4 +293,77 @@ mod test {
         println!("{:?}", &segments);
         assert_eq!(segments, vec![(0, 5), (5, 6), (6, 9)]);
     }
+
+    #[test]
+    fn test_gapped_fit1() {
+        use super::{gapped_fit, GappedPattern};
+        let seq = vec![
+            "he", "put", "the", "meeting", "off", "and", "put", "it", "off",
+        ];
+        let patterns = vec![
+            GappedPattern {
+                elements: vec![Some("put"), Some("off")],
+                max_gap: 2,
+            },
+            GappedPattern {
+                elements: vec![Some("put"), None, Some("off")],
+                max_gap: 0,
+            },
+        ];
+        let mut claimed = vec![false; seq.len()];
+        let matches = gapped_fit(&seq, &patterns, &mut claimed);
+        let matches = matches
+            .iter()
+            .map(|m| (m.pattern_idx, m.components.clone(), m.span))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matches,
+            vec![(0, vec![1, 4], (1, 4)), (1, vec![6, 8], (6, 8))]
+        );
+        assert_eq!(
+            claimed,
+            vec![false, true, true, true, true, false, true, true, true]
+        );
+    }
+
+    #[test]
+    fn test_gapped_fit_respects_claimed_and_max_gap() {
+        use super::{gapped_fit, GappedPattern};
+        let seq = vec!["put", "a", "b", "c", "off", "put", "x", "off"];
+        let patterns = vec![GappedPattern {
+            elements: vec![Some("put"), Some("off")],
+            max_gap: 2,
+        }];
+        let mut claimed = vec![false, false, false, false, false, false, true, false];
+        let matches = gapped_fit(&seq, &patterns, &mut claimed);
+        assert!(matches.is_empty());
+    }
+
+    #[test]
+    fn test_gapped_find_all_allows_overlap() {
+        use super::{gapped_find_all, GappedPattern};
+        let seq = vec!["put", "it", "off", "off"];
+        let patterns = vec![
+            GappedPattern {
+                elements: vec![Some("put"), Some("off")],
+                max_gap: 2,
+            },
+            GappedPattern {
+                elements: vec![Some("it"), Some("off")],
+                max_gap: 0,
+            },
+            GappedPattern {
+                elements: vec![Some("off"), Some("off")],
+                max_gap: 0,
+            },
+        ];
+        let matches = gapped_find_all(&seq, &patterns)
+            .iter()
+            .map(|m| (m.pattern_idx, m.components.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matches,
+            vec![(0, vec![0, 2]), (1, vec![1, 2]), (2, vec![2, 3])]
+        );
+    }
 }
@@ -0,0 +1,366 @@
+///! all of a language's phrases, indexed for phrase fitting and kept in memory between document loads
+///! - each loaded language keeps a Trie of its phrases, which phrase edits update in place
+///! - the automaton is rebuilt from the trie on the next document load after an edit, without querying the database
+///! - tries are saved under the data directory and reused after a restart if the phrase table has not changed
+use super::phrase_automaton::PhraseAutomaton;
+use super::*;
+use crate::db::models::phrase::{mk_phrase_trie, PhraseFingerprint};
+use crate::db::DB;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+pub struct PhraseIndex {
+    phrases: Vec<Phrase>,
+    /// every phrase by orthography_seq, as an index into phrases
+    automaton: PhraseAutomaton<String, usize>,
+    /// phrases that dp_best_fit over orthographies cannot find on its own
+    extra_phrases: Vec<Phrase>,
+}
+
+impl PhraseIndex {
+    pub fn new(phrases: Vec<Phrase>) -> Self {
+        let automaton = PhraseAutomaton::new(
+            phrases
+                .iter()
+                .enumerate()
+                .map(|(idx, phrase)| (phrase.orthography_seq.clone(), idx)),
+        );
+        let extra_phrases = phrases
+            .iter()
+            .filter(|phrase| phrase.is_pattern() || phrase.match_by_lemma)
+            .cloned()
+            .collect();
+        PhraseIndex {
+            phrases,
+            automaton,
+            extra_phrases,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.automaton.len()
+    }
+
+    /// same result as phrase_fit_pipeline with a Trie of the same phrases
+    pub fn fit(&self, document: AnnotatedDocV2) -> AnnotatedDocV2 {
+        fit_phrases_in_document(document, &self.automaton, &self.extra_phrases)
+    }
+
+    /// the phrases found in a fitted document, for its TermDictionary
+    pub fn matched_phrases(&self, document: &AnnotatedDocV2) -> BTreeMap<String, Phrase> {
+        let by_normalised_orthography = document
+            .phrase_matches
+            .iter()
+            .map(|phrase_match| phrase_match.normalised_orthography.as_str())
+            .collect::<BTreeSet<&str>>();
+        self.phrases
+            .iter()
+            .map(|phrase| (phrase.orthography_seq.join(" "), phrase))
+            .filter(|(key, _)| by_normalised_orthography.contains(key.as_str()))
+            .map(|(key, phrase)| (key, phrase.clone()))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPhraseTrie {
+    /// the language's phrase table when the trie was saved
+    fingerprint: PhraseFingerprint,
+    trie: Trie<String, Phrase>,
+}
+
+/// saved phrase tries, one file per language
+#[derive(Debug, Clone)]
+pub struct PhraseTrieStore {
+    dir: PathBuf,
+}
+
+impl PhraseTrieStore {
+    pub fn new(dir: PathBuf) -> Self {
+        PhraseTrieStore { dir }
+    }
+
+    fn path(&self, lang_id: &InfluxResourceId) -> PathBuf {
+        let name = match lang_id {
+            InfluxResourceId::SerialId(id) => id.to_string(),
+            InfluxResourceId::StringId(id) => id.clone(),
+        };
+        self.dir.join(format!("lang_{}.json", name))
+    }
+
+    /// the saved trie, unless it is missing or was saved from a different phrase table
+    fn load(
+        &self,
+        lang_id: &InfluxResourceId,
+        fingerprint: &PhraseFingerprint,
+    ) -> Option<Trie<String, Phrase>> {
+        let path = self.path(lang_id);
+        let file = File::open(&path).ok()?;
+        match serde_json::from_reader::<_, SavedPhraseTrie>(BufReader::new(file)) {
+            Ok(saved) if saved.fingerprint == *fingerprint => Some(saved.trie),
+            Ok(_) => {
+                debug!(lang_id = ?lang_id, "Saved phrase trie is out of date");
+                None
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to read saved phrase trie {}: {}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// written to a temporary file first, so a crash never leaves half a trie behind
+    fn save(
+        &self,
+        lang_id: &InfluxResourceId,
+        fingerprint: PhraseFingerprint,
+        trie: Trie<String, Phrase>,
+    ) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(lang_id);
+        let tmp_path = path.with_extension("json.tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut writer, &SavedPhraseTrie { fingerprint, trie })?;
+        writer.flush()?;
+        std::fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    fn remove(&self, lang_id: &InfluxResourceId) {
+        let _ = std::fs::remove_file(self.path(lang_id));
+    }
+}
+
+struct LanguagePhrases {
+    trie: Trie<String, Phrase>,
+    /// built from trie on document load, None after an edit
+    index: Option<Arc<PhraseIndex>>,
+}
+
+fn phrases_in(trie: &Trie<String, Phrase>) -> Vec<Phrase> {
+    trie.get_all_entries()
+        .into_iter()
+        .map(|(_, phrase)| phrase.clone())
+        .collect()
+}
+
+/// phrase tries and indexes by language, shared through ServerState
+#[derive(Clone, Default)]
+pub struct PhraseIndexCache {
+    languages: Arc<RwLock<HashMap<InfluxResourceId, LanguagePhrases>>>,
+    /// bumped on every edit, so an index or saved trie built from phrases read before it is not kept
+    generation: Arc<AtomicU64>,
+    /// None keeps tries in memory only
+    store: Option<PhraseTrieStore>,
+}
+
+impl PhraseIndexCache {
+    pub fn with_store(store: PhraseTrieStore) -> Self {
+        PhraseIndexCache {
+            store: Some(store),
+            ..Default::default()
+        }
+    }
+
+    pub async fn get_or_build(
+        &self,
+        db: &DB,
+        lang_id: InfluxResourceId,
+    ) -> anyhow::Result<Arc<PhraseIndex>> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let edited_phrases = match self.languages.read().unwrap().get(&lang_id) {
+            Some(LanguagePhrases {
+                index: Some(index), ..
+            }) => return Ok(index.clone()),
+            Some(language) => Some(phrases_in(&language.trie)),
+            None => None,
+        };
+        // the trie needs saving unless it was just read from the store
+        let (loaded_trie, phrases, needs_save) = match edited_phrases {
+            Some(phrases) => (None, phrases, true),
+            None => {
+                let (trie, from_store) = self.load_trie(db, lang_id.clone()).await?;
+                let phrases = phrases_in(&trie);
+                (Some(trie), phrases, !from_store)
+            }
+        };
+        let index = Arc::new(tokio::task::spawn_blocking(move || PhraseIndex::new(phrases)).await?);
+        debug!(lang_id = ?lang_id, phrases = index.len(), "Built phrase index");
+
+        {
+            let mut languages = self.languages.write().unwrap();
+            if self.generation.load(Ordering::SeqCst) != generation {
+                return Ok(index);
+            }
+            match loaded_trie {
+                Some(trie) => {
+                    languages.insert(
+                        lang_id.clone(),
+                        LanguagePhrases {
+                            trie,
+                            index: Some(index.clone()),
+                        },
+                    );
+                }
+                None => {
+                    if let Some(language) = languages.get_mut(&lang_id) {
+                        language.index = Some(index.clone());
+                    }
+                }
+            }
+        }
+        if needs_save {
+            self.spawn_save(db.clone(), lang_id);
+        }
+        Ok(index)
+    }
+
+    /// the saved trie if it matches the phrase table, otherwise every phrase from the database.
+    /// also returns whether the trie came from the store
+    async fn load_trie(
+        &self,
+        db: &DB,
+        lang_id: InfluxResourceId,
+    ) -> anyhow::Result<(Trie<String, Phrase>, bool)> {
+        if let Some(store) = self.store.clone() {
+            let fingerprint = db.phrase_fingerprint(lang_id.clone()).await?;
+            let saved_lang_id = lang_id.clone();
+            let saved =
+                tokio::task::spawn_blocking(move || store.load(&saved_lang_id, &fingerprint))
+                    .await?;
+            if let Some(trie) = saved {
+                return Ok((trie, true));
+            }
+        }
+        let phrases = db.query_phrases_by_lang_id(lang_id).await?;
+        Ok((mk_phrase_trie(phrases), false))
+    }
+
+    /// save the language's trie in the background
+    /// - edits remove the saved trie, and a trie that an edit raced with is removed again after saving
+    fn spawn_save(&self, db: DB, lang_id: InfluxResourceId) {
+        let Some(store) = self.store.clone() else {
+            return;
+        };
+        let cache = self.clone();
+        tokio::spawn(async move {
+            let generation = cache.generation.load(Ordering::SeqCst);
+            let fingerprint = match db.phrase_fingerprint(lang_id.clone()).await {
+                Ok(fingerprint) => fingerprint,
+                Err(err) => {
+                    warn!("Failed to fingerprint phrases of {:?}: {}", lang_id, err);
+                    return;
+                }
+            };
+            let Some(trie) = cache
+                .languages
+                .read()
+                .unwrap()
+                .get(&lang_id)
+                .map(|language| language.trie.clone())
+            else {
+                return;
+            };
+            if cache.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let saving_store = store.clone();
+            let saving_lang_id = lang_id.clone();
+            let saved = tokio::task::spawn_blocking(move || {
+                saving_store.save(&saving_lang_id, fingerprint, trie)
+            })
+            .await;
+            match saved {
+                Ok(Ok(())) => debug!(lang_id = ?lang_id, "Saved phrase trie"),
+                Ok(Err(err)) => warn!("Failed to save phrase trie of {:?}: {}", lang_id, err),
+                Err(err) => warn!("Failed to save phrase trie of {:?}: {}", lang_id, err),
+            }
+            if cache.generation.load(Ordering::SeqCst) != generation {
+                store.remove(&lang_id);
+            }
+        });
+    }
+
+    fn edit(&self, lang_id: &InfluxResourceId, edit: impl FnOnce(&mut Trie<String, Phrase>)) {
+        {
+            let mut languages = self.languages.write().unwrap();
+            self.generation.fetch_add(1, Ordering::SeqCst);
+            if let Some(language) = languages.get_mut(lang_id) {
+                edit(&mut language.trie);
+                language.index = None;
+            }
+        }
+        if let Some(store) = &self.store {
+            store.remove(lang_id);
+        }
+    }
+
+    /// a phrase was created or updated. previous is the phrase before an update, whose orthography_seq may differ
+    pub fn phrase_saved(&self, previous: Option<&Phrase>, phrase: &Phrase) {
+        self.edit(&phrase.lang_id, |trie| {
+            if let Some(previous) = previous {
+                trie.remove(previous.orthography_seq.clone());
+            }
+            trie.insert_with_payload(phrase.orthography_seq.clone(), phrase.clone());
+        });
+    }
+
+    pub fn phrase_deleted(&self, phrase: &Phrase) {
+        self.edit(&phrase.lang_id, |trie| {
+            trie.remove(phrase.orthography_seq.clone());
+        });
+    }
+
+    /// forget everything about the language, e.g. once it is deleted
+    pub fn invalidate(&self, lang_id: &InfluxResourceId) {
+        {
+            let mut languages = self.languages.write().unwrap();
+            self.generation.fetch_add(1, Ordering::SeqCst);
+            languages.remove(lang_id);
+        }
+        if let Some(store) = &self.store {
+            store.remove(lang_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phrase_trie_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PhraseTrieStore::new(dir.path().join("phrase_tries"));
+        let lang_id = InfluxResourceId::SerialId(1);
+        let trie = mk_phrase_trie(vec![Phrase::essential_phrase(
+            lang_id.clone(),
+            vec!["take".to_string(), "off".to_string()],
+        )]);
+        let fingerprint = PhraseFingerprint {
+            count: 1,
+            last_updated: Some(chrono::Utc::now()),
+        };
+
+        assert!(store.load(&lang_id, &fingerprint).is_none());
+        store
+            .save(&lang_id, fingerprint.clone(), trie.clone())
+            .unwrap();
+        assert_eq!(store.load(&lang_id, &fingerprint), Some(trie));
+        // a phrase was added while the server was not running
+        let changed = PhraseFingerprint {
+            count: 2,
+            ..fingerprint.clone()
+        };
+        assert!(store.load(&lang_id, &changed).is_none());
+
+        store.remove(&lang_id);
+        assert!(store.load(&lang_id, &fingerprint).is_none());
+    }
+}
@@ -0,0 +1,132 @@
+///! re-annotating only the sentences that a term edit can change, instead of the whole document
+///! - a token edit changes the sentences where it occurs, by orthography or as a lemma
+///! - a phrase edit changes the sentences containing its onset, since phrase fitting never matches without it
+use super::*;
+
+/// orthographies and lemmas whose sentences a term edit may have changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AffectedTerms {
+    pub orthographies: BTreeSet<String>,
+    pub lemmas: BTreeSet<String>,
+}
+
+impl AffectedTerms {
+    pub fn of_token(token: &Token) -> Self {
+        AffectedTerms {
+            orthographies: BTreeSet::from([token.orthography.clone()]),
+            lemmas: BTreeSet::from([token.orthography.clone()]),
+        }
+    }
+
+    pub fn of_phrase(phrase: &Phrase) -> Self {
+        let onset = phrase.orthography_seq.first().cloned().into_iter();
+        AffectedTerms {
+            orthographies: onset.clone().collect(),
+            lemmas: match phrase.match_by_lemma {
+                true => onset.collect(),
+                false => BTreeSet::new(),
+            },
+        }
+    }
+
+    pub fn extend(&mut self, other: AffectedTerms) {
+        self.orthographies.extend(other.orthographies);
+        self.lemmas.extend(other.lemmas);
+    }
+
+    fn affects(&self, segments: &[SentSegV2]) -> bool {
+        segments.iter().any(|seg| {
+            let orthography = match &seg.inner {
+                SentSegVariants::TokenSeg { orthography, .. } => orthography.clone(),
+                SentSegVariants::PunctuationSeg => seg.text.to_lowercase(),
+                _ => return false,
+            };
+            self.orthographies.contains(&orthography)
+                || seg
+                    .attributes
+                    .lemma
+                    .as_ref()
+                    .is_some_and(|lemma| self.lemmas.contains(&lemma.to_lowercase()))
+        })
+    }
+}
+
+/// the affected sentences of a tokenised document, as a document of their own to be annotated
+pub fn affected_sentences(doc: &AnnotatedDocV2, affected: &AffectedTerms) -> AnnotatedDocV2 {
+    chunking::select_sentences(doc, |segments| affected.affects(segments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::lang::ParserConfig;
+    use expect_test::expect;
+
+    fn render(doc: &AnnotatedDocV2) -> String {
+        doc.segments
+            .iter()
+            .map(|doc_seg| {
+                format!(
+                    "{}..{} {:?}",
+                    doc_seg.start_char, doc_seg.end_char, doc_seg.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_affected_sentences() {
+        let mut doc = tokeniser::unicode_tokenise(
+            "She took off. They take it. Nothing here. Take care!",
+            ParserConfig {
+                which_parser: tokeniser::UNICODE_PARSER.to_string(),
+                parser_args: hashmap! {},
+            },
+        );
+        for doc_seg in doc.segments.iter_mut() {
+            if let DocSegVariants::Sentence { segments } = &mut doc_seg.inner {
+                for seg in segments.iter_mut() {
+                    if seg.text == "took" {
+                        seg.attributes.lemma = Some("take".to_string());
+                    }
+                }
+            }
+        }
+
+        let mut take = Phrase::essential_phrase(
+            InfluxResourceId::SerialId(1),
+            vec!["take".to_string(), "off".to_string()],
+        );
+        expect![[r#"
+            14..27 "They take it."
+            42..52 "Take care!""#]]
+        .assert_eq(&render(&affected_sentences(
+            &doc,
+            &AffectedTerms::of_phrase(&take),
+        )));
+
+        take.match_by_lemma = true;
+        let affected = affected_sentences(&doc, &AffectedTerms::of_phrase(&take));
+        expect![[r#"
+            0..13 "She took off."
+            14..27 "They take it."
+            42..52 "Take care!""#]]
+        .assert_eq(&render(&affected));
+        assert!(affected.orthography_set.contains("care"));
+        assert!(!affected.orthography_set.contains("nothing"));
+
+        let mut affected_terms = AffectedTerms::of_token(&Token::essential_token(
+            InfluxResourceId::SerialId(1),
+            "here",
+        ));
+        affected_terms.extend(AffectedTerms::of_phrase(&Phrase::essential_phrase(
+            InfluxResourceId::SerialId(1),
+            vec!["she".to_string(), "took".to_string()],
+        )));
+        expect![[r#"
+            0..13 "She took off."
+            28..41 "Nothing here.""#]]
+        .assert_eq(&render(&affected_sentences(&doc, &affected_terms)));
+    }
+}
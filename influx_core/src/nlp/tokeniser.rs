@@ -0,0 +1,309 @@
+///! tokenisers turn text into an AnnotatedDocV2 before phrase fitting
+///! - NlpServiceTokeniser delegates to the python NLP service
+///! - UnicodeTokeniser runs in process, for space-delimited languages, and needs no NLP service
+use super::*;
+use crate::db::models::lang::ParserConfig;
+use crate::integration::nlp_client::NlpClient;
+use async_trait::async_trait;
+use std::sync::Arc;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// `which_parser` value that selects the in-process tokeniser
+pub const UNICODE_PARSER: &str = "unicode";
+
+#[async_trait]
+pub trait Tokeniser: Send + Sync {
+    async fn tokenise(
+        &self,
+        text: &str,
+        language_code: &str,
+        parser_config: &ParserConfig,
+    ) -> anyhow::Result<AnnotatedDocV2>;
+
+    /// changes whenever the same text and ParserConfig may tokenise differently, so cached annotations can be dropped
+    async fn parser_version(&self) -> anyhow::Result<String>;
+}
+
+pub struct NlpServiceTokeniser {
+    pub client: NlpClient,
+}
+
+pub struct UnicodeTokeniser;
+
+/// pick the tokeniser for a parser config. anything other than UNICODE_PARSER is handled by the NLP service
+pub fn tokeniser_for(parser_config: &ParserConfig, nlp_client: &NlpClient) -> Arc<dyn Tokeniser> {
+    match parser_config.which_parser.as_str() {
+        UNICODE_PARSER => Arc::new(UnicodeTokeniser),
+        _ => Arc::new(NlpServiceTokeniser {
+            client: nlp_client.clone(),
+        }),
+    }
+}
+
+#[async_trait]
+impl Tokeniser for NlpServiceTokeniser {
+    async fn tokenise(
+        &self,
+        text: &str,
+        language_code: &str,
+        parser_config: &ParserConfig,
+    ) -> anyhow::Result<AnnotatedDocV2> {
+        let payload = json!({
+            "text": text,
+            "language_code": language_code,
+            "parser_config": parser_config
+        });
+        // errors stay typed as NlpError inside the anyhow::Error, so callers can downcast
+        let mut annotated_document: AnnotatedDocV2 =
+            self.client.post_json("/tokeniser", &payload).await?;
+        debug!(
+            segments_count = annotated_document.segments.len(),
+            orthography_count = annotated_document.orthography_set.len(),
+            lemma_count = annotated_document.lemma_set.len(),
+            "Parsed NLP response"
+        );
+
+        annotated_document.parser_config = parser_config.clone();
+        Ok(annotated_document)
+    }
+
+    async fn parser_version(&self) -> anyhow::Result<String> {
+        self.client.parser_version().await.ok_or_else(|| {
+            anyhow::anyhow!(
+                "NLP service at {} did not report its parser version",
+                self.client.base_url()
+            )
+        })
+    }
+}
+
+#[async_trait]
+impl Tokeniser for UnicodeTokeniser {
+    async fn tokenise(
+        &self,
+        text: &str,
+        _language_code: &str,
+        parser_config: &ParserConfig,
+    ) -> anyhow::Result<AnnotatedDocV2> {
+        Ok(unicode_tokenise(text, parser_config.clone()))
+    }
+
+    async fn parser_version(&self) -> anyhow::Result<String> {
+        Ok(format!("{}={}", UNICODE_PARSER, env!("CARGO_PKG_VERSION")))
+    }
+}
+
+fn plain_attributes() -> SegAttribute {
+    SegAttribute {
+        lemma: None,
+        upos: None,
+        xpos: None,
+        dependency: None,
+        misc: btreemap! {},
+        conjugation_chain: None,
+    }
+}
+
+/// char offsets, like the NLP service, where the segmenter works in byte offsets
+struct CharOffsets {
+    byte_offsets: Vec<usize>,
+}
+
+impl CharOffsets {
+    fn new(text: &str) -> Self {
+        CharOffsets {
+            byte_offsets: text.char_indices().map(|(byte, _)| byte).collect(),
+        }
+    }
+
+    fn of(&self, byte_offset: usize) -> usize {
+        self.byte_offsets
+            .binary_search(&byte_offset)
+            .unwrap_or_else(|insert_at| insert_at)
+    }
+}
+
+/// fill the gaps between a sentence's segments with whitespace segments
+fn recover_sentence_whitespace(text_chars: &[char], segments: Vec<SentSegV2>) -> Vec<SentSegV2> {
+    let mut result = vec![];
+    let mut fill_line = segments.first().map(|seg| seg.start_char).unwrap_or(0);
+    for seg in segments {
+        if seg.start_char > fill_line {
+            result.push(SentSegV2 {
+                sentence_idx: seg.sentence_idx,
+                text: text_chars[fill_line..seg.start_char].iter().collect(),
+                start_char: fill_line,
+                end_char: seg.start_char,
+                inner: SentSegVariants::WhitespaceSeg,
+                attributes: plain_attributes(),
+            });
+        }
+        fill_line = seg.end_char;
+        result.push(seg);
+    }
+    result
+}
+
+/// fill the gaps between sentences, and before and after them, with document whitespace
+fn recover_document_whitespace(text_chars: &[char], segments: Vec<DocSegV2>) -> Vec<DocSegV2> {
+    let mk_whitespace = |start_char: usize, end_char: usize| DocSegV2 {
+        text: text_chars[start_char..end_char].iter().collect(),
+        start_char,
+        end_char,
+        inner: DocSegVariants::DocumentWhitespace,
+    };
+    let mut result = vec![];
+    let mut last_char = 0;
+    for seg in segments {
+        if seg.start_char > last_char {
+            result.push(mk_whitespace(last_char, seg.start_char));
+        }
+        last_char = seg.end_char;
+        result.push(seg);
+    }
+    if last_char < text_chars.len() {
+        result.push(mk_whitespace(last_char, text_chars.len()));
+    }
+    result
+}
+
+/// Unicode (UAX #29) sentence and word segmentation. words with an alphanumeric character become
+/// tokens, other non-whitespace becomes punctuation. there is no lemmatisation or tagging
+pub fn unicode_tokenise(text: &str, parser_config: ParserConfig) -> AnnotatedDocV2 {
+    let char_offsets = CharOffsets::new(text);
+    let text_chars = text.chars().collect::<Vec<char>>();
+    let mut orthography_set = BTreeSet::new();
+    let mut token_idx = 0;
+
+    let mut sentence_segments = vec![];
+    for (sentence_byte_start, sentence) in text.split_sentence_bound_indices() {
+        let sentence_idx = sentence_segments.len();
+        let mut segments = vec![];
+        for (word_byte_start, word) in sentence.split_word_bound_indices() {
+            if word.chars().all(char::is_whitespace) {
+                continue;
+            }
+            let byte_start = sentence_byte_start + word_byte_start;
+            let orthography = word.to_lowercase();
+            orthography_set.insert(orthography.clone());
+            let inner = if word.chars().any(char::is_alphanumeric) {
+                SentSegVariants::TokenSeg {
+                    idx: token_idx,
+                    orthography,
+                }
+            } else {
+                SentSegVariants::PunctuationSeg
+            };
+            token_idx += 1;
+            segments.push(SentSegV2 {
+                sentence_idx,
+                text: word.to_string(),
+                start_char: char_offsets.of(byte_start),
+                end_char: char_offsets.of(byte_start + word.len()),
+                inner,
+                attributes: plain_attributes(),
+            });
+        }
+
+        let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+            continue;
+        };
+        let (start_char, end_char) = (first.start_char, last.end_char);
+        sentence_segments.push(DocSegV2 {
+            text: text_chars[start_char..end_char].iter().collect(),
+            start_char,
+            end_char,
+            inner: DocSegVariants::Sentence {
+                segments: recover_sentence_whitespace(&text_chars, segments),
+            },
+        });
+    }
+
+    AnnotatedDocV2 {
+        text: text.to_string(),
+        segments: recover_document_whitespace(&text_chars, sentence_segments),
+        orthography_set,
+        lemma_set: BTreeSet::new(),
+        parser_config,
+        phrase_matches: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+    use maplit::btreeset;
+
+    fn render(doc: &AnnotatedDocV2) -> String {
+        doc.segments
+            .iter()
+            .map(|doc_seg| match &doc_seg.inner {
+                DocSegVariants::DocumentWhitespace => {
+                    format!("doc whitespace {:?}", doc_seg.text)
+                }
+                DocSegVariants::Sentence { segments } => format!(
+                    "sentence {}..{} {:?}\n{}",
+                    doc_seg.start_char,
+                    doc_seg.end_char,
+                    doc_seg.text,
+                    segments
+                        .iter()
+                        .map(|seg| {
+                            let kind = match &seg.inner {
+                                SentSegVariants::TokenSeg { idx, orthography } => {
+                                    format!("token {} {:?}", idx, orthography)
+                                }
+                                SentSegVariants::PunctuationSeg => "punct".to_string(),
+                                SentSegVariants::WhitespaceSeg => "space".to_string(),
+                                SentSegVariants::PhraseSeg { .. } => "phrase".to_string(),
+                            };
+                            format!(
+                                "  [{}] {}..{} {:?} {}",
+                                seg.sentence_idx, seg.start_char, seg.end_char, seg.text, kind
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_unicode_tokenise() {
+        let doc = unicode_tokenise(
+            "  Ça va? Don't  worry, café.\n",
+            ParserConfig {
+                which_parser: UNICODE_PARSER.to_string(),
+                parser_args: hashmap! {},
+            },
+        );
+        expect![[r#"
+            doc whitespace "  "
+            sentence 2..8 "Ça va?"
+              [0] 2..4 "Ça" token 0 "ça"
+              [0] 4..5 " " space
+              [0] 5..7 "va" token 1 "va"
+              [0] 7..8 "?" punct
+            doc whitespace " "
+            sentence 9..28 "Don't  worry, café."
+              [1] 9..14 "Don't" token 3 "don't"
+              [1] 14..16 "  " space
+              [1] 16..21 "worry" token 4 "worry"
+              [1] 21..22 "," punct
+              [1] 22..23 " " space
+              [1] 23..27 "café" token 6 "café"
+              [1] 27..28 "." punct
+            doc whitespace "\n""#]]
+        .assert_eq(&render(&doc));
+        assert_eq!(
+            doc.orthography_set,
+            btreeset! {"ça", "va", "?", "don't", "worry", ",", "café", "."}
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect::<BTreeSet<String>>()
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::db::models::lang::{Language, ParserConfig};
 use crate::embedded_db::EmbeddedDb;
 use crate::ServerState;
 use anyhow::Result;
@@ -23,3 +24,23 @@ impl TestDb {
 pub fn create_test_app(state: ServerState) -> Router {
     crate::create_app_router(state)
 }
+
+/// a language with no dictionaries, TTS or translation settings, for tests that only need one to hold
+/// terms and documents
+pub fn test_language(name: &str, language_code: &str, which_parser: &str) -> Language {
+    Language {
+        id: None,
+        name: name.to_string(),
+        language_code: language_code.to_string(),
+        dicts: vec![],
+        tts_rate: None,
+        tts_pitch: None,
+        tts_voice: None,
+        deepl_source_lang: None,
+        deepl_target_lang: None,
+        parser_config: ParserConfig {
+            which_parser: which_parser.to_string(),
+            parser_args: Default::default(),
+        },
+    }
+}
@@ -1,7 +1,12 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::hash::Hash;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, S: Serialize",
+    deserialize = "T: Deserialize<'de>, S: Deserialize<'de>"
+))]
 pub struct Trie<T: Eq + Hash + Clone, S> {
     children: HashMap<T, Trie<T, S>>,
     is_terminal: bool,
@@ -54,6 +59,71 @@ impl<T: Eq + Hash + Clone, S> Trie<T, S> {
         curr.is_terminal
     }
 
+    /// unmark seq and return its payload, pruning the nodes that no longer lead to an entry
+    /// - returns whether seq was in the trie
+    pub fn remove<I>(&mut self, seq: I) -> (bool, Option<S>)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut seq = seq.into_iter();
+        match seq.next() {
+            None => {
+                let was_terminal = self.is_terminal;
+                self.is_terminal = false;
+                (was_terminal, self.payload.take())
+            }
+            Some(x) => {
+                let Some(child) = self.children.get_mut(&x) else {
+                    return (false, None);
+                };
+                let removed = child.remove(seq);
+                if !child.is_terminal && child.children.is_empty() {
+                    self.children.remove(&x);
+                }
+                removed
+            }
+        }
+    }
+
+    /// the longest entry that is a prefix of seq[start..], as its length and payload
+    pub fn longest_prefix_match(&self, seq: &[T], start: usize) -> Option<(usize, Option<&S>)> {
+        let mut longest = None;
+        let mut curr = self;
+        for (i, x) in seq.iter().skip(start).enumerate() {
+            match curr.children.get(x) {
+                Some(child) => curr = child,
+                None => break,
+            }
+            if curr.is_terminal {
+                longest = Some((i + 1, curr.payload.as_ref()));
+            }
+        }
+        longest
+    }
+
+    /// entries with payloads that start with prefix, including prefix itself
+    pub fn get_entries_with_prefix<I>(&self, prefix: I) -> Vec<(Vec<T>, &S)>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut curr = self;
+        let mut path = vec![];
+        for x in prefix {
+            match curr.children.get(&x) {
+                Some(child) => curr = child,
+                None => return vec![],
+            }
+            path.push(x);
+        }
+        let mut entries = Vec::new();
+        curr.get_all_entries_recursive(path, &mut entries);
+        entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.is_terminal && self.children.is_empty()
+    }
+
     pub fn search_for_payload<I>(&self, seq: I) -> (bool, Option<&S>)
     where
         I: IntoIterator<Item = T>,
@@ -178,6 +248,68 @@ mod tests {
         assert!(!trie.search(vec!["hello", "world", "wide", "web", "design"]));
     }
 
+    #[test]
+    fn test_trie_remove_prunes() {
+        let mut trie: super::Trie<i32, &str> = super::Trie::new();
+        trie.insert_with_payload(vec![1, 2, 3], "a");
+        trie.insert_with_payload(vec![1, 2], "b");
+        trie.insert_with_payload(vec![1, 4], "c");
+
+        assert_eq!(trie.remove(vec![1, 2, 3, 4]), (false, None));
+        assert_eq!(trie.remove(vec![1]), (false, None));
+        assert_eq!(trie.remove(vec![1, 2, 3]), (true, Some("a")));
+        assert!(!trie.search(vec![1, 2, 3]));
+        assert!(trie.search(vec![1, 2]));
+        // the node for 3 led to nothing else, so it is gone
+        assert_eq!(
+            trie,
+            super::Trie::new_with_entries_and_payloads(vec![(vec![1, 2], "b"), (vec![1, 4], "c")])
+        );
+
+        assert_eq!(trie.remove(vec![1, 2]), (true, Some("b")));
+        assert_eq!(trie.remove(vec![1, 4]), (true, Some("c")));
+        assert!(trie.is_empty());
+        assert_eq!(trie, super::Trie::new());
+    }
+
+    #[test]
+    fn test_trie_longest_prefix_match_and_prefix_entries() {
+        let trie: super::Trie<i32, &str> = super::Trie::new_with_entries_and_payloads(vec![
+            (vec![1, 2], "a"),
+            (vec![1, 2, 3, 4], "b"),
+            (vec![2, 3], "c"),
+        ]);
+        let seq = [0, 1, 2, 3, 5];
+        assert_eq!(trie.longest_prefix_match(&seq, 0), None);
+        assert_eq!(trie.longest_prefix_match(&seq, 1), Some((2, Some(&"a"))));
+        assert_eq!(trie.longest_prefix_match(&seq, 2), Some((2, Some(&"c"))));
+        assert_eq!(
+            trie.longest_prefix_match(&[1, 2, 3, 4, 5], 0),
+            Some((4, Some(&"b")))
+        );
+        assert_eq!(trie.longest_prefix_match(&seq, 5), None);
+
+        let mut entries = trie.get_entries_with_prefix(vec![1]);
+        entries.sort();
+        assert_eq!(entries, vec![(vec![1, 2], &"a"), (vec![1, 2, 3, 4], &"b")]);
+        assert_eq!(
+            trie.get_entries_with_prefix(vec![1, 2, 3]),
+            vec![(vec![1, 2, 3, 4], &"b")]
+        );
+        assert!(trie.get_entries_with_prefix(vec![3]).is_empty());
+    }
+
+    #[test]
+    fn test_trie_serde_roundtrip() {
+        let trie: super::Trie<String, i32> = super::Trie::new_with_entries_and_payloads(vec![
+            (vec!["take".to_string(), "off".to_string()], 1),
+            (vec!["take".to_string()], 2),
+        ]);
+        let json = serde_json::to_string(&trie).unwrap();
+        let restored: super::Trie<String, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, trie);
+    }
+
     #[test]
     fn test_search_prefixes() {
         let mut trie: super::Trie<i32, ()> = super::Trie::new();
@@ -19,10 +19,16 @@ async fn test_dictionary_resource_serving() {
         .unwrap();
     let state = ServerState {
         db,
-        nlp_url: "http://test".to_string(),
-        stardict_manager: std::sync::Arc::new(tokio::sync::Mutex::new(
-            influx_core::integration::stardict::StardictManager::new(),
-        )),
+        nlp_client: influx_core::integration::nlp_client::NlpClient::new(
+            "http://test",
+            Default::default(),
+        )
+        .unwrap(),
+        stardict_manager: influx_core::integration::stardict::StardictManager::new(),
+        server_url: "http://127.0.0.1:3000".to_string(),
+        tokenisation_queue: influx_core::jobs::TokenisationQueue::new(),
+        bypass_annotation_cache: Default::default(),
+        phrase_indexes: Default::default(),
     };
 
     let app = create_app_router(state);
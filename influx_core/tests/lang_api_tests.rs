@@ -43,6 +43,7 @@ fn create_test_language(name: &str) -> Language {
     Language {
         id: None,
         name: name.to_string(),
+        language_code: "en".to_string(),
         dicts: vec!["dict1".to_string(), "dict2".to_string()],
         tts_rate: Some(1.0),
         tts_pitch: Some(0.5),
@@ -60,10 +61,16 @@ async fn setup_test_server() -> (TestServer, TestDb) {
     let test_db = TestDb::new().await.unwrap();
     let app = create_test_app(ServerState {
         db: test_db.db.clone(),
-        nlp_url: "http://127.0.0.1:3001".to_string(),
-        stardict_manager: std::sync::Arc::new(tokio::sync::Mutex::new(
-            influx_core::integration::stardict::StardictManager::new(),
-        )),
+        nlp_client: influx_core::integration::nlp_client::NlpClient::new(
+            "http://127.0.0.1:3001",
+            Default::default(),
+        )
+        .unwrap(),
+        stardict_manager: influx_core::integration::stardict::StardictManager::new(),
+        server_url: "http://127.0.0.1:3000".to_string(),
+        tokenisation_queue: influx_core::jobs::TokenisationQueue::new(),
+        bypass_annotation_cache: Default::default(),
+        phrase_indexes: Default::default(),
     });
     let server = TestServer::new(app).unwrap();
     (server, test_db)
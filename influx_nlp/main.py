@@ -6,6 +6,10 @@ from dataclasses import dataclass
 from loguru import logger
 from lib.annotation import AnnotatedDocV2, ParserConfig
 import logging
+from importlib import metadata
+
+# bump when parsing output changes without a dependency upgrade, so that influx_core drops its cached annotations
+PARSING_REVISION = 1
 
 
 class LoguruHandler(logging.Handler):
@@ -24,6 +28,7 @@ class LoguruHandler(logging.Handler):
 class TokeniserRequest:
     text: str
     parser_config: ParserConfig
+    language_code: str = "und"  # BCP-47 tag of the document's language
 
 
 @dataclass
@@ -43,6 +48,27 @@ def root_handler() -> str:
     return f"Influx NLP Service running on port {context.port}"
 
 
+def installed_version(package: str) -> str:
+    try:
+        return metadata.version(package)
+    except metadata.PackageNotFoundError:
+        return "none"
+
+
+@app.route("/version")
+def version_handler() -> dict:
+    # anything that can change the parse of a text, compared by influx_core as an opaque string
+    version = ";".join(
+        [
+            f"influx-nlp={installed_version('influx-nlp')}",
+            f"revision={PARSING_REVISION}",
+            f"spacy={installed_version('spacy')}",
+            f"ginza={installed_version('ginza')}",
+        ]
+    )
+    return {"version": version}
+
+
 @app.route("/tokeniser", methods=["POST"])
 def tokeniser_handler() -> dict:
     request_data = request.get_json()
@@ -54,6 +80,7 @@ def tokeniser_handler() -> dict:
             which_parser=request_data["parser_config"]["which_parser"],
             parser_args=request_data["parser_config"]["parser_args"],
         ),
+        language_code=request_data.get("language_code", "und"),
     )
 
     logger.info(
@@ -62,6 +89,7 @@ def tokeniser_handler() -> dict:
             "text_length": len(data.text),
             "text_preview": data.text[:20] + "..." if len(data.text) > 20 else data.text,
             "parser_config": data.parser_config,
+            "language_code": data.language_code,
         },
     )
 